@@ -0,0 +1,966 @@
+//! The `pact_broker_client` crate provides a HAL client for the pact broker, and functions to publish
+//! pacts to the pact broker and to query the pact broker matrix to see if a version can be deployed.
+
+#![warn(missing_docs)]
+
+#[macro_use] extern crate pact_matching;
+#[macro_use] extern crate serde_json;
+#[macro_use] extern crate log;
+#[macro_use] extern crate maplit;
+extern crate itertools;
+extern crate regex;
+extern crate hyper;
+
+#[cfg(test)]
+#[macro_use(expect)]
+extern crate expectest;
+
+use pact_matching::models::{Pact, OptionalBody, build_query_string};
+use itertools::Itertools;
+use std::collections::HashMap;
+use hyper::client::*;
+use std::error::Error;
+use hyper::header::{Accept, qitem, ContentType, Headers, Authorization, Basic, Bearer};
+use hyper::mime::{Mime, TopLevel, SubLevel};
+use regex::{Regex, Captures};
+use hyper::Url;
+use hyper::status::StatusCode;
+use hyper::method::Method;
+use std::io::{self, Read};
+use std::fmt;
+use std::thread;
+use std::time::Duration;
+
+fn is_true(object: &serde_json::Map<String, serde_json::Value>, field: &String) -> bool {
+    match object.get(field) {
+        Some(json) => match json {
+            &serde_json::Value::Bool(b) => b,
+            _ => false
+        },
+        None => false
+    }
+}
+
+fn as_string(json: &serde_json::Value) -> String {
+    match json {
+        &serde_json::Value::String(ref s) => s.clone(),
+        _ => format!("{}", json)
+    }
+}
+
+fn content_type(response: &Response) -> String {
+    match response.headers.get::<ContentType>() {
+        Some(header) => format!("{}", header),
+        None => s!("text/plain")
+    }
+}
+
+fn json_content_type(response: &Response) -> bool {
+    match response.headers.get::<ContentType>() {
+        Some(header) => {
+            let &ContentType(ref mime) = header;
+            match mime.clone() {
+                Mime(TopLevel::Application, SubLevel::Json, _) => true,
+                Mime(TopLevel::Application, SubLevel::Ext(ext), _) => ext == "hal+json",
+                _ => false
+            }
+        },
+        None => false
+    }
+}
+
+fn join_paths(base: &String, path: String) -> String {
+    let mut full_path = s!(base.trim_right_matches("/"));
+    full_path.push('/');
+    full_path.push_str(path.trim_left_matches("/"));
+    full_path
+}
+
+/// Reads the body of the HTTP response. An empty body is returned as `OptionalBody::Empty`, and
+/// `OptionalBody::Missing` is returned if the body could not be read.
+pub fn extract_body(response: &mut Response) -> OptionalBody {
+    let mut buffer = vec![];
+    match response.read_to_end(&mut buffer) {
+        Ok(size) => if size > 0 {
+                OptionalBody::Present(buffer)
+            } else {
+                OptionalBody::Empty
+            },
+        Err(err) => {
+            warn!("Failed to read response body: {}", err);
+            OptionalBody::Missing
+        }
+    }
+}
+
+fn hal_accept_header() -> Accept {
+    Accept(vec![
+        qitem(Mime(TopLevel::Application, SubLevel::Ext(s!("hal+json")), vec![])),
+        qitem(Mime(TopLevel::Application, SubLevel::Json, vec![]))
+    ])
+}
+
+fn find_entry(map: &serde_json::Map<String, serde_json::Value>, key: &String) -> Option<(String, serde_json::Value)> {
+    match map.keys().find(|k| k.to_lowercase() == key.to_lowercase() ) {
+        Some(k) => map.get(k).map(|v| (key.clone(), v.clone()) ),
+        None => None
+    }
+}
+
+/// Errors that can occur when accessing the pact broker
+#[derive(Debug, Clone)]
+pub enum PactBrokerError {
+    /// A link was missing or malformed
+    LinkError(String),
+    /// The response from the pact broker was not valid
+    ContentError(String),
+    /// The request to the pact broker failed
+    IoError(String),
+    /// The resource was not found
+    NotFound(String),
+    /// A URL was not valid
+    UrlError(String),
+    /// The pact broker rejected the credentials, or credentials are required
+    AuthError(String),
+    /// The request to the pact broker timed out
+    Timeout(String)
+}
+
+impl PartialEq<String> for PactBrokerError {
+    fn eq(&self, other: &String) -> bool {
+        let message = match self {
+            &PactBrokerError::LinkError(ref s) => s.clone(),
+            &PactBrokerError::ContentError(ref s) => s.clone(),
+            &PactBrokerError::IoError(ref s) => s.clone(),
+            &PactBrokerError::NotFound(ref s) => s.clone(),
+            &PactBrokerError::UrlError(ref s) => s.clone(),
+            &PactBrokerError::AuthError(ref s) => s.clone(),
+            &PactBrokerError::Timeout(ref s) => s.clone()
+        };
+        message == *other
+    }
+}
+
+impl <'a> PartialEq<&'a str> for PactBrokerError {
+    fn eq(&self, other: &&str) -> bool {
+        let message = match self {
+            &PactBrokerError::LinkError(ref s) => s.clone(),
+            &PactBrokerError::ContentError(ref s) => s.clone(),
+            &PactBrokerError::IoError(ref s) => s.clone(),
+            &PactBrokerError::NotFound(ref s) => s.clone(),
+            &PactBrokerError::UrlError(ref s) => s.clone(),
+            &PactBrokerError::AuthError(ref s) => s.clone(),
+            &PactBrokerError::Timeout(ref s) => s.clone()
+        };
+        message.as_str() == *other
+    }
+}
+
+/// A link in a HAL resource
+#[derive(Debug, Clone)]
+pub struct Link {
+    /// Name of the link
+    pub name: String,
+    /// URL of the link
+    pub href: Option<String>,
+    /// If the URL is a template that contains `{name}` placeholders
+    pub templated: bool
+}
+
+impl Link {
+
+    /// Creates a link from the JSON data of a HAL resource
+    pub fn from_json(link: &String, link_data: &serde_json::Map<String, serde_json::Value>) -> Link {
+        Link {
+            name: link.clone(),
+            href: find_entry(link_data, &s!("href")).map(|(_, href)| as_string(&href)),
+            templated: is_true(link_data, &s!("templated"))
+        }
+    }
+
+}
+
+/// Credentials used to authenticate with the pact broker
+#[derive(Clone, PartialEq)]
+pub enum HttpAuth {
+    /// Basic authentication with a username and optional password
+    User(String, Option<String>),
+    /// Bearer token authentication
+    Token(String)
+}
+
+impl fmt::Debug for HttpAuth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HttpAuth::User(ref username, _) => write!(f, "User({}, ****)", username),
+            HttpAuth::Token(_) => write!(f, "Token(****)")
+        }
+    }
+}
+
+/// Longest delay between retries of a failed request
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Client for navigating and updating the HAL resources of a pact broker
+pub struct HALClient {
+    /// Base URL of the pact broker
+    pub url: String,
+    /// The last resource that was fetched
+    pub path_info: Option<serde_json::Value>,
+    /// Credentials to send with each request
+    pub auth: Option<HttpAuth>,
+    /// Number of times a request is retried if the connection fails or the pact broker returns a
+    /// server error (5xx) response. Only GET and PUT requests are retried, as a POST may have been
+    /// processed by the pact broker before the failure.
+    pub retries: u32,
+    /// Delay before the first retry. The delay is doubled for each following retry, up to a
+    /// maximum of one minute.
+    pub retry_backoff: Duration,
+    /// Read and write timeout for requests to the pact broker
+    pub timeout: Option<Duration>
+}
+
+impl HALClient {
+
+    /// Creates a client with no URL
+    pub fn default() -> HALClient {
+        HALClient::with_url(&s!(""), None)
+    }
+
+    /// Creates a client for the pact broker at the given URL, with optional credentials. Failed
+    /// requests are retried twice, and requests time out after 30 seconds.
+    pub fn with_url(url: &String, auth: Option<HttpAuth>) -> HALClient {
+        HALClient {
+            url: url.clone(),
+            path_info: None,
+            auth: auth,
+            retries: 2,
+            retry_backoff: Duration::from_millis(250),
+            timeout: Some(Duration::from_secs(30))
+        }
+    }
+
+    /// Sets the number of times failed GET and PUT requests are retried, and the delay before the
+    /// first retry
+    pub fn with_retries(mut self, retries: u32, retry_backoff: Duration) -> HALClient {
+        self.retries = retries;
+        self.retry_backoff = retry_backoff;
+        self
+    }
+
+    /// Sets the timeout for requests to the pact broker
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> HALClient {
+        self.timeout = timeout;
+        self
+    }
+
+    fn http_client(&self) -> Client {
+        let mut client = Client::new();
+        client.set_read_timeout(self.timeout);
+        client.set_write_timeout(self.timeout);
+        client
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        2u32.checked_pow(attempt.saturating_sub(1))
+            .and_then(|factor| self.retry_backoff.checked_mul(factor))
+            .map(|delay| ::std::cmp::min(delay, MAX_RETRY_BACKOFF))
+            .unwrap_or(MAX_RETRY_BACKOFF)
+    }
+
+    fn request_headers(&self) -> Headers {
+        let mut headers = Headers::new();
+        headers.set(hal_accept_header());
+        match self.auth {
+            Some(HttpAuth::User(ref username, ref password)) => headers.set(Authorization(Basic {
+                username: username.clone(),
+                password: password.clone()
+            })),
+            Some(HttpAuth::Token(ref token)) => headers.set(Authorization(Bearer { token: token.clone() })),
+            None => ()
+        }
+        headers
+    }
+
+    /// Fetches the root resource (if not already fetched), and then the resource for the given link,
+    /// which becomes the current resource
+    pub fn navigate(&mut self, link: &str, template_values: &HashMap<String, String>) -> Result<serde_json::Value, PactBrokerError> {
+        if self.path_info.is_none() {
+            self.path_info = Some(try!(self.fetch("/")));
+        }
+        self.path_info = Some(try!(self.fetch_link(link, template_values)));
+        Ok(self.path_info.clone().unwrap())
+    }
+
+    /// Finds the link with the given name in the current resource
+    pub fn find_link(&self, link: &str) -> Result<Link, PactBrokerError> {
+        match self.path_info {
+            None => Err(PactBrokerError::LinkError(format!("No previous resource has been fetched from the pact broker. URL: '{}', LINK: '{}'",
+                self.url, link))),
+            Some(ref json) => match json.get("_links") {
+                Some(json) => match json.get(link) {
+                    Some(link_data) => link_data.as_object()
+                        .map(|link_data| Link::from_json(&s!(link), &link_data))
+                        .ok_or(PactBrokerError::LinkError(format!("Link is malformed, expcted an object but got {}. URL: '{}', LINK: '{}'",
+                            link_data, self.url, link))),
+                    None => Err(PactBrokerError::LinkError(format!("Link '{}' was not found in the response, only the following links where found: {:?}. URL: '{}', LINK: '{}'",
+                        link, json.as_object().unwrap_or(&json!({}).as_object().unwrap()).keys().join(", "), self.url, link)))
+                },
+                None => Err(PactBrokerError::LinkError(format!("Expected a HAL+JSON response from the pact broker, but got a response with no '_links'. URL: '{}', LINK: '{}'",
+                    self.url, link)))
+            }
+        }
+    }
+
+    /// Fetches the resource for the link with the given name from the current resource
+    pub fn fetch_link(&self, link: &str, template_values: &HashMap<String, String>) -> Result<serde_json::Value, PactBrokerError> {
+        let link_data = try!(self.find_link(link));
+        self.fetch_url(&link_data, template_values)
+    }
+
+    /// Returns the path of the link URL, with any template values replaced
+    pub fn resolve_link_path(&self, link: &Link, template_values: &HashMap<String, String>) -> Result<String, PactBrokerError> {
+        let link_url = try!(if link.templated {
+            debug!("Link URL is templated");
+            self.parse_link_url(&link, template_values)
+        } else {
+            link.href.clone().ok_or(
+                PactBrokerError::LinkError(format!("Link is malformed, there is no href. URL: '{}', LINK: '{}'",
+                    self.url, link.name)))
+        });
+        let base = try!(Url::parse(&self.url).map_err(|err| PactBrokerError::UrlError(format!("{}", err.description()))));
+        let url = try!(base.join(&link_url).map_err(|err| PactBrokerError::UrlError(format!("{}", err.description()))));
+        match url.query() {
+            Some(query) => Ok(format!("{}?{}", url.path(), query)),
+            None => Ok(s!(url.path()))
+        }
+    }
+
+    /// Fetches the resource for the given link
+    pub fn fetch_url(&self, link: &Link, template_values: &HashMap<String, String>) -> Result<serde_json::Value, PactBrokerError> {
+        let path = try!(self.resolve_link_path(link, template_values));
+        self.fetch(&path)
+    }
+
+    /// Fetches the resource at the given path
+    pub fn fetch(&self, path: &str) -> Result<serde_json::Value, PactBrokerError> {
+        debug!("Fetching path '{}' from pact broker", path);
+        let url = join_paths(&self.url.clone(), s!(path));
+        self.send_with_retries(path, true, |client| client.get(&url)
+            .headers(self.request_headers())
+            .send())
+    }
+
+    /// Posts the JSON body to the URL of the given link, with any template values replaced
+    pub fn post_json(&self, link: &Link, template_values: &HashMap<String, String>, body: &String) -> Result<serde_json::Value, PactBrokerError> {
+        self.send_json(Method::Post, link, template_values, body)
+    }
+
+    /// Puts the JSON body to the URL of the given link, with any template values replaced
+    pub fn put_json(&self, link: &Link, template_values: &HashMap<String, String>, body: &String) -> Result<serde_json::Value, PactBrokerError> {
+        self.send_json(Method::Put, link, template_values, body)
+    }
+
+    fn send_json(&self, method: Method, link: &Link, template_values: &HashMap<String, String>, body: &String) -> Result<serde_json::Value, PactBrokerError> {
+        let path = try!(self.resolve_link_path(link, template_values));
+        debug!("Sending JSON to path '{}' on pact broker with method {}", path, method);
+        let url = join_paths(&self.url.clone(), path.clone());
+        let idempotent = method != Method::Post;
+        self.send_with_retries(&path, idempotent, |client| client.request(method.clone(), &url)
+            .headers(self.request_headers())
+            .header(ContentType(Mime(TopLevel::Application, SubLevel::Json, vec![])))
+            .body(body.as_str())
+            .send())
+    }
+
+    fn send_with_retries<F>(&self, path: &str, idempotent: bool, send: F) -> Result<serde_json::Value, PactBrokerError>
+        where F: Fn(&Client) -> Result<Response, hyper::Error> {
+        let client = self.http_client();
+        let retries = if idempotent { self.retries } else { 0 };
+        let mut attempt = 0;
+        loop {
+            let res = send(&client);
+            let retry = match res {
+                Ok(ref response) => response.status.is_server_error(),
+                Err(_) => true
+            };
+            if retry && attempt < retries {
+                attempt += 1;
+                let delay = self.backoff(attempt);
+                warn!("Request to pact broker path '{}' failed, retrying in {}ms (retry {} of {})", path,
+                    delay.as_secs() * 1000 + (delay.subsec_nanos() / 1_000_000) as u64, attempt, retries);
+                thread::sleep(delay);
+            } else {
+                return self.parse_broker_response(path, res).map_err(|err| if attempt > 0 {
+                    retries_exhausted(err, attempt + 1)
+                } else {
+                    err
+                });
+            }
+        }
+    }
+
+    fn parse_broker_response(&self, path: &str, res: Result<Response, hyper::Error>) -> Result<serde_json::Value, PactBrokerError> {
+        match res {
+            Ok(mut response) => {
+                if response.status.is_success() {
+                    if json_content_type(&response) {
+                        match extract_body(&mut response) {
+                            OptionalBody::Present(body) => serde_json::from_slice(&body)
+                                    .map_err(|err| PactBrokerError::ContentError(format!("Did not get a valid HAL response body from pact broker path '{}' - {}: {}. URL: '{}'",
+                                                                                         path, err.description(), err, self.url))),
+                            _ => Err(PactBrokerError::ContentError(format!("Did not get a valid HAL response body from pact broker path '{}'. URL: '{}'",
+                                                                          path, self.url)))
+
+                        }
+                    } else {
+                        Err(PactBrokerError::ContentError(format!("Did not get a HAL response from pact broker path '{}', content type is '{}'. URL: '{}'",
+                            path, content_type(&response), self.url)))
+                    }
+                } else {
+                    if response.status == StatusCode::NotFound {
+                        Err(PactBrokerError::NotFound(format!("Request to pact broker path '{}' failed: {}. URL: '{}'", path,
+                            response.status, self.url)))
+                    } else if response.status == StatusCode::Unauthorized || response.status == StatusCode::Forbidden {
+                        Err(PactBrokerError::AuthError(format!("Authentication failed for pact broker path '{}': {}. URL: '{}'", path,
+                            response.status, self.url)))
+                    } else {
+                        Err(PactBrokerError::IoError(format!("Request to pact broker path '{}' failed: {}. URL: '{}'", path,
+                            response.status, self.url)))
+                    }
+                }
+            },
+            Err(hyper::Error::Io(ref err)) if err.kind() == io::ErrorKind::TimedOut || err.kind() == io::ErrorKind::WouldBlock =>
+                Err(PactBrokerError::Timeout(format!("Request to pact broker path '{}' timed out. URL: '{}'", path, self.url))),
+            Err(err) => Err(PactBrokerError::IoError(format!("Failed to access pact broker path '{}' - {:?}. URL: '{}'",
+                path, err.description(), self.url)))
+        }
+    }
+
+    /// Replaces the `{name}` placeholders in the link URL with the template values
+    pub fn parse_link_url(&self, link: &Link, values: &HashMap<String, String>) -> Result<String, PactBrokerError> {
+        match link.href {
+            Some(ref href) => {
+                debug!("templated URL = {}", href);
+                let re = Regex::new(r"\{(\w+)\}").unwrap();
+                let final_url = re.replace_all(href, |caps: &Captures| {
+                    let lookup = caps.at(1).unwrap();
+                    debug!("Looking up value for key '{}'", lookup);
+                    match values.get(lookup) {
+                        Some(val) => val.clone(),
+                        None => {
+                            warn!("No value was found for key '{}', mapped values are {:?}",
+                                lookup, values);
+                            format!("{{{}}}", lookup)
+                        }
+                    }
+                });
+                debug!("final URL = {}", final_url);
+                Ok(final_url)
+            },
+            None => Err(PactBrokerError::LinkError(format!("Expected a HAL+JSON response from the pact broker, but got a link with no HREF. URL: '{}', LINK: '{}'",
+                self.url, link.name)))
+        }
+    }
+
+    /// Returns all the links with the given name from the current resource, and from any following
+    /// pages of the resource. Each page is fetched using its `next` link, and the last page fetched
+    /// becomes the current resource.
+    pub fn iter_links_across_pages(&mut self, link: String) -> Result<Vec<Link>, PactBrokerError> {
+        let mut links = try!(self.iter_links(link.clone()));
+        let mut pages = vec![];
+        loop {
+            let next = match self.find_link("next") {
+                Ok(next) => next,
+                Err(_) => break
+            };
+            match next.href {
+                Some(ref href) if !pages.contains(href) => pages.push(href.clone()),
+                _ => break
+            }
+            debug!("Fetching the next page of '{}' links - {:?}", link, next);
+            self.path_info = Some(try!(self.fetch_url(&next, &hashmap!{})));
+            match self.iter_links(link.clone()) {
+                Ok(page_links) => links.extend(page_links),
+                Err(_) => break
+            }
+        }
+        Ok(links)
+    }
+
+    /// Returns all the links with the given name from the current resource
+    pub fn iter_links(&self, link: String) -> Result<Vec<Link>, PactBrokerError> {
+        match self.path_info {
+            None => Err(PactBrokerError::LinkError(format!("No previous resource has been fetched from the pact broker. URL: '{}', LINK: '{}'",
+                self.url, link))),
+            Some(ref json) => match json.get("_links") {
+                Some(json) => match json.get(&link) {
+                    Some(link_data) => link_data.as_array()
+                        .map(|link_data| link_data.iter().map(|link_json| match link_json {
+                            &serde_json::Value::Object(ref data) => Link::from_json(&link, data),
+                            &serde_json::Value::String(ref s) => Link { name: link.clone(), href: Some(s.clone()), templated: false },
+                            _ => Link { name: link.clone(), href: Some(link_json.to_string()), templated: false }
+                        }).collect())
+                        .ok_or(PactBrokerError::LinkError(format!("Link is malformed, expcted an object but got {}. URL: '{}', LINK: '{}'",
+                            link_data, self.url, link))),
+                    None => Err(PactBrokerError::LinkError(format!("Link '{}' was not found in the response, only the following links where found: {:?}. URL: '{}', LINK: '{}'",
+                        link, json.as_object().unwrap_or(&json!({}).as_object().unwrap()).keys().join(", "), self.url, link)))
+                },
+                None => Err(PactBrokerError::LinkError(format!("Expected a HAL+JSON response from the pact broker, but got a response with no '_links'. URL: '{}', LINK: '{}'",
+                    self.url, link)))
+            }
+        }
+    }
+}
+
+fn retries_exhausted(err: PactBrokerError, attempts: u32) -> PactBrokerError {
+    let suffix = format!(" (failed after {} attempts)", attempts);
+    match err {
+        PactBrokerError::IoError(s) => PactBrokerError::IoError(s + &suffix),
+        PactBrokerError::Timeout(s) => PactBrokerError::Timeout(s + &suffix),
+        _ => err
+    }
+}
+
+/// Publishes the pact to the pact broker for the given consumer version, using the `pb:publish-pact`
+/// link. The consumer version is then tagged with each of the tags.
+pub fn publish_pact(broker_url: &String, auth: &Option<HttpAuth>, pact: &Pact, consumer_version: &String,
+    tags: &Vec<String>) -> Result<serde_json::Value, PactBrokerError> {
+    let mut client = HALClient::with_url(broker_url, auth.clone());
+    client.path_info = Some(try!(client.fetch("/")));
+    let publish_link = try!(client.find_link("pb:publish-pact"));
+    let result = try!(client.put_json(&publish_link, &hashmap!{
+        s!("provider") => pact.provider.name.clone(),
+        s!("consumer") => pact.consumer.name.clone(),
+        s!("consumerApplicationVersion") => consumer_version.clone()
+    }, &pact.to_json().to_string()));
+    debug!("Published pact between {} and {} for version {}", pact.consumer.name, pact.provider.name, consumer_version);
+
+    if !tags.is_empty() {
+        let tag_link = try!(client.find_link("pb:pacticipant-version-tag"));
+        for tag in tags {
+            try!(client.put_json(&tag_link, &hashmap!{
+                s!("pacticipant") => pact.consumer.name.clone(),
+                s!("version") => consumer_version.clone(),
+                s!("tag") => tag.clone()
+            }, &s!("{}")));
+            debug!("Tagged version {} of {} with '{}'", consumer_version, pact.consumer.name, tag);
+        }
+    }
+
+    Ok(result)
+}
+
+/// What to check a pacticipant version against, when asking if it can be deployed
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeploymentTarget {
+    /// The latest versions of all the integrated pacticipants
+    Latest,
+    /// The latest versions of the integrated pacticipants with the tag
+    Tag(String),
+    /// The versions of the integrated pacticipants deployed to the environment
+    Environment(String)
+}
+
+/// A row of the pact broker matrix, for a consumer version and provider version
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatrixRow {
+    /// Name of the consumer
+    pub consumer: String,
+    /// Version of the consumer
+    pub consumer_version: Option<String>,
+    /// Name of the provider
+    pub provider: String,
+    /// Version of the provider, if the pact has been verified
+    pub provider_version: Option<String>,
+    /// If the verification passed, if the pact has been verified
+    pub success: Option<bool>
+}
+
+impl MatrixRow {
+    /// Creates a matrix row from the JSON returned by the pact broker
+    pub fn from_json(json: &serde_json::Value) -> MatrixRow {
+        let name = |key: &str| json.get(key).and_then(|p| p.get("name")).map(as_string).unwrap_or(s!(""));
+        let version = |key: &str| json.get(key).and_then(|p| p.get("version")).and_then(|v| v.get("number"))
+            .map(as_string);
+        MatrixRow {
+            consumer: name("consumer"),
+            consumer_version: version("consumer"),
+            provider: name("provider"),
+            provider_version: version("provider"),
+            success: json.get("verificationResult").and_then(|v| v.get("success")).and_then(|s| s.as_bool())
+        }
+    }
+}
+
+/// Result of asking the pact broker if a pacticipant version can be deployed
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanIDeployResult {
+    /// If the version is compatible with all its integrations
+    pub deployable: bool,
+    /// The reason given by the pact broker
+    pub reason: String,
+    /// The verification matrix for the version
+    pub matrix: Vec<MatrixRow>
+}
+
+impl CanIDeployResult {
+    /// Creates the result from the JSON returned by the pact broker matrix resource
+    pub fn from_json(json: &serde_json::Value) -> Result<CanIDeployResult, PactBrokerError> {
+        match json.get("summary") {
+            Some(summary) => Ok(CanIDeployResult {
+                deployable: summary.get("deployable").and_then(|d| d.as_bool()).unwrap_or(false),
+                reason: summary.get("reason").map(as_string).unwrap_or(s!("")),
+                matrix: match json.get("matrix") {
+                    Some(&serde_json::Value::Array(ref rows)) => rows.iter().map(MatrixRow::from_json).collect(),
+                    _ => vec![]
+                }
+            }),
+            None => Err(PactBrokerError::ContentError(format!("Expected the pact broker matrix to have a summary, but got {}",
+                json)))
+        }
+    }
+}
+
+fn matrix_query(pacticipant: &String, version: &String, target: &DeploymentTarget) -> String {
+    let mut query = hashmap!{
+        s!("q[][pacticipant]") => vec![pacticipant.clone()],
+        s!("q[][version]") => vec![version.clone()],
+        s!("latestby") => vec![s!("cvp")]
+    };
+    match *target {
+        DeploymentTarget::Latest => { query.insert(s!("latest"), vec![s!("true")]); },
+        DeploymentTarget::Tag(ref tag) => {
+            query.insert(s!("latest"), vec![s!("true")]);
+            query.insert(s!("tag"), vec![tag.clone()]);
+        },
+        DeploymentTarget::Environment(ref environment) => {
+            query.insert(s!("environment"), vec![environment.clone()]);
+        }
+    }
+    build_query_string(query)
+}
+
+/// Asks the pact broker matrix if the version of the pacticipant is compatible with all of its
+/// integrations, for the given deployment target.
+pub fn can_i_deploy(broker_url: &String, auth: &Option<HttpAuth>, pacticipant: &String, version: &String,
+    target: &DeploymentTarget) -> Result<CanIDeployResult, PactBrokerError> {
+    let client = HALClient::with_url(broker_url, auth.clone());
+    let json = try!(client.fetch(&format!("/matrix?{}", matrix_query(pacticipant, version, target))));
+    debug!("Pact broker matrix for {} version {} = {}", pacticipant, version, json);
+    CanIDeployResult::from_json(&json)
+}
+
+#[cfg(test)]
+mod tests {
+    use expectest::prelude::*;
+    use super::*;
+    use super::{content_type, json_content_type, matrix_query};
+    use hyper::Url;
+    use hyper::client::response::Response;
+    use std::io::{self, Write, Read};
+    use hyper::http::{
+        RawStatus,
+        HttpMessage,
+        RequestHead,
+        ResponseHead,
+    };
+    use hyper::error::Error;
+    use hyper::version::HttpVersion;
+    use std::time::Duration;
+    use hyper::header::{Headers, ContentType, Authorization, Basic, Bearer};
+    use std::borrow::Cow;
+    use hyper::mime::{Mime, TopLevel, SubLevel, Attr, Value};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    #[test]
+    fn fetch_returns_an_error_if_there_is_no_pact_broker() {
+        let client = HALClient{ url: s!("http://idont.exist:6666"), .. HALClient::default() };
+        expect!(client.fetch(&s!("/"))).to(be_err());
+    }
+
+    #[derive(Debug, Clone)]
+    struct MockHttpMessage {
+        pub body: Option<String>,
+        pub headers: Headers,
+        pub status: RawStatus
+    }
+
+    impl HttpMessage for MockHttpMessage {
+
+        fn set_outgoing(&mut self, _head: RequestHead) -> Result<RequestHead, Error> {
+            Err(Error::Io(io::Error::new(io::ErrorKind::Other, "Not supported with MockHttpMessage")))
+        }
+
+        fn get_incoming(&mut self) -> Result<ResponseHead, Error> {
+            Ok(ResponseHead {
+                headers: self.headers.clone(),
+                raw_status: self.status.clone(),
+                version: HttpVersion::Http11,
+            })
+        }
+
+        fn has_body(&self) -> bool {
+            self.body.is_some()
+        }
+
+        fn set_read_timeout(&self, _dur: Option<Duration>) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn set_write_timeout(&self, _dur: Option<Duration>) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn close_connection(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    impl Write for MockHttpMessage {
+
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::Other, "Not supported with MockHttpMessage"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::Error::new(io::ErrorKind::Other, "Not supported with MockHttpMessage"))
+        }
+
+    }
+
+    impl Read for MockHttpMessage {
+
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::Other, "Not supported with MockHttpMessage"))
+        }
+
+    }
+
+    #[test]
+    fn content_type_test() {
+        let mut message = MockHttpMessage {
+            body: None,
+            status: RawStatus(200, Cow::Owned(s!("OK"))),
+            headers: Headers::new()
+        };
+        let url = Url::parse("http://localhost").unwrap();
+
+        let response = Response::with_message(url.clone(), Box::new(message.clone())).unwrap();
+        expect!(content_type(&response)).to(be_equal_to(s!("text/plain")));
+
+        message.headers.set::<ContentType>(
+            ContentType(Mime(TopLevel::Application, SubLevel::Ext(s!("hal+json")),
+                vec![(Attr::Charset, Value::Utf8)])));
+        let response = Response::with_message(url.clone(), Box::new(message.clone())).unwrap();
+        expect!(content_type(&response)).to(be_equal_to(s!("application/hal+json; charset=utf-8")));
+    }
+
+    #[test]
+    fn json_content_type_test() {
+        let mut message = MockHttpMessage {
+            body: None,
+            status: RawStatus(200, Cow::Owned(s!("OK"))),
+            headers: Headers::new()
+        };
+        let url = Url::parse("http://localhost").unwrap();
+
+        let response = Response::with_message(url.clone(), Box::new(message.clone())).unwrap();
+        expect!(json_content_type(&response)).to(be_false());
+
+        message.headers.set::<ContentType>(
+            ContentType(Mime(TopLevel::Application, SubLevel::Json, vec![])));
+        let response = Response::with_message(url.clone(), Box::new(message.clone())).unwrap();
+        expect!(json_content_type(&response)).to(be_true());
+
+        message.headers.set::<ContentType>(
+            ContentType(Mime(TopLevel::Application, SubLevel::Ext(s!("hal+json")),
+                vec![(Attr::Charset, Value::Utf8)])));
+        let response = Response::with_message(url.clone(), Box::new(message.clone())).unwrap();
+        expect!(json_content_type(&response)).to(be_true());
+    }
+
+    #[test]
+    fn parse_link_url_returns_error_if_there_is_no_href() {
+        let client = HALClient::default();
+        let link = Link { name: s!("link"), href: None, templated: false };
+        expect!(client.parse_link_url(&link, &hashmap!{})).to(be_err().value(
+            "Expected a HAL+JSON response from the pact broker, but got a link with no HREF. URL: '', LINK: 'link'"));
+    }
+
+    #[test]
+    fn parse_link_url_replaces_all_tokens_in_href() {
+        let client = HALClient::default();
+        let values = hashmap!{ s!("valA") => s!("A"), s!("valB") => s!("B") };
+
+        let link = Link { name: s!("link"), href: Some(s!("http://localhost")), templated: false };
+        expect!(client.parse_link_url(&link, &values)).to(be_ok().value("http://localhost"));
+
+        let link = Link { name: s!("link"), href: Some(s!("http://{valA}/{valB}")), templated: false };
+        expect!(client.parse_link_url(&link, &values)).to(be_ok().value("http://A/B"));
+
+        let link = Link { name: s!("link"), href: Some(s!("http://{valA}/{valC}")), templated: false };
+        expect!(client.parse_link_url(&link, &values)).to(be_ok().value("http://A/{valC}"));
+    }
+
+    #[test]
+    fn fetch_link_returns_an_error_if_a_previous_resource_has_not_been_fetched() {
+        let client = HALClient{ url: s!("http://localhost"), .. HALClient::default() };
+        let result = client.fetch_link(&s!("anything_will_do"), &hashmap!{});
+        expect!(result).to(be_err().value(s!("No previous resource has been fetched from the pact broker. URL: 'http://localhost', LINK: 'anything_will_do'")));
+    }
+
+    #[test]
+    fn request_headers_includes_the_credentials() {
+        let client = HALClient::with_url(&s!("http://localhost"), None);
+        expect!(client.request_headers().get::<Authorization<Basic>>()).to(be_none());
+
+        let client = HALClient::with_url(&s!("http://localhost"), Some(HttpAuth::User(s!("user"), Some(s!("pass")))));
+        expect!(client.request_headers().get::<Authorization<Basic>>()).to(be_some().value(
+            &Authorization(Basic { username: s!("user"), password: Some(s!("pass")) })));
+
+        let client = HALClient::with_url(&s!("http://localhost"), Some(HttpAuth::Token(s!("1234"))));
+        expect!(client.request_headers().get::<Authorization<Bearer>>()).to(be_some().value(
+            &Authorization(Bearer { token: s!("1234") })));
+    }
+
+    #[test]
+    fn http_auth_does_not_display_the_secrets() {
+        expect!(format!("{:?}", HttpAuth::User(s!("user"), Some(s!("pass"))))).to(be_equal_to(s!("User(user, ****)")));
+        expect!(format!("{:?}", HttpAuth::Token(s!("1234")))).to(be_equal_to(s!("Token(****)")));
+    }
+
+    #[test]
+    fn matrix_query_includes_the_deployment_target() {
+        expect!(matrix_query(&s!("Foo Service"), &s!("1.0.0"), &DeploymentTarget::Latest)).to(be_equal_to(
+            s!("latest=true&latestby=cvp&q[][pacticipant]=Foo+Service&q[][version]=1%2e0%2e0")));
+        expect!(matrix_query(&s!("Foo"), &s!("1"), &DeploymentTarget::Tag(s!("prod")))).to(be_equal_to(
+            s!("latest=true&latestby=cvp&q[][pacticipant]=Foo&q[][version]=1&tag=prod")));
+        expect!(matrix_query(&s!("Foo"), &s!("1"), &DeploymentTarget::Environment(s!("production")))).to(be_equal_to(
+            s!("environment=production&latestby=cvp&q[][pacticipant]=Foo&q[][version]=1")));
+    }
+
+    #[test]
+    fn can_i_deploy_result_is_parsed_from_the_matrix() {
+        let json = json!({
+            "summary": { "deployable": false, "reason": "Missing one or more verification results" },
+            "matrix": [
+                {
+                    "consumer": { "name": "Foo", "version": { "number": "1.0.0" } },
+                    "provider": { "name": "Bar", "version": { "number": "2.0.0" } },
+                    "verificationResult": { "success": true }
+                },
+                {
+                    "consumer": { "name": "Foo", "version": { "number": "1.0.0" } },
+                    "provider": { "name": "Baz", "version": null },
+                    "verificationResult": null
+                }
+            ]
+        });
+        expect!(CanIDeployResult::from_json(&json)).to(be_ok().value(CanIDeployResult {
+            deployable: false,
+            reason: s!("Missing one or more verification results"),
+            matrix: vec![
+                MatrixRow { consumer: s!("Foo"), consumer_version: Some(s!("1.0.0")), provider: s!("Bar"),
+                    provider_version: Some(s!("2.0.0")), success: Some(true) },
+                MatrixRow { consumer: s!("Foo"), consumer_version: Some(s!("1.0.0")), provider: s!("Baz"),
+                    provider_version: None, success: None }
+            ]
+        }));
+        expect!(CanIDeployResult::from_json(&json!({ "matrix": [] }))).to(be_err());
+    }
+
+    #[test]
+    fn retry_backoff_doubles_for_each_retry() {
+        let client = HALClient::default().with_retries(3, Duration::from_millis(100));
+        expect!(client.backoff(1)).to(be_equal_to(Duration::from_millis(100)));
+        expect!(client.backoff(2)).to(be_equal_to(Duration::from_millis(200)));
+        expect!(client.backoff(3)).to(be_equal_to(Duration::from_millis(400)));
+    }
+
+    #[test]
+    fn retry_backoff_is_capped_at_the_maximum_delay() {
+        let client = HALClient::default().with_retries(100, Duration::from_millis(100));
+        expect!(client.backoff(11)).to(be_equal_to(Duration::from_secs(60)));
+        expect!(client.backoff(33)).to(be_equal_to(Duration::from_secs(60)));
+        expect!(client.backoff(100)).to(be_equal_to(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn fetch_returns_the_error_after_the_retries_are_exhausted() {
+        let client = HALClient{ url: s!("http://idont.exist:6666"), .. HALClient::default() }
+            .with_retries(1, Duration::from_millis(1));
+        let result = client.fetch(&s!("/"));
+        expect!(result.clone()).to(be_err());
+        match result {
+            Err(PactBrokerError::IoError(ref message)) => {
+                expect!(message.ends_with("(failed after 2 attempts)")).to(be_true());
+            },
+            _ => panic!("Expected an IoError, but got {:?}", result)
+        }
+    }
+
+    fn read_request(stream: &mut TcpStream) {
+        let mut request = vec![];
+        let mut buffer = [0; 1024];
+        loop {
+            match stream.read(&mut buffer) {
+                Ok(0) | Err(_) => return,
+                Ok(size) => request.extend_from_slice(&buffer[..size])
+            }
+            let text = String::from_utf8_lossy(&request).into_owned();
+            match text.find("\r\n\r\n") {
+                Some(end) => {
+                    let length = text[..end].lines()
+                        .find(|line| line.to_lowercase().starts_with("content-length:"))
+                        .and_then(|line| line[15..].trim().parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length {
+                        return;
+                    }
+                },
+                None => ()
+            }
+        }
+    }
+
+    /// Starts a server that responds to every request with a 503, and returns its URL and the
+    /// number of requests it has received
+    fn unavailable_server() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+        let count = Arc::new(AtomicUsize::new(0));
+        let requests = count.clone();
+        thread::spawn(move || for stream in listener.incoming() {
+            match stream {
+                Ok(mut stream) => {
+                    read_request(&mut stream);
+                    requests.fetch_add(1, Ordering::SeqCst);
+                    let _ = stream.write_all(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+                },
+                Err(_) => break
+            }
+        });
+        (url, count)
+    }
+
+    #[test]
+    fn get_requests_are_retried_on_server_errors() {
+        let (url, requests) = unavailable_server();
+        let client = HALClient::with_url(&url, None).with_retries(2, Duration::from_millis(1));
+        expect!(client.fetch("/")).to(be_err());
+        expect!(requests.load(Ordering::SeqCst)).to(be_equal_to(3));
+    }
+
+    #[test]
+    fn post_requests_are_not_retried_on_server_errors() {
+        let (url, requests) = unavailable_server();
+        let client = HALClient::with_url(&url, None).with_retries(2, Duration::from_millis(1));
+        let link = Link { name: s!("pb:publish-verification-results"), href: Some(format!("{}/results", url)), templated: false };
+        expect!(client.post_json(&link, &hashmap!{}, &s!("{}"))).to(be_err());
+        expect!(requests.load(Ordering::SeqCst)).to(be_equal_to(1));
+    }
+}
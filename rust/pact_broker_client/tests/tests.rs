@@ -0,0 +1,326 @@
+#[macro_use] extern crate pact_matching;
+#[macro_use(expect)] extern crate expectest;
+#[macro_use] extern crate maplit;
+extern crate pact_broker_client;
+extern crate pact_consumer;
+extern crate env_logger;
+extern crate serde_json;
+
+use pact_matching::models::{Pact, OptionalBody, Consumer, Provider};
+use pact_broker_client::{HALClient, publish_pact};
+use pact_consumer::*;
+use expectest::prelude::*;
+use env_logger::*;
+use std::time::Duration;
+
+#[test]
+fn fetch_returns_an_error_if_it_does_not_get_a_success_response() {
+    let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
+        .has_pact_with(s!("PactBroker"))
+        .given(s!("the pact broker has a valid pact"))
+        .upon_receiving(s!("a request to a non-existant path"))
+            .path(s!("/hello"))
+        .will_respond_with()
+            .status(404)
+        .build();
+
+    let result = pact_runner.run(&|broker_url| {
+        let client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
+        let result = client.fetch(&s!("/hello"));
+        expect!(result).to(be_err().value(format!("Request to pact broker path \'/hello\' failed: 404 Not Found. URL: '{}'",
+            broker_url)));
+        Ok(())
+    });
+    expect!(result).to(be_equal_to(VerificationResult::PactVerified));
+}
+
+#[test]
+fn fetch_returns_an_error_if_it_does_not_get_a_hal_response() {
+    let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
+        .has_pact_with(s!("PactBrokerStub"))
+        .upon_receiving(s!("a request to a non-json resource"))
+            .path(s!("/nonjson"))
+        .will_respond_with()
+            .status(200)
+            .headers(hashmap!{ s!("Content-Type") => s!("text/html") })
+            .body(OptionalBody::Present(s!("<html></html>").into()))
+        .build();
+
+    let result = pact_runner.run(&|broker_url| {
+        let client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
+        let result = client.fetch(&s!("/nonjson"));
+        expect!(result).to(be_err().value(format!("Did not get a HAL response from pact broker path \'/nonjson\', content type is 'text/html'. URL: '{}'",
+            broker_url)));
+        Ok(())
+    });
+    expect!(result).to(be_equal_to(VerificationResult::PactVerified));
+}
+
+#[test]
+fn fetch_returns_an_error_if_it_does_not_get_a_valid_hal_response() {
+    let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
+        .has_pact_with(s!("PactBrokerStub"))
+        .upon_receiving(s!("a request to a non-hal resource"))
+            .path(s!("/nonhal"))
+        .will_respond_with()
+            .status(200)
+            .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
+        .upon_receiving(s!("a request to a non-hal resource 2"))
+            .path(s!("/nonhal2"))
+        .will_respond_with()
+            .status(200)
+            .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
+            .body(OptionalBody::Present(s!("<html>This is not JSON</html>").into()))
+        .build();
+
+    let result = pact_runner.run(&|broker_url| {
+        let client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
+        let result = client.fetch(&s!("/nonhal"));
+        expect!(result).to(be_err().value(format!("Did not get a valid HAL response body from pact broker path \'/nonhal\'. URL: '{}'",
+            broker_url)));
+        let result = client.fetch(&s!("/nonhal2"));
+        expect!(result).to(be_err().value(format!("Did not get a valid HAL response body from pact broker path \'/nonhal2\' - JSON error: expected value at line 1 column 1. URL: '{}'",
+            broker_url)));
+        Ok(())
+    });
+    expect!(result).to(be_equal_to(VerificationResult::PactVerified));
+}
+
+#[test]
+fn fetch_link_returns_an_error_if_the_previous_resource_was_not_hal() {
+    init().unwrap_or(());
+    let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
+        .has_pact_with(s!("PactBrokerStub"))
+            .upon_receiving(s!("a request to a non-hal json resource"))
+            .path(s!("/"))
+        .will_respond_with()
+            .status(200)
+            .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
+            .body(OptionalBody::Present(s!("{}").into()))
+        .build();
+
+    let result = pact_runner.run(&|broker_url| {
+        let mut client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
+        let result = client.fetch(&s!("/"));
+        expect!(result.clone()).to(be_ok());
+        client.path_info = result.ok();
+        let result = client.fetch_link(&s!("hal2"), &hashmap!{});
+        expect!(result).to(be_err().value(format!("Expected a HAL+JSON response from the pact broker, but got a response with no '_links'. URL: '{}', LINK: 'hal2'",
+            broker_url)));
+        Ok(())
+    });
+    expect!(result).to(be_equal_to(VerificationResult::PactVerified));
+}
+
+#[test]
+fn fetch_link_returns_an_error_if_the_previous_resource_links_are_not_correctly_formed() {
+    init().unwrap_or(());
+    let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
+        .has_pact_with(s!("PactBrokerStub"))
+            .upon_receiving(s!("a request to a hal resource with invalid links"))
+            .path(s!("/"))
+        .will_respond_with()
+            .status(200)
+            .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
+            .body(OptionalBody::Present(s!("{\"_links\":[{\"next\":{\"href\":\"abc\"}},{\"prev\":{\"href\":\"def\"}}]}").into()))
+        .build();
+
+    let result = pact_runner.run(&|broker_url| {
+        let mut client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
+        let result = client.fetch(&s!("/"));
+        expect!(result.clone()).to(be_ok());
+        client.path_info = result.ok();
+        let result = client.fetch_link(&s!("any"), &hashmap!{});
+        expect!(result).to(be_err().value(format!("Link 'any' was not found in the response, only the following links where found: \"\". URL: '{}', LINK: 'any'",
+            broker_url)));
+        Ok(())
+    });
+    expect!(result).to(be_equal_to(VerificationResult::PactVerified));
+}
+
+#[test]
+fn fetch_link_returns_an_error_if_the_previous_resource_does_not_have_the_link() {
+    let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
+        .has_pact_with(s!("PactBrokerStub"))
+            .upon_receiving(s!("a request to a hal resource"))
+            .path(s!("/"))
+        .will_respond_with()
+            .status(200)
+            .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
+            .body(OptionalBody::Present(s!("{\"_links\":{\"next\":{\"href\":\"/abc\"},\"prev\":{\"href\":\"/def\"}}}").into()))
+        .build();
+
+    let result = pact_runner.run(&|broker_url| {
+        let mut client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
+        let result = client.fetch(&s!("/"));
+        expect!(result.clone()).to(be_ok());
+        client.path_info = result.ok();
+        let result = client.fetch_link(&s!("any"), &hashmap!{});
+        expect!(result).to(be_err().value(format!("Link 'any' was not found in the response, only the following links where found: \"next, prev\". URL: '{}', LINK: 'any'",
+            broker_url)));
+        Ok(())
+    });
+    expect!(result).to(be_equal_to(VerificationResult::PactVerified));
+}
+
+#[test]
+fn fetch_link_returns_the_resource_for_the_link() {
+    let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
+        .has_pact_with(s!("PactBrokerStub"))
+        .upon_receiving(s!("a request to a hal resource"))
+            .path(s!("/"))
+        .will_respond_with()
+            .status(200)
+            .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
+            .body(OptionalBody::Present(s!("{\"_links\":{\"next\":{\"href\":\"/abc\"},\"prev\":{\"href\":\"/def\"}}}").into()))
+        .upon_receiving(s!("a request to next"))
+            .path(s!("/abc"))
+        .will_respond_with()
+            .status(200)
+            .headers(hashmap!{ s!("Content-Type") => s!("application/json") })
+            .body(OptionalBody::Present(s!("\"Yay! You found your way here\"").into()))
+        .build();
+
+    let result = pact_runner.run(&|broker_url| {
+        let mut client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
+        let result = client.fetch(&s!("/"));
+        expect!(result.clone()).to(be_ok());
+        client.path_info = result.ok();
+        let result = client.fetch_link(&s!("next"), &hashmap!{});
+        expect!(result).to(be_ok().value(serde_json::Value::String(s!("Yay! You found your way here"))));
+        Ok(())
+    });
+    expect!(result).to(be_equal_to(VerificationResult::PactVerified));
+}
+
+#[test]
+fn fetch_link_returns_handles_absolute_resource_links() {
+    init().unwrap_or(());
+    let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
+        .has_pact_with(s!("PactBrokerStub"))
+        .upon_receiving(s!("a request to a hal resource with absolute paths"))
+            .path(s!("/"))
+        .will_respond_with()
+            .status(200)
+            .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
+            .body(OptionalBody::Present(s!("{\"_links\":{\"next\":{\"href\":\"http://localhost/abc\"},\"prev\":{\"href\":\"http://localhost/def\"}}}").into()))
+        .upon_receiving(s!("a request to next"))
+            .path(s!("/abc"))
+        .will_respond_with()
+            .status(200)
+            .headers(hashmap!{ s!("Content-Type") => s!("application/json") })
+            .body(OptionalBody::Present(s!("\"Yay! You found your way here\"").into()))
+        .build();
+
+    let result = pact_runner.run(&|broker_url| {
+        let mut client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
+        let result = client.fetch(&s!("/"));
+        expect!(result.clone()).to(be_ok());
+        client.path_info = result.ok();
+        let result = client.fetch_link(&s!("next"), &hashmap!{});
+        expect!(result).to(be_ok().value(serde_json::Value::String(s!("Yay! You found your way here"))));
+        Ok(())
+    });
+    expect!(result).to(be_equal_to(VerificationResult::PactVerified));
+}
+
+#[test]
+fn fetch_link_returns_the_resource_for_the_templated_link() {
+    init().unwrap_or(());
+    let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
+        .has_pact_with(s!("PactBrokerStub"))
+        .upon_receiving(s!("a request to a templated hal resource"))
+            .path(s!("/"))
+        .will_respond_with()
+            .status(200)
+            .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
+            .body(OptionalBody::Present(s!("{\"_links\":{\"document\":{\"href\":\"/doc/{id}\",\"templated\":true}}}").into()))
+        .upon_receiving(s!("a request for a document"))
+            .path(s!("/doc/abc"))
+        .will_respond_with()
+            .status(200)
+            .headers(hashmap!{ s!("Content-Type") => s!("application/json") })
+            .body(OptionalBody::Present(s!("\"Yay! You found your way here\"").into()))
+        .build();
+
+    let result = pact_runner.run(&|broker_url| {
+        let mut client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
+        let result = client.fetch(&s!("/"));
+        expect!(result.clone()).to(be_ok());
+        client.path_info = result.ok();
+        let result = client.fetch_link(&s!("document"), &hashmap!{ s!("id") => s!("abc") });
+        expect!(result).to(be_ok().value(serde_json::Value::String(s!("Yay! You found your way here"))));
+        Ok(())
+    });
+    expect!(result).to(be_equal_to(VerificationResult::PactVerified));
+}
+
+#[test]
+fn publish_pact_puts_the_pact_and_tags_the_consumer_version() {
+    init().unwrap_or(());
+
+    let pact = Pact { consumer: Consumer { name: s!("Consumer") },
+        provider: Provider { name: s!("happy_provider") },
+        .. Pact::default() };
+    let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
+        .has_pact_with(s!("PactBroker"))
+        .upon_receiving(s!("a request to the pact broker root"))
+            .path(s!("/"))
+            .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
+        .will_respond_with()
+            .status(200)
+            .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
+            .body(OptionalBody::Present(s!(r#"
+                {
+                    "_links":{
+                        "pb:publish-pact":{"href":"http://localhost/pacts/provider/{provider}/consumer/{consumer}/version/{consumerApplicationVersion}","templated":true},
+                        "pb:pacticipant-version-tag":{"href":"http://localhost/pacticipants/{pacticipant}/versions/{version}/tags/{tag}","templated":true}
+                    }
+                }
+            "#).into()))
+        .upon_receiving(s!("a request to publish a pact"))
+            .method(s!("PUT"))
+            .path(s!("/pacts/provider/happy_provider/consumer/Consumer/version/1.0.0"))
+            .headers(hashmap!{ s!("Content-Type") => s!("application/json") })
+            .body(OptionalBody::Present(pact.to_json().to_string().into()))
+        .will_respond_with()
+            .status(201)
+            .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
+            .body(OptionalBody::Present(s!(r#"{"_links": {}}"#).into()))
+        .upon_receiving(s!("a request to tag a consumer version"))
+            .method(s!("PUT"))
+            .path(s!("/pacticipants/Consumer/versions/1.0.0/tags/prod"))
+        .will_respond_with()
+            .status(201)
+            .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
+            .body(OptionalBody::Present(s!(r#"{"name": "prod", "_links": {}}"#).into()))
+        .build();
+
+    let result = pact_runner.run(&|broker_url| {
+        let result = publish_pact(&broker_url, &None, &pact, &s!("1.0.0"), &vec![s!("prod")]);
+        expect!(result).to(be_ok());
+        Ok(())
+    });
+    expect!(result).to(be_equal_to(VerificationResult::PactVerified));
+}
+
+#[test]
+fn fetch_retries_the_request_if_the_pact_broker_returns_a_server_error() {
+    let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
+        .has_pact_with(s!("PactBroker"))
+        .given(s!("the pact broker is unavailable"))
+        .upon_receiving(s!("a request to an unavailable pact broker"))
+            .path(s!("/"))
+        .will_respond_with()
+            .status(503)
+        .build();
+
+    let result = pact_runner.run(&|broker_url| {
+        let client = HALClient::with_url(&broker_url, None).with_retries(2, Duration::from_millis(10));
+        let result = client.fetch(&s!("/"));
+        expect!(result).to(be_err().value(format!("Request to pact broker path \'/\' failed: 503 Service Unavailable. URL: '{}' (failed after 3 attempts)",
+            broker_url)));
+        Ok(())
+    });
+    expect!(result).to(be_equal_to(VerificationResult::PactVerified));
+}
@@ -43,7 +43,7 @@
 //!         .will_respond_with()
 //!             .status(200)
 //!             .headers(hashmap!{ s!("Content-Type") => s!("text/html") })
-//!             .body(OptionalBody::Present(s!("That is some good Mallory.")))
+//!             .body(OptionalBody::Present(s!("That is some good Mallory.").into()))
 //!         .build();
 //!
 //!     // Execute the run method to have the mock server run (the URL to the mock server will be passed in).
@@ -211,7 +211,7 @@ impl ConsumerPactBuilder {
             _ => self.pact.interactions.push(self.interaction.clone())
         }
         self.interaction = Interaction {
-            provider_state: Some(provider_state.clone()),
+            provider_states: vec![ProviderState::default(&provider_state)],
             .. Interaction::default()
         };
         self.state = BuilderState::BuildingRequest;
@@ -0,0 +1,115 @@
+use super::Mismatch;
+use super::DiffConfig;
+use std::collections::HashMap;
+use models::{Matchers, parse_query_string};
+
+/// Matches `application/x-www-form-urlencoded` bodies. Each form parameter is compared in the same way
+/// as a query parameter, with the matchers defined at the path `$.body.<parameter>`.
+pub fn match_form_urlencoded(expected: &String, actual: &String, config: DiffConfig,
+    mismatches: &mut Vec<super::Mismatch>, matchers: &Option<Matchers>) {
+    let expected_params = parse_query_string(expected).unwrap_or(HashMap::new());
+    let actual_params = parse_query_string(actual).unwrap_or(HashMap::new());
+    let mut param_mismatches = vec![];
+
+    for (key, value) in super::sorted_entries(&expected_params) {
+        match actual_params.get(key) {
+            Some(actual_value) => super::match_query_values(&vec![s!("$"), s!("body"), key.clone()],
+                "form parameter", key, value, actual_value, &mut param_mismatches, matchers),
+            None => param_mismatches.push(Mismatch::QueryMismatch { parameter: key.clone(),
+                expected: format!("{:?}", value),
+                actual: s!(""),
+                mismatch: format!("Expected form parameter '{}' but was missing", key) })
+        }
+    }
+    if config == DiffConfig::NoUnexpectedKeys {
+        for (key, value) in super::sorted_entries(&actual_params) {
+            if !expected_params.contains_key(key) {
+                param_mismatches.push(Mismatch::QueryMismatch { parameter: key.clone(),
+                    expected: s!(""),
+                    actual: format!("{:?}", value),
+                    mismatch: format!("Unexpected form parameter '{}' received", key) });
+            }
+        }
+    }
+
+    for mismatch in param_mismatches {
+        match mismatch {
+            Mismatch::QueryMismatch { parameter, expected, actual, mismatch } =>
+                mismatches.push(Mismatch::BodyMismatch { path: format!("$.body.{}", parameter),
+                    expected: Some(expected), actual: Some(actual), mismatch: mismatch }),
+            _ => mismatches.push(mismatch)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expectest::prelude::*;
+    use Mismatch;
+    use DiffConfig;
+    use models::RuleList;
+
+    #[test]
+    fn match_form_urlencoded_compares_each_parameter() {
+        let mut mismatches = vec![];
+        match_form_urlencoded(&s!("a=1&b=2&b=3"), &s!("b=2&a=1&b=3"), DiffConfig::NoUnexpectedKeys,
+            &mut mismatches, &None);
+        expect!(mismatches.clone()).to(be_empty());
+
+        match_form_urlencoded(&s!("a=1&b=2"), &s!("a=2&b=2&c=3"), DiffConfig::NoUnexpectedKeys,
+            &mut mismatches, &None);
+        expect!(mismatches.iter()).to(have_count(2));
+        let descriptions: Vec<String> = mismatches.iter().map(|m| m.description()).collect();
+        expect!(descriptions.contains(&s!("$.body.c -> Unexpected form parameter 'c' received"))).to(be_true());
+    }
+
+    #[test]
+    fn match_form_urlencoded_reports_missing_parameters() {
+        let mut mismatches = vec![];
+        match_form_urlencoded(&s!("a=1&b=2"), &s!("a=1"), DiffConfig::AllowUnexpectedKeys,
+            &mut mismatches, &None);
+        expect!(mismatches).to(be_equal_to(vec![Mismatch::BodyMismatch { path: s!("$.body.b"),
+            expected: Some(s!("[\"2\"]")), actual: Some(s!("")), mismatch: s!("") }]));
+    }
+
+    #[test]
+    fn match_form_urlencoded_reports_the_mismatches_in_parameter_order() {
+        let mut mismatches = vec![];
+        match_form_urlencoded(&s!("e=1&a=1&d=1&b=1"), &s!("z=2&c=2&y=2"), DiffConfig::NoUnexpectedKeys,
+            &mut mismatches, &None);
+        let descriptions: Vec<String> = mismatches.iter().map(|m| m.description()).collect();
+        expect!(descriptions).to(be_equal_to(vec![
+            s!("$.body.a -> Expected form parameter 'a' but was missing"),
+            s!("$.body.b -> Expected form parameter 'b' but was missing"),
+            s!("$.body.d -> Expected form parameter 'd' but was missing"),
+            s!("$.body.e -> Expected form parameter 'e' but was missing"),
+            s!("$.body.c -> Unexpected form parameter 'c' received"),
+            s!("$.body.y -> Unexpected form parameter 'y' received"),
+            s!("$.body.z -> Unexpected form parameter 'z' received")
+        ]));
+    }
+
+    #[test]
+    fn match_form_urlencoded_ignores_unexpected_parameters_if_allowed() {
+        let mut mismatches = vec![];
+        match_form_urlencoded(&s!("a=1"), &s!("a=1&b=2"), DiffConfig::AllowUnexpectedKeys,
+            &mut mismatches, &None);
+        expect!(mismatches.clone()).to(be_empty());
+    }
+
+    #[test]
+    fn match_form_urlencoded_applies_matchers_to_the_parameters() {
+        let matchers = Some(hashmap!{
+            s!("$.body.id") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("\\d+") })
+        });
+        let mut mismatches = vec![];
+        match_form_urlencoded(&s!("id=100&name=fred"), &s!("id=2001&name=fred"), DiffConfig::NoUnexpectedKeys,
+            &mut mismatches, &matchers);
+        expect!(mismatches.clone()).to(be_empty());
+        match_form_urlencoded(&s!("id=100&name=fred"), &s!("id=abc&name=fred"), DiffConfig::NoUnexpectedKeys,
+            &mut mismatches, &matchers);
+        expect!(mismatches).to(be_equal_to(vec![Mismatch::BodyMismatch { path: s!("$.body.id"),
+            expected: Some(s!("100")), actual: Some(s!("abc")), mismatch: s!("") }]));
+    }
+}
@@ -9,6 +9,7 @@ use ansi_term::Colour::*;
 use std::str::FromStr;
 use models::Matchers;
 use matchers::*;
+use itertools::Itertools;
 
 fn type_of(json: &Value) -> String {
     match json {
@@ -80,13 +81,61 @@ impl Matches<Value> for Value {
                    (_, _) => Err(format!("Expected '{}' to be the same type as '{}'", value_of(self), value_of(actual))),
                }
            },
+           Matcher::MinMaxTypeMatcher(min, max) => {
+               match (self, actual) {
+                   (&Value::Array(_), &Value::Array(ref actual_array)) => if actual_array.len() < min {
+                       Err(format!("Expected '{}' to have at least {} item(s)", value_of(actual), min))
+                   } else if actual_array.len() > max {
+                       Err(format!("Expected '{}' to have at most {} item(s)", value_of(actual), max))
+                   } else {
+                       Ok(())
+                   },
+                   (&Value::Bool(_), &Value::Bool(_)) => Ok(()),
+                   (&Value::Number(_), &Value::Number(_)) => Ok(()),
+                   (&Value::Null, &Value::Null) => Ok(()),
+                   (&Value::Object(_), &Value::Object(_)) => Ok(()),
+                   (&Value::String(_), &Value::String(_)) => Ok(()),
+                   (_, _) => Err(format!("Expected '{}' to be the same type as '{}'", value_of(self), value_of(actual))),
+               }
+           },
            Matcher::EqualityMatcher => {
                if self == actual {
                    Ok(())
                } else {
                    Err(format!("Expected '{}' to be equal to '{}'", value_of(self), value_of(actual)))
                }
-           }
+           },
+           Matcher::ArrayContainsMatcher | Matcher::UnorderedMatcher => match actual {
+               &Value::Array(_) => Ok(()),
+               _ => Err(format!("Expected '{}' to be a list", value_of(actual)))
+           },
+           Matcher::EachKeyMatcher(_) | Matcher::EachValueMatcher => match actual {
+               &Value::Object(_) => Ok(()),
+               _ => Err(format!("Expected '{}' to be a map", value_of(actual)))
+           },
+           Matcher::IntegerMatcher => match actual {
+               &Value::Number(ref n) if n.is_i64() || n.is_u64() => Ok(()),
+               _ => Err(format!("Expected '{}' to be an integer value", value_of(actual)))
+           },
+           Matcher::DecimalMatcher => match actual {
+               &Value::Number(ref n) if n.is_f64() => Ok(()),
+               _ => Err(format!("Expected '{}' to be a decimal value", value_of(actual)))
+           },
+           Matcher::NumberMatcher => match actual {
+               &Value::Number(_) => Ok(()),
+               _ => Err(format!("Expected '{}' to be a number", value_of(actual)))
+           },
+           Matcher::NullMatcher => match actual {
+               &Value::Null => Ok(()),
+               _ => Err(format!("Expected '{}' to be a null value", value_of(actual)))
+           },
+           Matcher::BooleanMatcher => match actual {
+               &Value::Bool(_) => Ok(()),
+               &Value::String(ref s) if s == "true" || s == "false" => Ok(()),
+               _ => Err(format!("Expected '{}' to be a boolean value", value_of(actual)))
+           },
+           _ => match_string_format(&value_of(actual), matcher)
+               .unwrap_or(Err(format!("Unable to match '{}' using {:?}", value_of(actual), matcher)))
        };
        debug!("Comparing '{}' to '{}' using {:?} -> {:?}", self, actual, matcher, result);
        result
@@ -118,6 +167,15 @@ impl Matches<Vec<Value>> for Vec<Value> {
                    Ok(())
                }
            },
+           Matcher::MinMaxTypeMatcher(min, max) => {
+               if actual.len() < min {
+                   Err(format!("Expected '{}' to have a minimum length of {}", value_of(&Value::Array(actual.clone())), min))
+               } else if actual.len() > max {
+                   Err(format!("Expected '{}' to have a maximum length of {}", value_of(&Value::Array(actual.clone())), max))
+               } else {
+                   Ok(())
+               }
+           },
            Matcher::EqualityMatcher => {
                if self == actual {
                    Ok(())
@@ -125,7 +183,10 @@ impl Matches<Vec<Value>> for Vec<Value> {
                    Err(format!("Expected '{}' to be equal to '{}'", value_of(&Value::Array(self.clone())),
                     value_of(&&Value::Array(actual.clone()))))
                }
-           }
+           },
+           Matcher::IncludeMatcher(_) => match_string_format(&Value::Array(actual.clone()).to_string(), matcher).unwrap(),
+           Matcher::ArrayContainsMatcher | Matcher::UnorderedMatcher => Ok(()),
+           _ => Err(format!("Unable to match '{}' using {:?}", value_of(&Value::Array(actual.clone())), matcher))
        };
        debug!("Comparing '{:?}' to '{:?}' using {:?} -> {:?}", self, actual, matcher, result);
        result
@@ -224,10 +285,48 @@ fn compare(path: &Vec<String>, expected: &Value, actual: &Value, config: &DiffCo
     }
 }
 
+fn compare_map_keys(path: &Vec<String>, actual: &serde_json::Map<String, Value>, matcher: &Matcher,
+    mismatches: &mut Vec<super::Mismatch>) {
+    match *matcher {
+        Matcher::EachKeyMatcher(Some(ref regex)) => for key in actual.keys() {
+            if !regex.is_match(key) {
+                let mut p = path.to_vec();
+                p.push(key.clone());
+                mismatches.push(Mismatch::BodyMismatch { path: p.join("."),
+                    expected: Some(regex.to_string()),
+                    actual: Some(key.clone()),
+                    mismatch: format!("Expected key '{}' to match '{}'", key, regex)});
+            }
+        },
+        _ => ()
+    }
+}
+
 fn compare_maps(path: &Vec<String>, expected: &serde_json::Map<String, Value>, actual: &serde_json::Map<String, Value>,
     config: &DiffConfig, mismatches: &mut Vec<super::Mismatch>, matchers: &Option<Matchers>) {
+    let each_key = each_key_matcher(path, matchers);
+    let mut p = path.to_vec();
+    p.push(s!("any"));
+    let wildcard = wildcard_matcher_is_defined(&p, matchers);
+    // with each key and each value matchers, the keys of the expected map are only examples, and
+    // the values under any other keys are compared against the first example value
+    let dynamic_keys = each_key.is_some() || collection_matchers(path, matchers).contains(&Matcher::EachValueMatcher);
 
-    if expected.is_empty() && !actual.is_empty() {
+    if dynamic_keys {
+        match each_key {
+            Some(ref matcher) => compare_map_keys(path, actual, matcher, mismatches),
+            None => ()
+        }
+        for (key, value) in actual.iter() {
+            let mut p = path.to_vec();
+            p.push(key.clone());
+            if expected.contains_key(key) {
+                compare(&p, &expected[key], value, config, mismatches, matchers);
+            } else if !expected.is_empty() {
+                compare(&p, &expected.values().next().unwrap(), value, config, mismatches, matchers);
+            }
+        }
+    } else if expected.is_empty() && !actual.is_empty() {
       mismatches.push(Mismatch::BodyMismatch { path: path.join("."),
           expected: Some(value_of(&json!(expected))),
           actual: Some(value_of(&json!(actual))),
@@ -251,9 +350,7 @@ fn compare_maps(path: &Vec<String>, expected: &serde_json::Map<String, Value>, a
             _ => ()
         }
 
-        let mut p = path.to_vec();
-        p.push(s!("any"));
-        if wildcard_matcher_is_defined(&p, matchers) {
+        if wildcard {
             for (key, value) in actual.iter() {
                 let mut p = path.to_vec();
                 p.push(key.clone());
@@ -283,7 +380,11 @@ fn compare_maps(path: &Vec<String>, expected: &serde_json::Map<String, Value>, a
 fn compare_lists(path: &Vec<String>, expected: &Vec<Value>, actual: &Vec<Value>, config: &DiffConfig,
     mismatches: &mut Vec<super::Mismatch>, matchers: &Option<Matchers>) {
     let spath = path.join(".");
-    if matcher_is_defined(&path, matchers) {
+    if let Some(matcher) = array_contains_matcher(&path, matchers) {
+        debug!("compare_lists: {:?} matcher defined for path '{}'", matcher, spath);
+        compare_lists_unordered(path, expected, actual, matcher == Matcher::UnorderedMatcher, config,
+            mismatches, matchers);
+    } else if matcher_is_defined(&path, matchers) {
         debug!("compare_lists: matcher defined for path '{}'", spath);
         let expected_json = Value::Array(expected.clone());
         let actual_json = Value::Array(actual.clone());
@@ -317,6 +418,75 @@ fn compare_lists(path: &Vec<String>, expected: &Vec<Value>, actual: &Vec<Value>,
     }
 }
 
+// Tries to assign the expected item to an actual item it matches that has not been assigned yet, or
+// can be freed up by moving the expected item assigned to it to another actual item
+fn assign_item(index: usize, results: &Vec<Vec<Vec<Mismatch>>>, visited: &mut Vec<bool>,
+    assignments: &mut Vec<Option<usize>>) -> bool {
+    for actual_index in 0..assignments.len() {
+        if results[index][actual_index].is_empty() && !visited[actual_index] {
+            visited[actual_index] = true;
+            let assigned = assignments[actual_index];
+            let available = match assigned {
+                Some(other) => assign_item(other, results, visited, assignments),
+                None => true
+            };
+            if available {
+                assignments[actual_index] = Some(index);
+                return true;
+            }
+        }
+    }
+    false
+}
+
+fn compare_lists_unordered(path: &Vec<String>, expected: &Vec<Value>, actual: &Vec<Value>, exact: bool,
+    config: &DiffConfig, mismatches: &mut Vec<super::Mismatch>, matchers: &Option<Matchers>) {
+    let spath = path.join(".");
+    // each expected item is compared using the path of its own index, so that any matchers
+    // defined for it are applied
+    let results: Vec<Vec<Vec<Mismatch>>> = expected.iter().enumerate().map(|(index, value)| {
+        let mut p = path.to_vec();
+        p.push(index.to_string());
+        actual.iter().map(|actual_value| {
+            let mut item_mismatches = vec![];
+            compare(&p, value, actual_value, config, &mut item_mismatches, matchers);
+            item_mismatches
+        }).collect()
+    }).collect();
+
+    let mut assignments = vec![None; actual.len()];
+    for index in 0..expected.len() {
+        let mut visited = vec![false; actual.len()];
+        assign_item(index, &results, &mut visited, &mut assignments);
+    }
+
+    for (index, value) in expected.iter().enumerate() {
+        if !assignments.contains(&Some(index)) {
+            let closest = results[index].iter().enumerate().min_by_key(|&(_, item_mismatches)| item_mismatches.len());
+            let reason = match closest {
+                Some((actual_index, item_mismatches)) if !item_mismatches.is_empty() =>
+                    format!(" The closest item was at index {}: {}", actual_index,
+                        item_mismatches.iter().map(|mismatch| mismatch.description()).join(", ")),
+                Some(_) => s!(" The items that match it are already matched by other expected items."),
+                None => s!("")
+            };
+            mismatches.push(Mismatch::BodyMismatch { path: spath.clone(),
+                expected: Some(value_of(value)),
+                actual: Some(value_of(&json!(actual))),
+                mismatch: format!("Expected the list to contain an item matching {} (expected item {}), but it was not found.{}",
+                    value_of(value), index, reason)});
+        }
+    }
+
+    if exact && expected.len() != actual.len() {
+        mismatches.push(Mismatch::BodyMismatch { path: spath,
+            expected: Some(value_of(&json!(expected))),
+            actual: Some(value_of(&json!(actual))),
+            mismatch: format!("Expected a List with {} elements but received {} elements",
+                expected.len(), actual.len())});
+    }
+}
+
 fn compare_list_content(path: &Vec<String>, expected: &Vec<Value>, actual: &Vec<Value>, config: &DiffConfig,
     mismatches: &mut Vec<super::Mismatch>, matchers: &Option<Matchers>) {
     for (index, value) in expected.iter().enumerate() {
@@ -359,6 +529,7 @@ mod tests {
     use Mismatch;
     use DiffConfig;
     use matchers::*;
+    use models::{RuleList, RuleLogic};
     use regex::Regex;
 
     #[test]
@@ -551,11 +722,11 @@ mod tests {
         mismatches.clear();
 
         match_json(&val2, &val4, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &Some(hashmap!{
-            s!("$.body") => hashmap!{ s!("match") => s!("type") }
+            s!("$.body") => RuleList::new(hashmap!{ s!("match") => s!("type") })
         }));
         expect!(mismatches.clone()).to(be_empty());
         match_json(&val4, &val2, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &Some(hashmap!{
-            s!("$.body") => hashmap!{ s!("match") => s!("type") }
+            s!("$.body") => RuleList::new(hashmap!{ s!("match") => s!("type") })
         }));
         expect!(mismatches.clone()).to(be_empty());
     }
@@ -643,7 +814,7 @@ mod tests {
         mismatches.clear();
 
         match_json(&val3, &val2, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &Some(hashmap!{
-            s!("$.body.*") => hashmap!{ s!("match") => s!("type") }
+            s!("$.body.*") => RuleList::new(hashmap!{ s!("match") => s!("type") })
         }));
         expect!(mismatches.clone()).to(be_empty());
     }
@@ -690,6 +861,180 @@ mod tests {
         expect!(Value::String(s!("100")).matches(&Value::String(s!("101")), &matcher)).to(be_ok());
     }
 
+    #[test]
+    fn min_max_type_matcher_test() {
+        let matcher = Matcher::MinMaxTypeMatcher(1, 2);
+        expect!(Value::Array(vec![]).matches(&Value::Array(vec![]), &matcher)).to(be_err());
+        expect!(Value::Array(vec![]).matches(&Value::Array(vec![json!(100), json!(100)]), &matcher)).to(be_ok());
+        expect!(Value::Array(vec![]).matches(&Value::Array(vec![json!(100), json!(100), json!(100)]), &matcher)).to(be_err());
+        expect!(Value::String(s!("100")).matches(&json!(100), &matcher)).to(be_err());
+    }
+
+    #[test]
+    fn number_matchers_test() {
+        expect!(json!(100).matches(&json!(200), &Matcher::IntegerMatcher)).to(be_ok());
+        expect!(json!(100).matches(&json!(2.5), &Matcher::IntegerMatcher)).to(be_err());
+        expect!(json!(100).matches(&json!("200"), &Matcher::IntegerMatcher)).to(be_err());
+        expect!(json!(1.5).matches(&json!(2.5), &Matcher::DecimalMatcher)).to(be_ok());
+        expect!(json!(1.5).matches(&json!(200), &Matcher::DecimalMatcher)).to(be_err());
+        expect!(json!(100).matches(&json!(2.5), &Matcher::NumberMatcher)).to(be_ok());
+        expect!(json!(100).matches(&json!("2.5"), &Matcher::NumberMatcher)).to(be_err());
+    }
+
+    #[test]
+    fn null_boolean_and_format_matchers_test() {
+        expect!(Value::Null.matches(&Value::Null, &Matcher::NullMatcher)).to(be_ok());
+        expect!(Value::Null.matches(&json!(""), &Matcher::NullMatcher)).to(be_err());
+        expect!(json!(true).matches(&json!(false), &Matcher::BooleanMatcher)).to(be_ok());
+        expect!(json!(true).matches(&json!("false"), &Matcher::BooleanMatcher)).to(be_ok());
+        expect!(json!(true).matches(&json!(0), &Matcher::BooleanMatcher)).to(be_err());
+        expect!(json!("").matches(&json!("2017-06-21"), &Matcher::DateMatcher(s!("yyyy-MM-dd")))).to(be_ok());
+        expect!(json!("").matches(&json!("21/06/2017"), &Matcher::DateMatcher(s!("yyyy-MM-dd")))).to(be_err());
+        expect!(json!("").matches(&json!("hello world"), &Matcher::IncludeMatcher(s!("world")))).to(be_ok());
+        expect!(json!("").matches(&json!("hello"), &Matcher::IncludeMatcher(s!("world")))).to(be_err());
+    }
+
+    #[test]
+    fn compare_lists_with_array_contains_matcher_ignores_the_order_and_extra_items() {
+        let expected = s!(r#"{"items": [{"id": 1, "name": "a"}, {"id": 2, "name": "b"}]}"#);
+        let actual = s!(r#"{"items": [{"id": 3, "name": "c"}, {"id": 2, "name": "b"}, {"id": 1, "name": "a"}]}"#);
+        let matchers = Some(hashmap!{
+            s!("$.body.items") => RuleList::new(hashmap!{ s!("match") => s!("arrayContains") })
+        });
+        let mut mismatches = vec![];
+        match_json(&expected, &actual, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+        expect!(mismatches.clone()).to(be_empty());
+
+        let actual = s!(r#"{"items": [{"id": 3, "name": "c"}, {"id": 1, "name": "a"}]}"#);
+        match_json(&expected, &actual, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+        expect!(mismatches.iter()).to(have_count(1));
+        let mismatch = mismatches[0].clone();
+        expect!(mismatch.clone()).to(be_equal_to(Mismatch::BodyMismatch { path: s!("$.body.items"),
+            expected: Some(s!(r#"{"id":2,"name":"b"}"#)), actual: Some(s!(r#"[{"id":3,"name":"c"},{"id":1,"name":"a"}]"#)),
+            mismatch: s!("") }));
+        expect!(mismatch.description()).to(be_equal_to(s!("$.body.items -> Expected the list to contain an item matching {\"id\":2,\"name\":\"b\"} (expected item 1), but it was not found. The closest item was at index 0: $.body.items.1.id -> Expected '2' to be equal to '3', $.body.items.1.name -> Expected 'b' to be equal to 'c'")));
+    }
+
+    #[test]
+    fn compare_lists_with_array_contains_matcher_applies_the_item_matchers() {
+        let expected = s!(r#"[{"id": 1, "type": "a"}, {"id": 2, "type": "b"}]"#);
+        let actual = s!(r#"[{"id": 200, "type": "b"}, {"id": 100, "type": "a"}]"#);
+        let matchers = Some(hashmap!{
+            s!("$.body") => RuleList::new(hashmap!{ s!("match") => s!("arrayContains") }),
+            s!("$.body[*].id") => RuleList::new(hashmap!{ s!("match") => s!("integer") })
+        });
+        let mut mismatches = vec![];
+        match_json(&expected, &actual, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+        expect!(mismatches.clone()).to(be_empty());
+
+        let actual = s!(r#"[{"id": 200, "type": "b"}, {"id": 100, "type": "c"}]"#);
+        match_json(&expected, &actual, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+        expect!(mismatches.iter()).to(have_count(1));
+    }
+
+    #[test]
+    fn compare_lists_with_array_contains_matcher_matches_each_expected_item_to_a_distinct_item() {
+        let expected = s!(r#"[1, 1]"#);
+        let matchers = Some(hashmap!{
+            s!("$.body") => RuleList::new(hashmap!{ s!("match") => s!("arrayContains") })
+        });
+        let mut mismatches = vec![];
+        match_json(&expected, &s!("[2, 1, 1]"), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+        expect!(mismatches.clone()).to(be_empty());
+        match_json(&expected, &s!("[2, 1]"), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+        expect!(mismatches.iter()).to(have_count(1));
+    }
+
+    #[test]
+    fn compare_lists_with_unordered_matcher_requires_the_same_number_of_items() {
+        let expected = s!(r#"["a", "b", "c"]"#);
+        let matchers = Some(hashmap!{
+            s!("$.body") => RuleList::new(hashmap!{ s!("match") => s!("unordered") })
+        });
+        let mut mismatches = vec![];
+        match_json(&expected, &s!(r#"["c", "a", "b"]"#), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+        expect!(mismatches.clone()).to(be_empty());
+        match_json(&expected, &s!(r#"["c", "a", "b", "d"]"#), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+        expect!(mismatches.iter()).to(have_count(1));
+        mismatches.clear();
+        match_json(&expected, &s!(r#"["c", "a"]"#), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+        expect!(mismatches.iter()).to(have_count(2));
+    }
+
+    #[test]
+    fn compare_maps_with_each_key_matcher_applies_the_regex_to_every_key() {
+        let expected = s!(r#"{"ids": {"100": "a"}}"#);
+        let matchers = Some(hashmap!{
+            s!("$.body.ids") => RuleList::new(hashmap!{ s!("match") => s!("eachKey"), s!("regex") => s!("^\\d+$") })
+        });
+        let mut mismatches = vec![];
+        match_json(&expected, &s!(r#"{"ids": {"200": "a", "300": "a"}}"#), DiffConfig::AllowUnexpectedKeys,
+            &mut mismatches, &matchers);
+        expect!(mismatches.clone()).to(be_empty());
+        match_json(&expected, &s!(r#"{"ids": {"200": "a", "abc": "a"}}"#), DiffConfig::NoUnexpectedKeys,
+            &mut mismatches, &matchers);
+        expect!(mismatches.clone()).to(be_equal_to(vec![Mismatch::BodyMismatch { path: s!("$.body.ids.abc"),
+            expected: Some(s!("^\\d+$")), actual: Some(s!("abc")), mismatch: s!("") }]));
+        expect!(mismatches[0].description()).to(be_equal_to(s!("$.body.ids.abc -> Expected key 'abc' to match '^\\d+$'")));
+    }
+
+    #[test]
+    fn compare_maps_with_only_an_each_key_matcher_compares_the_values_against_the_example() {
+        let expected = s!(r#"{"ids": {"1": {"name": "a"}}}"#);
+        let matchers = Some(hashmap!{
+            s!("$.body.ids") => RuleList::new(hashmap!{ s!("match") => s!("eachKey"), s!("regex") => s!("^\\d+$") })
+        });
+        let mut mismatches = vec![];
+        match_json(&expected, &s!(r#"{"ids": {"2": {"name": "a"}, "3": {"name": "a"}}}"#),
+            DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+        expect!(mismatches.clone()).to(be_empty());
+        match_json(&expected, &s!(r#"{"ids": {"2": 5}}"#), DiffConfig::AllowUnexpectedKeys, &mut mismatches,
+            &matchers);
+        expect!(mismatches.clone()).to(be_equal_to(vec![Mismatch::BodyMismatch { path: s!("$.body.ids.2"),
+            expected: Some(s!("{\"name\":\"a\"}")), actual: Some(s!("5")), mismatch: s!("") }]));
+    }
+
+    #[test]
+    fn compare_maps_with_each_value_matcher_applies_the_template_to_every_value() {
+        let expected = s!(r#"{"users": {"fred": {"id": 1, "admin": false}}}"#);
+        let matchers = Some(hashmap!{
+            s!("$.body.users") => RuleList::new(hashmap!{ s!("match") => s!("eachValue") }),
+            s!("$.body.users.*.id") => RuleList::new(hashmap!{ s!("match") => s!("integer") }),
+            s!("$.body.users.*.admin") => RuleList::new(hashmap!{ s!("match") => s!("boolean") })
+        });
+        let mut mismatches = vec![];
+        match_json(&expected, &s!(r#"{"users": {"mary": {"id": 2, "admin": true}, "john": {"id": 3, "admin": false}}}"#),
+            DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+        expect!(mismatches.clone()).to(be_empty());
+        match_json(&expected, &s!(r#"{"users": {"mary": {"id": 2, "admin": true}, "john": {"id": "3", "admin": false}}}"#),
+            DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+        expect!(mismatches.clone()).to(be_equal_to(vec![Mismatch::BodyMismatch { path: s!("$.body.users.john.id"),
+            expected: Some(s!("1")), actual: Some(s!("\"3\"")), mismatch: s!("") }]));
+    }
+
+    #[test]
+    fn each_key_and_each_value_matchers_can_be_used_inside_lists() {
+        let expected = s!(r#"[{"a1": 1}]"#);
+        let matchers = Some(hashmap!{
+            s!("$.body") => RuleList::new(hashmap!{ s!("match") => s!("type") }),
+            s!("$.body[*]") => RuleList {
+                rules: vec![
+                    hashmap!{ s!("match") => s!("eachKey"), s!("regex") => s!("^[a-z]\\d$") },
+                    hashmap!{ s!("match") => s!("eachValue") }
+                ],
+                rule_logic: RuleLogic::And
+            },
+            s!("$.body[*].*") => RuleList::new(hashmap!{ s!("match") => s!("type") })
+        });
+        let mut mismatches = vec![];
+        match_json(&expected, &s!(r#"[{"b2": 20, "c3": 30}, {"d4": 40}]"#), DiffConfig::AllowUnexpectedKeys,
+            &mut mismatches, &matchers);
+        expect!(mismatches.clone()).to(be_empty());
+        match_json(&expected, &s!(r#"[{"b2": 20}, {"dd": "40"}]"#), DiffConfig::AllowUnexpectedKeys,
+            &mut mismatches, &matchers);
+        expect!(mismatches.iter()).to(have_count(2));
+    }
+
     #[test]
     fn compare_maps_handles_wildcard_matchers() {
         let mut mismatches = vec![];
@@ -736,8 +1081,8 @@ mod tests {
         }"#);
 
         match_json(&val1, &val2, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &Some(hashmap!{
-            s!("$.body.articles[*].variants.*") => hashmap!{ s!("match") => s!("type") },
-            s!("$.body.articles[*].variants.*.bundles.*") => hashmap!{ s!("match") => s!("type") }
+            s!("$.body.articles[*].variants.*") => RuleList::new(hashmap!{ s!("match") => s!("type") }),
+            s!("$.body.articles[*].variants.*.bundles.*") => RuleList::new(hashmap!{ s!("match") => s!("type") })
         }));
         expect!(mismatches.clone()).to(be_empty());
         mismatches.clear();
@@ -15,7 +15,23 @@
 //! ## Reading and writing Pact files
 //!
 //! The [`Pact`](models/struct.Pact.html) struct in the [`models`)(models/index.html) module has methods to read and write pact JSON files. It supports all the specification
-//! versions up to V2, but will converted a V1 and V1.1 spec file to a V2 format.
+//! versions up to V3. V1, V1.1 and V2 spec files will be written in the V2 format, while V3 spec files are written
+//! in the V3 format (multiple provider states with parameters, query parameters as maps, matching rules grouped by category
+//! and generators).
+//!
+//! ## Message pacts
+//!
+//! The `MessagePact` struct in the `models` module reads and writes V3 message pact files (pact files with a `messages`
+//! array). Each `Message` has a description, provider states, contents, metadata and matching rules. The `match_message`
+//! function matches an actual message against the expected one, using the JSON or XML body matchers for the contents
+//! (based on the `contentType` metadata value) and matching rules in the `metadata` category for the metadata values.
+//!
+//! ## Generators
+//!
+//! V3 pacts can have generators for the request and response parts, which replace values with generated ones (random
+//! integers, decimals, hexadecimal strings, strings, values matching a regular expression, UUIDs, dates, times, booleans and
+//! values looked up from the provider state). They are stored by path in the same way as the matching rules, and applied
+//! with `Request::generate_request` and `Response::generate_response`.
 //!
 //! ## Matching request and response parts
 //!
@@ -71,12 +87,24 @@
 //!
 //! Postel's law governs if we allow unexpected keys or not.
 //!
+//! If there is an `eachKey` or `eachValue` matcher defined for the path to the map, the keys of the
+//! expected map are only treated as examples:
+//!
+//! 1. With an `eachKey` matcher that has a regex, every key in the actual map must match the regex.
+//! 2. For each key and value pair in the actual map:
+//!     1. if the expected map contains the key, compare the values
+//!     2. otherwise, if there is an `eachValue` matcher (or a wildcard matcher for the map), compare the
+//!     value against the first value of the expected map
+//! 3. The number of keys and missing keys are not checked.
+//!
 //! ##### For comparing lists
 //!
-//! 1. If there is a body matcher defined that matches the path to the list, default
+//! 1. If there is an `arrayContains` or `unordered` matcher defined for the path to the list, compare
+//! the list contents ignoring the order of the items.
+//! 2. If there is a body matcher defined that matches the path to the list, default
 //! to that matcher and then compare the list contents.
-//! 2. If the expected list is empty and the actual one is not, the lists don't match.
-//! 3. Otherwise
+//! 3. If the expected list is empty and the actual one is not, the lists don't match.
+//! 4. Otherwise
 //!     1. compare the list sizes
 //!     2. compare the list contents
 //!
@@ -87,6 +115,15 @@
 //!        with the actual value at the same index using the method for comparing values.
 //!     2. Otherwise the value doesn't match
 //!
+//! ###### For comparing list contents ignoring the order
+//!
+//! 1. Compare each value in the expected list with each value in the actual list, using the path
+//!    of the expected value (so any matchers defined for the expected value are applied).
+//! 2. Each expected value must match a different actual value. Any expected values that can not be
+//!    matched are reported, along with the closest actual value.
+//! 3. For the `unordered` matcher, the lists must also be the same size. The `arrayContains` matcher
+//!    allows the actual list to have additional values.
+//!
 //! ##### For comparing values
 //!
 //! 1. If there is a matcher defined that matches the path to the value, default to that
@@ -166,6 +203,47 @@
 //! matcher
 //! 2. Otherwise compare the values using equality.
 //!
+//! #### Form body matching rules
+//!
+//! `application/x-www-form-urlencoded` bodies are parsed into a Map of form parameters mapped to lists of
+//! values, and each parameter is compared in the same way as a query parameter (see Matching Query Maps),
+//! with the matchers defined at `$.body.<parameter>`. Missing parameters don't match, while unexpected
+//! parameters only don't match for requests.
+//!
+//! #### Multipart body matching rules
+//!
+//! `multipart/form-data` bodies are split into their parts, which are matched by name. Missing parts don't
+//! match, and unexpected parts only don't match for requests. For each part:
+//!
+//! 1. If the expected part has a filename, the actual part must have the same filename.
+//! 2. The content types of the parts must be the same (defaulting to `text/plain`).
+//! 3. If there is a matcher defined for the path to the part (`$.body.<part name>`), compare the contents
+//! with that matcher.
+//! 4. Otherwise compare the contents with the body matcher for the content type of the part. Matchers defined
+//! under the path of the part (i.e. `$.body.<part name>.id`) are applied to the contents of the part.
+//!
+//! #### Binary body matching rules
+//!
+//! Bodies are stored as bytes, so binary bodies (images, PDFs, protobuf payloads, etc.) are supported. Bodies with a
+//! binary content type (`image/*`, `audio/*`, `video/*`, `font/*`, `application/octet-stream`, `application/pdf`,
+//! `application/zip`, `application/gzip`, `application/x-protobuf`, etc.) are base64 encoded in pact files. When there is
+//! no `Content-Type` header, the content type is detected from the magic bytes of common binary formats. A `bodyEncoding`
+//! of `base64` and the `bodyContentType` are written alongside base64 encoded bodies (`contentsEncoding` and
+//! `contentsContentType` for messages), so the body is decoded when the pact file is read even without a header.
+//!
+//! 1. If there is a matcher defined for `$.body`, default to that matcher. The `contentType` matcher checks the magic
+//! bytes of the actual body.
+//! 2. Otherwise compare the bodies as bytes.
+//!
+//! #### Custom body matchers
+//!
+//! Bodies are matched with the body matcher registered for their content type. The JSON, XML, form and multipart matchers
+//! are registered by default, and other bodies are compared as text (or bytes for binary content types). Additional body
+//! matchers can be registered with `register_body_matcher`, passing a regular expression for the content types and a
+//! `BodyMatcher` (functions with the same signature as `json::match_json` can be used directly). The matcher is then used
+//! for all requests, responses and messages with a matching content type, including those matched by the mock server and
+//! the verifier. Matchers registered later take precedence, so a matcher can be registered for a vendor JSON media type.
+//!
 //! ### Matching Paths
 //!
 //! Paths are matched by the following:
@@ -221,6 +299,10 @@
 //! rules that corresponds to the path to the item, the comparison will be delegated to the defined matcher. Note that the
 //! matching rules cascade, so a rule can be specified on a value and will apply to all children of that value.
 //!
+//! With the V3 specification, the matching rules are grouped by category (`path`, `query`, `header` and `body`), and
+//! each path can have multiple matchers defined. These are combined with `AND` logic (all must match) unless the rule has
+//! `"combine": "OR"`, in which case at least one must match.
+//!
 //! ## Matcher Path expressions
 //!
 //! Pact does not support the full JSON path expressions, only ones that match the following rules:
@@ -323,6 +405,21 @@
 //! | Type | `{ "match": "type" }` | This executes a type based match against the values, that is, they are equal if they are the same type. |
 //! | MinType | `{ "match": "type", "min": 2 }` | This executes a type based match against the values, that is, they are equal if they are the same type. In addition, if the values represent a collection, the length of the actual value is compared against the minimum. |
 //! | MaxType | `{ "match": "type", "max": 10 }` | This executes a type based match against the values, that is, they are equal if they are the same type. In addition, if the values represent a collection, the length of the actual value is compared against the maximum. |
+//! | MinMaxType | `{ "match": "type", "min": 1, "max": 10 }` | This executes a type based match against the values, and if the values represent a collection, checks that the length of the actual value is between the minimum and maximum. |
+//! | Integer | `{ "match": "integer" }` | Checks that the actual value is an integer. |
+//! | Decimal | `{ "match": "decimal" }` | Checks that the actual value is a number with a decimal part. |
+//! | Number | `{ "match": "number" }` | Checks that the actual value is a number. |
+//! | Timestamp | `{ "match": "timestamp", "timestamp": "yyyy-MM-dd'T'HH:mm:ss" }` | Checks that the string representation of the actual value matches the date and time format. The format defaults to `yyyy-MM-dd'T'HH:mm:ss`. |
+//! | Date | `{ "match": "date", "date": "yyyy-MM-dd" }` | Checks that the string representation of the actual value matches the date format. The format defaults to `yyyy-MM-dd`. |
+//! | Time | `{ "match": "time", "time": "HH:mm:ss" }` | Checks that the string representation of the actual value matches the time format. The format defaults to `HH:mm:ss`. |
+//! | Include | `{ "match": "include", "value": "foo" }` | Checks that the string representation of the actual value contains the given value. |
+//! | Null | `{ "match": "null" }` | Checks that the actual value is a null value. |
+//! | Boolean | `{ "match": "boolean" }` | Checks that the actual value is a boolean, or the string `true` or `false`. |
+//! | ContentType | `{ "match": "contentType", "value": "application/json" }` | Checks that the content type detected from the actual value matches the given content type. |
+//! | ArrayContains | `{ "match": "arrayContains" }` | Checks that each item in the expected list matches a different item in the actual list, in any order. The actual list can have additional items. |
+//! | Unordered | `{ "match": "unordered" }` | Checks that each item in the expected list matches a different item in the actual list, in any order, and that the lists are the same size. |
+//! | EachKey | `{ "match": "eachKey", "regex": "^\\d+$" }` | Checks that every key of the actual map matches the regex. The keys of the expected map are treated as examples. |
+//! | EachValue | `{ "match": "eachValue" }` | Compares every value of the actual map against the first value of the expected map, regardless of the keys. |
 //!
 
 #![warn(missing_docs)]
@@ -338,6 +435,10 @@ extern crate semver;
 #[macro_use] extern crate itertools;
 extern crate rand;
 extern crate sxd_document;
+extern crate chrono;
+extern crate uuid;
+extern crate regex_syntax;
+extern crate base64;
 
 #[macro_use] extern crate hyper;
 extern crate ansi_term;
@@ -351,6 +452,7 @@ macro_rules! s {
 
 use std::collections::HashMap;
 use std::iter::FromIterator;
+use std::sync::{Arc, RwLock};
 use regex::Regex;
 use ansi_term::*;
 use ansi_term::Colour::*;
@@ -360,6 +462,9 @@ mod path_exp;
 mod matchers;
 pub mod json;
 mod xml;
+mod form_urlencoded;
+mod multipart;
+mod time_utils;
 
 use models::Matchers;
 use matchers::*;
@@ -368,13 +473,55 @@ fn strip_whitespace<'a, T: FromIterator<&'a str>>(val: &'a String, split_by: &'a
     val.split(split_by).map(|v| v.trim().clone() ).collect()
 }
 
+/// Trait for matching the bodies of a particular content type. Body matchers are registered for a
+/// content type with [`register_body_matcher`](fn.register_body_matcher.html).
+///
+/// Functions with the same signature as `json::match_json` implement this trait, with the bodies
+/// converted to strings.
+pub trait BodyMatcher: Send + Sync {
+    /// Matches the actual body to the expected one, adding any mismatches to `mismatches`. Matchers
+    /// for the body are defined with paths starting with `$.body`.
+    fn match_body(&self, expected: &Vec<u8>, actual: &Vec<u8>, config: DiffConfig,
+        mismatches: &mut Vec<Mismatch>, matchers: &Option<Matchers>);
+}
+
+impl<F> BodyMatcher for F where F: Fn(&String, &String, DiffConfig, &mut Vec<Mismatch>, &Option<Matchers>) + Send + Sync {
+    fn match_body(&self, expected: &Vec<u8>, actual: &Vec<u8>, config: DiffConfig,
+        mismatches: &mut Vec<Mismatch>, matchers: &Option<Matchers>) {
+        self(&String::from_utf8_lossy(expected).into_owned(), &String::from_utf8_lossy(actual).into_owned(),
+            config, mismatches, matchers)
+    }
+}
+
 lazy_static! {
-    static ref BODY_MATCHERS: [(Regex, fn(expected: &String, actual: &String, config: DiffConfig,
-            mismatches: &mut Vec<Mismatch>, matchers: &Option<Matchers>)); 3] = [
-        (Regex::new("application/.*json").unwrap(), json::match_json),
-        (Regex::new("application/json.*").unwrap(), json::match_json),
-        (Regex::new("application/.*xml").unwrap(), xml::match_xml)
-    ];
+    static ref BODY_MATCHERS: RwLock<Vec<(Regex, Arc<BodyMatcher>)>> = RwLock::new(vec![
+        (Regex::new("application/.*json").unwrap(), Arc::new(json::match_json) as Arc<BodyMatcher>),
+        (Regex::new("application/json.*").unwrap(), Arc::new(json::match_json) as Arc<BodyMatcher>),
+        (Regex::new("application/.*xml").unwrap(), Arc::new(xml::match_xml) as Arc<BodyMatcher>),
+        (Regex::new("application/x-www-form-urlencoded").unwrap(), Arc::new(form_urlencoded::match_form_urlencoded) as Arc<BodyMatcher>),
+        (Regex::new("multipart/form-data").unwrap(), Arc::new(multipart::MultipartMatcher) as Arc<BodyMatcher>)
+    ]);
+}
+
+/// Registers a body matcher for the content types that match the given regular expression. Registered
+/// matchers are used by `match_body` (and so by the mock server and verifier) for any request, response
+/// or message with a matching content type. Matchers registered later take precedence over earlier ones and
+/// the built in JSON, XML, form and multipart matchers, so a more specific matcher can be registered for a
+/// vendor JSON media type. Returns an error if the regular expression is not valid.
+pub fn register_body_matcher<M: BodyMatcher + 'static>(content_type: &str, matcher: M) -> Result<(), String> {
+    match Regex::new(content_type) {
+        Ok(regex) => {
+            let mut body_matchers = BODY_MATCHERS.write().unwrap();
+            body_matchers.insert(0, (regex, Arc::new(matcher) as Arc<BodyMatcher>));
+            Ok(())
+        },
+        Err(err) => Err(format!("Content type '{}' is not a valid regular expression - {}", content_type, err))
+    }
+}
+
+fn body_matcher_for(content_type: &String) -> Option<Arc<BodyMatcher>> {
+    let body_matchers = BODY_MATCHERS.read().unwrap();
+    body_matchers.iter().find(|mt| mt.0.is_match(content_type)).map(|mt| mt.1.clone())
 }
 
 static PARAMETERISED_HEADER_TYPES: [&'static str; 2] = ["accept", "content-type"];
@@ -444,6 +591,17 @@ pub enum Mismatch {
         actual: Option<String>,
         /// description of the mismatch
         mismatch: String
+    },
+    /// Message metadata mismatch
+    MetadataMismatch {
+        /// metadata key
+        key: String,
+        /// expected value
+        expected: String,
+        /// actual value
+        actual: String,
+        /// description of the mismatch
+        mismatch: String
     }
 }
 
@@ -512,6 +670,15 @@ impl Mismatch {
                     },
                     s!("mismatch") : json!(m)
                 })
+            },
+            &Mismatch::MetadataMismatch { key: ref k, expected: ref e, actual: ref a, mismatch: ref m } => {
+                json!({
+                    s!("type") : json!("MetadataMismatch"),
+                    s!("key") : json!(k),
+                    s!("expected") : json!(e),
+                    s!("actual") : json!(a),
+                    s!("mismatch") : json!(m)
+                })
             }
         }
     }
@@ -525,7 +692,8 @@ impl Mismatch {
             Mismatch::QueryMismatch { .. } => s!("QueryMismatch"),
             Mismatch::HeaderMismatch { .. } => s!("HeaderMismatch"),
             Mismatch::BodyTypeMismatch { .. } => s!("BodyTypeMismatch"),
-            Mismatch::BodyMismatch { .. } => s!("BodyMismatch")
+            Mismatch::BodyMismatch { .. } => s!("BodyMismatch"),
+            Mismatch::MetadataMismatch { .. } => s!("MetadataMismatch")
         }
     }
 
@@ -538,7 +706,8 @@ impl Mismatch {
             Mismatch::QueryMismatch { ref parameter, expected: ref e, .. } => format!("includes parameter '{}' with value '{}'", parameter, e),
             Mismatch::HeaderMismatch { ref key, expected: ref e, .. } => format!("includes header '{}' with value '{}'", key, e),
             Mismatch::BodyTypeMismatch { .. } => s!("has a matching body"),
-            Mismatch::BodyMismatch { .. } => s!("has a matching body")
+            Mismatch::BodyMismatch { .. } => s!("has a matching body"),
+            Mismatch::MetadataMismatch { ref key, expected: ref e, .. } => format!("has metadata '{}' with value '{}'", key, e)
         }
     }
 
@@ -551,7 +720,8 @@ impl Mismatch {
             Mismatch::QueryMismatch { ref mismatch, .. } => mismatch.clone(),
             Mismatch::HeaderMismatch { ref mismatch, .. } => mismatch.clone(),
             Mismatch::BodyTypeMismatch {  expected: ref e, actual: ref a } => format!("expected '{}' body but was '{}'", e, a),
-            Mismatch::BodyMismatch { ref path, ref mismatch, .. } => format!("{} -> {}", path, mismatch)
+            Mismatch::BodyMismatch { ref path, ref mismatch, .. } => format!("{} -> {}", path, mismatch),
+            Mismatch::MetadataMismatch { ref mismatch, .. } => mismatch.clone()
         }
     }
 
@@ -566,7 +736,9 @@ impl Mismatch {
             Mismatch::HeaderMismatch { expected: ref e, actual: ref a, key: ref k, .. } => format!("Expected header '{}' to have value '{}' but was '{}'",
                 Style::new().bold().paint(k.clone()), Red.paint(e.to_string()), Green.paint(a.to_string())),
             Mismatch::BodyTypeMismatch {  expected: ref e, actual: ref a } => format!("expected '{}' body but was '{}'", Red.paint(e.clone()), Green.paint(a.clone())),
-            Mismatch::BodyMismatch { ref path, ref mismatch, .. } => format!("{} -> {}", Style::new().bold().paint(path.clone()), mismatch)
+            Mismatch::BodyMismatch { ref path, ref mismatch, .. } => format!("{} -> {}", Style::new().bold().paint(path.clone()), mismatch),
+            Mismatch::MetadataMismatch { expected: ref e, actual: ref a, key: ref k, .. } => format!("Expected message metadata '{}' to have value '{}' but was '{}'",
+                Style::new().bold().paint(k.clone()), Red.paint(e.to_string()), Green.paint(a.to_string()))
         }
     }
 }
@@ -602,6 +774,10 @@ impl PartialEq for Mismatch {
                 &Mismatch::BodyMismatch{ path: ref p2, expected: ref e2, actual: ref a2, mismatch: _ }) => {
                 p1 == p2 && e1 == e2 && a1 == a2
             },
+            (&Mismatch::MetadataMismatch{ key: ref p1, expected: ref e1, actual: ref a1, mismatch: _ },
+                &Mismatch::MetadataMismatch{ key: ref p2, expected: ref e2, actual: ref a2, mismatch: _ }) => {
+                p1 == p2 && e1 == e2 && a1 == a2
+            },
             (_, _) => false
         }
     }
@@ -648,11 +824,10 @@ pub fn match_path(expected: String, actual: String, mismatches: &mut Vec<Mismatc
     }
 }
 
-fn compare_query_parameter_value(key: &String, expected: &String, actual: &String,
+fn compare_query_parameter_value(path: &Vec<String>, key: &String, expected: &String, actual: &String,
     mismatches: &mut Vec<Mismatch>, matchers: &Option<Matchers>) {
-    let path = vec![s!("$"), s!("query"), key.clone()];
-    let matcher_result = if matchers::matcher_is_defined(&path, matchers) {
-        matchers::match_values(&path, matchers.clone().unwrap(), expected, actual)
+    let matcher_result = if matchers::matcher_is_defined(path, matchers) {
+        matchers::match_values(path, matchers.clone().unwrap(), expected, actual)
     } else {
         expected.matches(actual, &Matcher::EqualityMatcher)
     };
@@ -666,21 +841,23 @@ fn compare_query_parameter_value(key: &String, expected: &String, actual: &Strin
     }
 }
 
-fn compare_query_parameter_values(key: &String, expected: &Vec<String>, actual: &Vec<String>,
-    mismatches: &mut Vec<Mismatch>, matchers: &Option<Matchers>) {
+fn compare_query_parameter_values(path: &Vec<String>, kind: &str, key: &String, expected: &Vec<String>,
+    actual: &Vec<String>, mismatches: &mut Vec<Mismatch>, matchers: &Option<Matchers>) {
     for (index, val) in expected.iter().enumerate() {
         if index < actual.len() {
-            compare_query_parameter_value(key, val, &actual[index], mismatches, matchers);
+            compare_query_parameter_value(path, key, val, &actual[index], mismatches, matchers);
         } else {
             mismatches.push(Mismatch::QueryMismatch { parameter: key.clone(),
                 expected: format!("{:?}", expected),
                 actual: format!("{:?}", actual),
-                mismatch: format!("Expected query parameter '{}' value '{}' but was missing", key, val) });
+                mismatch: format!("Expected {} '{}' value '{}' but was missing", kind, key, val) });
         }
     }
 }
 
-fn match_query_values(key: &String, expected: &Vec<String>, actual: &Vec<String>,
+/// Compares the values of a query parameter (or form parameter, as `kind` describes it) at the given
+/// path. Any mismatches are returned as `QueryMismatch`es.
+fn match_query_values(path: &Vec<String>, kind: &str, key: &String, expected: &Vec<String>, actual: &Vec<String>,
     mismatches: &mut Vec<Mismatch>, matchers: &Option<Matchers>) {
     if expected.is_empty() && !actual.is_empty() {
         mismatches.push(Mismatch::QueryMismatch { parameter: key.clone(),
@@ -693,25 +870,33 @@ fn match_query_values(key: &String, expected: &Vec<String>, actual: &Vec<String>
                 expected: format!("{:?}", expected),
                 actual: format!("{:?}", actual),
                 mismatch: format!(
-                    "Expected query parameter '{}' with {} value(s) but received {} value(s)",
-                    key, expected.len(), actual.len()) });
+                    "Expected {} '{}' with {} value(s) but received {} value(s)",
+                    kind, key, expected.len(), actual.len()) });
         }
-        compare_query_parameter_values(key, expected, actual, mismatches, matchers);
+        compare_query_parameter_values(path, kind, key, expected, actual, mismatches, matchers);
     }
 }
 
+/// Returns the entries of the map sorted by key, so mismatches are reported in a consistent order
+fn sorted_entries<T>(map: &HashMap<String, T>) -> Vec<(&String, &T)> {
+    let mut entries: Vec<(&String, &T)> = map.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
 fn match_query_maps(expected: HashMap<String, Vec<String>>, actual: HashMap<String, Vec<String>>,
     mismatches: &mut Vec<Mismatch>, matchers: &Option<Matchers>) {
-    for (key, value) in &expected {
+    for (key, value) in sorted_entries(&expected) {
         match actual.get(key) {
-            Some(actual_value) => match_query_values(key, value, actual_value, mismatches, matchers),
+            Some(actual_value) => match_query_values(&vec![s!("$"), s!("query"), key.clone()],
+                "query parameter", key, value, actual_value, mismatches, matchers),
             None => mismatches.push(Mismatch::QueryMismatch { parameter: key.clone(),
                 expected: format!("{:?}", value),
                 actual: "".to_string(),
                 mismatch: format!("Expected query parameter '{}' but was missing", key) })
         }
     }
-    for (key, value) in &actual {
+    for (key, value) in sorted_entries(&actual) {
         match expected.get(key) {
             Some(_) => (),
             None => mismatches.push(Mismatch::QueryMismatch { parameter: key.clone(),
@@ -728,13 +913,13 @@ pub fn match_query(expected: Option<HashMap<String, Vec<String>>>,
     matchers: &Option<Matchers>) {
     match (actual, expected) {
         (Some(aqm), Some(eqm)) => match_query_maps(eqm, aqm, mismatches, matchers),
-        (Some(aqm), None) => for (key, value) in &aqm {
+        (Some(aqm), None) => for (key, value) in sorted_entries(&aqm) {
             mismatches.push(Mismatch::QueryMismatch { parameter: key.clone(),
                 expected: "".to_string(),
                 actual: format!("{:?}", value),
                 mismatch: format!("Unexpected query parameter '{}' received", key) });
         },
-        (None, Some(eqm)) => for (key, value) in &eqm {
+        (None, Some(eqm)) => for (key, value) in sorted_entries(&eqm) {
             mismatches.push(Mismatch::QueryMismatch { parameter: key.clone(),
                 expected: format!("{:?}", value),
                 actual: "".to_string(),
@@ -813,7 +998,7 @@ fn find_entry<T>(map: &HashMap<String, T>, key: &String) -> Option<(String, T)>
 
 fn match_header_maps(expected: HashMap<String, String>, actual: HashMap<String, String>,
     mismatches: &mut Vec<Mismatch>, matchers: &Option<Matchers>) {
-    for (key, value) in &expected {
+    for (key, value) in sorted_entries(&expected) {
         match find_entry(&actual, key) {
             Some((_, actual_value)) => match_header_value(key, value, &actual_value, mismatches, matchers),
             None => mismatches.push(Mismatch::HeaderMismatch { key: key.clone(),
@@ -831,7 +1016,7 @@ pub fn match_headers(expected: Option<HashMap<String, String>>,
     match (actual, expected) {
         (Some(aqm), Some(eqm)) => match_header_maps(eqm, aqm, mismatches, matchers),
         (Some(_), None) => (),
-        (None, Some(eqm)) => for (key, value) in &eqm {
+        (None, Some(eqm)) => for (key, value) in sorted_entries(&eqm) {
             mismatches.push(Mismatch::HeaderMismatch { key: key.clone(),
                 expected: format!("{:?}", value),
                 actual: "".to_string(),
@@ -841,30 +1026,61 @@ pub fn match_headers(expected: Option<HashMap<String, String>>,
     };
 }
 
-fn compare_bodies(mimetype: String, expected: &String, actual: &String, config: DiffConfig,
+fn compare_bodies(mimetype: &String, expected: &Vec<u8>, actual: &Vec<u8>, config: DiffConfig,
     mismatches: &mut Vec<Mismatch>, matchers: &Option<Matchers>) {
-    match BODY_MATCHERS.iter().find(|mt| mt.0.is_match(&mimetype)) {
-        Some(ref match_fn) => match_fn.1(expected, actual, config, mismatches, matchers),
-        None => match_text(expected, actual, mismatches)
+    match body_matcher_for(mimetype) {
+        Some(body_matcher) => body_matcher.match_body(expected, actual, config, mismatches, matchers),
+        None => if models::is_binary_content_type(mimetype) {
+            match_binary(expected, actual, mismatches, matchers)
+        } else {
+            match_text(&String::from_utf8_lossy(expected).into_owned(),
+                &String::from_utf8_lossy(actual).into_owned(), mismatches)
+        }
+    }
+}
+
+/// Matches binary bodies. These are compared as bytes, unless there is a matcher defined for the
+/// body (i.e. a `contentType` matcher to check the magic bytes of the actual body).
+fn match_binary(expected: &Vec<u8>, actual: &Vec<u8>, mismatches: &mut Vec<Mismatch>,
+    matchers: &Option<Matchers>) {
+    let path = vec![s!("$"), s!("body")];
+    let matcher_result = if matchers::matcher_is_defined(&path, matchers) {
+        matchers::match_values(&path, matchers.clone().unwrap(), expected, actual)
+    } else {
+        expected.matches(actual, &Matcher::EqualityMatcher)
+    };
+    match matcher_result {
+        Err(message) => mismatches.push(Mismatch::BodyMismatch { path: s!("$.body"),
+            expected: Some(base64::encode(expected)),
+            actual: Some(base64::encode(actual)),
+            mismatch: message }),
+        Ok(_) => ()
     }
 }
 
 /// Matches the actual body to the expected one. This takes into account the content type of each.
 pub fn match_body(expected: &models::HttpPart, actual: &models::HttpPart, config: DiffConfig,
     mismatches: &mut Vec<Mismatch>, matchers: &Option<Matchers>) {
-    debug!("expected content type = '{}', actual content type = '{}'", expected.content_type(),
-           actual.content_type());
-    if expected.content_type() == actual.content_type() {
-        match (expected.body(), actual.body()) {
+    match_contents(expected.content_type(), expected.body(), actual.content_type(), actual.body(),
+        config, mismatches, matchers);
+}
+
+fn match_contents(expected_content_type: String, expected: &models::OptionalBody,
+    actual_content_type: String, actual: &models::OptionalBody, config: DiffConfig,
+    mismatches: &mut Vec<Mismatch>, matchers: &Option<Matchers>) {
+    debug!("expected content type = '{}', actual content type = '{}'", expected_content_type,
+           actual_content_type);
+    if expected_content_type == actual_content_type {
+        match (expected, actual) {
             (&models::OptionalBody::Missing, _) => (),
-            (&models::OptionalBody::Null, &models::OptionalBody::Present(ref b)) => {
-                mismatches.push(Mismatch::BodyMismatch { expected: None, actual: Some(b.clone()),
-                    mismatch: format!("Expected empty body but received '{}'", b.clone()),
+            (&models::OptionalBody::Null, &models::OptionalBody::Present(_)) => {
+                mismatches.push(Mismatch::BodyMismatch { expected: None, actual: Some(actual.value()),
+                    mismatch: format!("Expected empty body but received '{}'", actual.value()),
                     path: s!("/")});
             },
-            (&models::OptionalBody::Empty, &models::OptionalBody::Present(ref b)) => {
-                mismatches.push(Mismatch::BodyMismatch { expected: None, actual: Some(b.clone()),
-                    mismatch: format!("Expected empty body but received '{}'", b.clone()),
+            (&models::OptionalBody::Empty, &models::OptionalBody::Present(_)) => {
+                mismatches.push(Mismatch::BodyMismatch { expected: None, actual: Some(actual.value()),
+                    mismatch: format!("Expected empty body but received '{}'", actual.value()),
                     path: s!("/")});
             },
             (&models::OptionalBody::Null, _) => (),
@@ -875,13 +1091,13 @@ pub fn match_body(expected: &models::HttpPart, actual: &models::HttpPart, config
                     path: s!("/")});
             },
             (_, _) => {
-                compare_bodies(expected.content_type(), &expected.body().value(), &actual.body().value(),
+                compare_bodies(&expected_content_type, &expected.bytes(), &actual.bytes(),
                     config, mismatches, matchers);
             }
         }
-    } else if expected.body().is_present() {
-        mismatches.push(Mismatch::BodyTypeMismatch { expected: expected.content_type(),
-            actual: actual.content_type() });
+    } else if expected.is_present() {
+        mismatches.push(Mismatch::BodyTypeMismatch { expected: expected_content_type,
+            actual: actual_content_type });
     }
 }
 
@@ -919,6 +1135,54 @@ pub fn match_response(expected: models::Response, actual: models::Response) -> V
     mismatches
 }
 
+fn match_metadata_value(key: &String, expected: &String, actual: &String, mismatches: &mut Vec<Mismatch>,
+    matchers: &Option<Matchers>) {
+    let path = vec![s!("$"), s!("metadata"), key.clone()];
+    let matcher_result = if matchers::matcher_is_defined(&path, matchers) {
+        matchers::match_values(&path, matchers.clone().unwrap(), expected, actual)
+    } else {
+        expected.matches(actual, &Matcher::EqualityMatcher)
+    };
+    match matcher_result {
+        Err(_) => mismatches.push(Mismatch::MetadataMismatch { key: key.clone(),
+                expected: expected.clone(),
+                actual: actual.clone(),
+                mismatch: format!("Expected message metadata '{}' to have value '{}' but was '{}'", key, expected, actual)}),
+        Ok(_) => ()
+    }
+}
+
+/// Matches the actual message metadata to the expected one. The content type is ignored, as
+/// it is checked when the message contents are matched.
+pub fn match_metadata(expected: &HashMap<String, String>, actual: &HashMap<String, String>,
+    mismatches: &mut Vec<Mismatch>, matchers: &Option<Matchers>) {
+    for (key, value) in sorted_entries(expected) {
+        let lowercase_key = key.to_lowercase();
+        if lowercase_key != "contenttype" && lowercase_key != "content-type" {
+            match find_entry(actual, key) {
+                Some((_, actual_value)) => match_metadata_value(key, value, &actual_value, mismatches, matchers),
+                None => mismatches.push(Mismatch::MetadataMismatch { key: key.clone(),
+                    expected: value.clone(),
+                    actual: s!(""),
+                    mismatch: format!("Expected message metadata '{}' but was missing", key) })
+            }
+        }
+    }
+}
+
+/// Matches the expected and actual messages.
+pub fn match_message(expected: models::Message, actual: models::Message) -> Vec<Mismatch> {
+    let mut mismatches = vec![];
+
+    info!("comparing to expected message: {:?}", expected);
+    match_contents(expected.content_type(), &expected.contents, actual.content_type(), &actual.contents,
+        DiffConfig::AllowUnexpectedKeys, &mut mismatches, &expected.matching_rules);
+    match_metadata(&expected.metadata, &actual.metadata, &mut mismatches, &expected.matching_rules);
+
+    debug!("--> Mismatches: {:?}", mismatches);
+    mismatches
+}
+
 #[cfg(test)]
 #[macro_use(expect)]
 extern crate expectest;
@@ -1,4 +1,6 @@
-use models::Matchers;
+use models::{Matchers, RuleLogic, OptionalBody, detect_content_type_from_body};
+use time_utils::*;
+use std::collections::HashMap;
 use path_exp::*;
 use itertools::Itertools;
 use regex::Regex;
@@ -50,9 +52,20 @@ fn path_length(path_exp: String) -> usize {
     }
 }
 
+fn is_collection_rule(rule: &HashMap<String, String>) -> bool {
+    match rule.get("match") {
+        Some(val) => val == "arrayContains" || val == "unordered" || val == "eachKey" || val == "eachValue",
+        None => false
+    }
+}
+
+// collection matchers (array contains, each key and each value) only apply to the list or map
+// they are defined for, and are not inherited by the items of the collection
 fn resolve_matchers(path: &Vec<String>, matchers: &Matchers) -> Matchers {
     matchers.iter().map(|(k, v)| (k.clone(), v.clone()))
-        .filter(|kv| calc_path_weight(kv.0.clone(), path) > 0).collect()
+        .filter(|kv| calc_path_weight(kv.0.clone(), path) > 0)
+        .filter(|kv| path_length(kv.0.clone()) == path.len() || !kv.1.rules.iter().any(is_collection_rule))
+        .collect()
 }
 
 pub fn matcher_is_defined(path: &Vec<String>, matchers: &Option<Matchers>) -> bool {
@@ -62,11 +75,50 @@ pub fn matcher_is_defined(path: &Vec<String>, matchers: &Option<Matchers>) -> bo
     }
 }
 
+/// Returns the collection matchers (`arrayContains`, `unordered`, `eachKey` and `eachValue`)
+/// defined for the list or map at the path.
+pub fn collection_matchers(path: &Vec<String>, matchers: &Option<Matchers>) -> Vec<Matcher> {
+    match *matchers {
+        Some(ref m) => {
+            let path_str = path.iter().join(".");
+            m.iter()
+                .filter(|&(k, _)| calc_path_weight(k.clone(), path) > 0 && path_length(k.clone()) == path.len())
+                .flat_map(|(_, rules)| rules.rules.iter().filter(|rule| is_collection_rule(rule)))
+                .filter_map(|rule| matcher_from_rule(&path_str, rule).ok())
+                .collect()
+        },
+        None => vec![]
+    }
+}
+
+/// Returns the array contains matcher (`arrayContains` or `unordered`) defined for the list at the
+/// path, if there is one.
+pub fn array_contains_matcher(path: &Vec<String>, matchers: &Option<Matchers>) -> Option<Matcher> {
+    collection_matchers(path, matchers).into_iter().find(|matcher| match *matcher {
+        Matcher::ArrayContainsMatcher | Matcher::UnorderedMatcher => true,
+        _ => false
+    })
+}
+
+/// Returns the `eachKey` matcher defined for the map at the path, if there is one.
+pub fn each_key_matcher(path: &Vec<String>, matchers: &Option<Matchers>) -> Option<Matcher> {
+    collection_matchers(path, matchers).into_iter().find(|matcher| match *matcher {
+        Matcher::EachKeyMatcher(_) => true,
+        _ => false
+    })
+}
+
+/// Returns true if there is a wildcard matcher (a path ending in `.*`) defined for the path, or an
+/// `eachValue` matcher defined for the parent map.
 pub fn wildcard_matcher_is_defined(path: &Vec<String>, matchers: &Option<Matchers>) -> bool {
     match *matchers {
         Some(ref m) => m.iter().map(|(k, _)| k.clone())
             .filter(|k| calc_path_weight(k.clone(), path) > 0 && path_length(k.clone()) == path.len())
-            .any(|k| k.ends_with(".*")),
+            .any(|k| k.ends_with(".*")) ||
+            match path.split_last() {
+                Some((_, parent)) => collection_matchers(&parent.to_vec(), matchers).contains(&Matcher::EachValueMatcher),
+                None => false
+            },
         None => false
     }
 }
@@ -77,7 +129,45 @@ pub enum Matcher {
     RegexMatcher(Regex),
     TypeMatcher,
     MinTypeMatcher(usize),
-    MaxTypeMatcher(usize)
+    MaxTypeMatcher(usize),
+    MinMaxTypeMatcher(usize, usize),
+    IntegerMatcher,
+    DecimalMatcher,
+    NumberMatcher,
+    TimestampMatcher(String),
+    DateMatcher(String),
+    TimeMatcher(String),
+    IncludeMatcher(String),
+    NullMatcher,
+    BooleanMatcher,
+    ContentTypeMatcher(String),
+    ArrayContainsMatcher,
+    UnorderedMatcher,
+    EachKeyMatcher(Option<Regex>),
+    EachValueMatcher
+}
+
+/// Matches a string value against one of the format matchers (date/time, include, content type),
+/// returning `None` if the matcher is not one of them.
+pub fn match_string_format(actual: &String, matcher: &Matcher) -> Option<Result<(), String>> {
+    match *matcher {
+        Matcher::TimestampMatcher(ref format) | Matcher::DateMatcher(ref format) | Matcher::TimeMatcher(ref format) =>
+            Some(validate_datetime(actual, format)),
+        Matcher::IncludeMatcher(ref value) => Some(if actual.contains(value) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' to include '{}'", actual, value))
+        }),
+        Matcher::ContentTypeMatcher(ref content_type) => {
+            let detected = detect_content_type_from_body(&OptionalBody::Present(actual.clone().into_bytes()));
+            Some(if detected == *content_type {
+                Ok(())
+            } else {
+                Err(format!("Expected value with content type '{}' but detected '{}'", content_type, detected))
+            })
+        },
+        _ => None
+    }
 }
 
 pub trait Matches<A> {
@@ -95,14 +185,38 @@ impl Matches<String> for String {
                    Err(format!("Expected '{}' to match '{}'", actual, regex))
                }
            },
-           Matcher::TypeMatcher | Matcher::MinTypeMatcher(_) | Matcher::MaxTypeMatcher(_) => Ok(()),
+           Matcher::TypeMatcher | Matcher::MinTypeMatcher(_) | Matcher::MaxTypeMatcher(_) |
+           Matcher::MinMaxTypeMatcher(_, _) => Ok(()),
            Matcher::EqualityMatcher => {
                if self == actual {
                    Ok(())
                } else {
                    Err(format!("Expected '{}' to be equal to '{}'", self, actual))
                }
-           }
+           },
+           Matcher::IntegerMatcher => if actual.parse::<i64>().is_ok() {
+               Ok(())
+           } else {
+               Err(format!("Expected '{}' to be an integer value", actual))
+           },
+           Matcher::DecimalMatcher => if actual.contains('.') && actual.parse::<f64>().is_ok() {
+               Ok(())
+           } else {
+               Err(format!("Expected '{}' to be a decimal value", actual))
+           },
+           Matcher::NumberMatcher => if actual.parse::<f64>().is_ok() {
+               Ok(())
+           } else {
+               Err(format!("Expected '{}' to be a number", actual))
+           },
+           Matcher::NullMatcher => Err(format!("Expected '{}' to be a null value", actual)),
+           Matcher::BooleanMatcher => if actual == "true" || actual == "false" {
+               Ok(())
+           } else {
+               Err(format!("Expected '{}' to be a boolean value", actual))
+           },
+           _ => match_string_format(actual, matcher)
+               .unwrap_or(Err(format!("Unable to match '{}' using {:?}", actual, matcher)))
        }
     }
 }
@@ -118,9 +232,11 @@ impl Matches<u64> for String {
                    Err(format!("Expected '{}' to match '{}'", actual, regex))
                }
            },
-           Matcher::TypeMatcher | Matcher::MinTypeMatcher(_) | Matcher::MaxTypeMatcher(_) => Err(
+           Matcher::TypeMatcher | Matcher::MinTypeMatcher(_) | Matcher::MaxTypeMatcher(_) |
+           Matcher::MinMaxTypeMatcher(_, _) => Err(
                format!("Expected '{}' (String) to be the same type as '{}' (Number)", self, actual)),
-           Matcher::EqualityMatcher => Err(format!("Expected '{}' (String) to be equal to '{}' (Number)", self, actual))
+           Matcher::EqualityMatcher => Err(format!("Expected '{}' (String) to be equal to '{}' (Number)", self, actual)),
+           _ => match_integer_value(actual, matcher)
        }
     }
 }
@@ -136,14 +252,16 @@ impl Matches<u64> for u64 {
                    Err(format!("Expected '{}' to match '{}'", actual, regex))
                }
            },
-           Matcher::TypeMatcher | Matcher::MinTypeMatcher(_) | Matcher::MaxTypeMatcher(_) => Ok(()),
+           Matcher::TypeMatcher | Matcher::MinTypeMatcher(_) | Matcher::MaxTypeMatcher(_) |
+           Matcher::MinMaxTypeMatcher(_, _) => Ok(()),
            Matcher::EqualityMatcher => {
                if self == actual {
                    Ok(())
                } else {
                    Err(format!("Expected '{}' to be equal to '{}'", self, actual))
                }
-           }
+           },
+           _ => match_integer_value(actual, matcher)
        }
     }
 }
@@ -159,9 +277,11 @@ impl Matches<f64> for u64 {
                    Err(format!("Expected '{}' to match '{}'", actual, regex))
                }
            },
-           Matcher::TypeMatcher | Matcher::MinTypeMatcher(_) | Matcher::MaxTypeMatcher(_) => Err(
+           Matcher::TypeMatcher | Matcher::MinTypeMatcher(_) | Matcher::MaxTypeMatcher(_) |
+           Matcher::MinMaxTypeMatcher(_, _) => Err(
                format!("Expected '{}' (Integer) to be the same type as '{}' (Decimal)", self, actual)),
-           Matcher::EqualityMatcher => Err(format!("Expected '{}' (Integer) to be equal to '{}' (Decimal)", self, actual))
+           Matcher::EqualityMatcher => Err(format!("Expected '{}' (Integer) to be equal to '{}' (Decimal)", self, actual)),
+           _ => match_decimal_value(actual, matcher)
        }
     }
 }
@@ -177,14 +297,16 @@ impl Matches<f64> for f64 {
                    Err(format!("Expected '{}' to match '{}'", actual, regex))
                }
            },
-           Matcher::TypeMatcher | Matcher::MinTypeMatcher(_) | Matcher::MaxTypeMatcher(_) => Ok(()),
+           Matcher::TypeMatcher | Matcher::MinTypeMatcher(_) | Matcher::MaxTypeMatcher(_) |
+           Matcher::MinMaxTypeMatcher(_, _) => Ok(()),
            Matcher::EqualityMatcher => {
                if self == actual {
                    Ok(())
                } else {
                    Err(format!("Expected '{}' to be equal to '{}'", self, actual))
                }
-           }
+           },
+           _ => match_decimal_value(actual, matcher)
        }
     }
 }
@@ -200,131 +322,249 @@ impl Matches<u64> for f64 {
                    Err(format!("Expected '{}' to match '{}'", actual, regex))
                }
            },
-           Matcher::TypeMatcher | Matcher::MinTypeMatcher(_) | Matcher::MaxTypeMatcher(_) => Err(
+           Matcher::TypeMatcher | Matcher::MinTypeMatcher(_) | Matcher::MaxTypeMatcher(_) |
+           Matcher::MinMaxTypeMatcher(_, _) => Err(
                format!("Expected '{}' (Decimal) to be the same type as '{}' (Integer)", self, actual)),
-           Matcher::EqualityMatcher => Err(format!("Expected '{}' (Decimal) to be equal to '{}' (Integer)", self, actual))
+           Matcher::EqualityMatcher => Err(format!("Expected '{}' (Decimal) to be equal to '{}' (Integer)", self, actual)),
+           _ => match_integer_value(actual, matcher)
        }
     }
 }
 
-fn select_best_matcher(path: &Vec<String>, matchers: &Matchers) -> Result<Matcher, String> {
-    let path_str = path.iter().join(".");
-    let matcher = match matchers.iter().max_by_key(|&(k, _)| calc_path_weight(k.clone(), path)) {
-        Some(kv) => {
-            match kv.1.get("match") {
-                Some(val) => {
-                    match val.as_str() {
-                        "regex" => {
-                            match kv.1.get("regex") {
-                                Some(regex) => {
-                                    match Regex::new(regex) {
-                                        Ok(regex) => Ok(Matcher::RegexMatcher(regex)),
-                                        Err(err) => {
-                                            error!("Failed to compile regular expression '{}' provided for regex matcher for path '{}' - {}",
-                                                regex, path_str, err);
-                                            Err(format!("Failed to compile regular expression '{}' provided for regex matcher for path '{}' - {}",
-                                                regex, path_str, err))
-                                        }
-                                    }
-                                },
-                                None => {
-                                    error!("No regular expression provided for regex matcher for path '{}'",
-                                        path_str);
-                                    Err(format!("No regular expression provided for regex matcher for path '{}'",
-                                        path_str))
-                                }
-                            }
-                        },
-                        "type" => if kv.1.contains_key("min") {
-                            let min = kv.1.get("min").unwrap();
-                            match min.parse() {
-                                Ok(min) => Ok(Matcher::MinTypeMatcher(min)),
-                                Err(err) => {
-                                    warn!("Failed to parse minimum value '{}', defaulting to type matcher - {}", min, err);
-                                    Ok(Matcher::TypeMatcher)
-                                }
-                            }
-                        } else if kv.1.contains_key("max") {
-                            let max = kv.1.get("max").unwrap();
-                            match max.parse() {
-                                Ok(max) => Ok(Matcher::MaxTypeMatcher(max)),
+impl Matches<Vec<u8>> for Vec<u8> {
+    fn matches(&self, actual: &Vec<u8>, matcher: &Matcher) -> Result<(), String> {
+        debug!("comparing {} bytes to {} bytes using {:?}", self.len(), actual.len(), matcher);
+        match *matcher {
+           Matcher::TypeMatcher => Ok(()),
+           Matcher::EqualityMatcher => {
+               if self == actual {
+                   Ok(())
+               } else {
+                   Err(format!("Expected binary contents of {} bytes to be equal to the expected {} bytes",
+                       actual.len(), self.len()))
+               }
+           },
+           Matcher::ContentTypeMatcher(ref content_type) => {
+               let detected = detect_content_type_from_body(&OptionalBody::Present(actual.clone()));
+               if detected == *content_type {
+                   Ok(())
+               } else {
+                   Err(format!("Expected binary contents with content type '{}' but detected '{}'", content_type, detected))
+               }
+           },
+           _ => Err(format!("Unable to match binary contents using {:?}", matcher))
+       }
+    }
+}
+
+fn match_integer_value(actual: &u64, matcher: &Matcher) -> Result<(), String> {
+    match *matcher {
+        Matcher::IntegerMatcher | Matcher::NumberMatcher => Ok(()),
+        Matcher::DecimalMatcher => Err(format!("Expected {} to be a decimal value", actual)),
+        Matcher::NullMatcher => Err(format!("Expected {} to be a null value", actual)),
+        Matcher::BooleanMatcher => Err(format!("Expected {} to be a boolean value", actual)),
+        _ => match_string_format(&actual.to_string(), matcher)
+            .unwrap_or(Err(format!("Unable to match {} using {:?}", actual, matcher)))
+    }
+}
+
+fn match_decimal_value(actual: &f64, matcher: &Matcher) -> Result<(), String> {
+    match *matcher {
+        Matcher::DecimalMatcher | Matcher::NumberMatcher => Ok(()),
+        Matcher::IntegerMatcher => Err(format!("Expected {} to be an integer value", actual)),
+        Matcher::NullMatcher => Err(format!("Expected {} to be a null value", actual)),
+        Matcher::BooleanMatcher => Err(format!("Expected {} to be a boolean value", actual)),
+        _ => match_string_format(&actual.to_string(), matcher)
+            .unwrap_or(Err(format!("Unable to match {} using {:?}", actual, matcher)))
+    }
+}
+
+fn parse_limit(rule: &HashMap<String, String>, name: &str) -> Option<usize> {
+    match rule.get(name) {
+        Some(value) => match value.parse() {
+            Ok(limit) => Some(limit),
+            Err(err) => {
+                warn!("Failed to parse {} value '{}', ignoring it - {}", name, value, err);
+                None
+            }
+        },
+        None => None
+    }
+}
+
+fn type_matcher_from_rule(rule: &HashMap<String, String>) -> Result<Matcher, String> {
+    match (parse_limit(rule, "min"), parse_limit(rule, "max")) {
+        (Some(min), Some(max)) => Ok(Matcher::MinMaxTypeMatcher(min, max)),
+        (Some(min), None) => Ok(Matcher::MinTypeMatcher(min)),
+        (None, Some(max)) => Ok(Matcher::MaxTypeMatcher(max)),
+        (None, None) => Ok(Matcher::TypeMatcher)
+    }
+}
+
+fn format_from_rule(path_str: &String, rule: &HashMap<String, String>, name: &str, default: &str) -> Result<String, String> {
+    let format = rule.get(name).or(rule.get("format")).cloned().unwrap_or(s!(default));
+    match to_regex_pattern(&format) {
+        Ok(_) => Ok(format),
+        Err(err) => {
+            error!("Invalid format provided for {} matcher for path '{}' - {}", name, path_str, err);
+            Err(format!("Invalid format provided for {} matcher for path '{}' - {}", name, path_str, err))
+        }
+    }
+}
+
+fn matcher_from_rule(path_str: &String, rule: &HashMap<String, String>) -> Result<Matcher, String> {
+    match rule.get("match") {
+        Some(val) => {
+            match val.as_str() {
+                "regex" => {
+                    match rule.get("regex") {
+                        Some(regex) => {
+                            match Regex::new(regex) {
+                                Ok(regex) => Ok(Matcher::RegexMatcher(regex)),
                                 Err(err) => {
-                                    warn!("Failed to parse maximum value '{}', defaulting to type matcher - {}", max, err);
-                                    Ok(Matcher::TypeMatcher)
+                                    error!("Failed to compile regular expression '{}' provided for regex matcher for path '{}' - {}",
+                                        regex, path_str, err);
+                                    Err(format!("Failed to compile regular expression '{}' provided for regex matcher for path '{}' - {}",
+                                        regex, path_str, err))
                                 }
                             }
-                        } else {
-                            Ok(Matcher::TypeMatcher)
                         },
-                        _ => {
-                            warn!("Unrecognised matcher type '{}' for path '{}', defaulting to equality",
-                                val, path_str);
-                            Ok(Matcher::EqualityMatcher)
+                        None => {
+                            error!("No regular expression provided for regex matcher for path '{}'",
+                                path_str);
+                            Err(format!("No regular expression provided for regex matcher for path '{}'",
+                                path_str))
                         }
                     }
                 },
-                None => {
-                    warn!("Matcher defined for path '{}' does not have an explicit 'match' attribute, falling back to equality, type or regular expression matching",
-                        path_str);
-                    if kv.1.contains_key("regex") {
-                        let regex = kv.1.get("regex").unwrap();
-                        match Regex::new(regex) {
-                            Ok(regex) => Ok(Matcher::RegexMatcher(regex)),
-                            Err(err) => {
-                                error!("Failed to compile regular expression '{}' provided for regex matcher for path '{}' - {}",
-                                    regex, path_str, err);
-                                Err(format!("Failed to compile regular expression '{}' provided for regex matcher for path '{}' - {}",
-                                    regex, path_str, err))
-                            }
-                        }
-                    } else if kv.1.contains_key("min") {
-                        let min = kv.1.get("min").unwrap();
-                        match min.parse() {
-                            Ok(min) => Ok(Matcher::MinTypeMatcher(min)),
-                            Err(err) => {
-                                warn!("Failed to parse minimum value '{}', defaulting to type matcher - {}", min, err);
-                                Ok(Matcher::TypeMatcher)
-                            }
-                        }
-                    } else if kv.1.contains_key("max") {
-                        let max = kv.1.get("max").unwrap();
-                        match max.parse() {
-                            Ok(max) => Ok(Matcher::MaxTypeMatcher(max)),
-                            Err(err) => {
-                                warn!("Failed to parse maximum value '{}', defaulting to type matcher - {}", max, err);
-                                Ok(Matcher::TypeMatcher)
-                            }
+                "type" => type_matcher_from_rule(rule),
+                "equality" => Ok(Matcher::EqualityMatcher),
+                "integer" => Ok(Matcher::IntegerMatcher),
+                "decimal" | "real" => Ok(Matcher::DecimalMatcher),
+                "number" => Ok(Matcher::NumberMatcher),
+                "null" => Ok(Matcher::NullMatcher),
+                "boolean" => Ok(Matcher::BooleanMatcher),
+                "timestamp" | "datetime" => format_from_rule(path_str, rule, "timestamp", DEFAULT_TIMESTAMP_FORMAT)
+                    .map(|format| Matcher::TimestampMatcher(format)),
+                "date" => format_from_rule(path_str, rule, "date", DEFAULT_DATE_FORMAT)
+                    .map(|format| Matcher::DateMatcher(format)),
+                "time" => format_from_rule(path_str, rule, "time", DEFAULT_TIME_FORMAT)
+                    .map(|format| Matcher::TimeMatcher(format)),
+                "include" => match rule.get("value") {
+                    Some(value) => Ok(Matcher::IncludeMatcher(value.clone())),
+                    None => {
+                        error!("No value provided for include matcher for path '{}'", path_str);
+                        Err(format!("No value provided for include matcher for path '{}'", path_str))
+                    }
+                },
+                "arrayContains" => Ok(Matcher::ArrayContainsMatcher),
+                "unordered" => Ok(Matcher::UnorderedMatcher),
+                "eachKey" => match rule.get("regex") {
+                    Some(regex) => match Regex::new(regex) {
+                        Ok(regex) => Ok(Matcher::EachKeyMatcher(Some(regex))),
+                        Err(err) => {
+                            error!("Failed to compile regular expression '{}' provided for eachKey matcher for path '{}' - {}",
+                                regex, path_str, err);
+                            Err(format!("Failed to compile regular expression '{}' provided for eachKey matcher for path '{}' - {}",
+                                regex, path_str, err))
                         }
-                    } else {
-                        error!("Invalid matcher definition {:?} for path '{}'", kv.1, path_str);
-                        Err(format!("Invalid matcher definition {:?} for path '{}'", kv.1, path_str))
+                    },
+                    None => Ok(Matcher::EachKeyMatcher(None))
+                },
+                "eachValue" => Ok(Matcher::EachValueMatcher),
+                "contentType" => match rule.get("value") {
+                    Some(value) => Ok(Matcher::ContentTypeMatcher(value.clone())),
+                    None => {
+                        error!("No content type provided for contentType matcher for path '{}'", path_str);
+                        Err(format!("No content type provided for contentType matcher for path '{}'", path_str))
+                    }
+                },
+                _ => {
+                    warn!("Unrecognised matcher type '{}' for path '{}', defaulting to equality",
+                        val, path_str);
+                    Ok(Matcher::EqualityMatcher)
+                }
+            }
+        },
+        None => {
+            warn!("Matcher defined for path '{}' does not have an explicit 'match' attribute, falling back to equality, type or regular expression matching",
+                path_str);
+            if rule.contains_key("regex") {
+                let regex = rule.get("regex").unwrap();
+                match Regex::new(regex) {
+                    Ok(regex) => Ok(Matcher::RegexMatcher(regex)),
+                    Err(err) => {
+                        error!("Failed to compile regular expression '{}' provided for regex matcher for path '{}' - {}",
+                            regex, path_str, err);
+                        Err(format!("Failed to compile regular expression '{}' provided for regex matcher for path '{}' - {}",
+                            regex, path_str, err))
                     }
                 }
+            } else if rule.contains_key("min") || rule.contains_key("max") {
+                type_matcher_from_rule(rule)
+            } else {
+                error!("Invalid matcher definition {:?} for path '{}'", rule, path_str);
+                Err(format!("Invalid matcher definition {:?} for path '{}'", rule, path_str))
+            }
+        }
+    }
+}
+
+fn select_best_matchers(path: &Vec<String>, matchers: &Matchers) -> Result<(Vec<Matcher>, RuleLogic), String> {
+    let path_str = path.iter().join(".");
+    let result = match resolve_matchers(path, matchers).iter().max_by_key(|&(k, _)| calc_path_weight(k.clone(), path)) {
+        Some(kv) => {
+            let mut rules = vec![];
+            for rule in &kv.1.rules {
+                rules.push(try!(matcher_from_rule(&path_str, rule)));
+            }
+            if rules.is_empty() {
+                warn!("Matcher defined for path '{}' does not have any matching rules, defaulting to equality",
+                    path_str);
+                rules.push(Matcher::EqualityMatcher);
             }
+            Ok((rules, kv.1.rule_logic.clone()))
         },
         None => {
             warn!("Could not find an appropriate matcher for path '{}', defaulting to equality",
                 path_str);
-            Ok(Matcher::EqualityMatcher)
+            Ok((vec![Matcher::EqualityMatcher], RuleLogic::And))
         }
     };
-    debug!("Using Matcher for path '{}': {:?}", path_str, matcher);
-    matcher
+    debug!("Using Matchers for path '{}': {:?}", path_str, result);
+    result
 }
 
 pub fn match_values<E, A>(path: &Vec<String>, matchers: Matchers, expected: &E, actual: &A) -> Result<(), String>
     where E: Matches<A> {
-    let matcher = select_best_matcher(path, &matchers);
-    match matcher {
+    match select_best_matchers(path, &matchers) {
         Err(err) => Err(format!("Matcher for path '{}' is invalid - {}", path.iter().join("."), err)),
-        Ok(ref matcher) => expected.matches(actual, matcher)
+        Ok((ref rules, ref rule_logic)) => {
+            let results = rules.iter().map(|matcher| expected.matches(actual, matcher)).collect::<Vec<Result<(), String>>>();
+            match *rule_logic {
+                RuleLogic::And => {
+                    let errors = results.iter().filter_map(|result| result.clone().err()).collect::<Vec<String>>();
+                    if errors.is_empty() {
+                        Ok(())
+                    } else {
+                        Err(errors.join(", "))
+                    }
+                },
+                RuleLogic::Or => if results.iter().any(|result| result.is_ok()) {
+                    Ok(())
+                } else {
+                    Err(results.iter().filter_map(|result| result.clone().err()).join(", or "))
+                }
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use super::{calc_path_weight, matches_token, select_best_matcher};
+    use super::{calc_path_weight, matches_token, select_best_matchers};
+    use models::{RuleList, RuleLogic};
     use expectest::prelude::*;
     use path_exp::*;
     use regex::Regex;
@@ -342,14 +582,14 @@ mod tests {
     #[test]
     fn matcher_is_defined_returns_true_when_the_path_does_have_a_matcher_entry() {
         expect!(matcher_is_defined(&vec![s!("$"), s!("a"), s!("b")], &Some(hashmap!{
-            s!("$.a.b") => hashmap!{}
+            s!("$.a.b") => RuleList::new(hashmap!{})
         }))).to(be_true());
     }
 
     #[test]
     fn matcher_is_defined_returns_true_when_the_parent_of_the_path_does_have_a_matcher_entry() {
         expect!(matcher_is_defined(&vec![s!("$"), s!("a"), s!("b"), s!("c")], &Some(hashmap!{
-            s!("$.a.b") => hashmap!{}
+            s!("$.a.b") => RuleList::new(hashmap!{})
         }))).to(be_true());
     }
 
@@ -366,25 +606,52 @@ mod tests {
     #[test]
     fn wildcard_matcher_is_defined_returns_false_when_the_path_does_have_a_matcher_entry_and_it_is_not_a_wildcard() {
         expect!(wildcard_matcher_is_defined(&vec![s!("$"), s!("a"), s!("b")], &Some(hashmap!{
-            s!("$.a.b") => hashmap!{},
-            s!("$.*") => hashmap!{}
+            s!("$.a.b") => RuleList::new(hashmap!{}),
+            s!("$.*") => RuleList::new(hashmap!{})
         }))).to(be_false());
     }
 
     #[test]
     fn wildcard_matcher_is_defined_returns_true_when_the_path_does_have_a_matcher_entry_and_it_is_a_widcard() {
         expect!(wildcard_matcher_is_defined(&vec![s!("$"), s!("a"), s!("b")], &Some(hashmap!{
-            s!("$.a.*") => hashmap!{}
+            s!("$.a.*") => RuleList::new(hashmap!{})
         }))).to(be_true());
     }
 
     #[test]
     fn wildcard_matcher_is_defined_returns_false_when_the_parent_of_the_path_does_have_a_matcher_entry() {
         expect!(wildcard_matcher_is_defined(&vec![s!("$"), s!("a"), s!("b"), s!("c")], &Some(hashmap!{
-            s!("$.a.*") => hashmap!{}
+            s!("$.a.*") => RuleList::new(hashmap!{})
+        }))).to(be_false());
+    }
+
+    #[test]
+    fn wildcard_matcher_is_defined_returns_true_when_the_parent_of_the_path_has_an_each_value_matcher() {
+        expect!(wildcard_matcher_is_defined(&vec![s!("$"), s!("a"), s!("b")], &Some(hashmap!{
+            s!("$.a") => RuleList::new(hashmap!{ s!("match") => s!("eachValue") })
+        }))).to(be_true());
+        expect!(wildcard_matcher_is_defined(&vec![s!("$"), s!("a"), s!("b"), s!("c")], &Some(hashmap!{
+            s!("$.a") => RuleList::new(hashmap!{ s!("match") => s!("eachValue") })
         }))).to(be_false());
     }
 
+    #[test]
+    fn each_key_and_each_value_matchers_are_not_inherited_by_children() {
+        let matchers = Some(hashmap!{
+            s!("$.a") => RuleList::new(hashmap!{ s!("match") => s!("eachKey"), s!("regex") => s!("^x") })
+        });
+        expect!(each_key_matcher(&vec![s!("$"), s!("a")], &matchers)).to(be_some());
+        expect!(each_key_matcher(&vec![s!("$"), s!("a"), s!("b")], &matchers)).to(be_none());
+        expect!(matcher_is_defined(&vec![s!("$"), s!("a"), s!("b")], &matchers)).to(be_false());
+    }
+
+    #[test]
+    fn matcher_from_rule_with_each_key_requires_a_valid_regex() {
+        expect!(matcher_from_rule(&s!("$.a"), &hashmap!{ s!("match") => s!("eachKey") })).to(be_ok().value(Matcher::EachKeyMatcher(None)));
+        expect!(matcher_from_rule(&s!("$.a"), &hashmap!{ s!("match") => s!("eachKey"), s!("regex") => s!("[") })).to(be_err());
+        expect!(matcher_from_rule(&s!("$.a"), &hashmap!{ s!("match") => s!("eachValue") })).to(be_ok().value(Matcher::EachValueMatcher));
+    }
+
     #[test]
     fn matches_token_test_with_root() {
         expect!(matches_token(&s!("$"), &PathToken::Root)).to(be_equal_to(2));
@@ -463,56 +730,85 @@ mod tests {
     #[test]
     fn select_best_matcher_selects_most_appropriate_by_weight() {
         let matchers = hashmap!{
-            s!("$") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("1") },
-            s!("$.body") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("2") },
-            s!("$.body.item1") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("3") },
-            s!("$.body.item2") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("4") },
-            s!("$.header.item1") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("5") },
-            s!("$.body.item1.level") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("6") },
-            s!("$.body.item1.level[1]") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("7") },
-            s!("$.body.item1.level[1].id") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("8") },
-            s!("$.body.item1.level[1].name") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("9") },
-            s!("$.body.item1.level[2]") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("10") },
-            s!("$.body.item1.level[2].id") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("11") },
-            s!("$.body.item1.level[*].id") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("12") },
-            s!("$.body.*.level[*].id") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("13") }
+            s!("$") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("1") }),
+            s!("$.body") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("2") }),
+            s!("$.body.item1") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("3") }),
+            s!("$.body.item2") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("4") }),
+            s!("$.header.item1") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("5") }),
+            s!("$.body.item1.level") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("6") }),
+            s!("$.body.item1.level[1]") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("7") }),
+            s!("$.body.item1.level[1].id") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("8") }),
+            s!("$.body.item1.level[1].name") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("9") }),
+            s!("$.body.item1.level[2]") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("10") }),
+            s!("$.body.item1.level[2].id") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("11") }),
+            s!("$.body.item1.level[*].id") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("12") }),
+            s!("$.body.*.level[*].id") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("13") })
         };
 
-        expect!(select_best_matcher(&vec![s!("$")], &matchers)).to(be_ok().value(Matcher::RegexMatcher(Regex::new("1").unwrap())));
-        expect!(select_best_matcher(&vec![s!("$"), s!("body")], &matchers)).to(be_ok().value(Matcher::RegexMatcher(Regex::new("2").unwrap())));
-        expect!(select_best_matcher(&vec![s!("$"), s!("a")], &matchers)).to(be_ok().value(Matcher::RegexMatcher(Regex::new("1").unwrap())));
+        expect!(select_best_matchers(&vec![s!("$")], &matchers)).to(be_ok().value((vec![Matcher::RegexMatcher(Regex::new("1").unwrap())], RuleLogic::And)));
+        expect!(select_best_matchers(&vec![s!("$"), s!("body")], &matchers)).to(be_ok().value((vec![Matcher::RegexMatcher(Regex::new("2").unwrap())], RuleLogic::And)));
+        expect!(select_best_matchers(&vec![s!("$"), s!("a")], &matchers)).to(be_ok().value((vec![Matcher::RegexMatcher(Regex::new("1").unwrap())], RuleLogic::And)));
 
-        expect!(select_best_matcher(&vec![s!("$"), s!("body"), s!("item1")], &matchers)).to(be_ok().value(Matcher::RegexMatcher(Regex::new("3").unwrap())));
-        expect!(select_best_matcher(&vec![s!("$"), s!("body"), s!("item2")], &matchers)).to(be_ok().value(Matcher::RegexMatcher(Regex::new("4").unwrap())));
-        expect!(select_best_matcher(&vec![s!("$"), s!("body"), s!("item3")], &matchers)).to(be_ok().value(Matcher::RegexMatcher(Regex::new("2").unwrap())));
+        expect!(select_best_matchers(&vec![s!("$"), s!("body"), s!("item1")], &matchers)).to(be_ok().value((vec![Matcher::RegexMatcher(Regex::new("3").unwrap())], RuleLogic::And)));
+        expect!(select_best_matchers(&vec![s!("$"), s!("body"), s!("item2")], &matchers)).to(be_ok().value((vec![Matcher::RegexMatcher(Regex::new("4").unwrap())], RuleLogic::And)));
+        expect!(select_best_matchers(&vec![s!("$"), s!("body"), s!("item3")], &matchers)).to(be_ok().value((vec![Matcher::RegexMatcher(Regex::new("2").unwrap())], RuleLogic::And)));
 
-        expect!(select_best_matcher(&vec![s!("$"), s!("header"), s!("item1")], &matchers)).to(be_ok().value(Matcher::RegexMatcher(Regex::new("5").unwrap())));
+        expect!(select_best_matchers(&vec![s!("$"), s!("header"), s!("item1")], &matchers)).to(be_ok().value((vec![Matcher::RegexMatcher(Regex::new("5").unwrap())], RuleLogic::And)));
 
-        expect!(select_best_matcher(&vec![s!("$"), s!("body"), s!("item1"), s!("level")], &matchers)).to(be_ok().value(Matcher::RegexMatcher(Regex::new("6").unwrap())));
-        expect!(select_best_matcher(&vec![s!("$"), s!("body"), s!("item1"), s!("level"), s!("1")], &matchers)).to(be_ok().value(Matcher::RegexMatcher(Regex::new("7").unwrap())));
-        expect!(select_best_matcher(&vec![s!("$"), s!("body"), s!("item1"), s!("level"), s!("2")], &matchers)).to(be_ok().value(Matcher::RegexMatcher(Regex::new("10").unwrap())));
-        expect!(select_best_matcher(&vec![s!("$"), s!("body"), s!("item1"), s!("level"), s!("1"), s!("id")], &matchers)).to(be_ok().value(Matcher::RegexMatcher(Regex::new("8").unwrap())));
-        expect!(select_best_matcher(&vec![s!("$"), s!("body"), s!("item1"), s!("level"), s!("1"), s!("name")], &matchers)).to(be_ok().value(Matcher::RegexMatcher(Regex::new("9").unwrap())));
-        expect!(select_best_matcher(&vec![s!("$"), s!("body"), s!("item1"), s!("level"), s!("1"), s!("other")], &matchers)).to(be_ok().value(Matcher::RegexMatcher(Regex::new("7").unwrap())));
-        expect!(select_best_matcher(&vec![s!("$"), s!("body"), s!("item1"), s!("level"), s!("2"), s!("id")], &matchers)).to(be_ok().value(Matcher::RegexMatcher(Regex::new("11").unwrap())));
-        expect!(select_best_matcher(&vec![s!("$"), s!("body"), s!("item1"), s!("level"), s!("3"), s!("id")], &matchers)).to(be_ok().value(Matcher::RegexMatcher(Regex::new("12").unwrap())));
-        expect!(select_best_matcher(&vec![s!("$"), s!("body"), s!("item2"), s!("level"), s!("1"), s!("id")], &matchers)).to(be_ok().value(Matcher::RegexMatcher(Regex::new("13").unwrap())));
-        expect!(select_best_matcher(&vec![s!("$"), s!("body"), s!("item2"), s!("level"), s!("3"), s!("id")], &matchers)).to(be_ok().value(Matcher::RegexMatcher(Regex::new("13").unwrap())));
+        expect!(select_best_matchers(&vec![s!("$"), s!("body"), s!("item1"), s!("level")], &matchers)).to(be_ok().value((vec![Matcher::RegexMatcher(Regex::new("6").unwrap())], RuleLogic::And)));
+        expect!(select_best_matchers(&vec![s!("$"), s!("body"), s!("item1"), s!("level"), s!("1")], &matchers)).to(be_ok().value((vec![Matcher::RegexMatcher(Regex::new("7").unwrap())], RuleLogic::And)));
+        expect!(select_best_matchers(&vec![s!("$"), s!("body"), s!("item1"), s!("level"), s!("2")], &matchers)).to(be_ok().value((vec![Matcher::RegexMatcher(Regex::new("10").unwrap())], RuleLogic::And)));
+        expect!(select_best_matchers(&vec![s!("$"), s!("body"), s!("item1"), s!("level"), s!("1"), s!("id")], &matchers)).to(be_ok().value((vec![Matcher::RegexMatcher(Regex::new("8").unwrap())], RuleLogic::And)));
+        expect!(select_best_matchers(&vec![s!("$"), s!("body"), s!("item1"), s!("level"), s!("1"), s!("name")], &matchers)).to(be_ok().value((vec![Matcher::RegexMatcher(Regex::new("9").unwrap())], RuleLogic::And)));
+        expect!(select_best_matchers(&vec![s!("$"), s!("body"), s!("item1"), s!("level"), s!("1"), s!("other")], &matchers)).to(be_ok().value((vec![Matcher::RegexMatcher(Regex::new("7").unwrap())], RuleLogic::And)));
+        expect!(select_best_matchers(&vec![s!("$"), s!("body"), s!("item1"), s!("level"), s!("2"), s!("id")], &matchers)).to(be_ok().value((vec![Matcher::RegexMatcher(Regex::new("11").unwrap())], RuleLogic::And)));
+        expect!(select_best_matchers(&vec![s!("$"), s!("body"), s!("item1"), s!("level"), s!("3"), s!("id")], &matchers)).to(be_ok().value((vec![Matcher::RegexMatcher(Regex::new("12").unwrap())], RuleLogic::And)));
+        expect!(select_best_matchers(&vec![s!("$"), s!("body"), s!("item2"), s!("level"), s!("1"), s!("id")], &matchers)).to(be_ok().value((vec![Matcher::RegexMatcher(Regex::new("13").unwrap())], RuleLogic::And)));
+        expect!(select_best_matchers(&vec![s!("$"), s!("body"), s!("item2"), s!("level"), s!("3"), s!("id")], &matchers)).to(be_ok().value((vec![Matcher::RegexMatcher(Regex::new("13").unwrap())], RuleLogic::And)));
     }
 
     #[test]
     fn select_best_matcher_selects_handles_missing_type_attribute() {
         let matchers = hashmap!{
-            s!("$.body.item1") => hashmap!{ s!("regex") => s!("3") },
-            s!("$.body.item2") => hashmap!{ s!("min") => s!("4") },
-            s!("$.body.item3") => hashmap!{ s!("max") => s!("4") },
-            s!("$.body.item4") => hashmap!{ s!("other") => s!("4") },
+            s!("$.body.item1") => RuleList::new(hashmap!{ s!("regex") => s!("3") }),
+            s!("$.body.item2") => RuleList::new(hashmap!{ s!("min") => s!("4") }),
+            s!("$.body.item3") => RuleList::new(hashmap!{ s!("max") => s!("4") }),
+            s!("$.body.item4") => RuleList::new(hashmap!{ s!("other") => s!("4") }),
         };
 
-        expect!(select_best_matcher(&vec![s!("$"), s!("body"), s!("item1")], &matchers)).to(be_ok().value(Matcher::RegexMatcher(Regex::new("3").unwrap())));
-        expect!(select_best_matcher(&vec![s!("$"), s!("body"), s!("item2")], &matchers)).to(be_ok().value(Matcher::MinTypeMatcher(4)));
-        expect!(select_best_matcher(&vec![s!("$"), s!("body"), s!("item3")], &matchers)).to(be_ok().value(Matcher::MaxTypeMatcher(4)));
-        expect!(select_best_matcher(&vec![s!("$"), s!("body"), s!("item4")], &matchers)).to(be_err());
+        expect!(select_best_matchers(&vec![s!("$"), s!("body"), s!("item1")], &matchers)).to(be_ok().value((vec![Matcher::RegexMatcher(Regex::new("3").unwrap())], RuleLogic::And)));
+        expect!(select_best_matchers(&vec![s!("$"), s!("body"), s!("item2")], &matchers)).to(be_ok().value((vec![Matcher::MinTypeMatcher(4)], RuleLogic::And)));
+        expect!(select_best_matchers(&vec![s!("$"), s!("body"), s!("item3")], &matchers)).to(be_ok().value((vec![Matcher::MaxTypeMatcher(4)], RuleLogic::And)));
+        expect!(select_best_matchers(&vec![s!("$"), s!("body"), s!("item4")], &matchers)).to(be_err());
+    }
+
+    #[test]
+    fn match_values_applies_all_the_rules_for_a_path() {
+        let and_matchers = hashmap!{
+            s!("$.body.item1") => RuleList {
+                rules: vec![
+                    hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("^\\d+$") },
+                    hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("^1") }
+                ],
+                rule_logic: RuleLogic::And
+            }
+        };
+        let or_matchers = hashmap!{
+            s!("$.body.item1") => RuleList {
+                rules: vec![
+                    hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("^\\d+$") },
+                    hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("^a") }
+                ],
+                rule_logic: RuleLogic::Or
+            }
+        };
+        let path = vec![s!("$"), s!("body"), s!("item1")];
+
+        expect!(match_values(&path, and_matchers.clone(), &s!("100"), &s!("123"))).to(be_ok());
+        expect!(match_values(&path, and_matchers.clone(), &s!("100"), &s!("223"))).to(be_err());
+        expect!(match_values(&path, or_matchers.clone(), &s!("100"), &s!("223"))).to(be_ok());
+        expect!(match_values(&path, or_matchers.clone(), &s!("100"), &s!("abc"))).to(be_ok());
+        expect!(match_values(&path, or_matchers.clone(), &s!("100"), &s!("b23"))).to(be_err());
     }
 
     #[test]
@@ -571,4 +867,132 @@ mod tests {
         expect!(100.matches(&100.1, &matcher)).to(be_err());
         expect!(100.1f64.matches(&100.2, &matcher)).to(be_ok());
     }
+
+    #[test]
+    fn select_best_matcher_selects_the_extended_matchers() {
+        let matchers = hashmap!{
+            s!("$.body.a") => RuleList::new(hashmap!{ s!("match") => s!("integer") }),
+            s!("$.body.b") => RuleList::new(hashmap!{ s!("match") => s!("decimal") }),
+            s!("$.body.c") => RuleList::new(hashmap!{ s!("match") => s!("number") }),
+            s!("$.body.d") => RuleList::new(hashmap!{ s!("match") => s!("timestamp") }),
+            s!("$.body.e") => RuleList::new(hashmap!{ s!("match") => s!("date"), s!("date") => s!("dd/MM/yyyy") }),
+            s!("$.body.f") => RuleList::new(hashmap!{ s!("match") => s!("time"), s!("format") => s!("HH:mm") }),
+            s!("$.body.g") => RuleList::new(hashmap!{ s!("match") => s!("include"), s!("value") => s!("abc") }),
+            s!("$.body.h") => RuleList::new(hashmap!{ s!("match") => s!("null") }),
+            s!("$.body.i") => RuleList::new(hashmap!{ s!("match") => s!("boolean") }),
+            s!("$.body.j") => RuleList::new(hashmap!{ s!("match") => s!("type"), s!("min") => s!("1"), s!("max") => s!("3") }),
+            s!("$.body.k") => RuleList::new(hashmap!{ s!("min") => s!("1"), s!("max") => s!("3") }),
+            s!("$.body.l") => RuleList::new(hashmap!{ s!("match") => s!("contentType"), s!("value") => s!("application/json") }),
+            s!("$.body.m") => RuleList::new(hashmap!{ s!("match") => s!("include") }),
+            s!("$.body.n") => RuleList::new(hashmap!{ s!("match") => s!("date"), s!("format") => s!("yyyy-QQ") })
+        };
+
+        let path = |name: &str| vec![s!("$"), s!("body"), s!(name)];
+        expect!(select_best_matchers(&path("a"), &matchers)).to(be_ok().value((vec![Matcher::IntegerMatcher], RuleLogic::And)));
+        expect!(select_best_matchers(&path("b"), &matchers)).to(be_ok().value((vec![Matcher::DecimalMatcher], RuleLogic::And)));
+        expect!(select_best_matchers(&path("c"), &matchers)).to(be_ok().value((vec![Matcher::NumberMatcher], RuleLogic::And)));
+        expect!(select_best_matchers(&path("d"), &matchers)).to(be_ok().value((vec![Matcher::TimestampMatcher(s!(DEFAULT_TIMESTAMP_FORMAT))], RuleLogic::And)));
+        expect!(select_best_matchers(&path("e"), &matchers)).to(be_ok().value((vec![Matcher::DateMatcher(s!("dd/MM/yyyy"))], RuleLogic::And)));
+        expect!(select_best_matchers(&path("f"), &matchers)).to(be_ok().value((vec![Matcher::TimeMatcher(s!("HH:mm"))], RuleLogic::And)));
+        expect!(select_best_matchers(&path("g"), &matchers)).to(be_ok().value((vec![Matcher::IncludeMatcher(s!("abc"))], RuleLogic::And)));
+        expect!(select_best_matchers(&path("h"), &matchers)).to(be_ok().value((vec![Matcher::NullMatcher], RuleLogic::And)));
+        expect!(select_best_matchers(&path("i"), &matchers)).to(be_ok().value((vec![Matcher::BooleanMatcher], RuleLogic::And)));
+        expect!(select_best_matchers(&path("j"), &matchers)).to(be_ok().value((vec![Matcher::MinMaxTypeMatcher(1, 3)], RuleLogic::And)));
+        expect!(select_best_matchers(&path("k"), &matchers)).to(be_ok().value((vec![Matcher::MinMaxTypeMatcher(1, 3)], RuleLogic::And)));
+        expect!(select_best_matchers(&path("l"), &matchers)).to(be_ok().value((vec![Matcher::ContentTypeMatcher(s!("application/json"))], RuleLogic::And)));
+        expect!(select_best_matchers(&path("m"), &matchers)).to(be_err());
+        expect!(select_best_matchers(&path("n"), &matchers)).to(be_err());
+    }
+
+    #[test]
+    fn binary_contents_matcher_test() {
+        let png = b"\x89PNG\r\n\x1A\n\x00\x00\x00\x0DIHDR".to_vec();
+        let jpeg = b"\xFF\xD8\xFF\xE0\x00\x10JFIF".to_vec();
+        expect!(png.matches(&png, &Matcher::EqualityMatcher)).to(be_ok());
+        expect!(png.matches(&jpeg, &Matcher::EqualityMatcher)).to(be_err());
+        expect!(png.matches(&jpeg, &Matcher::TypeMatcher)).to(be_ok());
+        expect!(png.matches(&png, &Matcher::ContentTypeMatcher(s!("image/png")))).to(be_ok());
+        expect!(png.matches(&jpeg, &Matcher::ContentTypeMatcher(s!("image/png")))).to(be_err());
+        expect!(png.matches(&png, &Matcher::IntegerMatcher)).to(be_err());
+    }
+
+    #[test]
+    fn min_max_type_matcher_test() {
+        let matcher = Matcher::MinMaxTypeMatcher(2, 3);
+        expect!(s!("100").matches(&s!("10a"), &matcher)).to(be_ok());
+        expect!(s!("100").matches(&100, &matcher)).to(be_err());
+        expect!(100.matches(&200, &matcher)).to(be_ok());
+        expect!(100.matches(&100.1, &matcher)).to(be_err());
+    }
+
+    #[test]
+    fn number_matchers_test() {
+        expect!(s!("100").matches(&s!("123"), &Matcher::IntegerMatcher)).to(be_ok());
+        expect!(s!("100").matches(&s!("12.3"), &Matcher::IntegerMatcher)).to(be_err());
+        expect!(100.matches(&123, &Matcher::IntegerMatcher)).to(be_ok());
+        expect!(100.matches(&12.3, &Matcher::IntegerMatcher)).to(be_err());
+        expect!(s!("100").matches(&s!("12.3"), &Matcher::DecimalMatcher)).to(be_ok());
+        expect!(s!("100").matches(&s!("123"), &Matcher::DecimalMatcher)).to(be_err());
+        expect!(100.1f64.matches(&12.3, &Matcher::DecimalMatcher)).to(be_ok());
+        expect!(100.1f64.matches(&123, &Matcher::DecimalMatcher)).to(be_err());
+        expect!(s!("100").matches(&s!("12.3"), &Matcher::NumberMatcher)).to(be_ok());
+        expect!(s!("100").matches(&s!("abc"), &Matcher::NumberMatcher)).to(be_err());
+        expect!(100.matches(&123, &Matcher::NumberMatcher)).to(be_ok());
+        expect!(100.matches(&12.3, &Matcher::NumberMatcher)).to(be_ok());
+    }
+
+    #[test]
+    fn date_time_matchers_test() {
+        let timestamp = Matcher::TimestampMatcher(s!(DEFAULT_TIMESTAMP_FORMAT));
+        expect!(s!("").matches(&s!("2017-06-21T10:01:02"), &timestamp)).to(be_ok());
+        expect!(s!("").matches(&s!("2017-06-21"), &timestamp)).to(be_err());
+        let date = Matcher::DateMatcher(s!("dd/MM/yyyy"));
+        expect!(s!("").matches(&s!("21/06/2017"), &date)).to(be_ok());
+        expect!(s!("").matches(&s!("2017-06-21"), &date)).to(be_err());
+        let time = Matcher::TimeMatcher(s!(DEFAULT_TIME_FORMAT));
+        expect!(s!("").matches(&s!("10:01:02"), &time)).to(be_ok());
+        expect!(s!("").matches(&s!("10:01"), &time)).to(be_err());
+        expect!(100.matches(&100, &time)).to(be_err());
+    }
+
+    #[test]
+    fn include_matcher_test() {
+        let matcher = Matcher::IncludeMatcher(s!("10"));
+        expect!(s!("100").matches(&s!("1000"), &matcher)).to(be_ok());
+        expect!(s!("100").matches(&s!("abc"), &matcher)).to(be_err());
+        expect!(100.matches(&2100, &matcher)).to(be_ok());
+        expect!(100.matches(&200, &matcher)).to(be_err());
+    }
+
+    #[test]
+    fn null_and_boolean_matchers_test() {
+        expect!(s!("true").matches(&s!("false"), &Matcher::BooleanMatcher)).to(be_ok());
+        expect!(s!("true").matches(&s!("yes"), &Matcher::BooleanMatcher)).to(be_err());
+        expect!(100.matches(&1, &Matcher::BooleanMatcher)).to(be_err());
+        expect!(s!("").matches(&s!(""), &Matcher::NullMatcher)).to(be_err());
+        expect!(100.matches(&100, &Matcher::NullMatcher)).to(be_err());
+    }
+
+    #[test]
+    fn content_type_matcher_test() {
+        let matcher = Matcher::ContentTypeMatcher(s!("application/json"));
+        expect!(s!("").matches(&s!("{\"a\": 1}"), &matcher)).to(be_ok());
+        expect!(s!("").matches(&s!("<a>1</a>"), &matcher)).to(be_err());
+    }
+
+    #[test]
+    fn array_contains_matchers_only_apply_to_the_list_they_are_defined_for() {
+        let matchers = Some(hashmap!{
+            s!("$.body.items") => RuleList::new(hashmap!{ s!("match") => s!("arrayContains") }),
+            s!("$.body.other") => RuleList::new(hashmap!{ s!("match") => s!("unordered") }),
+            s!("$.body.values") => RuleList::new(hashmap!{ s!("match") => s!("type") })
+        });
+        expect!(array_contains_matcher(&vec![s!("$"), s!("body"), s!("items")], &matchers)).to(be_some().value(Matcher::ArrayContainsMatcher));
+        expect!(array_contains_matcher(&vec![s!("$"), s!("body"), s!("other")], &matchers)).to(be_some().value(Matcher::UnorderedMatcher));
+        expect!(array_contains_matcher(&vec![s!("$"), s!("body"), s!("values")], &matchers)).to(be_none());
+        expect!(array_contains_matcher(&vec![s!("$"), s!("body"), s!("items"), s!("0")], &matchers)).to(be_none());
+        expect!(matcher_is_defined(&vec![s!("$"), s!("body"), s!("items")], &matchers)).to(be_true());
+        expect!(matcher_is_defined(&vec![s!("$"), s!("body"), s!("items"), s!("0")], &matchers)).to(be_false());
+        expect!(matcher_is_defined(&vec![s!("$"), s!("body"), s!("values"), s!("0")], &matchers)).to(be_true());
+    }
 }
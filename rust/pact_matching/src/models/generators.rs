@@ -0,0 +1,539 @@
+//! The `generators` module provides the structures for the generators of a pact interaction.
+//! Generators are used to replace values in requests and responses with generated ones. Like
+//! matching rules, they are stored keyed by the full path they apply to (i.e. `$.body.id`,
+//! `$.headers.Date`, `$.query.a`, `$.path` or `$.status`).
+//!
+//! The mock server applies the generators to the responses it returns, and the verifier applies
+//! them to the requests it sends to the provider (see `Request::generate_request` and
+//! `Response::generate_response`).
+
+use std::collections::{HashMap, BTreeMap};
+use serde_json::{self, Value};
+use rand::{self, Rng};
+use uuid::Uuid;
+use regex_syntax::{Expr, Repeater};
+use path_exp::*;
+use time_utils::*;
+use super::{OptionalBody, DetectedContentType};
+
+/// Generator used to generate a value for a request or response
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Generator {
+    /// Generates a random integer between the min and max values
+    RandomInt(i64, i64),
+    /// Generates a random UUID
+    Uuid,
+    /// Generates a random sequence of digits
+    RandomDecimal(u16),
+    /// Generates a random sequence of hexadecimal digits
+    RandomHexadecimal(u16),
+    /// Generates a random string of the provided size
+    RandomString(u16),
+    /// Generates a random string that matches the provided regex
+    Regex(String),
+    /// Generates a date value for the provided format specifier, using the current date
+    Date(Option<String>),
+    /// Generates a time value for the provided format specifier, using the current time
+    Time(Option<String>),
+    /// Generates a date and time value for the provided format specifier, using the current date and time
+    DateTime(Option<String>),
+    /// Generates a random boolean value
+    RandomBoolean,
+    /// Generates a value that is looked up from the provider state context using the given expression
+    ProviderStateGenerator(String)
+}
+
+fn json_to_num(value: Option<&Value>) -> Option<i64> {
+    match value {
+        Some(&Value::Number(ref n)) => n.as_i64(),
+        Some(&Value::String(ref s)) => s.parse().ok(),
+        _ => None
+    }
+}
+
+fn json_to_size(json: &Value, field: &str, default: u16) -> Option<u16> {
+    match json_to_num(json.get(field)) {
+        Some(n) if n >= 0 && n <= u16::max_value() as i64 => Some(n as u16),
+        Some(n) => {
+            warn!("Generator {} of {} is out of range, ignoring the generator", field, n);
+            None
+        },
+        None => Some(default)
+    }
+}
+
+fn json_to_string(value: Option<&Value>) -> Option<String> {
+    match value {
+        Some(&Value::String(ref s)) => Some(s.clone()),
+        Some(&Value::Null) | None => None,
+        Some(v) => Some(v.to_string())
+    }
+}
+
+impl Generator {
+    /// Converts a JSON map into a `Generator` struct, returning `None` if it can not be converted.
+    pub fn from_json(json: &Value) -> Option<Generator> {
+        match json_to_string(json.get("type")) {
+            Some(gen_type) => match gen_type.as_str() {
+                "RandomInt" => {
+                    let min = json_to_num(json.get("min")).unwrap_or(0);
+                    let max = json_to_num(json.get("max")).unwrap_or(10);
+                    Some(Generator::RandomInt(min, max))
+                },
+                "Uuid" => Some(Generator::Uuid),
+                "RandomDecimal" => json_to_size(json, "digits", 10).map(|digits| Generator::RandomDecimal(digits)),
+                "RandomHexadecimal" => json_to_size(json, "digits", 10).map(|digits| Generator::RandomHexadecimal(digits)),
+                "RandomString" => json_to_size(json, "size", 10).map(|size| Generator::RandomString(size)),
+                "Regex" => json_to_string(json.get("regex")).map(|regex| Generator::Regex(regex)),
+                "Date" => Some(Generator::Date(json_to_string(json.get("format")))),
+                "Time" => Some(Generator::Time(json_to_string(json.get("format")))),
+                "DateTime" => Some(Generator::DateTime(json_to_string(json.get("format")))),
+                "RandomBoolean" => Some(Generator::RandomBoolean),
+                "ProviderState" => json_to_string(json.get("expression"))
+                    .map(|expression| Generator::ProviderStateGenerator(expression)),
+                _ => {
+                    warn!("'{}' is not a known generator type", gen_type);
+                    None
+                }
+            },
+            None => {
+                warn!("Generator definition {} does not have a type attribute, ignoring it", json);
+                None
+            }
+        }
+    }
+
+    /// Converts this generator to a JSON value
+    pub fn to_json(&self) -> Value {
+        match *self {
+            Generator::RandomInt(min, max) => json!({ s!("type"): "RandomInt", s!("min"): min, s!("max"): max }),
+            Generator::Uuid => json!({ s!("type"): "Uuid" }),
+            Generator::RandomDecimal(digits) => json!({ s!("type"): "RandomDecimal", s!("digits"): digits }),
+            Generator::RandomHexadecimal(digits) => json!({ s!("type"): "RandomHexadecimal", s!("digits"): digits }),
+            Generator::RandomString(size) => json!({ s!("type"): "RandomString", s!("size"): size }),
+            Generator::Regex(ref regex) => json!({ s!("type"): "Regex", s!("regex"): regex }),
+            Generator::Date(ref format) => format_json("Date", format),
+            Generator::Time(ref format) => format_json("Time", format),
+            Generator::DateTime(ref format) => format_json("DateTime", format),
+            Generator::RandomBoolean => json!({ s!("type"): "RandomBoolean" }),
+            Generator::ProviderStateGenerator(ref expression) => json!({ s!("type"): "ProviderState", s!("expression"): expression })
+        }
+    }
+}
+
+fn format_json(gen_type: &str, format: &Option<String>) -> Value {
+    match *format {
+        Some(ref format) => json!({ s!("type"): gen_type, s!("format"): format }),
+        None => json!({ s!("type"): gen_type })
+    }
+}
+
+/// Data structure for representing a collection of generators, keyed by path
+pub type Generators = HashMap<String, Generator>;
+
+/// Parses the generators from the V3 `generators` attribute, which are grouped by category
+pub fn generators_from_json(json: &Value) -> Option<Generators> {
+    let mut generators = hashmap!{};
+    match json.get("generators") {
+        Some(&Value::Object(ref m)) => for (category, val) in m {
+            match category.as_str() {
+                "path" | "status" => match Generator::from_json(val) {
+                    Some(generator) => { generators.insert(super::category_path_to_key(category, &s!("")), generator); },
+                    None => ()
+                },
+                _ => match *val {
+                    Value::Object(ref paths) => for (path, gen_json) in paths {
+                        match Generator::from_json(gen_json) {
+                            Some(generator) => { generators.insert(super::category_path_to_key(category, path), generator); },
+                            None => ()
+                        }
+                    },
+                    _ => warn!("Ignoring invalid generators for category '{}' - {}", category, val)
+                }
+            }
+        },
+        Some(v) => warn!("Ignoring invalid generators - {}", v),
+        None => ()
+    }
+    if generators.is_empty() {
+        None
+    } else {
+        Some(generators)
+    }
+}
+
+/// Writes the generators out in the V3 layout, grouped by category
+pub fn generators_to_json(generators: &Generators) -> Value {
+    let mut categories: BTreeMap<String, Value> = BTreeMap::new();
+    for (key, generator) in generators {
+        match super::key_to_category_path(key) {
+            Some((ref category, _)) if category == "path" || category == "status" => {
+                categories.insert(category.clone(), generator.to_json());
+            },
+            Some((category, path)) => {
+                let entry = categories.entry(category).or_insert(json!({}));
+                entry.as_object_mut().unwrap().insert(path, generator.to_json());
+            },
+            None => warn!("Ignoring generator for path '{}' as it does not belong to a known category", key)
+        }
+    }
+    json!(categories)
+}
+
+/// Trait for generating a value to replace an existing one, which is used to apply the generators
+/// to the parts of requests and responses. The context contains any values that can be looked up
+/// by the provider state generators (i.e. the provider state parameters).
+pub trait GenerateValue<T> {
+    /// Generates a new value based on the existing value, returning `None` if one could not be
+    /// generated.
+    fn generate_value(&self, value: &T, context: &HashMap<String, Value>) -> Option<T>;
+}
+
+impl GenerateValue<String> for Generator {
+    fn generate_value(&self, value: &String, context: &HashMap<String, Value>) -> Option<String> {
+        match *self {
+            Generator::RandomInt(min, max) => Some(format!("{}", random_int(min, max))),
+            Generator::Uuid => Some(Uuid::new_v4().to_string()),
+            Generator::RandomDecimal(digits) => Some(random_decimal(digits)),
+            Generator::RandomHexadecimal(digits) => Some(random_hexadecimal(digits)),
+            Generator::RandomString(size) => Some(random_string(size)),
+            Generator::Regex(ref regex) => generate_from_regex(regex),
+            Generator::Date(ref format) => generate_datetime(format, DEFAULT_DATE_FORMAT),
+            Generator::Time(ref format) => generate_datetime(format, DEFAULT_TIME_FORMAT),
+            Generator::DateTime(ref format) => generate_datetime(format, DEFAULT_TIMESTAMP_FORMAT),
+            Generator::RandomBoolean => Some(format!("{}", rand::random::<bool>())),
+            Generator::ProviderStateGenerator(ref expression) => match lookup_expression(expression, context) {
+                Some(Value::String(s)) => Some(s),
+                Some(v) => Some(v.to_string()),
+                None => {
+                    warn!("Could not resolve provider state expression '{}', leaving the value '{}' as is", expression, value);
+                    None
+                }
+            }
+        }
+    }
+}
+
+impl GenerateValue<u16> for Generator {
+    fn generate_value(&self, value: &u16, context: &HashMap<String, Value>) -> Option<u16> {
+        match *self {
+            Generator::RandomInt(min, max) => {
+                // Limit the range to the values of a status code, instead of truncating the generated value
+                let clamp = |n: i64| n.max(0).min(u16::max_value() as i64);
+                Some(random_int(clamp(min), clamp(max)) as u16)
+            },
+            Generator::ProviderStateGenerator(ref expression) => match lookup_expression(expression, context) {
+                Some(Value::Number(ref n)) => n.as_u64().and_then(|n| if n <= u16::max_value() as u64 { Some(n as u16) } else { None }),
+                Some(Value::String(ref s)) => s.parse().ok(),
+                _ => None
+            },
+            _ => {
+                warn!("Generator {:?} can not generate a status code, leaving it as {}", self, value);
+                None
+            }
+        }
+    }
+}
+
+impl GenerateValue<Value> for Generator {
+    fn generate_value(&self, value: &Value, context: &HashMap<String, Value>) -> Option<Value> {
+        match *self {
+            Generator::RandomInt(min, max) => Some(json!(random_int(min, max))),
+            Generator::RandomDecimal(digits) => match random_decimal(digits).parse::<f64>() {
+                Ok(decimal) => Some(json!(decimal)),
+                Err(_) => None
+            },
+            Generator::RandomBoolean => Some(json!(rand::random::<bool>())),
+            Generator::ProviderStateGenerator(ref expression) => lookup_expression(expression, context),
+            _ => {
+                let string_value = match *value {
+                    Value::String(ref s) => s.clone(),
+                    _ => value.to_string()
+                };
+                self.generate_value(&string_value, context).map(|v| Value::String(v))
+            }
+        }
+    }
+}
+
+fn random_int(min: i64, max: i64) -> i64 {
+    // gen_range excludes the upper bound, so shift the range down when max + 1 would overflow
+    if min >= max {
+        min
+    } else if max < i64::max_value() {
+        rand::thread_rng().gen_range(min, max + 1)
+    } else if min > i64::min_value() {
+        rand::thread_rng().gen_range(min - 1, max) + 1
+    } else {
+        rand::thread_rng().gen()
+    }
+}
+
+fn random_digit<R: Rng>(rng: &mut R, from: u32) -> char {
+    ::std::char::from_digit(rng.gen_range(from, 10), 10).unwrap()
+}
+
+fn random_decimal(digits: u16) -> String {
+    let mut rng = rand::thread_rng();
+    let mut decimal = String::new();
+    decimal.push(random_digit(&mut rng, 1));
+    for _ in 1..digits {
+        decimal.push(random_digit(&mut rng, 0));
+    }
+    if digits > 1 {
+        let point = rng.gen_range(1, digits as usize);
+        decimal.insert(point, '.');
+    }
+    decimal
+}
+
+fn random_hexadecimal(digits: u16) -> String {
+    let mut rng = rand::thread_rng();
+    (0..digits).map(|_| format!("{:X}", rng.gen_range(0, 16))).collect()
+}
+
+fn random_string(size: u16) -> String {
+    rand::thread_rng().gen_ascii_chars().take(size as usize).collect()
+}
+
+fn generate_datetime(format: &Option<String>, default_format: &str) -> Option<String> {
+    let format = format.clone().unwrap_or(s!(default_format));
+    match format_current_datetime(&format) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("Failed to generate a date/time value - {}", err);
+            None
+        }
+    }
+}
+
+/// Looks up the value for a provider state expression in the context. If the expression contains
+/// `${name}` placeholders, they are replaced with the values from the context, otherwise the whole
+/// expression is taken as the name of the value.
+fn lookup_expression(expression: &String, context: &HashMap<String, Value>) -> Option<Value> {
+    if expression.contains("${") {
+        let mut result = String::new();
+        let mut remainder = expression.as_str();
+        while let Some(start) = remainder.find("${") {
+            result.push_str(&remainder[..start]);
+            match remainder[start..].find('}') {
+                Some(end) => {
+                    let name = &remainder[start + 2..start + end];
+                    match context.get(name) {
+                        Some(&Value::String(ref s)) => result.push_str(s),
+                        Some(v) => result.push_str(&v.to_string()),
+                        None => return None
+                    }
+                    remainder = &remainder[start + end + 1..];
+                },
+                None => return None
+            }
+        }
+        result.push_str(remainder);
+        Some(Value::String(result))
+    } else {
+        context.get(expression).cloned()
+    }
+}
+
+const MAX_REPEAT: u32 = 10;
+
+fn generate_from_regex(regex: &String) -> Option<String> {
+    match Expr::parse(regex) {
+        Ok(expr) => {
+            let mut result = String::new();
+            generate_from_expr(&expr, &mut rand::thread_rng(), &mut result);
+            Some(result)
+        },
+        Err(err) => {
+            warn!("'{}' is not a valid regular expression, can not generate a value from it - {}", regex, err);
+            None
+        }
+    }
+}
+
+fn random_char_in_range<R: Rng>(rng: &mut R, start: u32, end: u32) -> char {
+    // keep to printable characters where the range allows it
+    let end = if start < 0x7F { ::std::cmp::min(end, 0x7E) } else { end };
+    ::std::char::from_u32(rng.gen_range(start, end + 1))
+        .or(::std::char::from_u32(start))
+        .unwrap_or('?')
+}
+
+fn generate_from_ranges<R: Rng>(ranges: &Vec<(u32, u32)>, rng: &mut R, result: &mut String) {
+    // classes like \d and \w include non-ASCII ranges, so prefer the ASCII ones if there are any
+    let ascii_ranges: Vec<&(u32, u32)> = ranges.iter().filter(|range| range.0 < 0x7F).collect();
+    let candidates: Vec<&(u32, u32)> = if ascii_ranges.is_empty() { ranges.iter().collect() } else { ascii_ranges };
+    if !candidates.is_empty() {
+        let range = candidates[rng.gen_range(0, candidates.len())];
+        result.push(random_char_in_range(rng, range.0, range.1));
+    }
+}
+
+fn generate_from_expr<R: Rng>(expr: &Expr, rng: &mut R, result: &mut String) {
+    match *expr {
+        Expr::Literal { ref chars, .. } => result.extend(chars.iter()),
+        Expr::LiteralBytes { ref bytes, .. } => result.extend(bytes.iter().map(|b| *b as char)),
+        Expr::AnyChar | Expr::AnyCharNoNL | Expr::AnyByte | Expr::AnyByteNoNL =>
+            result.push(rng.gen_ascii_chars().next().unwrap()),
+        Expr::Class(ref class) => {
+            let ranges: Vec<(u32, u32)> = class.iter().map(|range| (range.start as u32, range.end as u32)).collect();
+            generate_from_ranges(&ranges, rng, result);
+        },
+        Expr::ClassBytes(ref class) => {
+            let ranges: Vec<(u32, u32)> = class.iter().map(|range| (range.start as u32, range.end as u32)).collect();
+            generate_from_ranges(&ranges, rng, result);
+        },
+        Expr::Group { ref e, .. } => generate_from_expr(e, rng, result),
+        Expr::Repeat { ref e, r, .. } => {
+            let (min, max) = match r {
+                Repeater::ZeroOrOne => (0, 1),
+                Repeater::ZeroOrMore => (0, MAX_REPEAT),
+                Repeater::OneOrMore => (1, MAX_REPEAT),
+                Repeater::Range { min, max } => (min, max.unwrap_or(min + MAX_REPEAT))
+            };
+            for _ in 0..rng.gen_range(min, max + 1) {
+                generate_from_expr(e, rng, result);
+            }
+        },
+        Expr::Concat(ref exprs) => for e in exprs {
+            generate_from_expr(e, rng, result);
+        },
+        Expr::Alternate(ref exprs) => if !exprs.is_empty() {
+            generate_from_expr(&exprs[rng.gen_range(0, exprs.len())], rng, result);
+        },
+        _ => ()
+    }
+}
+
+fn generators_for_category<'a>(generators: &'a Generators, category: &str) -> Vec<(String, &'a Generator)> {
+    generators.iter()
+        .filter_map(|(key, generator)| match super::key_to_category_path(key) {
+            Some((ref c, ref path)) if c == category => Some((path.clone(), generator)),
+            _ => None
+        })
+        .collect()
+}
+
+/// Applies the `header` generators to the headers. Header names are matched case-insensitively.
+pub fn generate_headers(generators: &Generators, headers: &Option<HashMap<String, String>>,
+    context: &HashMap<String, Value>) -> Option<HashMap<String, String>> {
+    headers.clone().map(|mut headers| {
+        for (name, generator) in generators_for_category(generators, "header") {
+            for (key, value) in headers.iter_mut() {
+                if key.to_lowercase() == name.to_lowercase() {
+                    match generator.generate_value(value, context) {
+                        Some(generated) => *value = generated,
+                        None => ()
+                    }
+                }
+            }
+        }
+        headers
+    })
+}
+
+/// Applies the `query` generators to the query parameters. All the values of a parameter are
+/// replaced.
+pub fn generate_query(generators: &Generators, query: &Option<HashMap<String, Vec<String>>>,
+    context: &HashMap<String, Value>) -> Option<HashMap<String, Vec<String>>> {
+    query.clone().map(|mut query| {
+        for (name, generator) in generators_for_category(generators, "query") {
+            match query.get_mut(&name) {
+                Some(values) => for value in values.iter_mut() {
+                    match generator.generate_value(value, context) {
+                        Some(generated) => *value = generated,
+                        None => ()
+                    }
+                },
+                None => ()
+            }
+        }
+        query
+    })
+}
+
+fn apply_json_generator(value: &mut Value, tokens: &[PathToken], generator: &Generator,
+    context: &HashMap<String, Value>) {
+    match tokens.first() {
+        None => match generator.generate_value(value, context) {
+            Some(generated) => *value = generated,
+            None => ()
+        },
+        Some(&PathToken::Root) => apply_json_generator(value, &tokens[1..], generator, context),
+        Some(&PathToken::Field(ref name)) => match *value {
+            Value::Object(ref mut map) => match map.get_mut(name) {
+                Some(child) => apply_json_generator(child, &tokens[1..], generator, context),
+                None => ()
+            },
+            _ => ()
+        },
+        Some(&PathToken::Index(index)) => match *value {
+            Value::Array(ref mut array) => match array.get_mut(index) {
+                Some(child) => apply_json_generator(child, &tokens[1..], generator, context),
+                None => ()
+            },
+            _ => ()
+        },
+        Some(&PathToken::StarIndex) => match *value {
+            Value::Array(ref mut array) => for child in array.iter_mut() {
+                apply_json_generator(child, &tokens[1..], generator, context);
+            },
+            _ => ()
+        },
+        Some(&PathToken::Star) => match *value {
+            Value::Object(ref mut map) => for (_, child) in map.iter_mut() {
+                apply_json_generator(child, &tokens[1..], generator, context);
+            },
+            Value::Array(ref mut array) => for child in array.iter_mut() {
+                apply_json_generator(child, &tokens[1..], generator, context);
+            },
+            _ => ()
+        }
+    }
+}
+
+/// Applies the `body` generators to the body. JSON bodies have the generators applied to the values
+/// at the generator paths, while other bodies only support a generator for the whole body (`$`).
+pub fn generate_body(generators: &Generators, body: &OptionalBody, content_type: &DetectedContentType,
+    context: &HashMap<String, Value>) -> OptionalBody {
+    let body_generators = generators_for_category(generators, "body");
+    match *body {
+        OptionalBody::Present(ref contents) if !body_generators.is_empty() => match *content_type {
+            DetectedContentType::Json => match serde_json::from_slice::<Value>(contents) {
+                Ok(mut json) => {
+                    for (path, generator) in body_generators {
+                        match parse_path_exp(path.clone()) {
+                            Ok(tokens) => apply_json_generator(&mut json, &tokens, generator, context),
+                            Err(err) => warn!("Ignoring generator with invalid path '{}' - {}", path, err)
+                        }
+                    }
+                    OptionalBody::Present(json.to_string().into_bytes())
+                },
+                Err(err) => {
+                    warn!("Failed to parse the body as JSON, not applying any generators - {}", err);
+                    body.clone()
+                }
+            },
+            DetectedContentType::Binary => {
+                warn!("Generators are not supported for binary bodies, not applying any generators");
+                body.clone()
+            },
+            _ => {
+                let mut contents = body.value();
+                for (path, generator) in body_generators {
+                    if path == "$" {
+                        match generator.generate_value(&contents, context) {
+                            Some(generated) => contents = generated,
+                            None => ()
+                        }
+                    } else {
+                        warn!("Generators for paths within the body are only supported for JSON bodies, ignoring the generator for '{}'", path);
+                    }
+                }
+                OptionalBody::Present(contents.into_bytes())
+            }
+        },
+        _ => body.clone()
+    }
+}
@@ -0,0 +1,217 @@
+//! The `matchingrules` module provides the structures for the matching rules of a pact
+//! interaction. Rules are stored keyed by the full path they apply to (i.e. `$.body.item`,
+//! `$.headers.Accept`, `$.query.a` or `$.path`), and are converted to and from both the V2 and
+//! the V3 (grouped by category) layouts of the pact file.
+
+use std::collections::{HashMap, BTreeMap};
+use std::hash::{Hash, Hasher};
+use serde_json::Value;
+
+/// Logic used to combine multiple matching rules defined for the same path
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RuleLogic {
+    /// All the rules must match
+    And,
+    /// At least one of the rules must match
+    Or
+}
+
+impl RuleLogic {
+    /// Parses the `combine` attribute of a V3 rule list, defaulting to `And`.
+    pub fn from_json(json: Option<&Value>) -> RuleLogic {
+        match json {
+            Some(&Value::String(ref s)) if s.to_uppercase() == "OR" => RuleLogic::Or,
+            _ => RuleLogic::And
+        }
+    }
+
+    /// Returns the `combine` value for this logic
+    pub fn to_string(&self) -> String {
+        match *self {
+            RuleLogic::And => s!("AND"),
+            RuleLogic::Or => s!("OR")
+        }
+    }
+}
+
+/// List of matching rules defined for a path. Each rule is the set of attributes from the
+/// pact file (i.e. `match`, `regex`, `min`).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RuleList {
+    /// Rules defined for the path
+    pub rules: Vec<HashMap<String, String>>,
+    /// How the rules should be combined
+    pub rule_logic: RuleLogic
+}
+
+impl RuleList {
+    /// Creates a rule list with a single rule
+    pub fn new(rule: HashMap<String, String>) -> RuleList {
+        RuleList {
+            rules: vec![rule],
+            rule_logic: RuleLogic::And
+        }
+    }
+
+    /// Creates an empty rule list with the given logic
+    pub fn empty(rule_logic: RuleLogic) -> RuleList {
+        RuleList {
+            rules: vec![],
+            rule_logic: rule_logic
+        }
+    }
+
+    /// Adds a rule to this list
+    pub fn add_rule(&mut self, rule: HashMap<String, String>) {
+        self.rules.push(rule)
+    }
+
+    /// If this rule list has no rules
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Converts this rule list to the V2 format. V2 only supports a single rule per path, so
+    /// only the first rule is written.
+    pub fn to_v2_json(&self) -> Value {
+        if self.rules.len() > 1 {
+            warn!("V2 pact files only support a single matching rule per path, only the first will be written");
+        }
+        match self.rules.first() {
+            Some(rule) => json!(rule.iter().fold(BTreeMap::new(), |mut map, kv| {
+                map.insert(kv.0.clone(), Value::String(kv.1.clone()));
+                map
+            })),
+            None => json!({})
+        }
+    }
+
+    /// Converts this rule list to the V3 format
+    pub fn to_v3_json(&self) -> Value {
+        let mut json = json!({
+            s!("matchers"): Value::Array(self.rules.iter().map(|rule| rule_to_v3_json(rule)).collect())
+        });
+        if self.rule_logic == RuleLogic::Or {
+            let map = json.as_object_mut().unwrap();
+            map.insert(s!("combine"), Value::String(self.rule_logic.to_string()));
+        }
+        json
+    }
+}
+
+impl Hash for RuleList {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for rule in &self.rules {
+            for (k, v) in rule {
+                k.hash(state);
+                v.hash(state);
+            }
+        }
+        self.rule_logic.hash(state);
+    }
+}
+
+fn rule_from_json(json: &Value) -> HashMap<String, String> {
+    match *json {
+        Value::Object(ref m) => m.iter().map(|(k, v)| {
+            (k.clone(), match v {
+                &Value::String(ref s) => s.clone(),
+                _ => v.to_string()
+            })
+        }).collect(),
+        _ => hashmap!{}
+    }
+}
+
+fn rule_to_v3_json(rule: &HashMap<String, String>) -> Value {
+    json!(rule.iter().fold(BTreeMap::new(), |mut map, kv| {
+        let value = match kv.0.as_str() {
+            "min" | "max" => match kv.1.parse::<u64>() {
+                Ok(n) => json!(n),
+                Err(_) => Value::String(kv.1.clone())
+            },
+            _ => Value::String(kv.1.clone())
+        };
+        map.insert(kv.0.clone(), value);
+        map
+    }))
+}
+
+fn rule_list_from_v3_json(json: &Value) -> RuleList {
+    match json.get("matchers") {
+        Some(&Value::Array(ref matchers)) => RuleList {
+            rules: matchers.iter().map(|m| rule_from_json(m)).collect(),
+            rule_logic: RuleLogic::from_json(json.get("combine"))
+        },
+        _ => RuleList::new(rule_from_json(json))
+    }
+}
+
+/// Returns true if the JSON matching rules are in the V3 layout, where the rules are grouped
+/// by category.
+pub fn is_v3_layout(json: &Value) -> bool {
+    match *json {
+        Value::Object(ref m) => !m.is_empty() && m.keys().all(|k| match k.as_str() {
+            "path" | "query" | "header" | "headers" | "body" | "metadata" => true,
+            _ => false
+        }),
+        _ => false
+    }
+}
+
+/// Parses matching rules in the V2 layout (keyed by path expression)
+pub fn rules_from_v2_json(json: &Value) -> HashMap<String, RuleList> {
+    match *json {
+        Value::Object(ref m) => m.iter()
+            .map(|(k, val)| (k.clone(), RuleList::new(rule_from_json(val))))
+            .collect(),
+        _ => hashmap!{}
+    }
+}
+
+/// Parses matching rules in the V3 layout (grouped by category)
+pub fn rules_from_v3_json(json: &Value) -> HashMap<String, RuleList> {
+    let mut rules = hashmap!{};
+    match *json {
+        Value::Object(ref m) => for (category, val) in m {
+            if category == "path" {
+                rules.insert(super::category_path_to_key("path", &s!("")), rule_list_from_v3_json(val));
+            } else {
+                match *val {
+                    Value::Object(ref paths) => for (path, rule_list) in paths {
+                        rules.insert(super::category_path_to_key(category, path), rule_list_from_v3_json(rule_list));
+                    },
+                    _ => warn!("Ignoring invalid matching rules for category '{}' - {}", category, val)
+                }
+            }
+        },
+        _ => ()
+    }
+    rules
+}
+
+/// Writes the matching rules in the V2 layout
+pub fn rules_to_v2_json(rules: &HashMap<String, RuleList>) -> Value {
+    json!(rules.iter().fold(BTreeMap::new(), |mut map, kv| {
+        map.insert(kv.0.clone(), kv.1.to_v2_json());
+        map
+    }))
+}
+
+/// Writes the matching rules in the V3 layout
+pub fn rules_to_v3_json(rules: &HashMap<String, RuleList>) -> Value {
+    let mut categories: BTreeMap<String, Value> = BTreeMap::new();
+    for (key, rule_list) in rules {
+        match super::key_to_category_path(key) {
+            Some((ref category, _)) if category == "path" => {
+                categories.insert(category.clone(), rule_list.to_v3_json());
+            },
+            Some((category, path)) => {
+                let entry = categories.entry(category).or_insert(json!({}));
+                entry.as_object_mut().unwrap().insert(path, rule_list.to_v3_json());
+            },
+            None => warn!("Ignoring matching rule for path '{}' as it does not belong to a known category", key)
+        }
+    }
+    json!(categories)
+}
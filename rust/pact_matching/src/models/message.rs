@@ -0,0 +1,352 @@
+//! The `message` module provides the structures for message pacts (V3 specification). Messages
+//! model asynchronous, non-HTTP interactions (i.e. a message placed on a queue) between a consumer
+//! and a provider, where the provider produces the message contents and the consumer consumes them.
+
+use std::collections::{HashMap, BTreeMap};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Error, ErrorKind};
+use std::io::prelude::*;
+use std::fs;
+use std::fs::File;
+use std::path::Path;
+use std::cmp::Ordering;
+use serde_json;
+use serde_json::Value;
+use itertools::Itertools;
+use hyper::client::Client;
+use strip_whitespace;
+use super::*;
+use super::{body_from_value, body_encoding, insert_body, detect_content_type_from_body, matchers_from_json, matchers_to_json,
+    parse_meta_data, determin_spec_version};
+
+/// Struct that defines a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// Description of this message interaction. This needs to be unique in the pact file.
+    pub description: String,
+    /// Optional provider states for the interaction.
+    /// See http://docs.pact.io/documentation/provider_states.html for more info on provider states.
+    pub provider_states: Vec<ProviderState>,
+    /// The contents of the message
+    pub contents: OptionalBody,
+    /// Metadata associated with this message (i.e. `contentType`).
+    pub metadata: HashMap<String, String>,
+    /// Matching rules
+    pub matching_rules: Option<Matchers>
+}
+
+fn metadata_from_json(json: &Value) -> HashMap<String, String> {
+    match json.get("metaData").or(json.get("metadata")) {
+        Some(&Value::Object(ref m)) => m.iter().map(|(key, val)| {
+            match val {
+                &Value::String(ref s) => (key.clone(), s.clone()),
+                _ => (key.clone(), val.to_string())
+            }
+        }).collect(),
+        _ => hashmap!{}
+    }
+}
+
+impl Message {
+    /// Constructs a `Message` from the `Json` struct.
+    pub fn from_json(index: usize, json: &Value, _: &PactSpecification) -> Message {
+        let description = match json.get("description") {
+            Some(v) => match *v {
+                Value::String(ref s) => s.clone(),
+                _ => v.to_string()
+            },
+            None => format!("Message {}", index)
+        };
+        let metadata = metadata_from_json(json);
+        let content_type = metadata.iter()
+            .find(|kv| kv.0.to_lowercase() == "contenttype" || kv.0.to_lowercase() == "content-type")
+            .map(|kv| kv.1.to_lowercase());
+        Message {
+            description: description,
+            provider_states: ProviderState::from_json(json),
+            contents: body_from_value(json.get("contents"), content_type, body_encoding(json, "contents")),
+            metadata: metadata,
+            matching_rules: matchers_from_json(json, s!("responseMatchingRules"))
+        }
+    }
+
+    /// Converts this message to a `Value` struct. Messages are always written in the V3 format.
+    pub fn to_json(&self) -> Value {
+        let mut value = json!({
+            s!("description"): Value::String(self.description.clone()),
+            s!("metaData"): json!(self.metadata.iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect::<BTreeMap<String, String>>())
+        });
+        {
+            let map = value.as_object_mut().unwrap();
+            insert_body(map, "contents", &self.contents, &self.content_type());
+            if !self.provider_states.is_empty() {
+                map.insert(s!("providerStates"),
+                    Value::Array(self.provider_states.iter().map(|state| state.to_json()).collect()));
+            }
+            if self.matching_rules.is_some() {
+                map.insert(s!("matchingRules"), matchers_to_json(&self.matching_rules.clone().unwrap(),
+                    &PactSpecification::V3));
+            }
+        }
+        value
+    }
+
+    /// Determins the content type of the message. If a `contentType` metadata value is present,
+    /// that will be returned. Otherwise, the contents will be inspected.
+    pub fn content_type(&self) -> String {
+        match self.metadata.iter().find(|kv| kv.0.to_lowercase() == "contenttype" || kv.0.to_lowercase() == "content-type") {
+            Some(kv) => match strip_whitespace::<Vec<&str>>(kv.1, ";").first() {
+                Some(v) => s!(*v),
+                None => detect_content_type_from_body(&self.contents)
+            },
+            None => detect_content_type_from_body(&self.contents)
+        }
+    }
+
+    /// Returns the names of the provider states of this message
+    pub fn provider_state_names(&self) -> Vec<String> {
+        self.provider_states.iter().map(|state| state.name.clone()).collect()
+    }
+
+    /// Returns list of conflicts if this message conflicts with the other message.
+    ///
+    /// Two messages conflict if they have the same description and provider states, but their
+    /// contents or metadata are not equal
+    pub fn conflicts_with(&self, other: &Message) -> Vec<PactConflict> {
+        if self.description == other.description && self.provider_states == other.provider_states {
+            let mut conflicts = vec![];
+            if self.contents != other.contents {
+                conflicts.push(PactConflict { interaction: self.description.clone(),
+                    description: format!("Message contents '{}' != '{}'", self.contents.value(), other.contents.value()) });
+            }
+            if self.metadata != other.metadata {
+                conflicts.push(PactConflict { interaction: self.description.clone(),
+                    description: format!("Message metadata {:?} != {:?}", self.metadata, other.metadata) });
+            }
+            if self.matching_rules != other.matching_rules {
+                conflicts.push(PactConflict { interaction: self.description.clone(),
+                    description: format!("Message matching rules {:?} != {:?}", self.matching_rules, other.matching_rules) });
+            }
+            conflicts
+        } else {
+            vec![]
+        }
+    }
+
+    /// Creates a default message
+    pub fn default() -> Message {
+        Message {
+            description: s!("Default Message"),
+            provider_states: vec![],
+            contents: OptionalBody::Missing,
+            metadata: hashmap!{},
+            matching_rules: None
+        }
+    }
+}
+
+impl Hash for Message {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.description.hash(state);
+        self.provider_states.hash(state);
+        self.contents.hash(state);
+        for (k, v) in self.metadata.iter().sorted_by(|a, b| Ord::cmp(&a.0, &b.0)) {
+            k.hash(state);
+            v.hash(state);
+        }
+        if self.matching_rules.is_some() {
+            for (k, v) in self.matching_rules.clone().unwrap() {
+                k.hash(state);
+                v.hash(state);
+            }
+        }
+    }
+}
+
+/// Struct that represents a message pact between the consumer and provider of a service.
+#[derive(Debug, Clone)]
+pub struct MessagePact {
+    /// Consumer side of the pact
+    pub consumer: Consumer,
+    /// Provider side of the pact
+    pub provider: Provider,
+    /// List of messages between the consumer and provider.
+    pub messages: Vec<Message>,
+    /// Metadata associated with this pact file.
+    pub metadata: BTreeMap<String, BTreeMap<String, String>>,
+    /// Specification version of this pact
+    pub specification_version: PactSpecification
+}
+
+fn parse_messages(pact_json: &Value, spec_version: PactSpecification) -> Vec<Message> {
+    match pact_json.get("messages") {
+        Some(&Value::Array(ref array)) => array.iter().enumerate().map(|(index, message_json)| {
+            Message::from_json(index, message_json, &spec_version)
+        }).collect(),
+        _ => vec![]
+    }
+}
+
+impl MessagePact {
+
+    /// Creates a `MessagePact` from a `Value` struct.
+    pub fn from_json(file: &String, pact_json: &Value) -> MessagePact {
+        let metadata = parse_meta_data(pact_json);
+        let spec_version = determin_spec_version(file, &metadata);
+
+        let consumer = match pact_json.get("consumer") {
+            Some(v) => Consumer::from_json(v),
+            None => Consumer { name: s!("consumer") }
+        };
+        let provider = match pact_json.get("provider") {
+            Some(v) => Provider::from_json(v),
+            None => Provider { name: s!("provider") }
+        };
+        MessagePact {
+            consumer: consumer,
+            provider: provider,
+            messages: parse_messages(pact_json, spec_version.clone()),
+            metadata: metadata,
+            specification_version: spec_version.clone()
+        }
+    }
+
+    /// Converts this pact to a `Value` struct. Message pacts are always written in the V3 format.
+    pub fn to_json(&self) -> Value {
+        json!({
+            s!("consumer"): self.consumer.to_json(),
+            s!("provider"): self.provider.to_json(),
+            s!("messages"): Value::Array(self.messages.iter().map(|m| m.to_json()).collect()),
+            s!("metadata"): json!(self.metadata_to_json())
+        })
+    }
+
+    /// Creates a BTreeMap of the metadata of this pact.
+    pub fn metadata_to_json(&self) -> BTreeMap<String, Value> {
+        let mut md_map: BTreeMap<String, Value> = self.metadata.iter()
+            .map(|(k, v)| {
+                (k.clone(), json!(v.iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect::<BTreeMap<String, String>>()))
+            })
+            .collect();
+        md_map.insert(s!("pact-specification"), json!({"version" : PactSpecification::V3.version_str()}));
+
+        md_map.insert(s!("pact-rust"), json!({"version" : s!(VERSION.unwrap_or("unknown"))}));
+        md_map
+    }
+
+    /// Merges this pact with the other pact, and returns a new pact with the messages sorted.
+    /// Returns an error if there is a merge conflict, which will occur if any message has the
+    /// same description and provider states and the contents or metadata are different.
+    pub fn merge(&self, pact: &MessagePact) -> Result<MessagePact, String> {
+        if self.consumer.name == pact.consumer.name && self.provider.name == pact.provider.name {
+            let conflicts = iproduct!(self.messages.clone(), pact.messages.clone())
+                .map(|m| m.0.conflicts_with(&m.1))
+                .filter(|conflicts| !conflicts.is_empty())
+                .collect::<Vec<Vec<PactConflict>>>();
+            let num_conflicts = conflicts.len();
+            if num_conflicts > 0 {
+                warn!("The following conflicting messages where found:");
+                for message_conflicts in conflicts {
+                    warn!(" Message '{}':", message_conflicts.first().unwrap().interaction);
+                    for conflict in message_conflicts {
+                        warn!("   {}", conflict.description);
+                    }
+                }
+                Err(format!("Unable to merge pacts, as there were {} conflict(s) between the messages",
+                    num_conflicts))
+            } else {
+                Ok(MessagePact {
+                    provider: self.provider.clone(),
+                    consumer: self.consumer.clone(),
+                    messages: self.messages.iter()
+                        .chain(pact.messages.iter())
+                        .cloned()
+                        .sorted_by(|a, b| {
+                            let cmp = Ord::cmp(&a.provider_state_names(), &b.provider_state_names());
+                            if cmp == Ordering::Equal {
+                                Ord::cmp(&a.description, &b.description)
+                            } else {
+                                cmp
+                            }
+                        }).into_iter()
+                        .unique()
+                        .collect(),
+                    metadata: self.metadata.clone(),
+                    specification_version: self.specification_version.clone()
+                })
+            }
+        } else {
+            Err(s!("Unable to merge pacts, as they have different consumers or providers"))
+        }
+    }
+
+    /// Determins the default file name for the pact. This is based on the consumer and
+    /// provider names.
+    pub fn default_file_name(&self) -> String {
+        format!("{}-{}.json", self.consumer.name, self.provider.name)
+    }
+
+    /// Reads the pact file and parses the resulting JSON into a `MessagePact` struct
+    pub fn read_pact(file: &Path) -> io::Result<MessagePact> {
+        let mut f = try!(File::open(file));
+        let pact_json = serde_json::from_reader(&mut f);
+        match pact_json {
+            Ok(ref json) => Ok(MessagePact::from_json(&format!("{:?}", file), json)),
+            Err(err) => Err(Error::new(ErrorKind::Other, format!("Failed to parse Pact JSON - {}", err)))
+        }
+    }
+
+    /// Reads the pact file from a URL and parses the resulting JSON into a `MessagePact` struct
+    pub fn from_url(url: &String) -> Result<MessagePact, String> {
+        let client = Client::new();
+        match client.get(url).send() {
+            Ok(mut res) => if res.status.is_success() {
+                    let pact_json = serde_json::de::from_reader(&mut res);
+                    match pact_json {
+                        Ok(ref json) => Ok(MessagePact::from_json(url, json)),
+                        Err(err) => Err(format!("Failed to parse Pact JSON - {}", err))
+                    }
+                } else {
+                    Err(format!("Request failed with status - {}", res.status))
+                },
+            Err(err) => Err(format!("Request failed - {}", err))
+        }
+    }
+
+    /// Writes this pact out to the provided file path. All directories in the path will
+    /// automatically created. If an existing pact is found at the path, this pact will be
+    /// merged into the pact file.
+    pub fn write_pact(&self, path: &Path) -> io::Result<()> {
+        try!(fs::create_dir_all(path.parent().unwrap()));
+        let pact = if path.exists() {
+            let existing_pact = try!(MessagePact::read_pact(path));
+            match existing_pact.merge(self) {
+                Ok(merged_pact) => merged_pact,
+                Err(ref message) => return Err(Error::new(ErrorKind::Other, message.clone()))
+            }
+        } else {
+            self.clone()
+        };
+        let mut file = try!(File::create(path));
+        try!(file.write_all(format!("{}", serde_json::to_string_pretty(&pact.to_json()).unwrap()).as_bytes()));
+        Ok(())
+    }
+
+    /// Returns a default MessagePact struct
+    pub fn default() -> MessagePact {
+        MessagePact {
+            consumer: Consumer { name: s!("default_consumer") },
+            provider: Provider { name: s!("default_provider") },
+            messages: Vec::new(),
+            metadata: btreemap!{
+                s!("pact-specification") => btreemap!{ s!("version") => PactSpecification::V3.version_str() },
+                s!("pact-rust") => btreemap!{ s!("version") => s!(VERSION.unwrap_or("unknown")) }
+            },
+            specification_version: PactSpecification::V3
+        }
+    }
+}
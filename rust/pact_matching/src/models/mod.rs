@@ -5,6 +5,7 @@ use std::collections::BTreeMap;
 use serde_json;
 use serde_json::Value;
 use hex::FromHex;
+use base64;
 use super::strip_whitespace;
 use regex::Regex;
 use semver::Version;
@@ -17,6 +18,15 @@ use std::path::Path;
 use std::cmp::Ordering;
 use std::hash::{Hash, Hasher};
 use hyper::client::Client;
+use path_exp::*;
+
+pub mod matchingrules;
+pub mod generators;
+pub mod message;
+
+pub use self::matchingrules::{RuleList, RuleLogic};
+pub use self::generators::{Generator, Generators, GenerateValue};
+pub use self::message::{Message, MessagePact};
 
 /// Version of the library
 pub const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
@@ -32,7 +42,9 @@ pub enum PactSpecification {
     /// Second version of the pact specification (https://github.com/pact-foundation/pact-specification/tree/version-1.1)
     V1_1,
     /// Version two of the pact specification (https://github.com/pact-foundation/pact-specification/tree/version-2)
-    V2
+    V2,
+    /// Version three of the pact specification (https://github.com/pact-foundation/pact-specification/tree/version-3)
+    V3
 }
 
 impl PactSpecification {
@@ -42,6 +54,7 @@ impl PactSpecification {
             PactSpecification::V1 => s!("1.0.0"),
             PactSpecification::V1_1 => s!("1.1.0"),
             PactSpecification::V2 => s!("2.0.0"),
+            PactSpecification::V3 => s!("3.0.0"),
             _ => s!("unknown")
         }
     }
@@ -52,6 +65,7 @@ impl PactSpecification {
             PactSpecification::V1 => s!("V1"),
             PactSpecification::V1_1 => s!("V1.1"),
             PactSpecification::V2 => s!("V2"),
+            PactSpecification::V3 => s!("V3"),
             _ => s!("unknown")
         }
     }
@@ -120,8 +134,9 @@ pub enum OptionalBody {
     /// A JSON body that is the null value. This state is to protect other language implementations
     /// from null values. It is treated as `Empty`.
     Null,
-    /// A non-empty body that is present in the pact file.
-    Present(String)
+    /// A non-empty body that is present in the pact file. The body is stored as bytes, so binary
+    /// bodies (images, PDFs, protobuf payloads, etc.) are supported.
+    Present(Vec<u8>)
 }
 
 impl OptionalBody {
@@ -134,14 +149,23 @@ impl OptionalBody {
         }
     }
 
-    /// Returns the body if present, otherwise returns the empty string.
+    /// Returns the body if present, otherwise returns the empty string. Any bytes that are not
+    /// valid UTF-8 are replaced with the replacement character.
     pub fn value(&self) -> String {
         match *self {
-            OptionalBody::Present(ref s) => s.clone(),
+            OptionalBody::Present(ref b) => String::from_utf8_lossy(b).into_owned(),
             _ => s!("")
         }
     }
 
+    /// Returns the bytes of the body if present, otherwise returns an empty vector.
+    pub fn bytes(&self) -> Vec<u8> {
+        match *self {
+            OptionalBody::Present(ref b) => b.clone(),
+            _ => vec![]
+        }
+    }
+
 }
 
 lazy_static! {
@@ -152,8 +176,22 @@ lazy_static! {
 
     static ref JSON_CONTENT_TYPE: Regex = Regex::new("application/.*json.*").unwrap();
     static ref XML_CONTENT_TYPE: Regex = Regex::new("application/.*xml").unwrap();
+    static ref BINARY_CONTENT_TYPE: Regex = Regex::new(
+        "^((image|audio|video|font)/.*|application/(octet-stream|pdf|zip|gzip|x-gzip|x-tar|x-protobuf|protobuf|vnd\\.google\\.protobuf|grpc.*))$").unwrap();
 }
 
+// magic bytes at the start of common binary formats, and the content types they indicate
+static MAGIC_BYTES: [(&'static [u8], &'static str); 8] = [
+    (b"\x89PNG\r\n\x1A\n", "image/png"),
+    (b"\xFF\xD8\xFF", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1F\x8B", "application/gzip"),
+    (b"BM", "image/bmp")
+];
+
 /// Enumeration of general content types
 #[derive(PartialEq, Debug, Clone, Eq)]
 pub enum DetectedContentType {
@@ -161,12 +199,51 @@ pub enum DetectedContentType {
     Json,
     /// XML content types
     Xml,
+    /// Binary content types (images, PDFs, protobuf payloads, etc.)
+    Binary,
     /// All other content types
     Text
 }
 
-/// Data structure for representing a collection of matchers
-pub type Matchers = HashMap<String, HashMap<String, String>>;
+/// Returns true if the content type is for binary data, which is stored base64 encoded in pact files
+/// and matched as bytes.
+pub fn is_binary_content_type(content_type: &str) -> bool {
+    BINARY_CONTENT_TYPE.is_match(&content_type.to_lowercase())
+}
+
+/// Data structure for representing a collection of matchers, keyed by path
+pub type Matchers = HashMap<String, RuleList>;
+
+/// Tries to detect the content type of the body, first by the magic bytes of common binary formats,
+/// and then by matching some regular exptressions against the first 32 characters. Bodies that are not
+/// valid UTF-8 are `application/octet-stream`. Default to `text/plain` if no match is found.
+pub fn detect_content_type_from_body(body: &OptionalBody) -> String {
+    match *body {
+        OptionalBody::Present(ref body) => {
+            match MAGIC_BYTES.iter().find(|magic| body.starts_with(magic.0)) {
+                Some(magic) => return s!(magic.1),
+                None => ()
+            }
+            let s: String = match String::from_utf8(body.clone()) {
+                Ok(body) => body.chars().take(32).collect(),
+                Err(_) => return s!("application/octet-stream")
+            };
+            debug!("Detecting content type from contents: '{}'", s);
+            if XMLREGEXP.is_match(s.as_str()) {
+                s!("application/xml")
+            } else if HTMLREGEXP.is_match(s.to_uppercase().as_str()) {
+                s!("text/html")
+            } else if XMLREGEXP2.is_match(s.as_str()) {
+                s!("application/xml")
+            } else if JSONREGEXP.is_match(s.as_str()) {
+                s!("application/json")
+            } else {
+                s!("text/plain")
+            }
+        },
+        _ => s!("text/plain")
+    }
+}
 
 /// Trait to specify an HTTP part of a message. It encapsulates the shared parts of a request and
 /// response.
@@ -196,24 +273,7 @@ pub trait HttpPart {
     /// Tries to detect the content type of the body by matching some regular exptressions against
     /// the first 32 characters. Default to `text/plain` if no match is found.
     fn detect_content_type(&self) -> String {
-        match *self.body() {
-            OptionalBody::Present(ref body) => {
-                let s: String = body.chars().take(32).collect();
-                debug!("Detecting content type from contents: '{}'", s);
-                if XMLREGEXP.is_match(s.as_str()) {
-                    s!("application/xml")
-                } else if HTMLREGEXP.is_match(s.to_uppercase().as_str()) {
-                    s!("text/html")
-                } else if XMLREGEXP2.is_match(s.as_str()) {
-                    s!("application/xml")
-                } else if JSONREGEXP.is_match(s.as_str()) {
-                    s!("application/json")
-                } else {
-                    s!("text/plain")
-                }
-            },
-            _ => s!("text/plain")
-        }
+        detect_content_type_from_body(self.body())
     }
 
     /// Returns the general content type (ignoring subtypes)
@@ -223,6 +283,8 @@ pub trait HttpPart {
             DetectedContentType::Json
         } else if XML_CONTENT_TYPE.is_match(&content_type[..]) {
             DetectedContentType::Xml
+        } else if is_binary_content_type(&content_type) {
+            DetectedContentType::Binary
         } else {
             DetectedContentType::Text
         }
@@ -242,8 +304,10 @@ pub struct Request {
     pub headers: Option<HashMap<String, String>>,
     /// Request body
     pub body: OptionalBody,
-    /// Request matching rules (currently not used)
-    pub matching_rules: Option<Matchers>
+    /// Request matching rules
+    pub matching_rules: Option<Matchers>,
+    /// Request generators
+    pub generators: Option<Generators>
 }
 
 impl HttpPart for Request {
@@ -278,12 +342,15 @@ impl Hash for Request {
         }
         self.body.hash(state);
         if self.matching_rules.is_some() {
-            for (k, map) in self.matching_rules.clone().unwrap() {
+            for (k, rules) in self.matching_rules.clone().unwrap() {
                 k.hash(state);
-                for (k2, v) in map.clone() {
-                    k2.hash(state);
-                    v.hash(state);
-                }
+                rules.hash(state);
+            }
+        }
+        if self.generators.is_some() {
+            for (k, generator) in self.generators.clone().unwrap() {
+                k.hash(state);
+                generator.hash(state);
             }
         }
     }
@@ -325,28 +392,116 @@ fn body_from_json(request: &Value, headers: &Option<HashMap<String, String>>) ->
         &None => None
     };
 
-    match request.get("body") {
+    body_from_value(request.get("body"), content_type, body_encoding(request, "body"))
+}
+
+/// Returns the encoding hint written alongside the body with the given key (`<key>Encoding`)
+fn body_encoding(json: &Value, key: &str) -> Option<String> {
+    match json.get(&format!("{}Encoding", key)) {
+        Some(&Value::String(ref encoding)) => Some(encoding.to_lowercase()),
+        _ => None
+    }
+}
+
+fn body_from_value(value: Option<&Value>, content_type: Option<String>, encoding: Option<String>) -> OptionalBody {
+    let content_type = content_type.unwrap_or(s!(""));
+    let base64_encoded = encoding.map(|encoding| encoding == "base64").unwrap_or(false);
+    match value {
         Some(v) => match *v {
             Value::String(ref s) => {
                 if s.is_empty() {
                     OptionalBody::Empty
-                } else if content_type.unwrap_or(s!("")) == "application/json" {
+                } else if base64_encoded || is_binary_content_type(&content_type) {
+                    match base64::decode(s) {
+                        Ok(bytes) => OptionalBody::Present(bytes),
+                        Err(err) => {
+                            warn!("Failed to decode the base64 encoded {} body, using it as is: {}", content_type, err);
+                            OptionalBody::Present(s.clone().into_bytes())
+                        }
+                    }
+                } else if content_type == "application/json" {
                     // fuck, that's all I have to say about this
                     match serde_json::from_str::<HashMap<String, Value>>(&s) {
-                        Ok(_) => OptionalBody::Present(s.clone()),
-                        Err(_) => OptionalBody::Present(format!("\"{}\"", s))
+                        Ok(_) => OptionalBody::Present(s.clone().into_bytes()),
+                        Err(_) => OptionalBody::Present(format!("\"{}\"", s).into_bytes())
                     }
                 } else {
-                    OptionalBody::Present(s.clone())
+                    OptionalBody::Present(s.clone().into_bytes())
                 }
             },
             Value::Null => OptionalBody::Null,
-            _ => OptionalBody::Present(v.to_string())
+            _ => OptionalBody::Present(v.to_string().into_bytes())
         },
         None => OptionalBody::Missing
     }
 }
 
+/// Converts the body to a JSON value for writing to a pact file. JSON bodies are written as JSON,
+/// binary bodies are base64 encoded, and all other bodies are written as strings.
+fn body_to_value(body: &OptionalBody, content_type: &String) -> Option<Value> {
+    match *body {
+        OptionalBody::Present(ref body) => if is_binary_content_type(content_type) {
+            Some(Value::String(base64::encode(body)))
+        } else {
+            let body = String::from_utf8_lossy(body).into_owned();
+            if content_type == "application/json" {
+                match serde_json::from_str(&body) {
+                    Ok(json_body) => Some(json_body),
+                    Err(err) => {
+                        warn!("Failed to parse json body: {}", err);
+                        Some(Value::String(body))
+                    }
+                }
+            } else {
+                Some(Value::String(body))
+            }
+        },
+        OptionalBody::Empty => Some(Value::String(s!(""))),
+        OptionalBody::Missing => None,
+        OptionalBody::Null => Some(Value::Null)
+    }
+}
+
+/// Inserts the body into the JSON map with the given key. Binary bodies are base64 encoded, so the
+/// encoding and content type are written alongside them (as `<key>Encoding` and `<key>ContentType`)
+/// to allow them to be decoded when there is no content type header.
+fn insert_body(map: &mut serde_json::Map<String, Value>, key: &str, body: &OptionalBody, content_type: &String) {
+    match body_to_value(body, content_type) {
+        Some(value) => {
+            map.insert(s!(key), value);
+            if body.is_present() && is_binary_content_type(content_type) {
+                map.insert(format!("{}Encoding", key), Value::String(s!("base64")));
+                map.insert(format!("{}ContentType", key), Value::String(content_type.clone()));
+            }
+        },
+        None => ()
+    }
+}
+
+fn query_from_json(query: &serde_json::Map<String, Value>) -> Option<HashMap<String, Vec<String>>> {
+    if query.is_empty() {
+        None
+    } else {
+        Some(query.iter().map(|(k, v)| {
+            (k.clone(), match *v {
+                Value::Array(ref values) => values.iter().map(|val| match *val {
+                    Value::String(ref s) => s.clone(),
+                    _ => val.to_string()
+                }).collect(),
+                Value::String(ref s) => vec![s.clone()],
+                _ => vec![v.to_string()]
+            })
+        }).collect())
+    }
+}
+
+fn query_to_json(query: &HashMap<String, Vec<String>>) -> Value {
+    json!(query.iter().fold(BTreeMap::new(), |mut map, kv| {
+        map.insert(kv.0.clone(), Value::Array(kv.1.iter().map(|v| Value::String(v.clone())).collect()));
+        map
+    }))
+}
+
 /// Converts a query string map into a query string
 pub fn build_query_string(query: HashMap<String, Vec<String>>) -> String {
     query.into_iter()
@@ -367,19 +522,10 @@ fn matchers_from_json(json: &Value, deprecated_name: String) -> Option<Matchers>
         (None, None) => None
     };
     let matchers = matchers_json.map(|v| {
-        match *v {
-            Value::Object(ref m) => m.iter().map(|(k, val)| {
-                (k.clone(), match *val {
-                    Value::Object(ref m2) => m2.iter().map(|(k2, v2)| {
-                        (k2.clone(), match v2 {
-                            &Value::String(ref s) => s.clone(),
-                            _ => v2.to_string()
-                        })
-                    }).collect(),
-                    _ => hashmap!{}
-                })
-            }).collect(),
-            _ => hashmap!{}
+        if matchingrules::is_v3_layout(v) {
+            matchingrules::rules_from_v3_json(v)
+        } else {
+            matchingrules::rules_from_v2_json(v)
         }
     });
     match matchers {
@@ -392,14 +538,59 @@ fn matchers_from_json(json: &Value, deprecated_name: String) -> Option<Matchers>
     }
 }
 
-fn matchers_to_json(matchers: &Matchers) -> Value {
-    json!(matchers.iter().fold(BTreeMap::new(), |mut map, kv| {
-        map.insert(kv.0.clone(), json!(kv.1.clone().iter().fold(BTreeMap::new(), |mut map, kv| {
-            map.insert(kv.0.clone(), Value::String(kv.1.clone()));
-            map
-        })));
-        map
-    }))
+fn matchers_to_json(matchers: &Matchers, spec_version: &PactSpecification) -> Value {
+    match *spec_version {
+        PactSpecification::V3 => matchingrules::rules_to_v3_json(matchers),
+        _ => matchingrules::rules_to_v2_json(matchers)
+    }
+}
+
+fn name_to_key(category: &str, name: &String) -> String {
+    if !name.is_empty() && name.chars().all(|ch| ch.is_alphanumeric()) {
+        format!("$.{}.{}", category, name)
+    } else {
+        format!("$.{}['{}']", category, name)
+    }
+}
+
+/// Converts a V3 category and path (i.e. `body` and `$.item`, or `header` and `Accept`) into the
+/// full path used to key matching rules and generators (i.e. `$.body.item` or `$.headers.Accept`).
+fn category_path_to_key(category: &str, path: &String) -> String {
+    match category {
+        "path" => s!("$.path"),
+        "status" => s!("$.status"),
+        "body" => if path.starts_with("$") {
+            format!("$.body{}", &path[1..])
+        } else {
+            format!("$.body.{}", path)
+        },
+        "header" | "headers" => name_to_key("headers", path),
+        _ => name_to_key(category, path)
+    }
+}
+
+/// Splits a full matching rule or generator path into its V3 category and path. This is the
+/// reverse of `category_path_to_key`.
+fn key_to_category_path(key: &String) -> Option<(String, String)> {
+    match parse_path_exp(key.clone()) {
+        Ok(tokens) => match tokens.get(1) {
+            Some(&PathToken::Field(ref category)) => match category.as_str() {
+                "path" | "status" => Some((category.clone(), s!(""))),
+                "body" if key.starts_with("$.body") => Some((s!("body"), format!("${}", &key[6..]))),
+                "query" | "header" | "headers" | "metadata" => match tokens.get(2) {
+                    Some(&PathToken::Field(ref name)) => Some((if category == "headers" { s!("header") } else { category.clone() },
+                        name.clone())),
+                    _ => None
+                },
+                _ => None
+            },
+            _ => None
+        },
+        Err(err) => {
+            warn!("Failed to parse path expression '{}' - {}", key, err);
+            None
+        }
+    }
 }
 
 impl Request {
@@ -422,6 +613,7 @@ impl Request {
         let query_val = match request_json.get("query") {
             Some(v) => match *v {
                 Value::String(ref s) => parse_query_string(s),
+                Value::Object(ref map) if *spec_version == PactSpecification::V3 => query_from_json(map),
                 _ => {
                     warn!("Only string versions of request query strings are supported with specification version {}, ignoring.",
                         spec_version.to_string());
@@ -437,12 +629,14 @@ impl Request {
             query: query_val,
             headers: headers.clone(),
             body: body_from_json(request_json, &headers),
-            matching_rules: matchers_from_json(request_json, s!("requestMatchingRules"))
+            matching_rules: matchers_from_json(request_json, s!("requestMatchingRules")),
+            generators: generators::generators_from_json(request_json)
         }
     }
 
-    /// Converts this `Request` to a `Value` struct.
-    pub fn to_json(&self) -> Value {
+    /// Converts this `Request` to a `Value` struct, using the format of the given
+    /// specification version.
+    pub fn to_json(&self, spec_version: &PactSpecification) -> Value {
         let mut json = json!({
             s!("method") : Value::String(self.method.to_uppercase()),
             s!("path") : Value::String(self.path.clone())
@@ -450,31 +644,20 @@ impl Request {
         {
             let mut map = json.as_object_mut().unwrap();
             if self.query.is_some() {
-                map.insert(s!("query"), Value::String(build_query_string(self.query.clone().unwrap())));
+                match *spec_version {
+                    PactSpecification::V3 => map.insert(s!("query"), query_to_json(&self.query.clone().unwrap())),
+                    _ => map.insert(s!("query"), Value::String(build_query_string(self.query.clone().unwrap())))
+                };
             }
             if self.headers.is_some() {
                 map.insert(s!("headers"), headers_to_json(&self.headers.clone().unwrap()));
             }
-            match self.body {
-                OptionalBody::Present(ref body) => {
-                    if self.content_type() == "application/json" {
-                        match serde_json::from_str(body) {
-                            Ok(json_body) => { map.insert(s!("body"), json_body); },
-                            Err(err) => {
-                                warn!("Failed to parse json body: {}", err);
-                                map.insert(s!("body"), Value::String(body.clone()));
-                            }
-                        }
-                    } else {
-                        map.insert(s!("body"), Value::String(body.clone()));
-                    }
-                },
-                OptionalBody::Empty => { map.insert(s!("body"), Value::String(s!(""))); },
-                OptionalBody::Missing => (),
-                OptionalBody::Null => { map.insert(s!("body"), Value::Null); }
-            }
+            insert_body(map, "body", &self.body, &self.content_type());
             if self.matching_rules.is_some() {
-                map.insert(s!("matchingRules"), matchers_to_json(&self.matching_rules.clone().unwrap()));
+                map.insert(s!("matchingRules"), matchers_to_json(&self.matching_rules.clone().unwrap(), spec_version));
+            }
+            if self.generators.is_some() && *spec_version == PactSpecification::V3 {
+                map.insert(s!("generators"), generators::generators_to_json(&self.generators.clone().unwrap()));
             }
         }
         json
@@ -488,7 +671,30 @@ impl Request {
             query: None,
             headers: None,
             body: OptionalBody::Missing,
-            matching_rules: None
+            matching_rules: None,
+            generators: None
+        }
+    }
+
+    /// Returns a copy of this request with the generators applied to it. The context provides the
+    /// values for any provider state generators.
+    pub fn generate_request(&self, context: &HashMap<String, Value>) -> Request {
+        match self.generators {
+            Some(ref generators) => {
+                let mut request = self.clone();
+                match generators.get("$.path") {
+                    Some(generator) => match generator.generate_value(&self.path, context) {
+                        Some(path) => request.path = path,
+                        None => ()
+                    },
+                    None => ()
+                }
+                request.headers = generators::generate_headers(generators, &self.headers, context);
+                request.query = generators::generate_query(generators, &self.query, context);
+                request.body = generators::generate_body(generators, &self.body, &self.content_type_enum(), context);
+                request
+            },
+            None => self.clone()
         }
     }
 
@@ -513,6 +719,9 @@ impl Request {
         if self.matching_rules != other.matching_rules {
             differences.push(format!("Request matching rules {:?} != {:?}", self.matching_rules, other.matching_rules));
         }
+        if self.generators != other.generators {
+            differences.push(format!("Request generators {:?} != {:?}", self.generators, other.generators));
+        }
         differences
     }
 }
@@ -526,8 +735,10 @@ pub struct Response {
     pub headers: Option<HashMap<String, String>>,
     /// Response body
     pub body: OptionalBody,
-    /// Response matching rules (not currently used)
-    pub matching_rules: Option<Matchers>
+    /// Response matching rules
+    pub matching_rules: Option<Matchers>,
+    /// Response generators
+    pub generators: Option<Generators>
 }
 
 impl Response {
@@ -543,7 +754,8 @@ impl Response {
             status: status_val,
             headers: headers.clone(),
             body: body_from_json(response, &headers),
-            matching_rules:  matchers_from_json(response, s!("responseMatchingRules"))
+            matching_rules: matchers_from_json(response, s!("responseMatchingRules")),
+            generators: generators::generators_from_json(response)
         }
     }
 
@@ -553,12 +765,35 @@ impl Response {
             status: 200,
             headers: None,
             body: OptionalBody::Missing,
-            matching_rules: None
+            matching_rules: None,
+            generators: None
         }
     }
 
-    /// Converts this response to a `Value` struct.
-    pub fn to_json(&self) -> Value {
+    /// Returns a copy of this response with the generators applied to it. The context provides the
+    /// values for any provider state generators.
+    pub fn generate_response(&self, context: &HashMap<String, Value>) -> Response {
+        match self.generators {
+            Some(ref generators) => {
+                let mut response = self.clone();
+                match generators.get("$.status") {
+                    Some(generator) => match generator.generate_value(&self.status, context) {
+                        Some(status) => response.status = status,
+                        None => ()
+                    },
+                    None => ()
+                }
+                response.headers = generators::generate_headers(generators, &self.headers, context);
+                response.body = generators::generate_body(generators, &self.body, &self.content_type_enum(), context);
+                response
+            },
+            None => self.clone()
+        }
+    }
+
+    /// Converts this response to a `Value` struct, using the format of the given
+    /// specification version.
+    pub fn to_json(&self, spec_version: &PactSpecification) -> Value {
         let mut json = json!({
             s!("status") : json!(self.status)
         });
@@ -567,26 +802,12 @@ impl Response {
             if self.headers.is_some() {
                 map.insert(s!("headers"), headers_to_json(&self.headers.clone().unwrap()));
             }
-            match self.body {
-                OptionalBody::Present(ref body) => {
-                    if self.content_type() == "application/json" {
-                        match serde_json::from_str(body) {
-                            Ok(json_body) => { map.insert(s!("body"), json_body); },
-                            Err(err) => {
-                                warn!("Failed to parse json body: {}", err);
-                                map.insert(s!("body"), Value::String(body.clone()));
-                            }
-                        }
-                    } else {
-                        map.insert(s!("body"), Value::String(body.clone()));
-                    }
-                },
-                OptionalBody::Empty => { map.insert(s!("body"), Value::String(s!(""))); },
-                OptionalBody::Missing => (),
-                OptionalBody::Null => { map.insert(s!("body"), Value::Null); }
-            }
+            insert_body(map, "body", &self.body, &self.content_type());
             if self.matching_rules.is_some() {
-                map.insert(s!("matchingRules"), matchers_to_json(&self.matching_rules.clone().unwrap()));
+                map.insert(s!("matchingRules"), matchers_to_json(&self.matching_rules.clone().unwrap(), spec_version));
+            }
+            if self.generators.is_some() && *spec_version == PactSpecification::V3 {
+                map.insert(s!("generators"), generators::generators_to_json(&self.generators.clone().unwrap()));
             }
         }
         json
@@ -607,6 +828,9 @@ impl Response {
         if self.matching_rules != other.matching_rules {
             differences.push(format!("Response matching rules {:?} != {:?}", self.matching_rules, other.matching_rules));
         }
+        if self.generators != other.generators {
+            differences.push(format!("Response generators {:?} != {:?}", self.generators, other.generators));
+        }
         differences
     }
 }
@@ -636,12 +860,15 @@ impl Hash for Response {
         }
         self.body.hash(state);
         if self.matching_rules.is_some() {
-            for (k, map) in self.matching_rules.clone().unwrap() {
+            for (k, rules) in self.matching_rules.clone().unwrap() {
                 k.hash(state);
-                for (k2, v) in map.clone() {
-                    k2.hash(state);
-                    v.hash(state);
-                }
+                rules.hash(state);
+            }
+        }
+        if self.generators.is_some() {
+            for (k, generator) in self.generators.clone().unwrap() {
+                k.hash(state);
+                generator.hash(state);
             }
         }
     }
@@ -656,14 +883,102 @@ pub struct PactConflict {
     pub description: String
 }
 
+/// Struct that defines a provider state. Provider states can optionally have parameters
+/// (V3 specification).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderState {
+    /// Description of this provider state
+    pub name: String,
+    /// Provider state parameters as key value pairs
+    pub params: HashMap<String, Value>
+}
+
+impl ProviderState {
+    /// Creates a provider state with the given name and no parameters
+    pub fn default(name: &String) -> ProviderState {
+        ProviderState {
+            name: name.clone(),
+            params: hashmap!{}
+        }
+    }
+
+    /// Builds a `ProviderState` from the V3 `Value` struct (`{ "name": ..., "params": {...} }`).
+    pub fn from_json_v3(pact_json: &Value) -> ProviderState {
+        let state = match pact_json.get("name") {
+            Some(v) => match *v {
+                Value::String(ref s) => s.clone(),
+                _ => v.to_string()
+            },
+            None => s!("")
+        };
+        let params = match pact_json.get("params") {
+            Some(&Value::Object(ref map)) => map.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            _ => hashmap!{}
+        };
+        ProviderState {
+            name: state,
+            params: params
+        }
+    }
+
+    /// Parses the provider states from an interaction. The V3 `providerStates` attribute is used
+    /// if present, otherwise the single V1/V2 `providerState` value.
+    pub fn from_json(pact_json: &Value) -> Vec<ProviderState> {
+        match pact_json.get("providerStates") {
+            Some(&Value::Array(ref states)) => states.iter().map(|state| match *state {
+                Value::String(ref s) => ProviderState::default(s),
+                _ => ProviderState::from_json_v3(state)
+            }).collect(),
+            _ => match pact_json.get("providerState").or(pact_json.get("provider_state")) {
+                Some(v) => match *v {
+                    Value::String(ref s) => if s.is_empty() {
+                        vec![]
+                    } else {
+                        vec![ProviderState::default(s)]
+                    },
+                    Value::Null => vec![],
+                    _ => vec![ProviderState::default(&v.to_string())]
+                },
+                None => vec![]
+            }
+        }
+    }
+
+    /// Converts this provider state to a V3 `Value` struct.
+    pub fn to_json(&self) -> Value {
+        let mut value = json!({
+            s!("name"): Value::String(self.name.clone())
+        });
+        if !self.params.is_empty() {
+            let map = value.as_object_mut().unwrap();
+            map.insert(s!("params"), json!(self.params.iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect::<BTreeMap<String, Value>>()));
+        }
+        value
+    }
+}
+
+impl Eq for ProviderState {}
+
+impl Hash for ProviderState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        for (k, v) in self.params.iter().sorted_by(|a, b| Ord::cmp(&a.0, &b.0)) {
+            k.hash(state);
+            v.to_string().hash(state);
+        }
+    }
+}
+
 /// Struct that defines an interaction (request and response pair)
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Interaction {
     /// Description of this interaction. This needs to be unique in the pact file.
     pub description: String,
-    /// Optional provider state for the interaction.
+    /// Optional provider states for the interaction.
     /// See http://docs.pact.io/documentation/provider_states.html for more info on provider states.
-    pub provider_state: Option<String>,
+    pub provider_states: Vec<ProviderState>,
     /// Request of the interaction
     pub request: Request,
     /// Response of the interaction
@@ -680,18 +995,7 @@ impl Interaction {
             },
             None => format!("Interaction {}", index)
         };
-        let provider_state = match pact_json.get("providerState").or(pact_json.get("provider_state")) {
-            Some(v) => match *v {
-                Value::String(ref s) => if s.is_empty() {
-                    None
-                } else {
-                    Some(s.clone())
-                },
-                Value::Null => None,
-                _ => Some(v.to_string())
-            },
-            None => None
-        };
+        let provider_states = ProviderState::from_json(pact_json);
         let request = match pact_json.get("request") {
             Some(v) => Request::from_json(v, spec_version),
             None => Request::default_request()
@@ -702,32 +1006,42 @@ impl Interaction {
         };
         Interaction {
              description: description,
-             provider_state: provider_state,
+             provider_states: provider_states,
              request: request,
              response: response
          }
     }
 
-    /// Converts this interaction to a `Value` struct.
-    pub fn to_json(&self) -> Value {
+    /// Converts this interaction to a `Value` struct, using the format of the given
+    /// specification version.
+    pub fn to_json(&self, spec_version: &PactSpecification) -> Value {
         let mut value = json!({
             s!("description") : Value::String(self.description.clone()),
-            s!("request") : self.request.to_json(),
-            s!("response") : self.response.to_json()
+            s!("request") : self.request.to_json(spec_version),
+            s!("response") : self.response.to_json(spec_version)
         });
-        if self.provider_state.is_some() {
+        if !self.provider_states.is_empty() {
             let mut map = value.as_object_mut().unwrap();
-            map.insert(s!("providerState"), json!(self.provider_state.clone().unwrap()));
+            match *spec_version {
+                PactSpecification::V3 => map.insert(s!("providerStates"),
+                    Value::Array(self.provider_states.iter().map(|state| state.to_json()).collect())),
+                _ => map.insert(s!("providerState"), json!(self.provider_states[0].name.clone()))
+            };
         }
         value
     }
 
+    /// Returns the names of the provider states of this interaction
+    pub fn provider_state_names(&self) -> Vec<String> {
+        self.provider_states.iter().map(|state| state.name.clone()).collect()
+    }
+
     /// Returns list of conflicts if this interaction conflicts with the other interaction.
     ///
     /// Two interactions conflict if they have the same description and provider state, but they request and
     /// responses are not equal
     pub fn conflicts_with(&self, other: &Interaction) -> Vec<PactConflict> {
-        if self.description == other.description && self.provider_state == other.provider_state {
+        if self.description == other.description && self.provider_states == other.provider_states {
             let mut conflicts = self.request.differences_from(&other.request).iter()
                 .map(|difference| PactConflict { interaction: self.description.clone(), description: difference.clone() } )
                 .collect::<Vec<PactConflict>>();
@@ -744,7 +1058,7 @@ impl Interaction {
     pub fn default() -> Interaction {
         Interaction {
              description: s!("Default Interaction"),
-             provider_state: None,
+             provider_states: vec![],
              request: Request::default_request(),
              response: Response::default_response()
         }
@@ -816,6 +1130,7 @@ fn determin_spec_version(file: &String, metadata: &BTreeMap<String, BTreeMap<Str
                             }
                         },
                         2 => PactSpecification::V2,
+                        3 => PactSpecification::V3,
                         _ => {
                             warn!("Unsupported specification version '{}' found in the metadata in the pact file {:?}, will try load it as a V2 specification", ver, file);
                             PactSpecification::Unknown
@@ -863,16 +1178,25 @@ impl Pact {
         }
     }
 
-    /// Converts this pact to a `Value` struct.
+    /// Converts this pact to a `Value` struct. V3 pacts are written in the V3 format, all
+    /// others in the V2 format.
     pub fn to_json(&self) -> Value {
+        let spec_version = self.write_specification_version();
         json!({
             s!("consumer"): self.consumer.to_json(),
             s!("provider"): self.provider.to_json(),
-            s!("interactions"): Value::Array(self.interactions.iter().map(|i| i.to_json()).collect()),
+            s!("interactions"): Value::Array(self.interactions.iter().map(|i| i.to_json(&spec_version)).collect()),
             s!("metadata"): json!(self.metadata_to_json())
         })
     }
 
+    fn write_specification_version(&self) -> PactSpecification {
+        match self.specification_version {
+            PactSpecification::V3 => PactSpecification::V3,
+            _ => PactSpecification::V2
+        }
+    }
+
     /// Creates a BTreeMap of the metadata of this pact.
     pub fn metadata_to_json(&self) -> BTreeMap<String, Value> {
         let mut md_map: BTreeMap<String, Value> = self.metadata.iter()
@@ -882,7 +1206,7 @@ impl Pact {
                 .collect::<BTreeMap<String, String>>()))
             })
             .collect();
-        md_map.insert(s!("pact-specification"), json!({"version" : PactSpecification::V2.version_str()}));
+        md_map.insert(s!("pact-specification"), json!({"version" : self.write_specification_version().version_str()}));
 
         md_map.insert(s!("pact-rust"), json!({"version" : s!(VERSION.unwrap_or("unknown"))}));
         md_map
@@ -916,7 +1240,7 @@ impl Pact {
                         .chain(pact.interactions.iter())
                         .cloned()
                         .sorted_by(|a, b| {
-                            let cmp = Ord::cmp(&a.provider_state, &b.provider_state);
+                            let cmp = Ord::cmp(&a.provider_state_names(), &b.provider_state_names());
                             if cmp == Ordering::Equal {
                                 Ord::cmp(&a.description, &b.description)
                             } else {
@@ -136,7 +136,7 @@ fn quickcheck_parse_query_string() {
 #[test]
 fn request_mimetype_is_based_on_the_content_type_header() {
     let request = Request { method: s!("GET"), path: s!("/"), query: None, headers: None,
-        body: OptionalBody::Missing, matching_rules: None };
+        body: OptionalBody::Missing, matching_rules: None, generators: None };
     expect!(request.content_type()).to(be_equal_to("text/plain"));
     expect!(Request {
         headers: Some(hashmap!{ s!("Content-Type") => s!("text/html") }), .. request.clone() }.content_type())
@@ -151,38 +151,38 @@ fn request_mimetype_is_based_on_the_content_type_header() {
         headers: Some(hashmap!{ s!("CONTENT-TYPE") => s!("application/json ; charset=UTF-8") }), .. request.clone() }.content_type())
         .to(be_equal_to("application/json"));
     expect!(Request {
-        body: OptionalBody::Present(s!("{\"json\": true}")), .. request.clone() }.content_type())
+        body: OptionalBody::Present(s!("{\"json\": true}").into()), .. request.clone() }.content_type())
         .to(be_equal_to("application/json"));
     expect!(Request {
-        body: OptionalBody::Present(s!("{}")), .. request.clone() }.content_type())
+        body: OptionalBody::Present(s!("{}").into()), .. request.clone() }.content_type())
         .to(be_equal_to("application/json"));
     expect!(Request {
-        body: OptionalBody::Present(s!("[]")), .. request.clone() }.content_type())
+        body: OptionalBody::Present(s!("[]").into()), .. request.clone() }.content_type())
         .to(be_equal_to("application/json"));
     expect!(Request {
-        body: OptionalBody::Present(s!("[1,2,3]")), .. request.clone() }.content_type())
+        body: OptionalBody::Present(s!("[1,2,3]").into()), .. request.clone() }.content_type())
         .to(be_equal_to("application/json"));
     expect!(Request {
-        body: OptionalBody::Present(s!("\"string\"")), .. request.clone() }.content_type())
+        body: OptionalBody::Present(s!("\"string\"").into()), .. request.clone() }.content_type())
         .to(be_equal_to("application/json"));
     expect!(Request {
-        body: OptionalBody::Present(s!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<json>false</json>")), .. request.clone() }.content_type())
+        body: OptionalBody::Present(s!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<json>false</json>").into()), .. request.clone() }.content_type())
         .to(be_equal_to("application/xml"));
     expect!(Request {
-        body: OptionalBody::Present(s!("<json>false</json>")), .. request.clone() }.content_type())
+        body: OptionalBody::Present(s!("<json>false</json>").into()), .. request.clone() }.content_type())
         .to(be_equal_to("application/xml"));
     expect!(Request {
-        body: OptionalBody::Present(s!("this is not json")), .. request.clone() }.content_type())
+        body: OptionalBody::Present(s!("this is not json").into()), .. request.clone() }.content_type())
         .to(be_equal_to("text/plain"));
     expect!(Request {
-        body: OptionalBody::Present(s!("<html><body>this is also not json</body></html>")), .. request.clone() }.content_type())
+        body: OptionalBody::Present(s!("<html><body>this is also not json</body></html>").into()), .. request.clone() }.content_type())
         .to(be_equal_to("text/html"));
 }
 
 #[test]
 fn content_type_enum_test() {
     let request = Request { method: s!("GET"), path: s!("/"), query: None, headers: None,
-        body: OptionalBody::Missing, matching_rules: None };
+        body: OptionalBody::Missing, matching_rules: None, generators: None };
     expect!(request.content_type_enum()).to(be_equal_to(DetectedContentType::Text));
     expect!(Request {
         headers: Some(hashmap!{ s!("Content-Type") => s!("text/html") }), .. request.clone() }.content_type_enum())
@@ -204,6 +204,20 @@ fn content_type_enum_test() {
         .to(be_equal_to(DetectedContentType::Xml));
 }
 
+#[test]
+fn content_type_enum_test_with_binary_bodies() {
+    let request = Request { headers: Some(hashmap!{ s!("Content-Type") => s!("image/png") }),
+        .. Request::default_request() };
+    expect!(request.content_type_enum()).to(be_equal_to(DetectedContentType::Binary));
+    expect!(Request {
+        headers: Some(hashmap!{ s!("Content-Type") => s!("application/x-protobuf") }), .. request.clone() }.content_type_enum())
+        .to(be_equal_to(DetectedContentType::Binary));
+    expect!(Request { headers: None, body: OptionalBody::Present(b"%PDF-1.4".to_vec()), .. request.clone() }
+        .content_type()).to(be_equal_to(s!("application/pdf")));
+    expect!(Request { headers: None, body: OptionalBody::Present(vec![0, 159, 146, 150]), .. request.clone() }
+        .content_type()).to(be_equal_to(s!("application/octet-stream")));
+}
+
 #[test]
 fn loading_interaction_from_json() {
     let interaction_json = r#"{
@@ -212,7 +226,7 @@ fn loading_interaction_from_json() {
     }"#;
     let interaction = Interaction::from_json(0, &serde_json::from_str({interaction_json}).unwrap(), &PactSpecification::V1_1);
     expect!(interaction.description).to(be_equal_to("String"));
-    expect!(interaction.provider_state).to(be_some().value("provider state"));
+    expect!(interaction.provider_states).to(be_equal_to(vec![ProviderState::default(&s!("provider state"))]));
 }
 
 #[test]
@@ -222,7 +236,7 @@ fn defaults_to_number_if_no_description() {
     }"#;
     let interaction = Interaction::from_json(0, &serde_json::from_str({interaction_json}).unwrap(), &PactSpecification::V1_1);
     expect!(interaction.description).to(be_equal_to("Interaction 0"));
-    expect!(interaction.provider_state).to(be_some().value("provider state"));
+    expect!(interaction.provider_states).to(be_equal_to(vec![ProviderState::default(&s!("provider state"))]));
 }
 
 #[test]
@@ -230,7 +244,7 @@ fn defaults_to_none_if_no_provider_state() {
     let interaction_json = r#"{
     }"#;
     let interaction = Interaction::from_json(0, &serde_json::from_str({interaction_json}).unwrap(), &PactSpecification::V1_1);
-    expect!(interaction.provider_state).to(be_none());
+    expect!(interaction.provider_states.clone()).to(be_empty());
 }
 
 #[test]
@@ -239,7 +253,7 @@ fn defaults_to_none_if_provider_state_null() {
         "providerState": null
     }"#;
     let interaction = Interaction::from_json(0, &serde_json::from_str({interaction_json}).unwrap(), &PactSpecification::V1_1);
-    expect!(interaction.provider_state).to(be_none());
+    expect!(interaction.provider_states.clone()).to(be_empty());
 }
 
 #[test]
@@ -358,20 +372,22 @@ fn load_basic_pact() {
     expect!(pact.interactions.iter()).to(have_count(1));
     let interaction = pact.interactions[0].clone();
     expect!(interaction.description).to(be_equal_to("a retrieve Mallory request"));
-    expect!(interaction.provider_state).to(be_none());
+    expect!(interaction.provider_states.clone()).to(be_empty());
     expect!(interaction.request).to(be_equal_to(Request {
         method: s!("GET"),
         path: s!("/mallory"),
         query: Some(hashmap!{ s!("name") => vec![s!("ron")], s!("status") => vec![s!("good")] }),
         headers: None,
         body: OptionalBody::Missing,
-        matching_rules: None
+        matching_rules: None,
+        generators: None
     }));
     expect!(interaction.response).to(be_equal_to(Response {
         status: 200,
         headers: Some(hashmap!{ s!("Content-Type") => s!("text/html") }),
-        body: OptionalBody::Present(s!("\"That is some good Mallory.\"")),
-        matching_rules: None
+        body: OptionalBody::Present(s!("\"That is some good Mallory.\"").into()),
+        matching_rules: None,
+        generators: None
     }));
     expect!(pact.specification_version).to(be_equal_to(PactSpecification::V2));
     expect!(pact.metadata.iter()).to(have_count(0));
@@ -430,20 +446,22 @@ fn load_pact() {
     expect!(pact.interactions.iter()).to(have_count(1));
     let interaction = pact.interactions[0].clone();
     expect!(interaction.description).to(be_equal_to("test interaction"));
-    expect!(interaction.provider_state).to(be_some().value("test state"));
+    expect!(interaction.provider_states).to(be_equal_to(vec![ProviderState::default(&s!("test state"))]));
     expect!(interaction.request).to(be_equal_to(Request {
         method: s!("GET"),
         path: s!("/"),
         query: Some(hashmap!{ s!("q") => vec![s!("p"), s!("p2")], s!("r") => vec![s!("s")] }),
         headers: Some(hashmap!{ s!("testreqheader") => s!("testreqheadervalue") }),
-        body: OptionalBody::Present(s!("{\"test\":true}")),
-        matching_rules: None
+        body: OptionalBody::Present(s!("{\"test\":true}").into()),
+        matching_rules: None,
+        generators: None
     }));
     expect!(interaction.response).to(be_equal_to(Response {
         status: 200,
         headers: Some(hashmap!{ s!("testreqheader") => s!("testreqheaderval") }),
-        body: OptionalBody::Present(s!("{\"responsetest\":true}")),
-        matching_rules: None
+        body: OptionalBody::Present(s!("{\"responsetest\":true}").into()),
+        matching_rules: None,
+        generators: None
     }));
 }
 
@@ -500,8 +518,9 @@ fn load_pact_encoded_query_string() {
         query: Some(hashmap!{ s!("datetime") => vec![s!("2011-12-03T10:15:30+01:00")],
             s!("description") => vec![s!("hello world!")] }),
         headers: Some(hashmap!{ s!("testreqheader") => s!("testreqheadervalue") }),
-        body: OptionalBody::Present(s!("{\"test\":true}")),
-        matching_rules: None
+        body: OptionalBody::Present(s!("{\"test\":true}").into()),
+        matching_rules: None,
+        generators: None
     }));
 }
 
@@ -530,20 +549,21 @@ fn load_pact_converts_methods_to_uppercase() {
         query: None,
         headers: None,
         body: OptionalBody::Missing,
-        matching_rules: None
+        matching_rules: None,
+        generators: None
     }));
 }
 
 #[test]
 fn request_to_json_with_defaults() {
     let request = Request::default_request();
-    expect!(request.to_json().to_string()).to(be_equal_to("{\"method\":\"GET\",\"path\":\"/\"}"));
+    expect!(request.to_json(&PactSpecification::V2).to_string()).to(be_equal_to("{\"method\":\"GET\",\"path\":\"/\"}"));
 }
 
 #[test]
 fn request_to_json_converts_methods_to_upper_case() {
     let request = Request { method: s!("post"), .. Request::default_request() };
-    expect!(request.to_json().to_string()).to(be_equal_to("{\"method\":\"POST\",\"path\":\"/\"}"));
+    expect!(request.to_json(&PactSpecification::V2).to_string()).to(be_equal_to("{\"method\":\"POST\",\"path\":\"/\"}"));
 }
 
 #[test]
@@ -552,7 +572,7 @@ fn request_to_json_with_a_query() {
         s!("a") => vec![s!("1"), s!("2")],
         s!("b") => vec![s!("3")]
     }), .. Request::default_request() };
-    expect!(request.to_json().to_string()).to(
+    expect!(request.to_json(&PactSpecification::V2).to_string()).to(
         be_equal_to(r#"{"method":"GET","path":"/","query":"a=1&a=2&b=3"}"#)
     );
 }
@@ -562,7 +582,7 @@ fn request_to_json_with_a_query_must_encode_the_query() {
     let request = Request { query: Some(hashmap!{
         s!("datetime") => vec![s!("2011-12-03T10:15:30+01:00")],
         s!("description") => vec![s!("hello world!")] }), .. Request::default_request() };
-    expect!(request.to_json().to_string()).to(
+    expect!(request.to_json(&PactSpecification::V2).to_string()).to(
         be_equal_to(r#"{"method":"GET","path":"/","query":"datetime=2011-12-03T10%3a15%3a30%2b01%3a00&description=hello+world%21"}"#)
     );
 }
@@ -572,7 +592,7 @@ fn request_to_json_with_a_query_must_encode_the_query_with_utf8_chars() {
     let request = Request { query: Some(hashmap!{
         s!("a") => vec![s!("b=c&d❤")]
     }), .. Request::default_request() };
-    expect!(request.to_json().to_string()).to(
+    expect!(request.to_json(&PactSpecification::V2).to_string()).to(
         be_equal_to(r#"{"method":"GET","path":"/","query":"a=b%3dc%26d%27%64"}"#)
     );
 }
@@ -583,7 +603,7 @@ fn request_to_json_with_headers() {
         s!("HEADERA") => s!("VALUEA"),
         s!("HEADERB") => s!("VALUEB1, VALUEB2")
     }), .. Request::default_request() };
-    expect!(request.to_json().to_string()).to(
+    expect!(request.to_json(&PactSpecification::V2).to_string()).to(
         be_equal_to(r#"{"headers":{"HEADERA":"VALUEA","HEADERB":"VALUEB1, VALUEB2"},"method":"GET","path":"/"}"#)
     );
 }
@@ -592,8 +612,8 @@ fn request_to_json_with_headers() {
 fn request_to_json_with_json_body() {
     let request = Request { headers: Some(hashmap!{
         s!("Content-Type") => s!("application/json")
-    }), body: OptionalBody::Present(s!(r#"{"key": "value"}"#)), .. Request::default_request() };
-    expect!(request.to_json().to_string()).to(
+    }), body: OptionalBody::Present(s!(r#"{"key": "value"}"#).into()), .. Request::default_request() };
+    expect!(request.to_json(&PactSpecification::V2).to_string()).to(
         be_equal_to(r#"{"body":{"key":"value"},"headers":{"Content-Type":"application/json"},"method":"GET","path":"/"}"#)
     );
 }
@@ -602,16 +622,25 @@ fn request_to_json_with_json_body() {
 #[test]
 fn request_to_json_with_non_json_body() {
     let request = Request { headers: Some(hashmap!{ s!("Content-Type") => s!("text/plain") }),
-        body: OptionalBody::Present(s!("This is some text")), .. Request::default_request() };
-    expect!(request.to_json().to_string()).to(
+        body: OptionalBody::Present(s!("This is some text").into()), .. Request::default_request() };
+    expect!(request.to_json(&PactSpecification::V2).to_string()).to(
         be_equal_to(r#"{"body":"This is some text","headers":{"Content-Type":"text/plain"},"method":"GET","path":"/"}"#)
     );
 }
 
+#[test]
+fn request_to_json_with_binary_body() {
+    let request = Request { headers: Some(hashmap!{ s!("Content-Type") => s!("image/png") }),
+        body: OptionalBody::Present(b"\x89PNG\r\n\x1A\n\x00\x00\x00\x0DIHDR".to_vec()), .. Request::default_request() };
+    expect!(request.to_json(&PactSpecification::V2).to_string()).to(
+        be_equal_to(r#"{"body":"iVBORw0KGgoAAAANSUhEUg==","bodyContentType":"image/png","bodyEncoding":"base64","headers":{"Content-Type":"image/png"},"method":"GET","path":"/"}"#)
+    );
+}
+
 #[test]
 fn request_to_json_with_empty_body() {
     let request = Request { body: OptionalBody::Empty, .. Request::default_request() };
-    expect!(request.to_json().to_string()).to(
+    expect!(request.to_json(&PactSpecification::V2).to_string()).to(
         be_equal_to(r#"{"body":"","method":"GET","path":"/"}"#)
     );
 }
@@ -619,7 +648,7 @@ fn request_to_json_with_empty_body() {
 #[test]
 fn request_to_json_with_null_body() {
     let request = Request { body: OptionalBody::Null, .. Request::default_request() };
-    expect!(request.to_json().to_string()).to(
+    expect!(request.to_json(&PactSpecification::V2).to_string()).to(
         be_equal_to(r#"{"body":null,"method":"GET","path":"/"}"#)
     );
 }
@@ -627,7 +656,7 @@ fn request_to_json_with_null_body() {
 #[test]
 fn response_to_json_with_defaults() {
     let response = Response::default_response();
-    expect!(response.to_json().to_string()).to(be_equal_to("{\"status\":200}"));
+    expect!(response.to_json(&PactSpecification::V2).to_string()).to(be_equal_to("{\"status\":200}"));
 }
 
 #[test]
@@ -636,7 +665,7 @@ fn response_to_json_with_headers() {
         s!("HEADERA") => s!("VALUEA"),
         s!("HEADERB") => s!("VALUEB1, VALUEB2")
     }), .. Response::default_response() };
-    expect!(response.to_json().to_string()).to(
+    expect!(response.to_json(&PactSpecification::V2).to_string()).to(
         be_equal_to(r#"{"headers":{"HEADERA":"VALUEA","HEADERB":"VALUEB1, VALUEB2"},"status":200}"#)
     );
 }
@@ -645,8 +674,8 @@ fn response_to_json_with_headers() {
 fn response_to_json_with_json_body() {
     let response = Response { headers: Some(hashmap!{
         s!("Content-Type") => s!("application/json")
-    }), body: OptionalBody::Present(s!(r#"{"key": "value"}"#)), .. Response::default_response() };
-    expect!(response.to_json().to_string()).to(
+    }), body: OptionalBody::Present(s!(r#"{"key": "value"}"#).into()), .. Response::default_response() };
+    expect!(response.to_json(&PactSpecification::V2).to_string()).to(
         be_equal_to(r#"{"body":{"key":"value"},"headers":{"Content-Type":"application/json"},"status":200}"#)
     );
 }
@@ -654,8 +683,8 @@ fn response_to_json_with_json_body() {
 #[test]
 fn response_to_json_with_non_json_body() {
     let response = Response { headers: Some(hashmap!{ s!("Content-Type") => s!("text/plain") }),
-        body: OptionalBody::Present(s!("This is some text")), .. Response::default_response() };
-    expect!(response.to_json().to_string()).to(
+        body: OptionalBody::Present(s!("This is some text").into()), .. Response::default_response() };
+    expect!(response.to_json(&PactSpecification::V2).to_string()).to(
         be_equal_to(r#"{"body":"This is some text","headers":{"Content-Type":"text/plain"},"status":200}"#)
     );
 }
@@ -663,7 +692,7 @@ fn response_to_json_with_non_json_body() {
 #[test]
 fn response_to_json_with_empty_body() {
     let response = Response { body: OptionalBody::Empty, .. Response::default_response() };
-    expect!(response.to_json().to_string()).to(
+    expect!(response.to_json(&PactSpecification::V2).to_string()).to(
         be_equal_to(r#"{"body":"","status":200}"#)
     );
 }
@@ -671,7 +700,7 @@ fn response_to_json_with_empty_body() {
 #[test]
 fn response_to_json_with_null_body() {
     let response = Response { body: OptionalBody::Null, .. Response::default_response() };
-    expect!(response.to_json().to_string()).to(
+    expect!(response.to_json(&PactSpecification::V2).to_string()).to(
         be_equal_to(r#"{"body":null,"status":200}"#)
     );
 }
@@ -701,7 +730,7 @@ fn write_pact_test() {
         interactions: vec![
             Interaction {
                 description: s!("Test Interaction"),
-                provider_state: Some(s!("Good state to be in")),
+                provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
                 request: Request::default_request(),
                 response: Response::default_response()
             }
@@ -756,7 +785,7 @@ fn write_pact_test_should_merge_pacts() {
         interactions: vec![
             Interaction {
                 description: s!("Test Interaction 2"),
-                provider_state: Some(s!("Good state to be in")),
+                provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
                 request: Request::default_request(),
                 response: Response::default_response()
             }
@@ -769,7 +798,7 @@ fn write_pact_test_should_merge_pacts() {
         interactions: vec![
             Interaction {
                 description: s!("Test Interaction"),
-                provider_state: Some(s!("Good state to be in")),
+                provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
                 request: Request::default_request(),
                 response: Response::default_response()
             }
@@ -839,7 +868,7 @@ fn write_pact_test_should_not_merge_pacts_with_conflicts() {
         interactions: vec![
             Interaction {
                 description: s!("Test Interaction"),
-                provider_state: Some(s!("Good state to be in")),
+                provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
                 request: Request::default_request(),
                 response: Response::default_response()
             }
@@ -852,7 +881,7 @@ fn write_pact_test_should_not_merge_pacts_with_conflicts() {
         interactions: vec![
             Interaction {
                 description: s!("Test Interaction"),
-                provider_state: Some(s!("Good state to be in")),
+                provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
                 request: Request::default_request(),
                 response: Response { status: 400, .. Response::default_response() }
             }
@@ -945,7 +974,7 @@ fn pact_merge_does_not_merge_where_there_are_conflicting_interactions() {
         interactions: vec![
             Interaction {
                 description: s!("Test Interaction"),
-                provider_state: Some(s!("Good state to be in")),
+                provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
                 request: Request::default_request(),
                 response: Response::default_response()
             }
@@ -958,7 +987,7 @@ fn pact_merge_does_not_merge_where_there_are_conflicting_interactions() {
         interactions: vec![
             Interaction {
                 description: s!("Test Interaction"),
-                provider_state: Some(s!("Good state to be in")),
+                provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
                 request: Request { path: s!("/other"), .. Request::default_request() },
                 response: Response::default_response()
             }
@@ -976,7 +1005,7 @@ fn pact_merge_removes_duplicates() {
         interactions: vec![
             Interaction {
                 description: s!("Test Interaction"),
-                provider_state: Some(s!("Good state to be in")),
+                provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
                 request: Request::default_request(),
                 response: Response::default_response()
             }
@@ -988,13 +1017,13 @@ fn pact_merge_removes_duplicates() {
         interactions: vec![
             Interaction {
                 description: s!("Test Interaction"),
-                provider_state: Some(s!("Good state to be in")),
+                provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
                 request: Request::default_request(),
                 response: Response::default_response()
             },
             Interaction {
                 description: s!("Test Interaction 2"),
-                provider_state: Some(s!("Good state to be in")),
+                provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
                 request: Request::default_request(),
                 response: Response::default_response()
             }
@@ -1010,13 +1039,13 @@ fn pact_merge_removes_duplicates() {
 fn interactions_do_not_conflict_if_they_have_different_descriptions() {
     let interaction1 = Interaction {
         description: s!("Test Interaction"),
-        provider_state: Some(s!("Good state to be in")),
+        provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
         request: Request::default_request(),
         response: Response::default_response()
     };
     let interaction2 =Interaction {
         description: s!("Test Interaction 2"),
-        provider_state: Some(s!("Good state to be in")),
+        provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
         request: Request::default_request(),
         response: Response::default_response()
     };
@@ -1027,13 +1056,13 @@ fn interactions_do_not_conflict_if_they_have_different_descriptions() {
 fn interactions_do_not_conflict_if_they_have_different_provider_states() {
     let interaction1 = Interaction {
         description: s!("Test Interaction"),
-        provider_state: Some(s!("Good state to be in")),
+        provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
         request: Request::default_request(),
         response: Response::default_response()
     };
     let interaction2 =Interaction {
         description: s!("Test Interaction"),
-        provider_state: Some(s!("Bad state to be in")),
+        provider_states: vec![ProviderState::default(&s!("Bad state to be in"))],
         request: Request::default_request(),
         response: Response::default_response()
     };
@@ -1044,13 +1073,13 @@ fn interactions_do_not_conflict_if_they_have_different_provider_states() {
 fn interactions_do_not_conflict_if_they_have_the_same_requests_and_responses() {
     let interaction1 = Interaction {
         description: s!("Test Interaction"),
-        provider_state: Some(s!("Good state to be in")),
+        provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
         request: Request::default_request(),
         response: Response::default_response()
     };
     let interaction2 =Interaction {
         description: s!("Test Interaction"),
-        provider_state: Some(s!("Good state to be in")),
+        provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
         request: Request::default_request(),
         response: Response::default_response()
     };
@@ -1061,13 +1090,13 @@ fn interactions_do_not_conflict_if_they_have_the_same_requests_and_responses() {
 fn interactions_conflict_if_they_have_different_requests() {
     let interaction1 = Interaction {
         description: s!("Test Interaction"),
-        provider_state: Some(s!("Good state to be in")),
+        provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
         request: Request::default_request(),
         response: Response::default_response()
     };
     let interaction2 =Interaction {
         description: s!("Test Interaction"),
-        provider_state: Some(s!("Good state to be in")),
+        provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
         request: Request { method: s!("POST"), .. Request::default_request() },
         response: Response::default_response()
     };
@@ -1078,13 +1107,13 @@ fn interactions_conflict_if_they_have_different_requests() {
 fn interactions_conflict_if_they_have_different_responses() {
     let interaction1 = Interaction {
         description: s!("Test Interaction"),
-        provider_state: Some(s!("Good state to be in")),
+        provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
         request: Request::default_request(),
         response: Response::default_response()
     };
     let interaction2 =Interaction {
         description: s!("Test Interaction"),
-        provider_state: Some(s!("Good state to be in")),
+        provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
         request: Request::default_request(),
         response: Response { status: 400, .. Response::default_response() }
     };
@@ -1170,7 +1199,7 @@ fn matchers_from_json_handles_matcher_with_no_matching_rules() {
      "#).unwrap();
     let matchers = matchers_from_json(&json, s!("deprecatedName"));
     expect!(matchers).to(be_some().value(hashmap!{
-        s!("*.path") => hashmap!{}
+        s!("*.path") => RuleList::new(hashmap!{})
     }));
 }
 
@@ -1191,10 +1220,10 @@ fn matchers_from_json_loads_matchers_correctly() {
      "#).unwrap();
     let matchers = matchers_from_json(&json, s!("deprecatedName"));
     expect!(matchers).to(be_some().value(hashmap!{
-        s!("*.path") => hashmap!{
+        s!("*.path") => RuleList::new(hashmap!{
             s!("match") => s!("regex"),
             s!("regex") => s!(r#"\d+"#)
-        }
+        })
     }));
 }
 
@@ -1215,10 +1244,10 @@ fn matchers_from_json_loads_matchers_from_deprecated_name() {
      "#).unwrap();
     let matchers = matchers_from_json(&json, s!("deprecatedName"));
     expect!(matchers).to(be_some().value(hashmap!{
-        s!("*.path") => hashmap!{
+        s!("*.path") => RuleList::new(hashmap!{
             s!("match") => s!("regex"),
             s!("regex") => s!(r#"\d+"#)
-        }
+        })
     }));
 }
 
@@ -1229,10 +1258,10 @@ fn write_pact_test_with_matchers() {
         interactions: vec![
             Interaction {
                 description: s!("Test Interaction"),
-                provider_state: Some(s!("Good state to be in")),
+                provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
                 request: Request {
                     matching_rules: Some(hashmap!{
-                        s!("*.body") => hashmap!{ s!("match") => s!("type") }
+                        s!("*.body") => RuleList::new(hashmap!{ s!("match") => s!("type") })
                     }),
                     .. Request::default_request()
                 },
@@ -1330,7 +1359,7 @@ fn body_from_json_returns_json_string_if_the_body_is_json_but_not_a_string() {
       }
      "#).unwrap();
     let body = body_from_json(&json, &None);
-    expect!(body).to(be_equal_to(OptionalBody::Present(s!("{\"test\":true}"))));
+    expect!(body).to(be_equal_to(OptionalBody::Present(s!("{\"test\":true}").into())));
 }
 
 #[test]
@@ -1358,7 +1387,7 @@ fn body_from_json_returns_the_body_if_the_body_is_a_string() {
       }
      "#).unwrap();
     let body = body_from_json(&json, &None);
-    expect!(body).to(be_equal_to(OptionalBody::Present(s!("<?xml version=\"1.0\"?> <body></body>"))));
+    expect!(body).to(be_equal_to(OptionalBody::Present(s!("<?xml version=\"1.0\"?> <body></body>").into())));
 }
 
 #[test]
@@ -1373,7 +1402,7 @@ fn body_from_json_returns_the_a_json_formatted_body_if_the_body_is_a_string_and_
      "#).unwrap();
     let headers = headers_from_json(&json);
     let body = body_from_json(&json, &headers);
-    expect!(body).to(be_equal_to(OptionalBody::Present(s!("\"This is actually a JSON string\""))));
+    expect!(body).to(be_equal_to(OptionalBody::Present(s!("\"This is actually a JSON string\"").into())));
 }
 
 #[test]
@@ -1388,5 +1417,429 @@ fn body_from_json_returns_the_body_if_the_content_type_is_json() {
      "#).unwrap();
     let headers = headers_from_json(&json);
     let body = body_from_json(&json, &headers);
-    expect!(body).to(be_equal_to(OptionalBody::Present(s!("{\"test\":true}"))));
+    expect!(body).to(be_equal_to(OptionalBody::Present(s!("{\"test\":true}").into())));
+}
+
+#[test]
+fn body_from_json_decodes_base64_encoded_binary_bodies() {
+    let json : serde_json::Value = serde_json::from_str(r#"
+      {
+          "path": "/",
+          "query": "",
+          "headers": {"Content-Type": "image/png"},
+          "body": "iVBORw0KGgoAAAANSUhEUg=="
+      }
+     "#).unwrap();
+    let headers = headers_from_json(&json);
+    let body = body_from_json(&json, &headers);
+    expect!(body).to(be_equal_to(OptionalBody::Present(b"\x89PNG\r\n\x1A\n\x00\x00\x00\x0DIHDR".to_vec())));
+}
+
+#[test]
+fn binary_bodies_with_no_content_type_header_round_trip_through_json() {
+    let body = OptionalBody::Present(b"\x00\x01\x02\xFF\xFEnot utf-8".to_vec());
+    let request = Request { body: body.clone(), .. Request::default_request() };
+    let json = request.to_json(&PactSpecification::V3);
+    expect!(json.get("bodyEncoding")).to(be_some().value(&json!("base64")));
+    expect!(json.get("bodyContentType")).to(be_some().value(&json!("application/octet-stream")));
+    expect!(Request::from_json(&json, &PactSpecification::V3).body).to(be_equal_to(body.clone()));
+
+    let response = Response { body: body.clone(), .. Response::default_response() };
+    let json = response.to_json(&PactSpecification::V3);
+    expect!(Response::from_json(&json, &PactSpecification::V3).body).to(be_equal_to(body.clone()));
+
+    let message = Message { contents: body.clone(), .. Message::default() };
+    let json = message.to_json();
+    expect!(json.get("contentsEncoding")).to(be_some().value(&json!("base64")));
+    expect!(Message::from_json(0, &json, &PactSpecification::V3).contents).to(be_equal_to(body));
+}
+
+#[test]
+fn load_v3_pact() {
+    let pact_json = r#"
+    {
+      "provider" : {
+        "name" : "test_provider"
+      },
+      "consumer" : {
+        "name" : "test_consumer"
+      },
+      "interactions" : [ {
+        "providerStates" : [
+          { "name": "test state", "params": { "name": "Testy" } },
+          { "name": "test state 2", "params": { "id": 1234 } }
+        ],
+        "description" : "test interaction",
+        "request" : {
+          "method" : "GET",
+          "path" : "/",
+          "query" : {
+            "q" : [ "p", "p2" ],
+            "r" : [ "s" ]
+          },
+          "matchingRules" : {
+            "path" : {
+              "matchers" : [ { "match": "regex", "regex": "\\/\\w*" } ]
+            },
+            "query" : {
+              "q" : {
+                "matchers" : [ { "match": "regex", "regex": "p\\d?" } ]
+              }
+            },
+            "header" : {
+              "Content-Type" : {
+                "matchers" : [ { "match": "regex", "regex": "application/.*" } ]
+              }
+            }
+          }
+        },
+        "response" : {
+          "status" : 200,
+          "body" : {
+            "id" : 100
+          },
+          "matchingRules" : {
+            "body" : {
+              "$.id" : {
+                "matchers" : [
+                  { "match": "type", "min": 1 },
+                  { "match": "regex", "regex": "\\d+" }
+                ],
+                "combine": "OR"
+              }
+            }
+          },
+          "generators" : {
+            "body" : {
+              "$.id" : { "type": "RandomInt", "min": 1, "max": 10 }
+            },
+            "status" : { "type": "RandomInt", "min": 200, "max": 299 }
+          }
+        }
+      } ],
+      "metadata" : {
+        "pactSpecification" : {
+          "version" : "3.0.0"
+        }
+      }
+    }
+    "#;
+    let pact = Pact::from_json(&s!(""), &serde_json::from_str(pact_json).unwrap());
+    expect!(pact.specification_version).to(be_equal_to(PactSpecification::V3));
+    expect!(pact.interactions.iter()).to(have_count(1));
+    let interaction = pact.interactions[0].clone();
+    expect!(interaction.provider_states).to(be_equal_to(vec![
+        ProviderState { name: s!("test state"), params: hashmap!{ s!("name") => json!("Testy") } },
+        ProviderState { name: s!("test state 2"), params: hashmap!{ s!("id") => json!(1234) } }
+    ]));
+    expect!(interaction.request.query).to(be_some().value(hashmap!{
+        s!("q") => vec![s!("p"), s!("p2")],
+        s!("r") => vec![s!("s")]
+    }));
+    expect!(interaction.request.matching_rules).to(be_some().value(hashmap!{
+        s!("$.path") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("\\/\\w*") }),
+        s!("$.query.q") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("p\\d?") }),
+        s!("$.headers['Content-Type']") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("application/.*") })
+    }));
+    expect!(interaction.response.matching_rules).to(be_some().value(hashmap!{
+        s!("$.body.id") => RuleList {
+            rules: vec![
+                hashmap!{ s!("match") => s!("type"), s!("min") => s!("1") },
+                hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("\\d+") }
+            ],
+            rule_logic: RuleLogic::Or
+        }
+    }));
+    expect!(interaction.response.generators).to(be_some().value(hashmap!{
+        s!("$.body.id") => Generator::RandomInt(1, 10),
+        s!("$.status") => Generator::RandomInt(200, 299)
+    }));
+}
+
+#[test]
+fn write_v3_pact_test() {
+    let pact = Pact { consumer: Consumer { name: s!("write_pact_test_consumer") },
+        provider: Provider { name: s!("write_pact_test_provider") },
+        interactions: vec![
+            Interaction {
+                description: s!("Test Interaction"),
+                provider_states: vec![ProviderState { name: s!("Good state to be in"), params: hashmap!{ s!("id") => json!(1) } }],
+                request: Request {
+                    query: Some(hashmap!{ s!("a") => vec![s!("1"), s!("2")] }),
+                    matching_rules: Some(hashmap!{
+                        s!("$.query.a") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("\\d+") })
+                    }),
+                    .. Request::default_request()
+                },
+                response: Response {
+                    matching_rules: Some(hashmap!{
+                        s!("$.body.id") => RuleList {
+                            rules: vec![
+                                hashmap!{ s!("match") => s!("type"), s!("min") => s!("1") },
+                                hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("\\d+") }
+                            ],
+                            rule_logic: RuleLogic::Or
+                        }
+                    }),
+                    generators: Some(hashmap!{
+                        s!("$.body.id") => Generator::Uuid,
+                        s!("$.headers['X-Date']") => Generator::Date(Some(s!("yyyy-MM-dd")))
+                    }),
+                    .. Response::default_response()
+                }
+            }
+        ],
+        specification_version: PactSpecification::V3,
+        .. Pact::default() };
+
+    let json = pact.to_json();
+    let interaction = &json["interactions"][0];
+    expect!(interaction["providerStates"].clone()).to(be_equal_to(json!([
+        { "name": "Good state to be in", "params": { "id": 1 } }
+    ])));
+    expect!(interaction["request"]["query"].clone()).to(be_equal_to(json!({ "a": ["1", "2"] })));
+    expect!(interaction["request"]["matchingRules"].clone()).to(be_equal_to(json!({
+        "query": { "a": { "matchers": [ { "match": "regex", "regex": "\\d+" } ] } }
+    })));
+    expect!(interaction["response"]["matchingRules"].clone()).to(be_equal_to(json!({
+        "body": {
+            "$.id": {
+                "matchers": [ { "match": "type", "min": 1 }, { "match": "regex", "regex": "\\d+" } ],
+                "combine": "OR"
+            }
+        }
+    })));
+    expect!(interaction["response"]["generators"].clone()).to(be_equal_to(json!({
+        "body": { "$.id": { "type": "Uuid" } },
+        "header": { "X-Date": { "type": "Date", "format": "yyyy-MM-dd" } }
+    })));
+    expect!(json["metadata"]["pact-specification"]["version"].clone()).to(be_equal_to(json!("3.0.0")));
+}
+
+#[test]
+fn write_v2_interaction_with_multiple_provider_states_uses_the_first_state() {
+    let interaction = Interaction {
+        provider_states: vec![ProviderState::default(&s!("state one")), ProviderState::default(&s!("state two"))],
+        .. Interaction::default()
+    };
+    let json = interaction.to_json(&PactSpecification::V2);
+    expect!(json["providerState"].clone()).to(be_equal_to(json!("state one")));
+    expect!(json.get("providerStates")).to(be_none());
+}
+
+#[test]
+fn load_message_pact() {
+    let pact_json = r#"
+    {
+        "consumer": { "name": "message_consumer" },
+        "provider": { "name": "message_provider" },
+        "messages": [
+            {
+                "description": "a user created message",
+                "providerStates": [ { "name": "a user exists", "params": { "id": 100 } } ],
+                "metaData": { "contentType": "application/json", "topic": "users" },
+                "contents": { "id": 100, "name": "Bob" },
+                "matchingRules": {
+                    "body": { "$.id": { "matchers": [ { "match": "type" } ] } },
+                    "metadata": { "topic": { "matchers": [ { "match": "regex", "regex": "\\w+" } ] } }
+                }
+            }
+        ],
+        "metadata": { "pactSpecification": { "version": "3.0.0" } }
+    }
+    "#;
+    let pact = MessagePact::from_json(&s!(""), &serde_json::from_str(pact_json).unwrap());
+    expect!(pact.consumer.name).to(be_equal_to(s!("message_consumer")));
+    expect!(pact.provider.name).to(be_equal_to(s!("message_provider")));
+    expect!(pact.specification_version).to(be_equal_to(PactSpecification::V3));
+    expect!(pact.messages.iter()).to(have_count(1));
+    let message = pact.messages[0].clone();
+    expect!(message.description).to(be_equal_to(s!("a user created message")));
+    expect!(message.provider_states).to(be_equal_to(vec![
+        ProviderState { name: s!("a user exists"), params: hashmap!{ s!("id") => json!(100) } }
+    ]));
+    expect!(message.metadata).to(be_equal_to(hashmap!{
+        s!("contentType") => s!("application/json"),
+        s!("topic") => s!("users")
+    }));
+    expect!(message.contents).to(be_equal_to(OptionalBody::Present(s!("{\"id\":100,\"name\":\"Bob\"}").into())));
+    expect!(message.matching_rules).to(be_equal_to(Some(hashmap!{
+        s!("$.body.id") => RuleList::new(hashmap!{ s!("match") => s!("type") }),
+        s!("$.metadata.topic") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("\\w+") })
+    })));
+}
+
+#[test]
+fn message_content_type_test() {
+    let message = Message { contents: OptionalBody::Present(s!("{\"a\": 1}").into()), .. Message::default() };
+    expect!(message.content_type()).to(be_equal_to(s!("application/json")));
+    let message = Message { contents: OptionalBody::Present(s!("{\"a\": 1}").into()),
+        metadata: hashmap!{ s!("contentType") => s!("text/plain; charset=UTF-8") }, .. Message::default() };
+    expect!(message.content_type()).to(be_equal_to(s!("text/plain")));
+}
+
+#[test]
+fn write_message_pact_test() {
+    let pact = MessagePact { consumer: Consumer { name: s!("write_pact_test_consumer") },
+        provider: Provider { name: s!("write_pact_test_provider") },
+        messages: vec![
+            Message {
+                description: s!("Test Message"),
+                provider_states: vec![ProviderState::default(&s!("Good state to be in"))],
+                contents: OptionalBody::Present(s!("{\"id\": 1}").into()),
+                metadata: hashmap!{ s!("contentType") => s!("application/json") },
+                matching_rules: Some(hashmap!{
+                    s!("$.body.id") => RuleList::new(hashmap!{ s!("match") => s!("type") })
+                })
+            }
+        ],
+        .. MessagePact::default() };
+    let mut dir = env::temp_dir();
+    let x = rand::random::<u16>();
+    dir.push(format!("pact_test_{}", x));
+    dir.push(pact.default_file_name());
+
+    let result = pact.write_pact(dir.as_path());
+
+    let pact_file = read_pact_file(dir.as_path().to_str().unwrap()).unwrap_or(s!(""));
+    fs::remove_dir_all(dir.parent().unwrap()).unwrap_or(());
+
+    expect!(result).to(be_ok());
+    let json: serde_json::Value = serde_json::from_str(&pact_file).unwrap();
+    expect!(json["messages"].clone()).to(be_equal_to(json!([
+        {
+            "description": "Test Message",
+            "providerStates": [ { "name": "Good state to be in" } ],
+            "contents": { "id": 1 },
+            "metaData": { "contentType": "application/json" },
+            "matchingRules": { "body": { "$.id": { "matchers": [ { "match": "type" } ] } } }
+        }
+    ])));
+    expect!(json["metadata"]["pact-specification"]["version"].clone()).to(be_equal_to(json!("3.0.0")));
+}
+
+#[test]
+fn merging_message_pacts_fails_if_the_messages_conflict() {
+    let pact = MessagePact { messages: vec![
+            Message { description: s!("Test Message"), contents: OptionalBody::Present(s!("a").into()), .. Message::default() }
+        ], .. MessagePact::default() };
+    let pact2 = MessagePact { messages: vec![
+            Message { description: s!("Test Message"), contents: OptionalBody::Present(s!("b").into()), .. Message::default() }
+        ], .. MessagePact::default() };
+    expect!(pact.merge(&pact)).to(be_ok());
+    expect!(pact.merge(&pact2)).to(be_err());
+}
+
+#[test]
+fn generators_generate_values_of_the_correct_form() {
+    let context = hashmap!{};
+    let value: Option<String> = Generator::RandomInt(1, 3).generate_value(&s!(""), &context);
+    expect!(value.clone()).to(be_some());
+    expect!(value.unwrap().parse::<i64>().unwrap()).to(be_less_or_equal_to(3));
+    let value: Option<String> = Generator::RandomDecimal(6).generate_value(&s!(""), &context);
+    expect!(Regex::new(r"^\d+\.\d+$").unwrap().is_match(&value.clone().unwrap())).to(be_true());
+    expect!(value.unwrap().len()).to(be_equal_to(7));
+    let value: Option<String> = Generator::RandomHexadecimal(8).generate_value(&s!(""), &context);
+    expect!(Regex::new(r"^[0-9A-F]{8}$").unwrap().is_match(&value.unwrap())).to(be_true());
+    let value: Option<String> = Generator::RandomString(12).generate_value(&s!(""), &context);
+    expect!(value.unwrap().len()).to(be_equal_to(12));
+    let value: Option<String> = Generator::Uuid.generate_value(&s!(""), &context);
+    expect!(Regex::new(r"^[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$").unwrap()
+        .is_match(&value.unwrap())).to(be_true());
+    let value: Option<String> = Generator::Regex(s!(r"\d{3}-[A-Z]+(x|y)?")).generate_value(&s!(""), &context);
+    expect!(Regex::new(r"^\d{3}-[A-Z]+(x|y)?$").unwrap().is_match(&value.unwrap())).to(be_true());
+    let value: Option<String> = Generator::Date(None).generate_value(&s!(""), &context);
+    expect!(Regex::new(r"^\d{4}-\d{2}-\d{2}$").unwrap().is_match(&value.unwrap())).to(be_true());
+    let value: Option<String> = Generator::DateTime(Some(s!("yyyy/MM/dd HH:mm"))).generate_value(&s!(""), &context);
+    expect!(Regex::new(r"^\d{4}/\d{2}/\d{2} \d{2}:\d{2}$").unwrap().is_match(&value.unwrap())).to(be_true());
+    let value: Option<String> = Generator::RandomBoolean.generate_value(&s!(""), &context);
+    expect!(value.clone().unwrap() == "true" || value.unwrap() == "false").to(be_true());
+}
+
+#[test]
+fn provider_state_generator_looks_up_values_from_the_context() {
+    let context = hashmap!{ s!("id") => json!(1234), s!("name") => json!("Fred") };
+    let value: Option<Value> = Generator::ProviderStateGenerator(s!("id")).generate_value(&json!(1), &context);
+    expect!(value).to(be_some().value(json!(1234)));
+    let value: Option<String> = Generator::ProviderStateGenerator(s!("/users/${id}/${name}")).generate_value(&s!(""), &context);
+    expect!(value).to(be_some().value(s!("/users/1234/Fred")));
+    let value: Option<String> = Generator::ProviderStateGenerator(s!("/users/${other}")).generate_value(&s!(""), &context);
+    expect!(value).to(be_none());
+}
+
+#[test]
+fn status_code_generators_stay_within_the_range_of_a_status_code() {
+    let context = hashmap!{ s!("status") => json!(70000) };
+    let value: Option<u16> = Generator::RandomInt(70000, 80000).generate_value(&200, &context);
+    expect!(value).to(be_some().value(65535));
+    let value: Option<u16> = Generator::RandomInt(-10, -1).generate_value(&200, &context);
+    expect!(value).to(be_some().value(0));
+    let value: Option<u16> = Generator::RandomInt(200, 70000).generate_value(&200, &context);
+    expect!(value.unwrap()).to(be_greater_or_equal_to(200));
+    let value: Option<u16> = Generator::ProviderStateGenerator(s!("status")).generate_value(&200, &context);
+    expect!(value).to(be_none());
+}
+
+#[test]
+fn random_int_generator_handles_the_limits_of_the_i64_range() {
+    let context = hashmap!{};
+    let max = i64::max_value();
+    let min = i64::min_value();
+    let value: Option<String> = Generator::RandomInt(max - 1, max).generate_value(&s!(""), &context);
+    expect!(value.unwrap().parse::<i64>().unwrap()).to(be_greater_or_equal_to(max - 1));
+    let value: Option<String> = Generator::RandomInt(min, min + 1).generate_value(&s!(""), &context);
+    expect!(value.unwrap().parse::<i64>().unwrap()).to(be_less_or_equal_to(min + 1));
+    let value: Option<String> = Generator::RandomInt(min, max).generate_value(&s!(""), &context);
+    expect!(value.unwrap().parse::<i64>()).to(be_ok());
+}
+
+#[test]
+fn generators_from_json_rejects_sizes_outside_the_u16_range() {
+    expect!(Generator::from_json(&json!({ "type": "RandomString", "size": 20 })))
+        .to(be_some().value(Generator::RandomString(20)));
+    expect!(Generator::from_json(&json!({ "type": "RandomString" }))).to(be_some().value(Generator::RandomString(10)));
+    expect!(Generator::from_json(&json!({ "type": "RandomString", "size": -1 }))).to(be_none());
+    expect!(Generator::from_json(&json!({ "type": "RandomDecimal", "digits": 65536 }))).to(be_none());
+    expect!(Generator::from_json(&json!({ "type": "RandomHexadecimal", "digits": "-5" }))).to(be_none());
+}
+
+#[test]
+fn generate_response_applies_the_generators() {
+    let response = Response {
+        status: 200,
+        headers: Some(hashmap!{ s!("Content-Type") => s!("application/json"), s!("X-Id") => s!("1") }),
+        body: OptionalBody::Present(s!(r#"{"id": 1, "items": [{"code": "a"}, {"code": "b"}], "name": "Fred"}"#).into()),
+        generators: Some(hashmap!{
+            s!("$.status") => Generator::RandomInt(201, 201),
+            s!("$.headers['X-Id']") => Generator::RandomInt(10, 10),
+            s!("$.body.id") => Generator::RandomInt(5, 5),
+            s!("$.body.items[*].code") => Generator::Regex(s!("[0-9]{2}"))
+        }),
+        .. Response::default_response()
+    };
+    let generated = response.generate_response(&hashmap!{});
+    expect!(generated.status).to(be_equal_to(201));
+    expect!(generated.headers.unwrap().get("X-Id").cloned()).to(be_some().value(s!("10")));
+    let body: Value = serde_json::from_str(&generated.body.value()).unwrap();
+    expect!(body["id"].clone()).to(be_equal_to(json!(5)));
+    expect!(body["name"].clone()).to(be_equal_to(json!("Fred")));
+    expect!(body["items"][0]["code"].as_str().unwrap().len()).to(be_equal_to(2));
+    expect!(body["items"][1]["code"].as_str().unwrap().len()).to(be_equal_to(2));
+}
+
+#[test]
+fn generate_request_applies_the_generators() {
+    let request = Request {
+        path: s!("/users/100"),
+        query: Some(hashmap!{ s!("a") => vec![s!("1"), s!("2")], s!("b") => vec![s!("3")] }),
+        generators: Some(hashmap!{
+            s!("$.path") => Generator::ProviderStateGenerator(s!("/users/${id}")),
+            s!("$.query.a") => Generator::RandomInt(7, 7)
+        }),
+        .. Request::default_request()
+    };
+    let generated = request.generate_request(&hashmap!{ s!("id") => json!(1234) });
+    expect!(generated.path).to(be_equal_to(s!("/users/1234")));
+    expect!(generated.query).to(be_some().value(hashmap!{ s!("a") => vec![s!("7"), s!("7")], s!("b") => vec![s!("3")] }));
+    expect!(request.generate_request(&hashmap!{}).path).to(be_equal_to(s!("/users/100")));
 }
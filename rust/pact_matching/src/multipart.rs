@@ -0,0 +1,382 @@
+use super::Mismatch;
+use super::DiffConfig;
+use super::BodyMatcher;
+use std::collections::HashMap;
+use base64;
+use models::{Matchers, is_binary_content_type};
+use matchers::*;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Part {
+    name: String,
+    filename: Option<String>,
+    content_type: String,
+    content: Vec<u8>
+}
+
+impl Part {
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.content).into_owned()
+    }
+
+    // binary contents are displayed in mismatches as base64, in the same way as binary bodies
+    fn display_content(&self) -> String {
+        if is_binary_content_type(&self.content_type) {
+            base64::encode(&self.content)
+        } else {
+            self.text()
+        }
+    }
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn split_bytes<'a>(body: &'a [u8], delimiter: &[u8]) -> Vec<&'a [u8]> {
+    let mut sections = vec![];
+    let mut rest = body;
+    while let Some(index) = find_bytes(rest, delimiter) {
+        sections.push(&rest[..index]);
+        rest = &rest[index + delimiter.len()..];
+    }
+    sections.push(rest);
+    sections
+}
+
+fn strip_line_ending(value: &[u8]) -> &[u8] {
+    if value.ends_with(b"\r\n") {
+        &value[..value.len() - 2]
+    } else if value.ends_with(b"\n") {
+        &value[..value.len() - 1]
+    } else {
+        value
+    }
+}
+
+fn strip_leading_line_ending(value: &[u8]) -> &[u8] {
+    if value.starts_with(b"\r\n") {
+        &value[2..]
+    } else if value.starts_with(b"\n") {
+        &value[1..]
+    } else {
+        value
+    }
+}
+
+// the boundary is in the content type header, but the bodies are matched on the base content type
+// so it is taken from the first delimiter line of the body
+fn detect_boundary(body: &[u8]) -> Option<String> {
+    body.split(|b| *b == b'\n').map(|line| String::from_utf8_lossy(line).trim_right().to_string())
+        .find(|line| line.starts_with("--") && line.len() > 2)
+        .map(|line| s!(&line[2..]))
+}
+
+fn parse_disposition_parameters(value: &str) -> HashMap<String, String> {
+    value.split(';').skip(1).filter_map(|param| {
+        let name_value: Vec<&str> = param.splitn(2, '=').map(|v| v.trim()).collect();
+        if name_value.len() == 2 {
+            Some((name_value[0].to_lowercase(), s!(name_value[1].trim_matches('"'))))
+        } else {
+            None
+        }
+    }).collect()
+}
+
+// only the headers of a part are text, the contents are kept as bytes so binary parts are not mangled
+fn parse_part(section: &[u8]) -> Result<Part, String> {
+    let section = strip_leading_line_ending(section);
+    let (headers, content) = match find_bytes(section, b"\r\n\r\n") {
+        Some(index) => (&section[..index], &section[index + 4..]),
+        None => match find_bytes(section, b"\n\n") {
+            Some(index) => (&section[..index], &section[index + 2..]),
+            None => return Err(s!("part has no blank line after the headers"))
+        }
+    };
+    let mut disposition = HashMap::new();
+    let mut content_type = s!("text/plain");
+    for header in String::from_utf8_lossy(headers).lines() {
+        let name_value: Vec<&str> = header.splitn(2, ':').map(|v| v.trim()).collect();
+        if name_value.len() == 2 {
+            match name_value[0].to_lowercase().as_str() {
+                "content-disposition" => disposition = parse_disposition_parameters(name_value[1]),
+                "content-type" => content_type = s!(name_value[1].split(';').next().unwrap_or("").trim()),
+                _ => ()
+            }
+        }
+    }
+    match disposition.get("name") {
+        Some(name) => Ok(Part {
+            name: name.clone(),
+            filename: disposition.get("filename").cloned(),
+            content_type: content_type,
+            content: strip_line_ending(content).to_vec()
+        }),
+        None => Err(s!("part has no name in its Content-Disposition header"))
+    }
+}
+
+fn parse_multipart(body: &[u8]) -> Result<Vec<Part>, String> {
+    match detect_boundary(body) {
+        Some(boundary) => {
+            let delimiter = format!("--{}", boundary);
+            let sections = split_bytes(body, delimiter.as_bytes());
+            // the first section is the preamble, and the closing delimiter is followed by `--`
+            sections.iter().skip(1).take_while(|section| !section.starts_with(b"--"))
+                .map(|section| parse_part(section)).collect()
+        },
+        None => Err(s!("no multipart boundary was found"))
+    }
+}
+
+// matchers for the contents of a part are defined under the path of the part, so they are moved to
+// the body root to match the contents with the body matcher for the part's content type
+fn part_matchers(name: &String, matchers: &Option<Matchers>) -> Option<Matchers> {
+    matchers.clone().map(|m| {
+        let prefixes = vec![format!("$.body.{}", name), format!("$.body['{}']", name)];
+        m.into_iter().filter_map(|(key, rules)| {
+            prefixes.iter().find(|prefix| key.starts_with(prefix.as_str()) &&
+                (key.len() == prefix.len() || key[prefix.len()..].starts_with(".") || key[prefix.len()..].starts_with("[")))
+                .map(|prefix| (format!("$.body{}", &key[prefix.len()..]), rules))
+        }).collect()
+    })
+}
+
+fn compare_part(expected: &Part, actual: &Part, config: DiffConfig, mismatches: &mut Vec<Mismatch>,
+    matchers: &Option<Matchers>) {
+    let path = vec![s!("$"), s!("body"), expected.name.clone()];
+    let spath = path.join(".");
+    match expected.filename {
+        Some(ref filename) if Some(filename) != actual.filename.as_ref() => {
+            mismatches.push(Mismatch::BodyMismatch { path: spath.clone(),
+                expected: Some(filename.clone()),
+                actual: actual.filename.clone(),
+                mismatch: format!("Expected part '{}' to have filename '{}' but was '{}'", expected.name,
+                    filename, actual.filename.clone().unwrap_or(s!("")))});
+        },
+        _ => ()
+    }
+
+    if expected.content_type != actual.content_type {
+        mismatches.push(Mismatch::BodyMismatch { path: spath.clone(),
+            expected: Some(expected.content_type.clone()),
+            actual: Some(actual.content_type.clone()),
+            mismatch: format!("Expected part '{}' to have content type '{}' but was '{}'", expected.name,
+                expected.content_type, actual.content_type)});
+    } else if matcher_is_defined(&path, matchers) {
+        let result = if is_binary_content_type(&expected.content_type) {
+            match_values(&path, matchers.clone().unwrap(), &expected.content, &actual.content)
+        } else {
+            match_values(&path, matchers.clone().unwrap(), &expected.text(), &actual.text())
+        };
+        match result {
+            Err(message) => mismatches.push(Mismatch::BodyMismatch { path: spath.clone(),
+                expected: Some(expected.display_content()),
+                actual: Some(actual.display_content()),
+                mismatch: message }),
+            Ok(_) => ()
+        }
+    } else {
+        let mut part_mismatches = vec![];
+        super::compare_bodies(&expected.content_type, &expected.content, &actual.content, config,
+            &mut part_mismatches, &part_matchers(&expected.name, matchers));
+        for mismatch in part_mismatches {
+            match mismatch {
+                Mismatch::BodyMismatch { path, expected, actual, mismatch } => {
+                    let path = if path.starts_with("$.body") {
+                        format!("{}{}", spath, &path[6..])
+                    } else {
+                        spath.clone()
+                    };
+                    mismatches.push(Mismatch::BodyMismatch { path: path, expected: expected, actual: actual,
+                        mismatch: mismatch });
+                },
+                _ => mismatches.push(mismatch)
+            }
+        }
+    }
+}
+
+/// Matches `multipart/form-data` bodies. The parts are matched by name, and then the filename, content
+/// type and contents of each part are compared. The contents are compared using the body matcher for
+/// the content type of the part, with any matchers defined under the path `$.body.<part name>`.
+pub fn match_multipart(expected: &Vec<u8>, actual: &Vec<u8>, config: DiffConfig,
+    mismatches: &mut Vec<super::Mismatch>, matchers: &Option<Matchers>) {
+    match (parse_multipart(expected), parse_multipart(actual)) {
+        (Ok(expected_parts), Ok(actual_parts)) => {
+            for expected_part in &expected_parts {
+                match actual_parts.iter().find(|part| part.name == expected_part.name) {
+                    Some(actual_part) => compare_part(expected_part, actual_part, config.clone(), mismatches,
+                        matchers),
+                    None => mismatches.push(Mismatch::BodyMismatch { path: format!("$.body.{}", expected_part.name),
+                        expected: Some(expected_part.display_content()),
+                        actual: None,
+                        mismatch: format!("Expected a part named '{}' but was missing", expected_part.name)})
+                }
+            }
+            if config == DiffConfig::NoUnexpectedKeys {
+                for actual_part in &actual_parts {
+                    if !expected_parts.iter().any(|part| part.name == actual_part.name) {
+                        mismatches.push(Mismatch::BodyMismatch { path: format!("$.body.{}", actual_part.name),
+                            expected: None,
+                            actual: Some(actual_part.display_content()),
+                            mismatch: format!("Unexpected part '{}' received", actual_part.name)});
+                    }
+                }
+            }
+        },
+        (expected_result, actual_result) => {
+            match expected_result {
+                Err(err) => mismatches.push(Mismatch::BodyMismatch { path: s!("$.body"),
+                    expected: Some(String::from_utf8_lossy(expected).into_owned()),
+                    actual: Some(String::from_utf8_lossy(actual).into_owned()),
+                    mismatch: format!("Failed to parse the expected body: '{}'", err)}),
+                _ => ()
+            }
+            match actual_result {
+                Err(err) => mismatches.push(Mismatch::BodyMismatch { path: s!("$.body"),
+                    expected: Some(String::from_utf8_lossy(expected).into_owned()),
+                    actual: Some(String::from_utf8_lossy(actual).into_owned()),
+                    mismatch: format!("Failed to parse the actual body: '{}'", err)}),
+                _ => ()
+            }
+        }
+    }
+}
+
+/// Body matcher for `multipart/form-data` bodies. The bodies are matched as bytes, so that binary parts
+/// are compared without being converted to strings.
+pub struct MultipartMatcher;
+
+impl BodyMatcher for MultipartMatcher {
+    fn match_body(&self, expected: &Vec<u8>, actual: &Vec<u8>, config: DiffConfig,
+        mismatches: &mut Vec<Mismatch>, matchers: &Option<Matchers>) {
+        match_multipart(expected, actual, config, mismatches, matchers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expectest::prelude::*;
+    use Mismatch;
+    use DiffConfig;
+    use models::RuleList;
+
+    fn multipart_body(boundary: &str, parts: Vec<(&str, Option<&str>, &str, &str)>) -> Vec<u8> {
+        let mut body = String::new();
+        for (name, filename, content_type, content) in parts {
+            body.push_str(&format!("--{}\r\n", boundary));
+            match filename {
+                Some(filename) => body.push_str(&format!(
+                    "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n", name, filename)),
+                None => body.push_str(&format!("Content-Disposition: form-data; name=\"{}\"\r\n", name))
+            }
+            body.push_str(&format!("Content-Type: {}\r\n\r\n{}\r\n", content_type, content));
+        }
+        body.push_str(&format!("--{}--\r\n", boundary));
+        body.into_bytes()
+    }
+
+    fn binary_multipart_body(boundary: &str, name: &str, content_type: &str, content: &[u8]) -> Vec<u8> {
+        let mut body = format!("--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}.bin\"\r\n\
+            Content-Type: {}\r\n\r\n", boundary, name, name, content_type).into_bytes();
+        body.extend_from_slice(content);
+        body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+        body
+    }
+
+    #[test]
+    fn parse_multipart_returns_the_parts_of_the_body() {
+        let body = multipart_body("abc123", vec![
+            ("name", None, "text/plain", "fred"),
+            ("file", Some("data.json"), "application/json", "{\"a\": 1}")
+        ]);
+        expect!(parse_multipart(&body)).to(be_ok().value(vec![
+            Part { name: s!("name"), filename: None, content_type: s!("text/plain"), content: b"fred".to_vec() },
+            Part { name: s!("file"), filename: Some(s!("data.json")), content_type: s!("application/json"),
+                content: b"{\"a\": 1}".to_vec() }
+        ]));
+        expect!(parse_multipart(b"not multipart")).to(be_err());
+    }
+
+    #[test]
+    fn match_multipart_compares_the_parts_by_name() {
+        let expected = multipart_body("abc", vec![
+            ("name", None, "text/plain", "fred"),
+            ("file", Some("data.json"), "application/json", "{\"a\": 1}")
+        ]);
+        let actual = multipart_body("xyz", vec![
+            ("file", Some("data.json"), "application/json", "{ \"a\" : 1 }"),
+            ("name", None, "text/plain", "fred")
+        ]);
+        let mut mismatches = vec![];
+        match_multipart(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &None);
+        expect!(mismatches.clone()).to(be_empty());
+    }
+
+    #[test]
+    fn match_multipart_reports_missing_and_unexpected_parts() {
+        let expected = multipart_body("abc", vec![("name", None, "text/plain", "fred")]);
+        let actual = multipart_body("abc", vec![("other", None, "text/plain", "fred")]);
+        let mut mismatches = vec![];
+        match_multipart(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &None);
+        let descriptions: Vec<String> = mismatches.iter().map(|m| m.description()).collect();
+        expect!(descriptions).to(be_equal_to(vec![
+            s!("$.body.name -> Expected a part named 'name' but was missing"),
+            s!("$.body.other -> Unexpected part 'other' received")
+        ]));
+    }
+
+    #[test]
+    fn match_multipart_compares_the_filename_and_content_type_of_the_parts() {
+        let expected = multipart_body("abc", vec![("file", Some("data.json"), "application/json", "{}")]);
+        let actual = multipart_body("abc", vec![("file", Some("data.xml"), "application/xml", "<a/>")]);
+        let mut mismatches = vec![];
+        match_multipart(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &None);
+        let descriptions: Vec<String> = mismatches.iter().map(|m| m.description()).collect();
+        expect!(descriptions).to(be_equal_to(vec![
+            s!("$.body.file -> Expected part 'file' to have filename 'data.json' but was 'data.xml'"),
+            s!("$.body.file -> Expected part 'file' to have content type 'application/json' but was 'application/xml'")
+        ]));
+    }
+
+    #[test]
+    fn match_multipart_uses_the_body_matcher_for_the_part_content_type() {
+        let expected = multipart_body("abc", vec![
+            ("name", None, "text/plain", "fred"),
+            ("file", None, "application/json", "{\"id\": 1, \"b\": \"c\"}")
+        ]);
+        let actual = multipart_body("abc", vec![
+            ("name", None, "text/plain", "mary"),
+            ("file", None, "application/json", "{\"id\": 200, \"b\": \"d\"}")
+        ]);
+        let matchers = Some(hashmap!{
+            s!("$.body.name") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("^\\w+$") }),
+            s!("$.body.file.id") => RuleList::new(hashmap!{ s!("match") => s!("integer") })
+        });
+        let mut mismatches = vec![];
+        match_multipart(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &matchers);
+        expect!(mismatches).to(be_equal_to(vec![Mismatch::BodyMismatch { path: s!("$.body.file.b"),
+            expected: Some(s!("\"c\"")), actual: Some(s!("\"d\"")), mismatch: s!("") }]));
+    }
+
+    #[test]
+    fn match_multipart_compares_binary_parts_as_bytes() {
+        let content = vec![0x89, 0x50, 0x4e, 0x47, 0xff, 0xfe, 0x00, 0x01];
+        let expected = binary_multipart_body("abc", "image", "image/png", &content);
+        let mut mismatches = vec![];
+        match_multipart(&expected, &binary_multipart_body("xyz", "image", "image/png", &content),
+            DiffConfig::NoUnexpectedKeys, &mut mismatches, &None);
+        expect!(mismatches.clone()).to(be_empty());
+
+        // both of these bytes are invalid UTF-8, so they would be equal if converted to strings
+        let mut other_content = content.clone();
+        other_content[5] = 0xfd;
+        match_multipart(&expected, &binary_multipart_body("abc", "image", "image/png", &other_content),
+            DiffConfig::NoUnexpectedKeys, &mut mismatches, &None);
+        expect!(mismatches).to(be_equal_to(vec![Mismatch::BodyMismatch { path: s!("$.body.image"),
+            expected: Some(base64::encode(&content)), actual: Some(base64::encode(&other_content)),
+            mismatch: s!("") }]));
+    }
+}
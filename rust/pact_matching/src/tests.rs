@@ -2,7 +2,7 @@ use super::*;
 use super::{match_header_value, strip_whitespace};
 use std::collections::HashMap;
 use expectest::prelude::*;
-use models::{Request, OptionalBody};
+use models::{Request, Response, OptionalBody, RuleList, Message, Matchers};
 
 #[test]
 fn match_method_returns_nothing_if_the_method_matches() {
@@ -167,6 +167,32 @@ fn match_query_returns_a_mismatch_if_the_values_are_not_the_same() {
         mismatch: s!("Expected 'b' but received 'c' for query parameter 'a'") });
 }
 
+#[test]
+fn match_query_returns_the_mismatches_in_parameter_order() {
+    let mut mismatches = vec![];
+    let expected = Some(hashmap!{ s!("c") => vec![s!("1")], s!("a") => vec![s!("1")], s!("b") => vec![s!("1")] });
+    let actual = Some(hashmap!{ s!("z") => vec![s!("1")], s!("y") => vec![s!("1")] });
+    match_query(expected, actual, &mut mismatches, &None);
+    let parameters: Vec<String> = mismatches.iter().map(|mismatch| match *mismatch {
+        Mismatch::QueryMismatch { ref parameter, .. } => parameter.clone(),
+        _ => s!("")
+    }).collect();
+    expect!(parameters).to(be_equal_to(vec![s!("a"), s!("b"), s!("c"), s!("y"), s!("z")]));
+}
+
+#[test]
+fn match_headers_returns_the_mismatches_in_header_order() {
+    let mut mismatches = vec![];
+    let expected = Some(hashmap!{ s!("C") => s!("1"), s!("A") => s!("1"), s!("B") => s!("1"), s!("D") => s!("1") });
+    let actual = Some(hashmap!{ s!("D") => s!("1") });
+    match_headers(expected, actual, &mut mismatches, &None);
+    let keys: Vec<String> = mismatches.iter().map(|mismatch| match *mismatch {
+        Mismatch::HeaderMismatch { ref key, .. } => key.clone(),
+        _ => s!("")
+    }).collect();
+    expect!(keys).to(be_equal_to(vec![s!("A"), s!("B"), s!("C")]));
+}
+
 #[test]
 fn matching_headers_be_true_when_headers_are_equal() {
     let mut mismatches = vec![];
@@ -333,10 +359,10 @@ fn body_does_not_match_if_different_content_types() {
     let mut mismatches = vec![];
     let expected = Request { method: s!("GET"), path: s!("/"), query: None,
         headers: Some(hashmap!{ s!("Content-Type") => s!("application/json") }),
-        body: OptionalBody::Present(s!("")), matching_rules: None };
+        body: OptionalBody::Present(s!("").into()), matching_rules: None, generators: None };
     let actual = Request { method: s!("GET"), path: s!("/"), query: None,
         headers: Some(hashmap!{ s!("Content-Type") => s!("text/plain") }),
-        body: OptionalBody::Missing, matching_rules: None };
+        body: OptionalBody::Missing, matching_rules: None, generators: None };
     match_body(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &None);
     expect!(mismatches.clone()).to_not(be_empty());
     expect!(mismatches[0].clone()).to(be_equal_to(Mismatch::BodyTypeMismatch { expected: s!("application/json"),
@@ -348,10 +374,10 @@ fn body_matches_if_expected_is_missing() {
     let mut mismatches = vec![];
     let expected = Request { method: s!("GET"), path: s!("/"), query: None,
         headers: Some(hashmap!{ s!("Content-Type") => s!("application/json") }),
-        body: OptionalBody::Missing, matching_rules: None };
+        body: OptionalBody::Missing, matching_rules: None, generators: None };
     let actual = Request { method: s!("GET"), path: s!("/"), query: None,
         headers: Some(hashmap!{ s!("Content-Type") => s!("application/json") }),
-        body: OptionalBody::Present(s!("{}")), matching_rules: None };
+        body: OptionalBody::Present(s!("{}").into()), matching_rules: None, generators: None };
     match_body(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &None);
     expect!(mismatches.clone()).to(be_empty());
 }
@@ -361,14 +387,84 @@ fn body_matches_with_extended_mime_types() {
     let mut mismatches = vec![];
     let expected = Request { method: s!("GET"), path: s!("/"), query: None,
         headers: Some(hashmap!{ s!("Content-Type") => s!("application/thrift+json") }),
-        body: OptionalBody::Present(s!(r#"{"test":true}"#)), matching_rules: None };
+        body: OptionalBody::Present(s!(r#"{"test":true}"#).into()), matching_rules: None, generators: None };
     let actual = Request { method: s!("GET"), path: s!("/"), query: None,
         headers: Some(hashmap!{ s!("Content-Type") => s!("application/thrift+json") }),
-        body: OptionalBody::Present(s!(r#"{"test": true}"#)), matching_rules: None };
+        body: OptionalBody::Present(s!(r#"{"test": true}"#).into()), matching_rules: None, generators: None };
+    match_body(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &None);
+    expect!(mismatches.clone()).to(be_empty());
+}
+
+#[test]
+fn body_matches_form_urlencoded_bodies_by_parameter() {
+    let mut mismatches = vec![];
+    let expected = Request { method: s!("POST"), path: s!("/"), query: None,
+        headers: Some(hashmap!{ s!("Content-Type") => s!("application/x-www-form-urlencoded") }),
+        body: OptionalBody::Present(s!("a=1&b=2").into()), matching_rules: None, generators: None };
+    let actual = Request { method: s!("POST"), path: s!("/"), query: None,
+        headers: Some(hashmap!{ s!("Content-Type") => s!("application/x-www-form-urlencoded; charset=UTF-8") }),
+        body: OptionalBody::Present(s!("b=2&a=1").into()), matching_rules: None, generators: None };
     match_body(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &None);
     expect!(mismatches.clone()).to(be_empty());
 }
 
+#[test]
+fn body_matches_binary_bodies_as_bytes() {
+    let mut mismatches = vec![];
+    let expected = Response { headers: Some(hashmap!{ s!("Content-Type") => s!("application/octet-stream") }),
+        body: OptionalBody::Present(vec![0, 159, 146, 150]), .. Response::default_response() };
+    let actual = Response { headers: Some(hashmap!{ s!("Content-Type") => s!("application/octet-stream") }),
+        body: OptionalBody::Present(vec![0, 159, 146, 150]), .. Response::default_response() };
+    match_body(&expected, &actual, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &None);
+    expect!(mismatches.clone()).to(be_empty());
+    let actual = Response { body: OptionalBody::Present(vec![0, 159, 146, 151]), .. actual };
+    match_body(&expected, &actual, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &None);
+    expect!(mismatches.iter()).to(have_count(1));
+}
+
+#[test]
+fn body_matches_binary_bodies_with_a_content_type_matcher() {
+    let mut mismatches = vec![];
+    let matchers = Some(hashmap!{
+        s!("$.body") => RuleList::new(hashmap!{ s!("match") => s!("contentType"), s!("value") => s!("image/png") })
+    });
+    let expected = Response { headers: Some(hashmap!{ s!("Content-Type") => s!("image/png") }),
+        body: OptionalBody::Present(b"\x89PNG\r\n\x1A\n\x00".to_vec()), .. Response::default_response() };
+    let actual = Response { headers: Some(hashmap!{ s!("Content-Type") => s!("image/png") }),
+        body: OptionalBody::Present(b"\x89PNG\r\n\x1A\n\x00\x00\x00\x0DIHDR".to_vec()), .. Response::default_response() };
+    match_body(&expected, &actual, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+    expect!(mismatches.clone()).to(be_empty());
+    let actual = Response { body: OptionalBody::Present(b"GIF89a".to_vec()), .. actual };
+    match_body(&expected, &actual, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+    expect!(mismatches.iter()).to(have_count(1));
+}
+
+#[test]
+fn body_matches_using_a_registered_body_matcher() {
+    expect!(register_body_matcher("application/x-test-csv", |expected: &String, actual: &String, _config: DiffConfig,
+        mismatches: &mut Vec<Mismatch>, _matchers: &Option<Matchers>| {
+        if expected.lines().count() != actual.lines().count() {
+            mismatches.push(Mismatch::BodyMismatch { path: s!("$.body"), expected: Some(expected.clone()),
+                actual: Some(actual.clone()), mismatch: s!("Expected the same number of rows") });
+        }
+    })).to(be_ok());
+    let mut mismatches = vec![];
+    let expected = Request { headers: Some(hashmap!{ s!("Content-Type") => s!("application/x-test-csv") }),
+        body: OptionalBody::Present(s!("a,b\n1,2").into()), .. Request::default_request() };
+    let actual = Request { headers: Some(hashmap!{ s!("Content-Type") => s!("application/x-test-csv") }),
+        body: OptionalBody::Present(s!("c,d\n3,4").into()), .. Request::default_request() };
+    match_body(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &None);
+    expect!(mismatches.clone()).to(be_empty());
+    let actual = Request { body: OptionalBody::Present(s!("c,d").into()), .. actual };
+    match_body(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &None);
+    expect!(mismatches.iter()).to(have_count(1));
+}
+
+#[test]
+fn register_body_matcher_returns_an_error_for_an_invalid_content_type_regex() {
+    expect!(register_body_matcher("application/[", json::match_json)).to(be_err());
+}
+
 #[test]
 fn partial_equal_for_method_mismatch() {
     let mismatch = Mismatch::MethodMismatch { expected: s!("get"), actual: s!("post") };
@@ -550,7 +646,7 @@ fn match_path_returns_a_mismatch_if_the_path_does_not_match() {
 fn match_path_returns_nothing_if_the_path_matches_with_a_matcher() {
     let mut mismatches = vec![];
     match_path(s!("/path/1234"), s!("/path/5678"), &mut mismatches, &Some(hashmap!{
-        s!("$.path") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("/path/\\d+") }
+        s!("$.path") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("/path/\\d+") })
     }));
     expect!(mismatches).to(be_empty());
 }
@@ -559,7 +655,7 @@ fn match_path_returns_nothing_if_the_path_matches_with_a_matcher() {
 fn match_path_returns_a_mismatch_if_the_path_does_not_match_with_a_matcher() {
     let mut mismatches = vec![];
     match_path(s!("/path/1234"), s!("/path/abc"), &mut mismatches, &Some(hashmap!{
-        s!("$.path") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("/path/\\d+") }
+        s!("$.path") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("/path/\\d+") })
     }));
     expect!(mismatches.clone()).to_not(be_empty());
     expect!(mismatches[0].clone()).to(be_equal_to(Mismatch::PathMismatch { expected: s!("/path/1234"),
@@ -576,7 +672,7 @@ fn match_query_returns_no_mismatch_if_the_values_are_not_the_same_but_match_by_a
     query_map.insert(s!("a"), vec![s!("c")]);
     let actual = Some(query_map);
     match_query(expected, actual, &mut mismatches, &Some(hashmap!{
-        s!("$.query.a") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("\\w+") }
+        s!("$.query.a") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("\\w+") })
     }));
     expect!(mismatches).to(be_empty());
 }
@@ -591,7 +687,7 @@ fn match_query_returns_a_mismatch_if_the_values_do_not_match_by_a_matcher() {
     query_map.insert(s!("a"), vec![s!("b")]);
     let actual = Some(query_map);
     match_query(expected, actual, &mut mismatches, &Some(hashmap!{
-        s!("$.query.a") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("\\d+") }
+        s!("$.query.a") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("\\d+") })
     }));
     expect!(mismatches.clone()).to_not(be_empty());
     assert_eq!(mismatches[0], Mismatch::QueryMismatch { parameter: s!("a"),
@@ -604,7 +700,7 @@ fn matching_headers_be_true_when_headers_match_by_matcher() {
     let mut mismatches = vec![];
     match_header_value(&s!("HEADER"), &s!("HEADERX"), &s!("HEADERY"),
         &mut mismatches, &Some(hashmap!{
-            s!("$.headers.HEADER") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("\\w+") }
+            s!("$.headers.HEADER") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("\\w+") })
         }));
     expect!(mismatches).to(be_empty());
 }
@@ -614,10 +710,99 @@ fn matching_headers_be_false_when_headers_do_not_match_by_matcher() {
     let mut mismatches = vec![];
     match_header_value(&s!("HEADER"), &s!("HEADER"), &s!("HEADER"),
         &mut mismatches, &Some(hashmap!{
-            s!("$.headers.HEADER") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("\\d+") }
+            s!("$.headers.HEADER") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("\\d+") })
         }));
     expect!(mismatches.clone()).to_not(be_empty());
     assert_eq!(mismatches[0], Mismatch::HeaderMismatch { key: s!("HEADER"),
         expected: s!("HEADER"), actual: s!("HEADER"),
         mismatch: s!("") });
 }
+
+#[test]
+fn match_message_returns_no_mismatches_if_the_contents_and_metadata_match() {
+    let expected = Message {
+        contents: OptionalBody::Present(s!("{\"id\": 100}").into()),
+        metadata: hashmap!{ s!("contentType") => s!("application/json"), s!("topic") => s!("users") },
+        .. Message::default()
+    };
+    let actual = Message {
+        contents: OptionalBody::Present(s!("{\"id\": 100, \"name\": \"Bob\"}").into()),
+        metadata: hashmap!{ s!("contentType") => s!("application/json"), s!("topic") => s!("users"),
+            s!("partition") => s!("1") },
+        .. Message::default()
+    };
+    expect!(match_message(expected, actual)).to(be_empty());
+}
+
+#[test]
+fn match_message_uses_the_matching_rules_for_the_contents_and_metadata() {
+    let expected = Message {
+        contents: OptionalBody::Present(s!("{\"id\": 100}").into()),
+        metadata: hashmap!{ s!("contentType") => s!("application/json"), s!("topic") => s!("users") },
+        matching_rules: Some(hashmap!{
+            s!("$.body.id") => RuleList::new(hashmap!{ s!("match") => s!("type") }),
+            s!("$.metadata.topic") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("^user") })
+        }),
+        .. Message::default()
+    };
+    let actual = Message {
+        contents: OptionalBody::Present(s!("{\"id\": 200}").into()),
+        metadata: hashmap!{ s!("contentType") => s!("application/json"), s!("topic") => s!("users-v2") },
+        .. Message::default()
+    };
+    expect!(match_message(expected, actual)).to(be_empty());
+}
+
+#[test]
+fn match_message_returns_mismatches_for_the_contents_and_metadata() {
+    let expected = Message {
+        contents: OptionalBody::Present(s!("{\"id\": 100}").into()),
+        metadata: hashmap!{ s!("contentType") => s!("application/json"), s!("topic") => s!("users"),
+            s!("key") => s!("100") },
+        .. Message::default()
+    };
+    let actual = Message {
+        contents: OptionalBody::Present(s!("{\"id\": 200}").into()),
+        metadata: hashmap!{ s!("contentType") => s!("application/json"), s!("topic") => s!("orders") },
+        .. Message::default()
+    };
+    let mismatches = match_message(expected, actual);
+    expect!(mismatches.iter()).to(have_count(3));
+    expect!(mismatches.iter().any(|m| *m == Mismatch::BodyMismatch { path: s!("$.body.id"),
+        expected: Some(s!("100")), actual: Some(s!("200")), mismatch: s!("") })).to(be_true());
+    expect!(mismatches.iter().any(|m| *m == Mismatch::MetadataMismatch { key: s!("topic"),
+        expected: s!("users"), actual: s!("orders"), mismatch: s!("") })).to(be_true());
+    expect!(mismatches.iter().any(|m| *m == Mismatch::MetadataMismatch { key: s!("key"),
+        expected: s!("100"), actual: s!(""), mismatch: s!("") })).to(be_true());
+}
+
+#[test]
+fn match_metadata_reports_the_mismatches_in_key_order() {
+    let expected = hashmap!{ s!("topic") => s!("users"), s!("key") => s!("100"), s!("partition") => s!("1"),
+        s!("contentType") => s!("application/json"), s!("destination") => s!("queue") };
+    let actual = hashmap!{ s!("topic") => s!("orders"), s!("partition") => s!("2") };
+    let mut mismatches = vec![];
+    match_metadata(&expected, &actual, &mut mismatches, &None);
+    expect!(mismatches).to(be_equal_to(vec![
+        Mismatch::MetadataMismatch { key: s!("destination"), expected: s!("queue"), actual: s!(""), mismatch: s!("") },
+        Mismatch::MetadataMismatch { key: s!("key"), expected: s!("100"), actual: s!(""), mismatch: s!("") },
+        Mismatch::MetadataMismatch { key: s!("partition"), expected: s!("1"), actual: s!("2"), mismatch: s!("") },
+        Mismatch::MetadataMismatch { key: s!("topic"), expected: s!("users"), actual: s!("orders"), mismatch: s!("") }
+    ]));
+}
+
+#[test]
+fn match_message_returns_a_mismatch_if_the_content_types_are_different() {
+    let expected = Message {
+        contents: OptionalBody::Present(s!("{\"id\": 100}").into()),
+        metadata: hashmap!{ s!("contentType") => s!("application/json") },
+        .. Message::default()
+    };
+    let actual = Message {
+        contents: OptionalBody::Present(s!("<id>100</id>").into()),
+        metadata: hashmap!{ s!("contentType") => s!("application/xml") },
+        .. Message::default()
+    };
+    expect!(match_message(expected, actual)).to(be_equal_to(vec![Mismatch::BodyTypeMismatch {
+        expected: s!("application/json"), actual: s!("application/xml") }]));
+}
@@ -0,0 +1,288 @@
+//! The `time_utils` module provides validation of date and time values against the date/time
+//! format patterns used by the other Pact implementations (Java `SimpleDateFormat` style patterns,
+//! i.e. `yyyy-MM-dd'T'HH:mm:ss`). The patterns are converted to regular expressions, which are
+//! then used to check the values. It also formats dates and times with the same patterns, which is
+//! used by the date and time generators.
+
+use regex::{Regex, quote};
+use chrono::{DateTime, TimeZone, Datelike, Timelike, Local};
+use std::fmt::Display;
+
+/// Default format used by the `timestamp` matcher and `DateTime` generator if no format is provided
+pub const DEFAULT_TIMESTAMP_FORMAT: &'static str = "yyyy-MM-dd'T'HH:mm:ss";
+/// Default format used by the `date` matcher and `Date` generator if no format is provided
+pub const DEFAULT_DATE_FORMAT: &'static str = "yyyy-MM-dd";
+/// Default format used by the `time` matcher and `Time` generator if no format is provided
+pub const DEFAULT_TIME_FORMAT: &'static str = "HH:mm:ss";
+
+fn pattern_for_letter(letter: char, count: usize) -> Result<String, String> {
+    let pattern = match letter {
+        'G' => s!("(AD|BC)"),
+        'y' | 'Y' | 'u' => match count {
+            2 => s!("\\d{2}"),
+            1 => s!("\\d+"),
+            _ => format!("\\d{{{}}}", count)
+        },
+        'M' | 'L' => match count {
+            1 => s!("(1[0-2]|[1-9])"),
+            2 => s!("(0[1-9]|1[0-2])"),
+            3 => s!("[A-Za-z]{3}"),
+            _ => s!("[A-Za-z]+")
+        },
+        'd' => if count == 1 { s!("(3[01]|[12]\\d|[1-9])") } else { s!("(0[1-9]|[12]\\d|3[01])") },
+        'D' => match count {
+            1 => s!("\\d{1,3}"),
+            2 => s!("\\d{2,3}"),
+            _ => s!("\\d{3}")
+        },
+        'E' => if count > 3 { s!("[A-Za-z]+") } else { s!("[A-Za-z]{3}") },
+        'a' => s!("(AM|PM|am|pm)"),
+        'H' => if count == 1 { s!("(2[0-3]|1\\d|\\d)") } else { s!("([01]\\d|2[0-3])") },
+        'k' => if count == 1 { s!("(2[0-4]|1\\d|[1-9])") } else { s!("(0[1-9]|1\\d|2[0-4])") },
+        'K' => if count == 1 { s!("(1[01]|\\d)") } else { s!("(0\\d|1[01])") },
+        'h' => if count == 1 { s!("(1[0-2]|[1-9])") } else { s!("(0[1-9]|1[0-2])") },
+        'm' | 's' => if count == 1 { s!("([1-5]\\d|\\d)") } else { s!("[0-5]\\d") },
+        'S' => format!("\\d{{{}}}", count),
+        'w' | 'W' | 'F' => s!("\\d+"),
+        'z' => s!("([A-Za-z]+([+-]\\d{2}:?\\d{2})?)"),
+        'Z' => s!("[+-]\\d{4}"),
+        'X' => match count {
+            1 => s!("(Z|[+-]\\d{2})"),
+            2 => s!("(Z|[+-]\\d{4})"),
+            _ => s!("(Z|[+-]\\d{2}:\\d{2})")
+        },
+        _ => return Err(format!("'{}' is not a supported date/time pattern letter", letter))
+    };
+    Ok(pattern)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum DateTimeToken {
+    Literal(char),
+    Letter(char, usize)
+}
+
+fn tokenise(format: &String) -> Result<Vec<DateTimeToken>, String> {
+    let chars: Vec<char> = format.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i];
+        if ch == '\'' {
+            if i + 1 < chars.len() && chars[i + 1] == '\'' {
+                tokens.push(DateTimeToken::Literal('\''));
+                i += 2;
+            } else {
+                i += 1;
+                let mut closed = false;
+                while i < chars.len() {
+                    if chars[i] == '\'' {
+                        if i + 1 < chars.len() && chars[i + 1] == '\'' {
+                            tokens.push(DateTimeToken::Literal('\''));
+                            i += 2;
+                        } else {
+                            closed = true;
+                            i += 1;
+                            break;
+                        }
+                    } else {
+                        tokens.push(DateTimeToken::Literal(chars[i]));
+                        i += 1;
+                    }
+                }
+                if !closed {
+                    return Err(format!("Unterminated quoted text in date/time pattern '{}'", format));
+                }
+            }
+        } else if (ch >= 'a' && ch <= 'z') || (ch >= 'A' && ch <= 'Z') {
+            let mut count = 1;
+            while i + count < chars.len() && chars[i + count] == ch {
+                count += 1;
+            }
+            tokens.push(DateTimeToken::Letter(ch, count));
+            i += count;
+        } else {
+            tokens.push(DateTimeToken::Literal(ch));
+            i += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+/// Converts a date/time format pattern into a regular expression that will match values in that
+/// format. Returns an error if the pattern is not valid.
+pub fn to_regex_pattern(format: &String) -> Result<String, String> {
+    let mut pattern = String::new();
+    for token in try!(tokenise(format)) {
+        match token {
+            DateTimeToken::Literal(ch) => pattern.push_str(&quote(&ch.to_string())),
+            DateTimeToken::Letter(ch, count) => pattern.push_str(&try!(pattern_for_letter(ch, count)
+                .map_err(|err| format!("Invalid date/time pattern '{}' - {}", format, err))))
+        }
+    }
+    Ok(pattern)
+}
+
+fn strftime_for_letter(letter: char, count: usize) -> Result<String, String> {
+    let specifier = match letter {
+        'G' => s!("AD"),
+        'y' | 'Y' | 'u' => if count == 2 { s!("%y") } else { s!("%Y") },
+        'M' | 'L' => match count {
+            1 => s!("%-m"),
+            2 => s!("%m"),
+            3 => s!("%b"),
+            _ => s!("%B")
+        },
+        'd' => if count == 1 { s!("%-d") } else { s!("%d") },
+        'D' => s!("%j"),
+        'E' => if count > 3 { s!("%A") } else { s!("%a") },
+        'a' => s!("%p"),
+        'H' => if count == 1 { s!("%-H") } else { s!("%H") },
+        'h' => if count == 1 { s!("%-I") } else { s!("%I") },
+        'm' => if count == 1 { s!("%-M") } else { s!("%M") },
+        's' => if count == 1 { s!("%-S") } else { s!("%S") },
+        'z' => s!("%Z"),
+        'Z' => s!("%z"),
+        'X' => if count < 3 { s!("%z") } else { s!("%:z") },
+        _ => return Err(format!("'{}' is not a supported date/time pattern letter", letter))
+    };
+    Ok(specifier)
+}
+
+fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+// strftime has no equivalent of these fields, so they are calculated in the same way as Java does
+// with weeks starting on a Sunday, and the first week of the year being the week containing 1 January
+fn numeric_field<Tz: TimeZone>(letter: char, datetime: &DateTime<Tz>) -> u32 {
+    let weekday = datetime.weekday().num_days_from_sunday();
+    match letter {
+        'k' => if datetime.hour() == 0 { 24 } else { datetime.hour() },
+        'K' => datetime.hour() % 12,
+        'F' => (datetime.day() - 1) / 7 + 1,
+        'W' => {
+            let first_of_month = (weekday + 35 - (datetime.day() - 1)) % 7;
+            (datetime.day() - 1 + first_of_month) / 7 + 1
+        },
+        _ => {
+            let days_in_year = if is_leap_year(datetime.year()) { 366 } else { 365 };
+            if datetime.ordinal0() + (6 - weekday) >= days_in_year {
+                // the last days of December are in the first week of the next year
+                1
+            } else {
+                let first_of_year = (weekday + 371 - datetime.ordinal0()) % 7;
+                (datetime.ordinal0() + first_of_year) / 7 + 1
+            }
+        }
+    }
+}
+
+/// Formats the date and time using the date/time format pattern. Returns an error if the pattern
+/// is not valid.
+pub fn format_datetime<Tz: TimeZone>(format: &String, datetime: &DateTime<Tz>) -> Result<String, String>
+    where Tz::Offset: Display {
+    let mut pattern = String::new();
+    for token in try!(tokenise(format)) {
+        match token {
+            DateTimeToken::Literal('%') => pattern.push_str("%%"),
+            DateTimeToken::Literal(ch) => pattern.push(ch),
+            // chrono can only format fractional seconds with a leading decimal point, so the digits
+            // are written directly into the pattern
+            DateTimeToken::Letter('S', count) => {
+                let digits = format!("{:09}", datetime.nanosecond());
+                pattern.push_str(&format!("{:0<1$}", &digits[..::std::cmp::min(count, 9)], count));
+            },
+            DateTimeToken::Letter(ch, count) if "kKwWF".contains(ch) =>
+                pattern.push_str(&format!("{:01$}", numeric_field(ch, datetime), count)),
+            DateTimeToken::Letter(ch, count) => pattern.push_str(&try!(strftime_for_letter(ch, count)
+                .map_err(|err| format!("Invalid date/time pattern '{}' - {}", format, err))))
+        }
+    }
+    Ok(datetime.format(&pattern).to_string())
+}
+
+/// Formats the current local date and time using the date/time format pattern.
+pub fn format_current_datetime(format: &String) -> Result<String, String> {
+    format_datetime(format, &Local::now())
+}
+
+/// Validates the value against the date/time format pattern.
+pub fn validate_datetime(value: &String, format: &String) -> Result<(), String> {
+    let pattern = try!(to_regex_pattern(format));
+    match Regex::new(&format!("^{}$", pattern)) {
+        Ok(re) => if re.is_match(value) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' to match a date/time pattern of '{}'", value, format))
+        },
+        Err(err) => Err(format!("Invalid date/time pattern '{}' - {}", format, err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expectest::prelude::*;
+    use chrono::{FixedOffset, TimeZone};
+
+    #[test]
+    fn validate_datetime_with_default_formats() {
+        expect!(validate_datetime(&s!("2017-06-21T10:01:02"), &s!(DEFAULT_TIMESTAMP_FORMAT))).to(be_ok());
+        expect!(validate_datetime(&s!("2017-06-21 10:01:02"), &s!(DEFAULT_TIMESTAMP_FORMAT))).to(be_err());
+        expect!(validate_datetime(&s!("2017-06-21"), &s!(DEFAULT_DATE_FORMAT))).to(be_ok());
+        expect!(validate_datetime(&s!("2017-13-21"), &s!(DEFAULT_DATE_FORMAT))).to(be_err());
+        expect!(validate_datetime(&s!("23:59:59"), &s!(DEFAULT_TIME_FORMAT))).to(be_ok());
+        expect!(validate_datetime(&s!("24:00:00"), &s!(DEFAULT_TIME_FORMAT))).to(be_err());
+    }
+
+    #[test]
+    fn validate_datetime_with_custom_formats() {
+        expect!(validate_datetime(&s!("21/06/17 10:01 PM"), &s!("dd/MM/yy hh:mm a"))).to(be_ok());
+        expect!(validate_datetime(&s!("Wed, 21 Jun 2017 10:01:02 +1000"), &s!("EEE, d MMM yyyy HH:mm:ss Z"))).to(be_ok());
+        expect!(validate_datetime(&s!("2017-06-21T10:01:02.123+10:00"), &s!("yyyy-MM-dd'T'HH:mm:ss.SSSXXX"))).to(be_ok());
+        expect!(validate_datetime(&s!("2017-06-21T10:01:02.123Z"), &s!("yyyy-MM-dd'T'HH:mm:ss.SSSXXX"))).to(be_ok());
+        expect!(validate_datetime(&s!("at 10 o'clock"), &s!("'at' H 'o''clock'"))).to(be_ok());
+    }
+
+    #[test]
+    fn format_datetime_test() {
+        let datetime = FixedOffset::east(36000).ymd(2017, 6, 21).and_hms_milli(9, 1, 2, 123);
+        expect!(format_datetime(&s!(DEFAULT_TIMESTAMP_FORMAT), &datetime)).to(be_ok().value(s!("2017-06-21T09:01:02")));
+        expect!(format_datetime(&s!("dd/M/yy h:mm a"), &datetime)).to(be_ok().value(s!("21/6/17 9:01 AM")));
+        expect!(format_datetime(&s!("yyyy-MM-dd'T'HH:mm:ss.SSSXXX"), &datetime)).to(be_ok().value(s!("2017-06-21T09:01:02.123+10:00")));
+        expect!(format_datetime(&s!("EEE, d MMM yyyy HH:mm:ss Z"), &datetime)).to(be_ok().value(s!("Wed, 21 Jun 2017 09:01:02 +1000")));
+        expect!(format_datetime(&s!("'100%' H"), &datetime)).to(be_ok().value(s!("100% 9")));
+        expect!(format_datetime(&s!("yyyy-MM-dd QQ"), &datetime)).to(be_err());
+    }
+
+    #[test]
+    fn format_datetime_formats_the_hour_and_week_fields_in_the_same_way_as_java() {
+        let midnight = FixedOffset::east(0).ymd(2017, 6, 21).and_hms(0, 5, 0);
+        expect!(format_datetime(&s!("k:mm kk K KK"), &midnight)).to(be_ok().value(s!("24:05 24 0 00")));
+        let afternoon = FixedOffset::east(0).ymd(2017, 6, 21).and_hms(15, 5, 0);
+        expect!(format_datetime(&s!("k:mm kk K KK"), &afternoon)).to(be_ok().value(s!("15:05 15 3 03")));
+        expect!(format_datetime(&s!("w ww W F"), &afternoon)).to(be_ok().value(s!("25 25 4 3")));
+        let new_year = FixedOffset::east(0).ymd(2017, 1, 1).and_hms(0, 0, 0);
+        expect!(format_datetime(&s!("w W F"), &new_year)).to(be_ok().value(s!("1 1 1")));
+        let end_of_year = FixedOffset::east(0).ymd(2017, 12, 31).and_hms(0, 0, 0);
+        expect!(format_datetime(&s!("w"), &end_of_year)).to(be_ok().value(s!("1")));
+        let end_of_leap_year = FixedOffset::east(0).ymd(2016, 12, 30).and_hms(0, 0, 0);
+        expect!(format_datetime(&s!("w"), &end_of_leap_year)).to(be_ok().value(s!("53")));
+        expect!(validate_datetime(&format_datetime(&s!("kk KK ww"), &midnight).unwrap(), &s!("kk KK ww"))).to(be_ok());
+    }
+
+    #[test]
+    fn format_current_datetime_generates_values_in_the_format() {
+        let value = format_current_datetime(&s!(DEFAULT_TIMESTAMP_FORMAT)).unwrap();
+        expect!(validate_datetime(&value, &s!(DEFAULT_TIMESTAMP_FORMAT))).to(be_ok());
+        let value = format_current_datetime(&s!("EEE, d MMM yyyy HH:mm:ss.SSS Z")).unwrap();
+        expect!(validate_datetime(&value, &s!("EEE, d MMM yyyy HH:mm:ss.SSS Z"))).to(be_ok());
+    }
+
+    #[test]
+    fn to_regex_pattern_returns_an_error_for_invalid_patterns() {
+        expect!(to_regex_pattern(&s!("yyyy-MM-dd'T"))).to(be_err());
+        expect!(to_regex_pattern(&s!("yyyy-MM-dd QQ"))).to(be_err());
+    }
+}
@@ -67,6 +67,13 @@ impl<'a> Matches<Element<'a>> for Element<'a> {
             } else {
                Ok(())
             },
+            Matcher::MinMaxTypeMatcher(min, max) => if actual.children().len() < min {
+               Err(format!("Expected '{}' to have at least {} children", actual.name().local_part(), min))
+            } else if actual.children().len() > max {
+               Err(format!("Expected '{}' to have at most {} children", actual.name().local_part(), max))
+            } else {
+               Ok(())
+            },
             Matcher::EqualityMatcher => {
                if self.name() == actual.name() {
                    Ok(())
@@ -74,13 +81,26 @@ impl<'a> Matches<Element<'a>> for Element<'a> {
                     Err(format!("Expected '{}' to be equal to '{}'", self.name().local_part(),
                         actual.name().local_part()))
                }
-            }
+            },
+            Matcher::NullMatcher => if actual.children().is_empty() {
+               Ok(())
+            } else {
+               Err(format!("Expected '{}' to be empty", actual.name().local_part()))
+            },
+            _ => text_of(self).matches(&text_of(actual), matcher)
        };
        debug!("Comparing '{:?}' to '{:?}' using {:?} -> {:?}", self, actual, matcher, result);
        result
     }
 }
 
+fn text_of(element: &Element) -> String {
+    s!(element.children().iter().cloned()
+        .filter(|child| child.text().is_some())
+        .map(|child| child.text().unwrap().text())
+        .collect::<String>().trim())
+}
+
 fn path_to_string(path: &Vec<String>) -> String {
     path.iter().enumerate().map(|(i, p)| {
         if i > 0 && !p.starts_with("[") {
@@ -211,14 +231,8 @@ fn compare_children(path: &Vec<String>, expected: &Element, actual: &Element, co
 
 fn compare_text(path: &Vec<String>, expected: &Element, actual: &Element,
     mismatches: &mut Vec<super::Mismatch>, matchers: &Option<Matchers>) {
-    let expected_text = s!(expected.children().iter().cloned()
-        .filter(|child| child.text().is_some())
-        .map(|child| child.text().unwrap().text())
-        .collect::<String>().trim());
-    let actual_text = s!(actual.children().iter().cloned()
-        .filter(|child| child.text().is_some())
-        .map(|child| child.text().unwrap().text())
-        .collect::<String>().trim());
+    let expected_text = text_of(expected);
+    let actual_text = text_of(actual);
     let mut p = path.to_vec();
     p.push(s!("#text"));
     let matcher_result = if matcher_is_defined(&p, matchers) {
@@ -260,6 +274,7 @@ mod tests {
     use expectest::prelude::*;
     use Mismatch;
     use DiffConfig;
+    use models::RuleList;
     use env_logger;
 
     #[test]
@@ -467,7 +482,7 @@ mod tests {
 
         mismatches.clear();
         match_xml(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &Some(hashmap!{
-            s!("$.body.foo.*") => hashmap!{ s!("match") => s!("type") }
+            s!("$.body.foo.*") => RuleList::new(hashmap!{ s!("match") => s!("type") })
         }));
         expect!(mismatches).to(be_empty());
     }
@@ -569,7 +584,7 @@ mod tests {
 
         mismatches.clear();
         match_xml(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &Some(hashmap!{
-            s!("$.body.foo") => hashmap!{ s!("match") => s!("type") }
+            s!("$.body.foo") => RuleList::new(hashmap!{ s!("match") => s!("type") })
         }));
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
@@ -648,7 +663,7 @@ mod tests {
 
         mismatches.clear();
         match_xml(&expected, &actual, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &Some(hashmap!{
-            s!("$.body.foo['#text']") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("[a-z ]+") }
+            s!("$.body.foo['#text']") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("[a-z ]+") })
         }));
         expect!(mismatches).to(be_empty());
     }
@@ -672,7 +687,7 @@ mod tests {
 
         mismatches.clear();
         match_xml(&expected, &actual, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &Some(hashmap!{
-            s!("$.body.foo['#text']") => hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("[a-z]+") }
+            s!("$.body.foo['#text']") => RuleList::new(hashmap!{ s!("match") => s!("regex"), s!("regex") => s!("[a-z]+") })
         }));
         expect!(mismatches).to(be_empty());
     }
@@ -688,8 +703,8 @@ mod tests {
         <foo><one/><one/><one/></foo>
         "#);
         match_xml(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &Some(hashmap!{
-            s!("$.body.foo") => hashmap!{ s!("match") => s!("type") },
-            // s!("$.body.foo[*]") => hashmap!{ s!("match") => s!("type") }
+            s!("$.body.foo") => RuleList::new(hashmap!{ s!("match") => s!("type") }),
+            // s!("$.body.foo[*]") => RuleList::new(hashmap!{ s!("match") => s!("type") })
         }));
         expect!(mismatches).to(be_empty());
     }
@@ -120,7 +120,8 @@ fn test_load_test_pact_lowercase_method() {
             expect(pact_interactions.len()).to(be_equal_to(pact_interactions_from_file.len()));
 
             for (pact_interaction, file_interaction) in pact_interactions.iter().zip(pact_interactions_from_file.iter()) {
-                expect(pact_interaction.get("providerState")).to(be_equal_to(file_interaction.get("providerState")));
+                expect(pact_interaction.get("providerStates").unwrap().to_string()).to(
+                    be_equal_to(s!("[{\"name\":\"test state\"}]")));
                 expect(pact_interaction.get("description")).to(be_equal_to(file_interaction.get("description")));
                 expect(pact_interaction.get("response")).to(be_equal_to(file_interaction.get("response")));
 
@@ -131,15 +132,14 @@ fn test_load_test_pact_lowercase_method() {
                 expect(pact_request.get("headers")).to(be_equal_to(file_request.get("headers")));
                 expect(pact_request.get("body")).to(be_equal_to(file_request.get("body")));
                 expect(pact_request.get("matchers")).to(be_equal_to(file_request.get("matchers")));
-                // This is a V3 pact, so we can't load the query string
-                expect(pact_request.get("query")).to(be_none());
+                expect(pact_request.get("query")).to(be_equal_to(file_request.get("query")));
             }
 
             expect(pact.metadata.get("pact-specification").unwrap().get("version")).to(be_some().value("3.0.0"));
             let metadata = pact_json.get("metadata").unwrap().as_object().unwrap();
             let expected_keys : Vec<String> = vec![s!("pact-jvm"), s!("pact-rust"), s!("pact-specification")];
             expect(metadata.keys().cloned().collect::<Vec<String>>()).to(be_equal_to(expected_keys));
-            expect(metadata.get("pact-specification").unwrap().to_string()).to(be_equal_to(s!("{\"version\":\"2.0.0\"}")));
+            expect(metadata.get("pact-specification").unwrap().to_string()).to(be_equal_to(s!("{\"version\":\"3.0.0\"}")));
         },
         Err(err) => panic!("Failed to load pact from '{:?}' - {}", pact_file, err)
     }
@@ -448,6 +448,79 @@ fn test_load_test_pact_matchers_old_format() {
     }
 }
 
-// v3-message-pact.json
-// v3-pact.json
-// test_pact_v3.json
+#[test]
+fn test_load_v3_pact() {
+    let pact_file = Path::new(file!()).parent().unwrap().join("v3-pact.json");
+    let pact_result = Pact::read_pact(&pact_file);
+
+    match pact_result {
+        Ok(ref pact) => {
+            let mut f = File::open(pact_file).unwrap();
+            let pact_json_from_file : serde_json::Value = serde_json::de::from_reader(&mut f).unwrap();
+            let pact_json = pact.to_json();
+            expect(pact_json.get("consumer")).to(be_equal_to(pact_json_from_file.get("consumer")));
+            expect(pact_json.get("provider")).to(be_equal_to(pact_json_from_file.get("provider")));
+            expect(pact_json.get("interactions")).to(be_equal_to(pact_json_from_file.get("interactions")));
+
+            expect(pact.specification_version.clone()).to(be_equal_to(PactSpecification::V3));
+            let metadata = pact_json.get("metadata").unwrap().as_object().unwrap();
+            let expected_keys : Vec<String> = vec![s!("pact-jvm"), s!("pact-rust"), s!("pact-specification")];
+            expect(metadata.keys().cloned().collect::<Vec<String>>()).to(be_equal_to(expected_keys));
+            expect(metadata.get("pact-specification").unwrap().to_string()).to(be_equal_to(s!("{\"version\":\"3.0.0\"}")));
+        },
+        Err(err) => panic!("Failed to load pact from '{:?}' - {}", pact_file, err)
+    }
+}
+
+#[test]
+fn test_load_test_pact_v3() {
+    let pact_file = Path::new(file!()).parent().unwrap().join("test_pact_v3.json");
+    let pact_result = Pact::read_pact(&pact_file);
+
+    match pact_result {
+        Ok(ref pact) => {
+            let mut f = File::open(pact_file).unwrap();
+            let pact_json_from_file : serde_json::Value = serde_json::de::from_reader(&mut f).unwrap();
+            let pact_json = pact.to_json();
+            let pact_interaction = pact_json.get("interactions").unwrap().as_array().unwrap()[0].clone();
+            let file_interaction = pact_json_from_file.get("interactions").unwrap().as_array().unwrap()[0].clone();
+            expect(pact_interaction.get("request")).to(be_equal_to(file_interaction.get("request")));
+            expect(pact_interaction.get("response")).to(be_equal_to(file_interaction.get("response")));
+            expect(pact_interaction.get("providerStates").unwrap().to_string()).to(
+                be_equal_to(s!("[{\"name\":\"test state\"}]")));
+            expect(pact.specification_version.clone()).to(be_equal_to(PactSpecification::V3));
+        },
+        Err(err) => panic!("Failed to load pact from '{:?}' - {}", pact_file, err)
+    }
+}
+
+#[test]
+fn test_load_v3_message_pact() {
+    let pact_file = Path::new(file!()).parent().unwrap().join("v3-message-pact.json");
+    let pact_result = MessagePact::read_pact(&pact_file);
+
+    match pact_result {
+        Ok(ref pact) => {
+            let mut f = File::open(pact_file).unwrap();
+            let pact_json_from_file : serde_json::Value = serde_json::de::from_reader(&mut f).unwrap();
+            let pact_json = pact.to_json();
+            expect(pact_json.get("consumer")).to(be_equal_to(pact_json_from_file.get("consumer")));
+            expect(pact_json.get("provider")).to(be_equal_to(pact_json_from_file.get("provider")));
+
+            let message_json = pact_json.get("messages").unwrap().as_array().unwrap()[0].clone();
+            let file_message_json = pact_json_from_file.get("messages").unwrap().as_array().unwrap()[0].clone();
+            expect(message_json.get("description")).to(be_equal_to(file_message_json.get("description")));
+            expect(message_json.get("contents")).to(be_equal_to(file_message_json.get("contents")));
+            expect(message_json.get("metaData")).to(be_equal_to(file_message_json.get("metaData")));
+            expect(message_json.get("providerStates").unwrap().to_string()).to(
+                be_equal_to(s!("[{\"name\":\"message exists\"}]")));
+
+            expect(pact.specification_version.clone()).to(be_equal_to(PactSpecification::V3));
+            let metadata = pact_json.get("metadata").unwrap().as_object().unwrap();
+            let expected_keys : Vec<String> = vec![s!("pact-jvm"), s!("pact-rust"), s!("pact-specification")];
+            expect(metadata.keys().cloned().collect::<Vec<String>>()).to(be_equal_to(expected_keys));
+            expect(metadata.get("pact-specification").unwrap().to_string()).to(be_equal_to(s!("{\"version\":\"3.0.0\"}")));
+        },
+        Err(err) => panic!("Failed to load pact from '{:?}' - {}", pact_file, err)
+    }
+}
@@ -2,6 +2,13 @@
 //! and generating responses based on a pact file. It implements the V2 Pact specification
 //! (https://github.com/pact-foundation/pact-specification/tree/version-2).
 //!
+//! If the interaction response has any generators (from a V3 pact), they will be applied to the
+//! response before it is returned. Provider state generators are not applied, as there are no
+//! provider state values on the consumer side.
+//!
+//! Request and response bodies are read and returned as bytes, so binary bodies (images, PDFs,
+//! protobuf payloads, etc.) are returned exactly as they are in the pact file.
+//!
 //! There are a number of exported functions using C bindings for controlling the mock server. These can be used in any
 //! language that supports C bindings.
 //!
@@ -316,8 +323,8 @@ fn extract_headers(headers: &Headers) -> Option<HashMap<String, String>> {
 }
 
 fn extract_body(req: &mut hyper::server::Request) -> OptionalBody {
-    let mut buffer = String::new();
-    match req.read_to_string(&mut buffer) {
+    let mut buffer = vec![];
+    match req.read_to_end(&mut buffer) {
         Ok(size) => if size > 0 {
                 OptionalBody::Present(buffer)
             } else {
@@ -337,7 +344,8 @@ fn hyper_request_to_pact_request(req: &mut hyper::server::Request) -> Request {
         query: extract_query_string(&req.uri),
         headers: extract_headers(&req.headers),
         body: extract_body(req),
-        matching_rules: None
+        matching_rules: None,
+        generators: None
     }
 }
 
@@ -404,10 +412,11 @@ pub fn start_mock_server(id: String, pact: Pact, port: i32) -> Result<i32, Strin
                     record_result(&mock_server_id, &match_result);
                     match match_result {
                         MatchResult::RequestMatch(ref interaction) => {
-                            info!("Request matched, sending response {:?}", interaction.response);
-                            *res.status_mut() = StatusCode::from_u16(interaction.response.status);
+                            let response = interaction.response.generate_response(&hashmap!{});
+                            info!("Request matched, sending response {:?}", response);
+                            *res.status_mut() = StatusCode::from_u16(response.status);
                             res.headers_mut().set(AccessControlAllowOrigin::Any);
-                            match interaction.response.headers {
+                            match response.headers {
                                 Some(ref headers) => {
                                     for (k, v) in headers.clone() {
                                         res.headers_mut().set_raw(k, vec![v.into_bytes()]);
@@ -415,9 +424,9 @@ pub fn start_mock_server(id: String, pact: Pact, port: i32) -> Result<i32, Strin
                                 },
                                 None => ()
                             }
-                            match interaction.response.body {
+                            match response.body {
                                 OptionalBody::Present(ref body) => {
-                                    res.send(body.as_bytes()).unwrap();
+                                    res.send(body.as_slice()).unwrap();
                                 },
                                 _ => ()
                             }
@@ -0,0 +1,109 @@
+use clap::ArgMatches;
+use pact_broker_client::{can_i_deploy, CanIDeployResult, DeploymentTarget, MatrixRow};
+use publish::broker_auth;
+
+fn deployment_target(matches: &ArgMatches) -> DeploymentTarget {
+    match (matches.value_of("to"), matches.value_of("to-environment")) {
+        (Some(tag), _) => DeploymentTarget::Tag(s!(tag)),
+        (None, Some(environment)) => DeploymentTarget::Environment(s!(environment)),
+        (None, None) => DeploymentTarget::Latest
+    }
+}
+
+fn verification_status(row: &MatrixRow) -> &'static str {
+    match row.success {
+        Some(true) => "true",
+        Some(false) => "false",
+        None => "???"
+    }
+}
+
+fn matrix_table(matrix: &Vec<MatrixRow>) -> String {
+    let headers = ["CONSUMER", "C.VERSION", "PROVIDER", "P.VERSION", "SUCCESS?"];
+    let rows: Vec<Vec<String>> = matrix.iter().map(|row| vec![
+        row.consumer.clone(),
+        row.consumer_version.clone().unwrap_or(s!("???")),
+        row.provider.clone(),
+        row.provider_version.clone().unwrap_or(s!("???")),
+        s!(verification_status(row))
+    ]).collect();
+    let widths: Vec<usize> = headers.iter().enumerate().map(|(i, header)| {
+        rows.iter().map(|row| row[i].len()).fold(header.len(), |max, len| if len > max { len } else { max })
+    }).collect();
+    let format_row = |values: Vec<String>| values.iter().enumerate()
+        .map(|(i, value)| format!("{:1$}", value, widths[i]))
+        .collect::<Vec<String>>()
+        .join(" | ")
+        .trim_right()
+        .to_string();
+
+    let mut table = format_row(headers.iter().map(|h| s!(*h)).collect());
+    table.push('\n');
+    table.push_str(&widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<String>>().join("-|-"));
+    table.push('\n');
+    for row in rows {
+        table.push_str(&format_row(row));
+        table.push('\n');
+    }
+    table
+}
+
+fn display_result(result: &CanIDeployResult) {
+    if !result.matrix.is_empty() {
+        println!("{}", matrix_table(&result.matrix));
+    }
+    if result.deployable {
+        println!("Computer says yes \\o/");
+    } else {
+        println!("Computer says no ¯\\_(ツ)_/¯");
+    }
+    println!();
+    println!("{}", result.reason);
+}
+
+pub fn check_can_i_deploy(matches: &ArgMatches) -> Result<(), i32> {
+    let broker_url = s!(matches.value_of("broker-url").unwrap());
+    let pacticipant = s!(matches.value_of("pacticipant").unwrap());
+    let version = s!(matches.value_of("version").unwrap());
+    let target = deployment_target(matches);
+
+    info!("Checking if {} version {} can be deployed ({:?}) with the pact broker at {}", pacticipant, version,
+        target, broker_url);
+    match can_i_deploy(&broker_url, &broker_auth(matches), &pacticipant, &version, &target) {
+        Ok(ref result) => {
+            display_result(result);
+            if result.deployable {
+                Ok(())
+            } else {
+                Err(1)
+            }
+        },
+        Err(err) => {
+            println!("Failed to query the pact broker matrix: {:?}", err);
+            Err(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use expectest::prelude::*;
+    use pact_broker_client::MatrixRow;
+    use super::matrix_table;
+
+    #[test]
+    fn matrix_table_aligns_the_columns() {
+        let matrix = vec![
+            MatrixRow { consumer: s!("Foo"), consumer_version: Some(s!("1.0.0")), provider: s!("Bar Service"),
+                provider_version: Some(s!("2")), success: Some(true) },
+            MatrixRow { consumer: s!("Foo"), consumer_version: Some(s!("1.0.0")), provider: s!("Baz"),
+                provider_version: None, success: None }
+        ];
+        expect!(matrix_table(&matrix)).to(be_equal_to(s!(
+"CONSUMER | C.VERSION | PROVIDER    | P.VERSION | SUCCESS?
+---------|-----------|-------------|-----------|---------
+Foo      | 1.0.0     | Bar Service | 2         | true
+Foo      | 1.0.0     | Baz         | ???       | ???
+")));
+    }
+}
@@ -7,6 +7,7 @@
 #[macro_use] extern crate clap;
 #[macro_use] extern crate pact_matching;
 extern crate pact_mock_server;
+extern crate pact_broker_client;
 #[macro_use] extern crate p_macro;
 #[macro_use] extern crate log;
 #[macro_use] extern crate maplit;
@@ -50,6 +51,8 @@ mod create_mock;
 mod list;
 mod verify;
 mod shutdown;
+mod publish;
+mod can_i_deploy;
 
 fn print_version() {
     println!("\npact mock server version  : v{}", crate_version!());
@@ -247,6 +250,114 @@ fn handle_command_args() -> Result<(), i32> {
                     .required_unless("mock-server-host")
                     .help("the port number of the mock server")
                     .validator(integer_value))
+                .setting(AppSettings::ColoredHelp))
+        .subcommand(SubCommand::with_name("publish")
+                .about("Publishes pact files to a pact broker")
+                .arg(Arg::with_name("file")
+                    .short("f")
+                    .long("file")
+                    .takes_value(true)
+                    .use_delimiter(false)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .empty_values(false)
+                    .required(true)
+                    .help("the pact file to publish (can be repeated)"))
+                .arg(Arg::with_name("broker-url")
+                    .short("b")
+                    .long("broker-url")
+                    .takes_value(true)
+                    .use_delimiter(false)
+                    .required(true)
+                    .help("the URL of the pact broker to publish the pacts to"))
+                .arg(Arg::with_name("consumer-version")
+                    .short("c")
+                    .long("consumer-version")
+                    .takes_value(true)
+                    .use_delimiter(false)
+                    .required(true)
+                    .help("the version of the consumer application the pacts are for"))
+                .arg(Arg::with_name("tag")
+                    .short("t")
+                    .long("tag")
+                    .takes_value(true)
+                    .use_delimiter(false)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .empty_values(false)
+                    .help("tag to apply to the consumer version (can be repeated)"))
+                .arg(Arg::with_name("user")
+                    .long("user")
+                    .takes_value(true)
+                    .use_delimiter(false)
+                    .conflicts_with("token")
+                    .help("username for the pact broker (can also be set with PACT_BROKER_USERNAME)"))
+                .arg(Arg::with_name("password")
+                    .long("password")
+                    .takes_value(true)
+                    .use_delimiter(false)
+                    .requires("user")
+                    .help("password for the pact broker (can also be set with PACT_BROKER_PASSWORD)"))
+                .arg(Arg::with_name("token")
+                    .long("token")
+                    .takes_value(true)
+                    .use_delimiter(false)
+                    .conflicts_with("user")
+                    .help("bearer token for the pact broker (can also be set with PACT_BROKER_TOKEN)"))
+                .setting(AppSettings::ColoredHelp))
+        .subcommand(SubCommand::with_name("can-i-deploy")
+                .about("Checks with the pact broker if a version of a pacticipant can be deployed")
+                .arg(Arg::with_name("pacticipant")
+                    .short("a")
+                    .long("pacticipant")
+                    .takes_value(true)
+                    .use_delimiter(false)
+                    .required(true)
+                    .help("the name of the pacticipant (consumer or provider) to check"))
+                .arg(Arg::with_name("version")
+                    .short("e")
+                    .long("version")
+                    .takes_value(true)
+                    .use_delimiter(false)
+                    .required(true)
+                    .help("the version of the pacticipant to check"))
+                .arg(Arg::with_name("broker-url")
+                    .short("b")
+                    .long("broker-url")
+                    .takes_value(true)
+                    .use_delimiter(false)
+                    .required(true)
+                    .help("the URL of the pact broker"))
+                .arg(Arg::with_name("to")
+                    .long("to")
+                    .takes_value(true)
+                    .use_delimiter(false)
+                    .conflicts_with("to-environment")
+                    .help("check against the latest versions of the integrations with this tag (defaults to the latest versions)"))
+                .arg(Arg::with_name("to-environment")
+                    .long("to-environment")
+                    .takes_value(true)
+                    .use_delimiter(false)
+                    .conflicts_with("to")
+                    .help("check against the versions of the integrations deployed to this environment"))
+                .arg(Arg::with_name("user")
+                    .long("user")
+                    .takes_value(true)
+                    .use_delimiter(false)
+                    .conflicts_with("token")
+                    .help("username for the pact broker (can also be set with PACT_BROKER_USERNAME)"))
+                .arg(Arg::with_name("password")
+                    .long("password")
+                    .takes_value(true)
+                    .use_delimiter(false)
+                    .requires("user")
+                    .help("password for the pact broker (can also be set with PACT_BROKER_PASSWORD)"))
+                .arg(Arg::with_name("token")
+                    .long("token")
+                    .takes_value(true)
+                    .use_delimiter(false)
+                    .conflicts_with("user")
+                    .help("bearer token for the pact broker (can also be set with PACT_BROKER_TOKEN)"))
                 .setting(AppSettings::ColoredHelp));
 
     let matches = app.get_matches_safe();
@@ -273,6 +384,8 @@ fn handle_command_args() -> Result<(), i32> {
                         ("create", Some(sub_matches)) => create_mock::create_mock_server(host, p, sub_matches),
                         ("verify", Some(sub_matches)) => verify::verify_mock_server(host, p, sub_matches),
                         ("shutdown", Some(sub_matches)) => shutdown::shutdown_mock_server(host, p, sub_matches),
+                        ("publish", Some(sub_matches)) => publish::publish_pacts(sub_matches),
+                        ("can-i-deploy", Some(sub_matches)) => can_i_deploy::check_can_i_deploy(sub_matches),
                         _ => Err(3)
                     }
                 },
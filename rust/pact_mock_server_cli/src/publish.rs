@@ -0,0 +1,51 @@
+use clap::ArgMatches;
+use std::path::Path;
+use pact_matching::models::Pact;
+use pact_broker_client::{publish_pact, HttpAuth};
+use std::env;
+
+fn option_or_env(matches: &ArgMatches, option: &str, env_var: &str) -> Option<String> {
+    match matches.value_of(option) {
+        Some(value) => Some(s!(value)),
+        None => match env::var(env_var) {
+            Ok(ref value) if !value.is_empty() => Some(value.clone()),
+            _ => None
+        }
+    }
+}
+
+pub fn broker_auth(matches: &ArgMatches) -> Option<HttpAuth> {
+    match option_or_env(matches, "token", "PACT_BROKER_TOKEN") {
+        Some(token) => Some(HttpAuth::Token(token)),
+        None => option_or_env(matches, "user", "PACT_BROKER_USERNAME")
+            .map(|user| HttpAuth::User(user, option_or_env(matches, "password", "PACT_BROKER_PASSWORD")))
+    }
+}
+
+pub fn publish_pacts(matches: &ArgMatches) -> Result<(), i32> {
+    let broker_url = s!(matches.value_of("broker-url").unwrap());
+    let consumer_version = s!(matches.value_of("consumer-version").unwrap());
+    let tags = matches.values_of_lossy("tag").unwrap_or(vec![]);
+    let files = matches.values_of_lossy("file").unwrap_or(vec![]);
+    let auth = broker_auth(matches);
+
+    let mut result = Ok(());
+    for file in files {
+        info!("Publishing pact file {} to {}", file, broker_url);
+        match Pact::read_pact(&Path::new(&file)) {
+            Ok(ref pact) => match publish_pact(&broker_url, &auth, pact, &consumer_version, &tags) {
+                Ok(_) => println!("Published pact between {} and {} for version {} to {}", pact.consumer.name,
+                    pact.provider.name, consumer_version, broker_url),
+                Err(err) => {
+                    println!("Failed to publish pact file '{}' to the pact broker: {:?}", file, err);
+                    result = Err(2);
+                }
+            },
+            Err(err) => {
+                println!("Failed to load pact file '{}': {}", file, err);
+                result = Err(1);
+            }
+        }
+    }
+    result
+}
@@ -1,9 +1,18 @@
 //! The `pact_verifier` crate provides the core logic to performing verification of providers.
-//! It implements the V2 Pact specification (https://github.com/pact-foundation/pact-specification/tree/version-2).
+//! It implements the V2 Pact specification (https://github.com/pact-foundation/pact-specification/tree/version-2),
+//! and can also verify V3 message pacts with `verify_message_provider`.
+//!
+//! Any generators defined on the interaction requests are applied to the requests sent to the
+//! provider, with the provider state parameters and any values returned from the state change
+//! request available to the provider state generators.
+//!
+//! The verification functions return a `VerificationResult` with the status, duration, mismatches
+//! and errors for each pact and interaction. The console output is rendered from this result.
 
 #![warn(missing_docs)]
 
 #[macro_use] extern crate pact_matching;
+extern crate pact_broker_client;
 extern crate ansi_term;
 #[macro_use] extern crate log;
 extern crate hyper;
@@ -11,6 +20,8 @@ extern crate hyper;
 extern crate itertools;
 extern crate regex;
 extern crate difference;
+#[cfg(feature = "tls")]
+extern crate openssl;
 #[macro_use] extern crate serde_json;
 
 #[cfg(test)]
@@ -24,18 +35,32 @@ extern crate env_logger;
 
 mod provider_client;
 mod pact_broker;
+mod verification_result;
+mod tls;
+mod state_handlers;
 
 use std::path::Path;
-use std::error::Error;
 use std::io;
 use std::fs;
 use pact_matching::*;
 use pact_matching::models::*;
 use ansi_term::*;
 use ansi_term::Colour::*;
-use std::collections::HashMap;
-use provider_client::{make_provider_request, make_state_change_request};
+use std::collections::{HashMap, BTreeMap, VecDeque};
+use std::cmp::min;
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use provider_client::{make_provider_request, make_state_change_request, make_message_request};
+pub use provider_client::{RequestFilter, ProviderTransport};
+pub use tls::{TlsOptions, HttpClient};
+pub use state_handlers::{StateHandler, StateHandlers};
+pub use pact_broker_client::HttpAuth;
+use pact_broker_client::PactBrokerError;
+use pact_broker::{PublishResultsLink, PactSelection};
+pub use pact_broker::{ConsumerVersionSelector, PendingPactOptions, BrokerPactInfo};
 use regex::Regex;
+use std::time::{Duration, Instant};
+pub use verification_result::*;
 
 /// Source for loading pacts
 #[derive(Debug, Clone)]
@@ -46,8 +71,16 @@ pub enum PactSource {
     Dir(String),
     /// Load the pact from a URL
     URL(String),
-    /// Load all pacts with the provider name from the pact broker url
-    BrokerUrl(String, String)
+    /// Load all pacts with the provider name from the pact broker url, with optional credentials
+    /// for the pact broker
+    BrokerUrl(String, String, Option<HttpAuth>),
+    /// Load the latest pacts with any of the consumer version tags for the provider name from the
+    /// pact broker url, with optional credentials for the pact broker
+    BrokerWithTags(String, String, Vec<String>, Option<HttpAuth>),
+    /// Load the pacts matching the consumer version selectors for the provider name from the pact
+    /// broker url, including any pending and work in progress pacts, with optional credentials
+    /// for the pact broker
+    BrokerWithSelectors(String, String, Vec<ConsumerVersionSelector>, PendingPactOptions, Option<HttpAuth>)
 }
 
 /// Information about the Provider to verify
@@ -55,7 +88,7 @@ pub enum PactSource {
 pub struct ProviderInfo {
     /// Provider Name
     pub name: String,
-    /// Provider protocol, defaults to HTTP
+    /// Provider protocol (http or https), defaults to http
     pub protocol: String,
     /// Hostname of the provider
     pub host: String,
@@ -65,10 +98,29 @@ pub struct ProviderInfo {
     pub path: String,
     /// URL to post state change requests to
     pub state_change_url: Option<String>,
+    /// In-process state handlers. A registered handler for a provider state is used instead of
+    /// the state change URL
+    pub state_handlers: StateHandlers,
     /// If teardown state change requests should be made (default is false)
     pub state_change_teardown: bool,
     /// If state change request data should be sent in the body (true) or as query parameters (false)
-    pub state_change_body: bool
+    pub state_change_body: bool,
+    /// Filter applied to each request before it is sent to the provider
+    pub request_filter: Option<RequestFilter>,
+    /// Transport used to send the requests to the provider, defaults to HTTP
+    pub transport: ProviderTransport,
+    /// Number of pacts to verify concurrently (defaults to 1, which verifies the pacts
+    /// sequentially). The interactions of each pact are always verified sequentially, and the
+    /// results are displayed in the order the pacts were loaded.
+    pub workers: usize,
+    /// Order the interactions of each pact are verified and reported in (defaults to the order
+    /// of the pact file)
+    pub interaction_order: InteractionOrder,
+    /// TLS options for HTTPS providers and state change URLs
+    pub tls_options: TlsOptions,
+    /// HTTP client for the provider and state change requests, which is created from the TLS
+    /// options when the first request is made
+    pub http_client: HttpClient
 }
 
 impl ProviderInfo {
@@ -81,24 +133,45 @@ impl ProviderInfo {
             port: 8080,
             path: s!("/"),
             state_change_url: None,
+            state_handlers: StateHandlers::new(),
             state_change_teardown: false,
-            state_change_body: true
+            state_change_body: true,
+            request_filter: None,
+            transport: ProviderTransport::Http,
+            workers: 1,
+            interaction_order: InteractionOrder::PactFile,
+            tls_options: TlsOptions::default(),
+            http_client: HttpClient::new()
         }
     }
 }
 
+/// Order the interactions of a pact are verified and reported in
+#[derive(Debug, Clone, PartialEq)]
+pub enum InteractionOrder {
+    /// The order the interactions are defined in the pact file
+    PactFile,
+    /// Sorted by the interaction description
+    Description,
+    /// Sorted by the provider states of the interaction, then by the description
+    ProviderState
+}
+
 /// Result of performing a match
 #[derive(Debug, Clone)]
 pub enum MismatchResult {
     /// Response mismatches
     Mismatches(Vec<Mismatch>, Response, Response),
+    /// Message mismatches
+    MessageMismatches(Vec<Mismatch>, Message, Message),
     /// Error occured
     Error(String)
 }
 
-fn verify_response_from_provider(provider: &ProviderInfo, interaction: &Interaction) -> Result<(), MismatchResult> {
+fn verify_response_from_provider(provider: &ProviderInfo, interaction: &Interaction,
+    context: &HashMap<String, serde_json::Value>) -> Result<(), MismatchResult> {
     let ref expected_response = interaction.response;
-    match make_provider_request(provider, &interaction.request) {
+    match make_provider_request(provider, &interaction.request.generate_request(context)) {
         Ok(ref actual_response) => {
             let mismatches = match_response(expected_response.clone(), actual_response.clone());
             if mismatches.is_empty() {
@@ -108,30 +181,40 @@ fn verify_response_from_provider(provider: &ProviderInfo, interaction: &Interact
             }
         },
         Err(err) => {
-            Err(MismatchResult::Error(s!(err.description())))
+            Err(MismatchResult::Error(err))
         }
     }
 }
 
-fn execute_state_change(provider_state: &String, provider: &ProviderInfo, setup: bool) -> Result<(), MismatchResult> {
-    if setup {
-        println!("  Given {}", Style::new().bold().paint(provider_state.clone()));
-    }
-    let result = match provider.state_change_url {
-        Some(_) => {
+fn execute_state_change(provider_state: &ProviderState, provider: &ProviderInfo, setup: bool)
+    -> Result<HashMap<String, serde_json::Value>, MismatchResult> {
+    let result = match (provider.state_handlers.handler_for(&provider_state.name), &provider.state_change_url) {
+        (Some(handler), _) => handler.execute(&provider_state.name, &provider_state.params, setup)
+            .map_err(|err| MismatchResult::Error(format!("State change handler failed: {}", err))),
+        (None, &Some(_)) => {
             let mut state_change_request = Request { method: s!("POST"), .. Request::default_request() };
             if provider.state_change_body {
-              let json_body = json!({
-                  s!("state") : json!(provider_state.clone()),
+              let mut json_body = json!({
+                  s!("state") : json!(provider_state.name.clone()),
                   s!("action") : json!(if setup {
                     s!("setup")
                   } else {
                     s!("teardown")
                   })
               });
-              state_change_request.body = OptionalBody::Present(json_body.to_string());
+              if !provider_state.params.is_empty() {
+                  let map = json_body.as_object_mut().unwrap();
+                  map.insert(s!("params"), json!(provider_state.params.clone()));
+              }
+              state_change_request.body = OptionalBody::Present(json_body.to_string().into_bytes());
             } else {
-              let mut query = hashmap!{ s!("state") => vec![provider_state.clone()] };
+              let mut query = hashmap!{ s!("state") => vec![provider_state.name.clone()] };
+              for (k, v) in provider_state.params.clone() {
+                  query.insert(k, vec![match v {
+                      serde_json::Value::String(ref s) => s.clone(),
+                      _ => v.to_string()
+                  }]);
+              }
               if setup {
                 query.insert(s!("action"), vec![s!("setup")]);
               } else {
@@ -139,35 +222,58 @@ fn execute_state_change(provider_state: &String, provider: &ProviderInfo, setup:
               }
               state_change_request.query = Some(query);
             }
-            match make_state_change_request(provider, &state_change_request) {
-                Ok(_) => Ok(()),
-                Err(err) => Err(MismatchResult::Error(err))
-            }
+            make_state_change_request(provider, &state_change_request)
+                .map_err(|err| MismatchResult::Error(err))
         },
-        None => {
-            if setup {
-                println!("    {}", Yellow.paint("WARNING: State Change ignored as there is no state change URL"));
-            }
-            Ok(())
-        }
+        (None, &None) => Ok(hashmap!{})
     };
 
-    debug!("State Change: \"{}\" -> {:?}", provider_state, result);
+    debug!("State Change: \"{:?}\" -> {:?}", provider_state, result);
     result
 }
 
 fn verify_interaction(provider: &ProviderInfo, interaction: &Interaction) -> Result<(), MismatchResult> {
-    match interaction.provider_state {
-        Some(ref state) => try!(execute_state_change(state, provider, true)),
-        None => ()
+    let mut context = hashmap!{};
+    for state in &interaction.provider_states {
+        context.extend(state.params.clone());
+        context.extend(try!(execute_state_change(state, provider, true)));
     }
 
-    let result = verify_response_from_provider(provider, interaction);
+    let result = verify_response_from_provider(provider, interaction, &context);
 
     if provider.state_change_teardown {
-        match interaction.provider_state {
-            Some(ref state) => try!(execute_state_change(state, provider, false)),
-            None => ()
+        for state in &interaction.provider_states {
+            try!(execute_state_change(state, provider, false));
+        }
+    }
+
+    result
+}
+
+fn verify_message_from_provider(provider: &ProviderInfo, message: &Message) -> Result<(), MismatchResult> {
+    match make_message_request(provider, message) {
+        Ok(ref actual_message) => {
+            let mismatches = match_message(message.clone(), actual_message.clone());
+            if mismatches.is_empty() {
+                Ok(())
+            } else {
+                Err(MismatchResult::MessageMismatches(mismatches, message.clone(), actual_message.clone()))
+            }
+        },
+        Err(err) => Err(MismatchResult::Error(err))
+    }
+}
+
+fn verify_message(provider: &ProviderInfo, message: &Message) -> Result<(), MismatchResult> {
+    for state in &message.provider_states {
+        try!(execute_state_change(state, provider, true));
+    }
+
+    let result = verify_message_from_provider(provider, message);
+
+    if provider.state_change_teardown {
+        for state in &message.provider_states {
+            try!(execute_state_change(state, provider, false));
         }
     }
 
@@ -193,16 +299,33 @@ fn display_result(status: u16, status_result: ANSIGenericString<str>,
     println!("      has a matching body ({})", body_result);
 }
 
-fn walkdir(dir: &Path) -> io::Result<Vec<io::Result<Pact>>> {
+fn display_message_result(metadata_results: Vec<(String, String, ANSIGenericString<str>)>,
+    body_result: ANSIGenericString<str>) {
+    println!("    generates a message which");
+    if !metadata_results.is_empty() {
+        println!("      includes metadata");
+        for (key, value, result) in metadata_results {
+            println!("        \"{}\" with value \"{}\" ({})", Style::new().bold().paint(key),
+                Style::new().bold().paint(value), result);
+        }
+    }
+    println!("      has a matching body ({})", body_result);
+}
+
+fn walkdir<P, F>(dir: &Path, read_pact: &F) -> io::Result<Vec<io::Result<P>>> where F: Fn(&Path) -> io::Result<P> {
     let mut pacts = vec![];
     debug!("Scanning {:?}", dir);
+    let mut paths = vec![];
     for entry in try!(fs::read_dir(dir)) {
-        let entry = try!(entry);
-        let path = entry.path();
+        paths.push(try!(entry).path());
+    }
+    // Directory entries are not returned in any particular order
+    paths.sort();
+    for path in paths {
         if path.is_dir() {
-            try!(walkdir(&path));
+            pacts.extend(try!(walkdir(&path, read_pact)));
         } else {
-            pacts.push(Pact::read_pact(&path))
+            pacts.push(read_pact(&path))
         }
     }
     Ok(pacts)
@@ -216,7 +339,26 @@ fn display_body_mismatch(expected: &Response, actual: &Response, path: &String)
     }
 }
 
-/// Filter information used to filter the interactions that are verified
+fn display_message_body_mismatch(expected: &Message, actual: &Message, path: &String) {
+    if expected.content_type().contains("json") {
+        println!("{}", pact_matching::json::display_diff(&expected.contents.value(),
+            &actual.contents.value(), path));
+    }
+}
+
+/// Options for publishing the verification results back to the pact broker. Results are only
+/// published for pacts that were loaded from a pact broker, and when the interactions were not filtered.
+#[derive(Debug, Clone)]
+pub struct PublishOptions {
+    /// Version of the provider application that was verified
+    pub provider_version: String,
+    /// URL of the build that performed the verification
+    pub build_url: Option<String>
+}
+
+/// Filter information used to filter the interactions that are verified. Filters can be combined
+/// with `All`, `Any` and `Not`, for example to verify the failing interactions from a previous run.
+#[derive(Debug, Clone)]
 pub enum FilterInfo {
     /// No filter, all interactions will be verified
     None,
@@ -225,7 +367,17 @@ pub enum FilterInfo {
     /// Filter on the interaction provider state
     State(String),
     /// Filter on both the interaction description and provider state
-    DescriptionAndState(String, String)
+    DescriptionAndState(String, String),
+    /// Filter on the consumer name of the pact, using a regular expression
+    Consumer(String),
+    /// Filter on the index of the interaction in the pact file (starting at 0)
+    Index(usize),
+    /// Only verify the interactions that do not match the filter
+    Not(Box<FilterInfo>),
+    /// Only verify the interactions that match all the filters
+    All(Vec<FilterInfo>),
+    /// Only verify the interactions that match any of the filters
+    Any(Vec<FilterInfo>)
 }
 
 impl FilterInfo {
@@ -268,285 +420,718 @@ impl FilterInfo {
 
     /// If the filter matches the interaction provider state using a regular expression. If the
     /// filter value is the empty string, then it will match interactions with no provider state.
-    ///
-    /// # Panics
-    /// If the state filter value can't be parsed as a regular expression
-    pub fn match_state(&self, interaction: &Interaction) -> bool {
-        match interaction.provider_state.clone() {
-            Some(state) => {
-                if self.state().is_empty() {
-                    false
-                } else {
-                    let re = Regex::new(&self.state()).unwrap();
-                    re.is_match(&state)
-                }
-            },
-            None => self.has_state() && self.state().is_empty()
+    /// Returns an error if the state filter value can't be parsed as a regular expression.
+    pub fn match_state(&self, interaction: &Interaction) -> Result<bool, String> {
+        if self.has_state() {
+            compile_filter_regex(&self.state())
+                .map(|re| match_provider_states(&re, &interaction.provider_states))
+        } else {
+            Ok(false)
         }
     }
 
-    /// If the filter matches the interaction description using a regular expression
-    ///
-    /// # Panics
-    /// If the description filter value can't be parsed as a regular expression
-    pub fn match_description(&self, interaction: &Interaction) -> bool {
-        let re = Regex::new(&self.description()).unwrap();
-        re.is_match(&interaction.description)
+    /// If the filter matches the interaction description using a regular expression. Returns an
+    /// error if the description filter value can't be parsed as a regular expression.
+    pub fn match_description(&self, interaction: &Interaction) -> Result<bool, String> {
+        compile_filter_regex(&self.description()).map(|re| re.is_match(&interaction.description))
     }
 
+    /// Validates the regular expressions of the filter
+    pub fn validate(&self) -> Result<(), String> {
+        self.compile().map(|_| ())
+    }
+
+    fn compile(&self) -> Result<Filter, String> {
+        let compile_filters = |filters: &Vec<FilterInfo>| filters.iter()
+            .map(|filter| filter.compile())
+            .collect::<Result<Vec<Filter>, String>>();
+        match self {
+            &FilterInfo::None => Ok(Filter::None),
+            &FilterInfo::Description(ref s) => compile_filter_regex(s).map(Filter::Description),
+            &FilterInfo::State(ref s) => compile_filter_regex(s).map(Filter::State),
+            &FilterInfo::DescriptionAndState(ref d, ref s) => compile_filter_regex(d)
+                .and_then(|d| compile_filter_regex(s).map(|s| Filter::DescriptionAndState(d, s))),
+            &FilterInfo::Consumer(ref s) => compile_filter_regex(s).map(Filter::Consumer),
+            &FilterInfo::Index(index) => Ok(Filter::Index(index)),
+            &FilterInfo::Not(ref filter) => filter.compile().map(|filter| Filter::Not(Box::new(filter))),
+            &FilterInfo::All(ref filters) => compile_filters(filters).map(Filter::All),
+            &FilterInfo::Any(ref filters) => compile_filters(filters).map(Filter::Any)
+        }
+    }
+
+}
+
+fn compile_filter_regex(value: &String) -> Result<Regex, String> {
+    Regex::new(value).map_err(|err| format!("'{}' is an invalid filter value: {}", value, err))
 }
 
-fn filter_interaction(interaction: &Interaction, filter: &FilterInfo) -> bool {
-    if filter.has_description() && filter.has_state() {
-      filter.match_description(interaction) && filter.match_state(interaction)
-    } else if filter.has_description() {
-      filter.match_description(interaction)
-    } else if filter.has_state() {
-      filter.match_state(interaction)
+/// Filter with the regular expressions of a `FilterInfo` compiled, so they are only parsed once
+/// for all the interactions
+#[derive(Debug, Clone)]
+enum Filter {
+    None,
+    Description(Regex),
+    State(Regex),
+    DescriptionAndState(Regex, Regex),
+    Consumer(Regex),
+    Index(usize),
+    Not(Box<Filter>),
+    All(Vec<Filter>),
+    Any(Vec<Filter>)
+}
+
+/// An empty state filter matches interactions with no provider state
+fn match_provider_states(state: &Regex, provider_states: &Vec<ProviderState>) -> bool {
+    if !provider_states.is_empty() {
+        !state.as_str().is_empty() && provider_states.iter().any(|provider_state| state.is_match(&provider_state.name))
     } else {
-      true
+        state.as_str().is_empty()
+    }
+}
+
+fn filter_by(consumer: &String, index: usize, description: &String, provider_states: &Vec<ProviderState>,
+    filter: &Filter) -> bool {
+    match filter {
+        &Filter::None => true,
+        &Filter::Description(ref re) => re.is_match(description),
+        &Filter::State(ref re) => match_provider_states(re, provider_states),
+        &Filter::DescriptionAndState(ref description_re, ref state_re) =>
+            description_re.is_match(description) && match_provider_states(state_re, provider_states),
+        &Filter::Consumer(ref re) => re.is_match(consumer),
+        &Filter::Index(filter_index) => index == filter_index,
+        &Filter::Not(ref filter) => !filter_by(consumer, index, description, provider_states, filter),
+        &Filter::All(ref filters) => filters.iter()
+            .all(|filter| filter_by(consumer, index, description, provider_states, filter)),
+        &Filter::Any(ref filters) => filters.iter()
+            .any(|filter| filter_by(consumer, index, description, provider_states, filter))
     }
 }
 
+fn filter_interaction(consumer: &String, index: usize, interaction: &Interaction, filter: &Filter) -> bool {
+    filter_by(consumer, index, &interaction.description, &interaction.provider_states, filter)
+}
+
+fn filter_message(consumer: &String, index: usize, message: &Message, filter: &Filter) -> bool {
+    filter_by(consumer, index, &message.description, &message.provider_states, filter)
+}
+
 fn filter_consumers(consumers: &Vec<String>, res: &Result<Pact, String>) -> bool {
     consumers.is_empty() || res.is_err() || consumers.contains(&res.clone().unwrap().consumer.name)
 }
 
-/// Verify the provider with the given pact sources
-pub fn verify_provider(provider_info: &ProviderInfo, source: Vec<PactSource>, filter: &FilterInfo,
-    consumers: &Vec<String>) -> bool {
-    let pacts = source.iter().flat_map(|s| {
+fn filter_message_consumers(consumers: &Vec<String>, res: &Result<MessagePact, String>) -> bool {
+    consumers.is_empty() || res.is_err() || consumers.contains(&res.clone().unwrap().consumer.name)
+}
+
+fn load_pacts<P, R, U, B>(source: &Vec<PactSource>, read_pact: R, from_url: U, from_broker: B)
+    -> Vec<Result<(P, Option<BrokerPactInfo>), String>>
+    where P: Clone,
+          R: Fn(&Path) -> io::Result<P>,
+          U: Fn(&String) -> Result<P, String>,
+          B: Fn(&String, &String, &PactSelection, &Option<HttpAuth>) -> Result<Vec<Result<(P, BrokerPactInfo), PactBrokerError>>, PactBrokerError> {
+    source.iter().flat_map(|s| {
         match s {
-            &PactSource::File(ref file) => vec![Pact::read_pact(Path::new(&file))
+            &PactSource::File(ref file) => vec![read_pact(Path::new(&file))
+                .map(|pact| (pact, None))
                 .map_err(|err| format!("Failed to load pact '{}' - {}", file, err))],
-            &PactSource::Dir(ref dir) => match walkdir(Path::new(dir)) {
+            &PactSource::Dir(ref dir) => match walkdir(Path::new(dir), &read_pact) {
                 Ok(ref pacts) => pacts.iter().map(|p| {
                         match p {
-                            &Ok(ref pact) => Ok(pact.clone()),
+                            &Ok(ref pact) => Ok((pact.clone(), None)),
                             &Err(ref err) => Err(format!("Failed to load pact from '{}' - {}", dir, err))
                         }
                     }).collect(),
                 Err(err) => vec![Err(format!("Could not load pacts from directory '{}' - {}", dir, err))]
             },
-            &PactSource::URL(ref url) => vec![Pact::from_url(url)
+            &PactSource::URL(ref url) => vec![from_url(url)
+                .map(|pact| (pact, None))
                 .map_err(|err| format!("Failed to load pact '{}' - {}", url, err))],
-            &PactSource::BrokerUrl(ref provider_name, ref broker_url) => match pact_broker::fetch_pacts_from_broker(broker_url, provider_name) {
-                Ok(ref pacts) => pacts.iter().map(|p| {
-                        match p {
-                            &Ok(ref pact) => Ok(pact.clone()),
-                            &Err(ref err) => Err(format!("Failed to load pact from '{}' - {:?}", broker_url, err))
-                        }
-                    }).collect(),
-                Err(err) => vec![Err(format!("Could not load pacts from the pact broker '{}' - {:?}", broker_url, err))]
-            }
+            &PactSource::BrokerUrl(ref provider_name, ref broker_url, ref auth) =>
+                load_pacts_from_broker(provider_name, broker_url, &PactSelection::Latest, auth, &from_broker),
+            &PactSource::BrokerWithTags(ref provider_name, ref broker_url, ref tags, ref auth) =>
+                load_pacts_from_broker(provider_name, broker_url, &PactSelection::LatestWithTags(tags.clone()), auth, &from_broker),
+            &PactSource::BrokerWithSelectors(ref provider_name, ref broker_url, ref selectors, ref options, ref auth) =>
+                load_pacts_from_broker(provider_name, broker_url, &PactSelection::Selectors(selectors.clone(), options.clone()),
+                    auth, &from_broker)
         }
-    })
-    .filter(|res| filter_consumers(consumers, res))
-    .collect::<Vec<Result<Pact, String>>>();
-
-    let mut verify_provider_result = true;
-    let mut all_errors: Vec<(String, MismatchResult)> = vec![];
-    for pact in pacts {
-        match pact {
-            Ok(ref pact) => {
-                println!("\nVerifying a pact between {} and {}",
-                    Style::new().bold().paint(pact.consumer.name.clone()),
-                    Style::new().bold().paint(pact.provider.name.clone()));
-
-                if pact.interactions.is_empty() {
-                    println!("         {}", Yellow.paint("WARNING: Pact file has no interactions"));
-                } else {
-                    let results: HashMap<Interaction, Result<(), MismatchResult>> = pact.interactions.iter()
-                    .filter(|interaction| filter_interaction(interaction, filter))
-                    .map(|interaction| {
-                        (interaction.clone(), verify_interaction(provider_info, interaction))
-                    }).collect();
-
-                    for (interaction, result) in results.clone() {
-                        let mut description = format!("Verifying a pact between {} and {}",
-                            pact.consumer.name.clone(), pact.provider.name.clone());
-                        if interaction.provider_state.is_some() {
-                            description.push_str(&format!(" Given {}",
-                                interaction.provider_state.clone().unwrap()));
-                        }
-                        description.push_str(" - ");
-                        description.push_str(&interaction.description);
-                        println!("  {}", interaction.description);
-                        match result {
-                            Ok(()) => {
-                                display_result(interaction.response.status, Green.paint("OK"),
-                                    interaction.response.headers.map(|h| h.iter().map(|(k, v)| {
-                                        (k.clone(), v.clone(), Green.paint("OK"))
-                                    }).collect()), Green.paint("OK"))
-                            },
-                            Err(ref err) => match err {
-                                &MismatchResult::Error(ref err_des) => {
-                                    println!("      {}", Red.paint(format!("Request Failed - {}", err_des)));
-                                    all_errors.push((description, MismatchResult::Error(err_des.clone())));
-                                    verify_provider_result = false;
-                                },
-                                &MismatchResult::Mismatches(ref mismatches, ref expected_response, ref actual_response) => {
-                                    description.push_str(" returns a response which ");
-                                    let status_result = if mismatches.iter().any(|m| m.mismatch_type() == s!("StatusMismatch")) {
-                                        verify_provider_result = false;
-                                        Red.paint("FAILED")
-                                    } else {
-                                        Green.paint("OK")
-                                    };
-                                    let header_results = match interaction.response.headers {
-                                        Some(ref h) => Some(h.iter().map(|(k, v)| {
-                                            (k.clone(), v.clone(), if mismatches.iter().any(|m| {
-                                                match m {
-                                                    &Mismatch::HeaderMismatch{ ref key, .. } => k == key,
-                                                    _ => false
-                                                }
-                                            }) {
-                                                verify_provider_result = false;
-                                                Red.paint("FAILED")
-                                            } else {
-                                                Green.paint("OK")
-                                            })
-                                        }).collect()),
-                                        None => None
-                                    };
-                                    let body_result = if mismatches.iter().any(|m| m.mismatch_type() == s!("BodyMismatch") ||
-                                        m.mismatch_type() == s!("BodyTypeMismatch")) {
-                                        verify_provider_result = false;
-                                        Red.paint("FAILED")
-                                    } else {
-                                        Green.paint("OK")
-                                    };
-
-                                    display_result(interaction.response.status, status_result, header_results,
-                                        body_result);
-
-                                    for mismatch in mismatches.clone() {
-                                        all_errors.push((description.clone(),
-                                            MismatchResult::Mismatches(vec![mismatch.clone()],
-                                                expected_response.clone(), actual_response.clone())));
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    println!("");
+    }).collect()
+}
+
+fn load_pacts_from_broker<P, B>(provider_name: &String, broker_url: &String, selection: &PactSelection,
+    auth: &Option<HttpAuth>, from_broker: &B) -> Vec<Result<(P, Option<BrokerPactInfo>), String>>
+    where P: Clone,
+          B: Fn(&String, &String, &PactSelection, &Option<HttpAuth>) -> Result<Vec<Result<(P, BrokerPactInfo), PactBrokerError>>, PactBrokerError> {
+    match from_broker(broker_url, provider_name, selection, auth) {
+        Ok(ref pacts) => pacts.iter().map(|p| {
+                match p {
+                    &Ok((ref pact, ref info)) => Ok((pact.clone(), Some(info.clone()))),
+                    &Err(ref err) => Err(format!("Failed to load pact from '{}' - {:?}", broker_url, err))
                 }
-            },
-            Err(err) => {
-                error!("Failed to load pact - {}", Red.paint(format!("{}", err)));
-                verify_provider_result = false;
-                all_errors.push((s!("Failed to load pact"), MismatchResult::Error(format!("{}", err))));
+            }).collect(),
+        Err(err) => vec![Err(format!("Could not load pacts from the pact broker '{}' - {:?}", broker_url, err))]
+    }
+}
+
+fn publish_result(result: &PactVerificationResult, link: &Option<PublishResultsLink>,
+    filter: &Filter, publish: &Option<PublishOptions>) {
+    match (publish, link) {
+        (&Some(ref options), &Some(ref link)) => {
+            match *filter {
+                Filter::None => {
+                    match pact_broker::publish_verification_results(link, result,
+                        &options.provider_version, &options.build_url) {
+                        Ok(_) => info!("Published verification results for the pact between {} and {}",
+                            result.consumer, result.provider),
+                        Err(err) => error!("Failed to publish verification results for the pact between {} and {} - {:?}",
+                            result.consumer, result.provider, err)
+                    }
+                },
+                _ => warn!("Not publishing verification results for the pact between {} and {} as the interactions were filtered",
+                    result.consumer, result.provider)
+            }
+        },
+        (&Some(_), &None) => debug!("Not publishing verification results for the pact between {} and {} as it has no link to publish the results to",
+            result.consumer, result.provider),
+        _ => ()
+    }
+}
+
+fn interaction_result(index: usize, description: &String, provider_states: Vec<String>, duration: Duration,
+    details: VerificationDetails, result: Result<(), MismatchResult>) -> InteractionVerificationResult {
+    let (status, mismatches, error, details) = match result {
+        Ok(()) => (VerificationStatus::Passed, vec![], None, details),
+        Err(MismatchResult::Mismatches(mismatches, expected, actual)) => (VerificationStatus::Failed, mismatches,
+            None, VerificationDetails::Response(expected, Some(actual))),
+        Err(MismatchResult::MessageMismatches(mismatches, expected, actual)) => (VerificationStatus::Failed,
+            mismatches, None, VerificationDetails::Message(expected, Some(actual))),
+        Err(MismatchResult::Error(err)) => (VerificationStatus::Error, vec![], Some(err), details)
+    };
+    InteractionVerificationResult {
+        index: index,
+        description: description.clone(),
+        provider_states: provider_states,
+        status: status,
+        duration: duration,
+        mismatches: mismatches,
+        error: error,
+        details: details
+    }
+}
+
+fn sort_interactions<T, F>(interactions: &mut Vec<T>, order: &InteractionOrder, key: F)
+    where F: Fn(&T) -> (String, Vec<String>) {
+    match *order {
+        InteractionOrder::PactFile => (),
+        InteractionOrder::Description => interactions.sort_by_key(|interaction| key(interaction).0),
+        InteractionOrder::ProviderState => interactions.sort_by_key(|interaction| {
+            let (description, provider_states) = key(interaction);
+            (provider_states, description)
+        })
+    }
+}
+
+fn verify_pact(provider_info: &ProviderInfo, pact: &Pact, filter: &Filter) -> PactVerificationResult {
+    let pact_start = Instant::now();
+    let mut interactions: Vec<(usize, &Interaction)> = pact.interactions.iter().enumerate()
+        .filter(|&(index, interaction)| filter_interaction(&pact.consumer.name, index, interaction, filter))
+        .collect();
+    sort_interactions(&mut interactions, &provider_info.interaction_order,
+        |&(_, interaction)| (interaction.description.clone(), interaction.provider_state_names()));
+    let interactions = interactions.into_iter()
+        .map(|(index, interaction)| {
+            let interaction_start = Instant::now();
+            let result = verify_interaction(provider_info, interaction);
+            interaction_result(index, &interaction.description, interaction.provider_state_names(),
+                interaction_start.elapsed(), VerificationDetails::Response(interaction.response.clone(), None),
+                result)
+        }).collect();
+    PactVerificationResult::new(pact.consumer.name.clone(), pact.provider.name.clone(),
+        pact_start.elapsed(), interactions)
+}
+
+fn verify_message_pact(provider_info: &ProviderInfo, pact: &MessagePact, filter: &Filter) -> PactVerificationResult {
+    let pact_start = Instant::now();
+    let mut messages: Vec<(usize, &Message)> = pact.messages.iter().enumerate()
+        .filter(|&(index, message)| filter_message(&pact.consumer.name, index, message, filter))
+        .collect();
+    sort_interactions(&mut messages, &provider_info.interaction_order,
+        |&(_, message)| (message.description.clone(), message.provider_state_names()));
+    let messages = messages.into_iter()
+        .map(|(index, message)| {
+            let message_start = Instant::now();
+            let result = verify_message(provider_info, message);
+            interaction_result(index, &message.description, message.provider_state_names(),
+                message_start.elapsed(), VerificationDetails::Message(message.clone(), None), result)
+        }).collect();
+    PactVerificationResult::new(pact.consumer.name.clone(), pact.provider.name.clone(),
+        pact_start.elapsed(), messages)
+}
+
+fn verify_loaded_pact<P, V>(provider_info: &ProviderInfo, pact: Result<(P, Option<BrokerPactInfo>), String>,
+    filter: &Filter, verify: V) -> (PactVerificationResult, Option<PublishResultsLink>)
+    where V: Fn(&ProviderInfo, &P, &Filter) -> PactVerificationResult {
+    match pact {
+        Ok((ref pact, ref info)) => {
+            let mut result = verify(provider_info, pact, filter);
+            result.pending = info.as_ref().map(|info| info.pending).unwrap_or(false);
+            (result, info.clone().and_then(|info| info.publish_link))
+        },
+        Err(err) => (PactVerificationResult::load_error(err), None)
+    }
+}
+
+fn verify_pacts<P, V>(provider_info: &ProviderInfo, pacts: Vec<Result<(P, Option<BrokerPactInfo>), String>>,
+    filter: &Filter, publish: &Option<PublishOptions>, message_pact: bool, verify: V) -> Vec<PactVerificationResult>
+    where P: Send + 'static,
+          V: Fn(&ProviderInfo, &P, &Filter) -> PactVerificationResult + Send + Copy + 'static {
+    let report = |(result, link): (PactVerificationResult, Option<PublishResultsLink>)| {
+        display_pact_result(provider_info, &result, message_pact);
+        publish_result(&result, &link, filter, publish);
+        result
+    };
+
+    if provider_info.workers <= 1 || pacts.len() <= 1 {
+        return pacts.into_iter().map(|pact| report(verify_loaded_pact(provider_info, pact, filter, verify))).collect();
+    }
+
+    let count = pacts.len();
+    let queue = Arc::new(Mutex::new(pacts.into_iter().enumerate().collect::<VecDeque<_>>()));
+    let (sender, receiver) = mpsc::channel();
+    for _ in 0..min(provider_info.workers, count) {
+        let queue = queue.clone();
+        let sender = sender.clone();
+        let provider_info = provider_info.clone();
+        let filter = filter.clone();
+        thread::spawn(move || loop {
+            let next = queue.lock().unwrap_or_else(|err| err.into_inner()).pop_front();
+            match next {
+                Some((index, pact)) => {
+                    let result = verify_loaded_pact(&provider_info, pact, &filter, verify);
+                    if sender.send((index, result)).is_err() {
+                        break;
+                    }
+                },
+                None => break
             }
+        });
+    }
+    drop(sender);
+
+    // Results are reported in the order the pacts were loaded, as soon as all the earlier pacts
+    // have been reported
+    let mut completed = BTreeMap::new();
+    let mut results = vec![];
+    for (index, result) in receiver {
+        completed.insert(index, result);
+        while let Some(result) = completed.remove(&results.len()) {
+            results.push(report(result));
         }
+    }
+    while results.len() < count {
+        let result = completed.remove(&results.len())
+            .unwrap_or((PactVerificationResult::load_error(s!("The worker verifying the pact failed")), None));
+        results.push(report(result));
+    }
+    results
+}
+
+fn invalid_filter_result(provider_info: &ProviderInfo, err: String, message_pact: bool, start: Instant) -> VerificationResult {
+    let pact_result = PactVerificationResult::load_error(err);
+    display_pact_result(provider_info, &pact_result, message_pact);
+    let result = VerificationResult { pacts: vec![pact_result], duration: start.elapsed() };
+    display_failures(&result);
+    result
+}
+
+/// Verify the provider with the given pact sources. The results are displayed on the console
+/// as each pact is verified, and returned as a `VerificationResult`. If the filter is not valid, no
+/// pacts are loaded and the result contains the error.
+pub fn verify_provider(provider_info: &ProviderInfo, source: Vec<PactSource>, filter: &FilterInfo,
+    consumers: &Vec<String>, publish: &Option<PublishOptions>) -> VerificationResult {
+    let start = Instant::now();
+    let filter = match filter.compile() {
+        Ok(filter) => filter,
+        Err(err) => return invalid_filter_result(provider_info, err, false, start)
     };
+    let pacts = load_pacts(&source, Pact::read_pact, Pact::from_url, pact_broker::fetch_pacts_from_broker)
+        .into_iter()
+        .filter(|res| filter_consumers(consumers, &res.clone().map(|(pact, _)| pact)))
+        .collect::<Vec<Result<(Pact, Option<BrokerPactInfo>), String>>>();
 
-    if !all_errors.is_empty() {
-        println!("\nFailures:\n");
+    let results = verify_pacts(provider_info, pacts, &filter, publish, false, verify_pact);
 
-        for (i, &(ref description, ref mismatch)) in all_errors.iter().enumerate() {
-            match mismatch {
-                &MismatchResult::Error(ref err) => println!("{}) {} - {}\n", i, description, err),
-                &MismatchResult::Mismatches(ref mismatch, ref expected_response, ref actual_response) => {
-                    let mismatch = mismatch.first().unwrap();
-                    println!("{}) {}{}", i, description, mismatch.summary());
-                    println!("    {}\n", mismatch.ansi_description());
+    let result = VerificationResult { pacts: results, duration: start.elapsed() };
+    display_failures(&result);
+    result
+}
 
-                    match mismatch {
-                        &Mismatch::BodyMismatch{ref path, ..} => display_body_mismatch(expected_response, actual_response, path),
-                        _ => ()
+fn display_pact_result(provider_info: &ProviderInfo, result: &PactVerificationResult, message_pact: bool) {
+    match result.error {
+        Some(ref err) => {
+            error!("Failed to load pact - {}", Red.paint(format!("{}", err)));
+            return;
+        },
+        None => ()
+    }
+
+    println!("\nVerifying a {}{} between {} and {}", if result.pending { "pending " } else { "" },
+        if message_pact { "message pact" } else { "pact" },
+        Style::new().bold().paint(result.consumer.clone()),
+        Style::new().bold().paint(result.provider.clone()));
+    if result.pending {
+        println!("  {}", Yellow.paint("NOTE: Failures of this pact will not fail the verification as it is pending"));
+    }
+
+    if result.interactions.is_empty() {
+        println!("         {}", Yellow.paint(if message_pact {
+            "WARNING: Pact has no messages to verify"
+        } else {
+            "WARNING: Pact has no interactions to verify"
+        }));
+        return;
+    }
+
+    for interaction in &result.interactions {
+        for state in &interaction.provider_states {
+            println!("  Given {}", Style::new().bold().paint(state.clone()));
+            if provider_info.state_change_url.is_none() && provider_info.state_handlers.handler_for(state).is_none() {
+                println!("    {}", Yellow.paint("WARNING: State Change ignored as there is no state change URL or state handler"));
+            }
+        }
+        println!("  {}", interaction.description);
+        match interaction.error {
+            Some(ref err) => {
+                println!("      {}", Red.paint(format!("Request Failed - {}", err)));
+                continue;
+            },
+            None => ()
+        }
+        match interaction.details {
+            VerificationDetails::Response(ref expected, _) => display_interaction_result(expected,
+                &interaction.mismatches),
+            VerificationDetails::Message(ref expected, _) => display_message_interaction_result(expected,
+                &interaction.mismatches)
+        }
+    }
+    println!("");
+}
+
+fn result_for(failed: bool) -> ANSIGenericString<'static, str> {
+    if failed {
+        Red.paint("FAILED")
+    } else {
+        Green.paint("OK")
+    }
+}
+
+fn display_interaction_result(expected: &Response, mismatches: &Vec<Mismatch>) {
+    let status_result = result_for(mismatches.iter().any(|m| m.mismatch_type() == s!("StatusMismatch")));
+    let header_results = expected.headers.clone().map(|h| h.iter().map(|(k, v)| {
+        (k.clone(), v.clone(), result_for(mismatches.iter().any(|m| {
+            match m {
+                &Mismatch::HeaderMismatch{ ref key, .. } => k == key,
+                _ => false
+            }
+        })))
+    }).collect());
+    let body_result = result_for(mismatches.iter().any(|m| m.mismatch_type() == s!("BodyMismatch") ||
+        m.mismatch_type() == s!("BodyTypeMismatch")));
+    display_result(expected.status, status_result, header_results, body_result);
+}
+
+fn display_message_interaction_result(expected: &Message, mismatches: &Vec<Mismatch>) {
+    let metadata_results = expected.metadata.iter().map(|(k, v)| {
+        (k.clone(), v.clone(), result_for(mismatches.iter().any(|m| {
+            match m {
+                &Mismatch::MetadataMismatch{ ref key, .. } => k == key,
+                &Mismatch::BodyTypeMismatch{ .. } => {
+                    let key = k.to_lowercase();
+                    key == "contenttype" || key == "content-type"
+                },
+                _ => false
+            }
+        })))
+    }).collect();
+    let body_result = result_for(mismatches.iter().any(|m| m.mismatch_type() == s!("BodyMismatch") ||
+        m.mismatch_type() == s!("BodyTypeMismatch")));
+    display_message_result(metadata_results, body_result);
+}
+
+fn display_failures(result: &VerificationResult) {
+    let mut count = 0;
+    let mut pending_count = 0;
+    for pact in &result.pacts {
+        match pact.error {
+            Some(ref err) => {
+                if count == 0 {
+                    println!("\nFailures:\n");
+                }
+                println!("{}) Failed to load pact - {}\n", count, err);
+                count += 1;
+            },
+            None => ()
+        }
+
+        for interaction in &pact.interactions {
+            if interaction.status == VerificationStatus::Passed {
+                continue;
+            }
+            let message = match interaction.details {
+                VerificationDetails::Message(..) => true,
+                VerificationDetails::Response(..) => false
+            };
+            let mut description = format!("Verifying a {}{} between {} and {}", if pact.pending { "pending " } else { "" },
+                if message { "message pact" } else { "pact" }, pact.consumer, pact.provider);
+            if !interaction.provider_states.is_empty() {
+                description.push_str(&format!(" Given {}", interaction.provider_states.join(" And ")));
+            }
+            description.push_str(" - ");
+            description.push_str(&interaction.description);
+
+            match interaction.error {
+                Some(ref err) => {
+                    if count == 0 {
+                        println!("\nFailures:\n");
                     }
+                    println!("{}) {} - {}\n", count, description, err);
+                    count += 1;
+                    if pact.pending {
+                        pending_count += 1;
+                    }
+                },
+                None => ()
+            }
+
+            description.push_str(if message { " generates a message which " } else { " returns a response which " });
+            for mismatch in &interaction.mismatches {
+                if count == 0 {
+                    println!("\nFailures:\n");
+                }
+                println!("{}) {}{}", count, description, mismatch.summary());
+                println!("    {}\n", mismatch.ansi_description());
+                match mismatch {
+                    &Mismatch::BodyMismatch{ref path, ..} => match interaction.details {
+                        VerificationDetails::Response(ref expected, Some(ref actual)) =>
+                            display_body_mismatch(expected, actual, path),
+                        VerificationDetails::Message(ref expected, Some(ref actual)) =>
+                            display_message_body_mismatch(expected, actual, path),
+                        _ => ()
+                    },
+                    _ => ()
+                }
+                count += 1;
+                if pact.pending {
+                    pending_count += 1;
                 }
             }
         }
+    }
 
-        println!("\nThere were {} pact failures\n", all_errors.len());
+    if count > 0 && pending_count > 0 {
+        println!("\nThere were {} pact failures ({} from pending pacts, which do not fail the verification)\n",
+            count, pending_count);
+    } else if count > 0 {
+        println!("\nThere were {} pact failures\n", count);
     }
+}
+
+/// Verify the message provider with the given message pact sources. Each message is generated by
+/// making a POST request to the provider (using the host, port and path of the provider info)
+/// with the description and provider states of the message as a JSON body. The body of the
+/// response is then matched against the expected message contents. The provider can return the
+/// message metadata as a JSON object in the `Pact-Message-Metadata` header, and the `Content-Type`
+/// header is used as the `contentType` metadata value. The results are displayed on the console
+/// as each pact is verified, and returned as a `VerificationResult`.
+pub fn verify_message_provider(provider_info: &ProviderInfo, source: Vec<PactSource>, filter: &FilterInfo,
+    consumers: &Vec<String>, publish: &Option<PublishOptions>) -> VerificationResult {
+    let start = Instant::now();
+    let filter = match filter.compile() {
+        Ok(filter) => filter,
+        Err(err) => return invalid_filter_result(provider_info, err, true, start)
+    };
+    let pacts = load_pacts(&source, MessagePact::read_pact, MessagePact::from_url,
+        pact_broker::fetch_message_pacts_from_broker)
+        .into_iter()
+        .filter(|res| filter_message_consumers(consumers, &res.clone().map(|(pact, _)| pact)))
+        .collect::<Vec<Result<(MessagePact, Option<BrokerPactInfo>), String>>>();
+
+    let results = verify_pacts(provider_info, pacts, &filter, publish, true, verify_message_pact);
 
-    verify_provider_result
+    let result = VerificationResult { pacts: results, duration: start.elapsed() };
+    display_failures(&result);
+    result
 }
 
 #[cfg(test)]
 mod tests {
     use expectest::prelude::*;
-    use super::{FilterInfo, filter_interaction, filter_consumers};
+    use super::{FilterInfo, Filter, ProviderInfo, StateHandlers, MismatchResult, ProviderTransport, filter_interaction,
+                filter_message, filter_consumers, execute_state_change, verify_interaction, verify_message, verify_pacts,
+                verify_provider, verify_message_provider,
+                PactVerificationResult, InteractionOrder, BrokerPactInfo, sort_interactions, walkdir};
     use pact_matching::models::*;
+    use pact_matching::models::generators::Generator;
+    use std::time::Duration;
+    use std::sync::mpsc::{self, Sender, Receiver};
+    use std::env;
+    use std::fs;
+    use std::path::Path;
 
     #[test]
     fn if_no_interaction_filter_is_defined_returns_true() {
         let interaction = Interaction::default();
-        expect!(filter_interaction(&interaction, &FilterInfo::None)).to(be_true());
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &FilterInfo::None.compile().unwrap())).to(be_true());
     }
 
     #[test]
     fn if_an_interaction_filter_is_defined_returns_false_if_the_description_does_not_match() {
         let interaction = Interaction { description: s!("bob"), .. Interaction::default() };
-        expect!(filter_interaction(&interaction, &FilterInfo::Description(s!("fred")))).to(be_false());
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &FilterInfo::Description(s!("fred")).compile().unwrap())).to(be_false());
     }
 
     #[test]
     fn if_an_interaction_filter_is_defined_returns_true_if_the_description_does_match() {
         let interaction = Interaction { description: s!("bob"), .. Interaction::default() };
-        expect!(filter_interaction(&interaction, &FilterInfo::Description(s!("bob")))).to(be_true());
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &FilterInfo::Description(s!("bob")).compile().unwrap())).to(be_true());
     }
 
     #[test]
     fn uses_regexs_to_match_the_description() {
         let interaction = Interaction { description: s!("bobby"), .. Interaction::default() };
-        expect!(filter_interaction(&interaction, &FilterInfo::Description(s!("bob.*")))).to(be_true());
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &FilterInfo::Description(s!("bob.*")).compile().unwrap())).to(be_true());
     }
 
     #[test]
     fn if_an_interaction_state_filter_is_defined_returns_false_if_the_state_does_not_match() {
-        let interaction = Interaction { provider_state: Some(s!("bob")), .. Interaction::default() };
-        expect!(filter_interaction(&interaction, &FilterInfo::State(s!("fred")))).to(be_false());
+        let interaction = Interaction { provider_states: vec![ProviderState::default(&s!("bob"))], .. Interaction::default() };
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &FilterInfo::State(s!("fred")).compile().unwrap())).to(be_false());
     }
 
     #[test]
     fn if_an_interaction_state_filter_is_defined_returns_true_if_the_state_does_match() {
-        let interaction = Interaction { provider_state: Some(s!("bob")), .. Interaction::default() };
-        expect!(filter_interaction(&interaction, &FilterInfo::State(s!("bob")))).to(be_true());
+        let interaction = Interaction { provider_states: vec![ProviderState::default(&s!("bob"))], .. Interaction::default() };
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &FilterInfo::State(s!("bob")).compile().unwrap())).to(be_true());
     }
 
     #[test]
     fn uses_regexs_to_match_the_state() {
-        let interaction = Interaction { provider_state: Some(s!("bobby")), .. Interaction::default() };
-        expect!(filter_interaction(&interaction, &FilterInfo::State(s!("bob.*")))).to(be_true());
+        let interaction = Interaction { provider_states: vec![ProviderState::default(&s!("bobby"))], .. Interaction::default() };
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &FilterInfo::State(s!("bob.*")).compile().unwrap())).to(be_true());
     }
 
     #[test]
     fn if_the_state_filter_is_empty_returns_false_if_the_interaction_state_is_defined() {
-        let interaction = Interaction { provider_state: Some(s!("bobby")), .. Interaction::default() };
-        expect!(filter_interaction(&interaction, &FilterInfo::State(s!("")))).to(be_false());
+        let interaction = Interaction { provider_states: vec![ProviderState::default(&s!("bobby"))], .. Interaction::default() };
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &FilterInfo::State(s!("")).compile().unwrap())).to(be_false());
     }
 
     #[test]
     fn if_the_state_filter_is_empty_returns_true_if_the_interaction_state_is_not_defined() {
-        let interaction = Interaction { provider_state: None, .. Interaction::default() };
-        expect!(filter_interaction(&interaction, &FilterInfo::State(s!("")))).to(be_true());
+        let interaction = Interaction { provider_states: vec![], .. Interaction::default() };
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &FilterInfo::State(s!("")).compile().unwrap())).to(be_true());
     }
 
     #[test]
     fn if_the_state_filter_and_interaction_filter_is_defined_must_match_both() {
-        let interaction = Interaction { description: s!("freddy"), provider_state: Some(s!("bobby")), .. Interaction::default() };
-        expect!(filter_interaction(&interaction, &FilterInfo::DescriptionAndState(s!(".*ddy"), s!("bob.*")))).to(be_true());
+        let interaction = Interaction { description: s!("freddy"), provider_states: vec![ProviderState::default(&s!("bobby"))], .. Interaction::default() };
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &FilterInfo::DescriptionAndState(s!(".*ddy"), s!("bob.*")).compile().unwrap())).to(be_true());
     }
 
     #[test]
     fn if_the_state_filter_and_interaction_filter_is_defined_is_false_if_the_provider_state_does_not_match() {
-        let interaction = Interaction { description: s!("freddy"), provider_state: Some(s!("boddy")), .. Interaction::default() };
-        expect!(filter_interaction(&interaction, &FilterInfo::DescriptionAndState(s!(".*ddy"), s!("bob.*")))).to(be_false());
+        let interaction = Interaction { description: s!("freddy"), provider_states: vec![ProviderState::default(&s!("boddy"))], .. Interaction::default() };
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &FilterInfo::DescriptionAndState(s!(".*ddy"), s!("bob.*")).compile().unwrap())).to(be_false());
     }
 
     #[test]
     fn if_the_state_filter_and_interaction_filter_is_defined_is_false_if_the_description_does_not_match() {
-        let interaction = Interaction { description: s!("frebby"), provider_state: Some(s!("bobby")), .. Interaction::default() };
-        expect!(filter_interaction(&interaction, &FilterInfo::DescriptionAndState(s!(".*ddy"), s!("bob.*")))).to(be_false());
+        let interaction = Interaction { description: s!("frebby"), provider_states: vec![ProviderState::default(&s!("bobby"))], .. Interaction::default() };
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &FilterInfo::DescriptionAndState(s!(".*ddy"), s!("bob.*")).compile().unwrap())).to(be_false());
     }
 
     #[test]
     fn if_the_state_filter_and_interaction_filter_is_defined_is_false_if_both_do_not_match() {
-        let interaction = Interaction { description: s!("joe"), provider_state: Some(s!("authur")), .. Interaction::default() };
-        expect!(filter_interaction(&interaction, &FilterInfo::DescriptionAndState(s!(".*ddy"), s!("bob.*")))).to(be_false());
+        let interaction = Interaction { description: s!("joe"), provider_states: vec![ProviderState::default(&s!("authur"))], .. Interaction::default() };
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &FilterInfo::DescriptionAndState(s!(".*ddy"), s!("bob.*")).compile().unwrap())).to(be_false());
+    }
+
+    #[test]
+    fn message_filters_match_on_the_description_and_provider_state() {
+        let message = Message { description: s!("freddy"), provider_states: vec![ProviderState::default(&s!("bobby"))], .. Message::default() };
+        expect!(filter_message(&s!("consumer"), 0, &message, &FilterInfo::None.compile().unwrap())).to(be_true());
+        expect!(filter_message(&s!("consumer"), 0, &message, &FilterInfo::DescriptionAndState(s!(".*ddy"), s!("bob.*")).compile().unwrap())).to(be_true());
+        expect!(filter_message(&s!("consumer"), 0, &message, &FilterInfo::Description(s!("joe")).compile().unwrap())).to(be_false());
+        expect!(filter_message(&s!("consumer"), 0, &message, &FilterInfo::State(s!("")).compile().unwrap())).to(be_false());
+    }
+
+    #[test]
+    fn consumer_filters_match_the_consumer_name_using_a_regex() {
+        let interaction = Interaction::default();
+        expect!(filter_interaction(&s!("Consumer"), 0, &interaction, &FilterInfo::Consumer(s!("^Con.*")).compile().unwrap())).to(be_true());
+        expect!(filter_interaction(&s!("Consumer"), 0, &interaction, &FilterInfo::Consumer(s!("^Other$")).compile().unwrap())).to(be_false());
+    }
+
+    #[test]
+    fn index_filters_match_the_index_of_the_interaction() {
+        let interaction = Interaction::default();
+        expect!(filter_interaction(&s!("consumer"), 2, &interaction, &FilterInfo::Index(2).compile().unwrap())).to(be_true());
+        expect!(filter_interaction(&s!("consumer"), 1, &interaction, &FilterInfo::Index(2).compile().unwrap())).to(be_false());
+    }
+
+    #[test]
+    fn filters_can_be_negated() {
+        let interaction = Interaction { description: s!("bob"), .. Interaction::default() };
+        let filter = FilterInfo::Not(Box::new(FilterInfo::Description(s!("bob"))));
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &filter.compile().unwrap())).to(be_false());
+        let filter = FilterInfo::Not(Box::new(FilterInfo::Description(s!("fred"))));
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &filter.compile().unwrap())).to(be_true());
+    }
+
+    #[test]
+    fn filters_can_be_combined() {
+        let interaction = Interaction {
+            description: s!("freddy"),
+            provider_states: vec![ProviderState::default(&s!("bob"))],
+            .. Interaction::default()
+        };
+        let any = FilterInfo::Any(vec![FilterInfo::Description(s!("joe")), FilterInfo::State(s!("bob"))]);
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &any.compile().unwrap())).to(be_true());
+        let any = FilterInfo::Any(vec![FilterInfo::Description(s!("joe")), FilterInfo::State(s!("mary"))]);
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &any.compile().unwrap())).to(be_false());
+        let all = FilterInfo::All(vec![FilterInfo::Description(s!("fred")), FilterInfo::State(s!("bob"))]);
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &all.compile().unwrap())).to(be_true());
+        let all = FilterInfo::All(vec![FilterInfo::Description(s!("fred")), FilterInfo::Index(1)]);
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &all.compile().unwrap())).to(be_false());
+        let failing = FilterInfo::Any(vec![
+            FilterInfo::All(vec![FilterInfo::Consumer(s!("^consumer$")), FilterInfo::Index(3)]),
+            FilterInfo::All(vec![FilterInfo::Consumer(s!("^other$")), FilterInfo::Index(0)])
+        ]);
+        expect!(filter_interaction(&s!("consumer"), 3, &interaction, &failing.compile().unwrap())).to(be_true());
+        expect!(filter_interaction(&s!("consumer"), 0, &interaction, &failing.compile().unwrap())).to(be_false());
+        expect!(filter_interaction(&s!("other"), 0, &interaction, &failing.compile().unwrap())).to(be_true());
+    }
+
+    #[test]
+    fn match_state_and_match_description_return_an_error_for_an_invalid_filter() {
+        let interaction = Interaction { description: s!("test"),
+            provider_states: vec![ProviderState::default(&s!("state one"))], .. Interaction::default() };
+        expect!(FilterInfo::State(s!("one")).match_state(&interaction)).to(be_ok().value(true));
+        expect!(FilterInfo::State(s!("two")).match_state(&interaction)).to(be_ok().value(false));
+        expect!(FilterInfo::Description(s!("test")).match_state(&interaction)).to(be_ok().value(false));
+        expect!(FilterInfo::State(s!("[a")).match_state(&interaction)).to(be_err());
+        expect!(FilterInfo::Description(s!("te.t")).match_description(&interaction)).to(be_ok().value(true));
+        expect!(FilterInfo::Description(s!("other")).match_description(&interaction)).to(be_ok().value(false));
+        expect!(FilterInfo::DescriptionAndState(s!("(te"), s!("one")).match_description(&interaction)).to(be_err());
+    }
+
+    #[test]
+    fn validate_checks_the_regexs_of_the_filter() {
+        expect!(FilterInfo::None.validate()).to(be_ok());
+        expect!(FilterInfo::Description(s!("a.*")).validate()).to(be_ok());
+        expect!(FilterInfo::Consumer(s!("[a")).validate()).to(be_err());
+        expect!(FilterInfo::Not(Box::new(FilterInfo::State(s!("(")))).validate()).to(be_err());
+        expect!(FilterInfo::Any(vec![FilterInfo::Index(1), FilterInfo::Description(s!("[a"))]).validate()).to(be_err());
+        expect!(FilterInfo::All(vec![FilterInfo::Index(1), FilterInfo::Description(s!("a"))]).validate()).to(be_ok());
     }
 
     #[test]
@@ -576,4 +1161,219 @@ mod tests {
         let result = Ok(Pact { consumer: Consumer { name: s!("bob") }, .. Pact::default() });
         expect!(filter_consumers(&consumers, &result)).to(be_true());
     }
+
+    #[test]
+    fn execute_state_change_uses_the_state_handler_for_the_provider_state() {
+        let provider = ProviderInfo {
+            state_handlers: StateHandlers::new()
+                .with_state("user exists", |_, params, setup| Ok(hashmap!{
+                    s!("id") => params.get("id").cloned().unwrap_or(json!(0)),
+                    s!("setup") => json!(setup)
+                })),
+            .. ProviderInfo::default()
+        };
+        let state = ProviderState { name: s!("user exists"), params: hashmap!{ s!("id") => json!(100) } };
+        expect!(execute_state_change(&state, &provider, true)).to(be_ok().value(hashmap!{
+            s!("id") => json!(100),
+            s!("setup") => json!(true)
+        }));
+        expect!(execute_state_change(&state, &provider, false)).to(be_ok().value(hashmap!{
+            s!("id") => json!(100),
+            s!("setup") => json!(false)
+        }));
+    }
+
+    #[test]
+    fn execute_state_change_returns_an_error_if_the_state_handler_fails() {
+        let provider = ProviderInfo {
+            state_handlers: StateHandlers::new().with_fallback(|name, _, _| Err(format!("Unknown state '{}'", name))),
+            .. ProviderInfo::default()
+        };
+        let state = ProviderState::default(&s!("user exists"));
+        let result = execute_state_change(&state, &provider, true);
+        match result {
+            Err(MismatchResult::Error(err)) => {
+                expect!(err).to(be_equal_to(s!("State change handler failed: Unknown state 'user exists'")));
+            },
+            _ => panic!("Expected an error, got {:?}", result)
+        }
+    }
+
+    #[test]
+    fn execute_state_change_ignores_the_state_if_there_is_no_handler_or_state_change_url() {
+        let provider = ProviderInfo::default();
+        let state = ProviderState::default(&s!("user exists"));
+        expect!(execute_state_change(&state, &provider, true)).to(be_ok().value(hashmap!{}));
+    }
+
+    #[test]
+    fn verify_message_matches_the_metadata_returned_by_the_provider() {
+        let provider = ProviderInfo {
+            transport: ProviderTransport::in_process(|_| Response {
+                headers: Some(hashmap!{
+                    s!("Content-Type") => s!("application/json"),
+                    s!("Pact-Message-Metadata") => s!(r#"{"topic":"users"}"#)
+                }),
+                body: OptionalBody::Present(s!(r#"{"id":1}"#).into_bytes()),
+                .. Response::default_response()
+            }),
+            .. ProviderInfo::default()
+        };
+        let message = Message {
+            description: s!("a user created message"),
+            contents: OptionalBody::Present(s!(r#"{"id":1}"#).into_bytes()),
+            metadata: hashmap!{ s!("contentType") => s!("application/json"), s!("topic") => s!("users") },
+            .. Message::default()
+        };
+        expect!(verify_message(&provider, &message)).to(be_ok());
+
+        let other_topic = Message {
+            metadata: hashmap!{ s!("contentType") => s!("application/json"), s!("topic") => s!("accounts") },
+            .. message
+        };
+        expect!(verify_message(&provider, &other_topic)).to(be_err());
+    }
+
+    #[test]
+    fn verify_interaction_can_verify_an_in_process_provider() {
+        let provider = ProviderInfo {
+            state_handlers: StateHandlers::new()
+                .with_state("user exists", |_, _, _| Ok(hashmap!{ s!("id") => json!(100) })),
+            transport: ProviderTransport::in_process(|request| Response {
+                status: if request.path == "/users/100" { 200 } else { 404 },
+                .. Response::default_response()
+            }),
+            .. ProviderInfo::default()
+        };
+        let interaction = Interaction {
+            provider_states: vec![ProviderState::default(&s!("user exists"))],
+            request: Request {
+                path: s!("/users/1"),
+                generators: Some(hashmap!{
+                    s!("$.path") => Generator::ProviderStateGenerator(s!("/users/${id}"))
+                }),
+                .. Request::default_request()
+            },
+            .. Interaction::default()
+        };
+        expect!(verify_interaction(&provider, &interaction)).to(be_ok());
+
+        let failing = Interaction {
+            request: Request { path: s!("/users/2"), .. Request::default_request() },
+            .. Interaction::default()
+        };
+        expect!(verify_interaction(&provider, &failing)).to(be_err());
+    }
+
+    struct TestPact {
+        consumer: String,
+        wait_for: Option<Receiver<()>>,
+        done: Option<Sender<()>>,
+        completed: Sender<String>
+    }
+
+    fn test_pacts(consumers: Vec<&str>) -> (Vec<TestPact>, Receiver<String>) {
+        let (completed, receiver) = mpsc::channel();
+        let pacts = consumers.iter().map(|consumer| TestPact {
+            consumer: s!(*consumer),
+            wait_for: None,
+            done: None,
+            completed: completed.clone()
+        }).collect();
+        (pacts, receiver)
+    }
+
+    fn verify_consumer(_: &ProviderInfo, pact: &TestPact, _: &Filter) -> PactVerificationResult {
+        if let Some(ref wait_for) = pact.wait_for {
+            wait_for.recv().unwrap();
+        }
+        pact.completed.send(pact.consumer.clone()).unwrap();
+        if let Some(ref done) = pact.done {
+            done.send(()).unwrap();
+        }
+        PactVerificationResult::new(pact.consumer.clone(), s!("provider"), Duration::from_millis(1), vec![])
+    }
+
+    #[test]
+    fn verify_pacts_returns_the_results_in_order_when_verifying_in_parallel() {
+        let provider = ProviderInfo { workers: 3, .. ProviderInfo::default() };
+        let (mut pacts, completed) = test_pacts(vec!["0", "1", "3"]);
+        // The first pact waits for the last pact, which waits for the second one, so they complete out of order
+        let (done_1, wait_for_1) = mpsc::channel();
+        let (done_3, wait_for_3) = mpsc::channel();
+        pacts[0].wait_for = Some(wait_for_3);
+        pacts[1].done = Some(done_1);
+        pacts[2].wait_for = Some(wait_for_1);
+        pacts[2].done = Some(done_3);
+        let mut pacts: Vec<Result<(TestPact, Option<BrokerPactInfo>), String>> = pacts.into_iter()
+            .map(|pact| Ok((pact, None))).collect();
+        pacts.insert(2, Err(s!("failed to load")));
+
+        let results = verify_pacts(&provider, pacts, &Filter::None, &None, false, verify_consumer);
+        expect!(completed.try_iter().collect::<Vec<String>>()).to(be_equal_to(vec![s!("1"), s!("3"), s!("0")]));
+        expect!(results.iter().map(|result| result.consumer.clone()).collect::<Vec<String>>())
+            .to(be_equal_to(vec![s!("0"), s!("1"), s!(""), s!("3")]));
+        expect!(results[2].error.clone()).to(be_some().value(s!("failed to load")));
+    }
+
+    #[test]
+    fn verify_pacts_verifies_the_pacts_sequentially_with_one_worker() {
+        let provider = ProviderInfo::default();
+        let (pacts, completed) = test_pacts(vec!["0", "3"]);
+        let pacts = pacts.into_iter().map(|pact| Ok((pact, None))).collect();
+        let results = verify_pacts(&provider, pacts, &Filter::None, &None, false, verify_consumer);
+        expect!(completed.try_iter().collect::<Vec<String>>()).to(be_equal_to(vec![s!("0"), s!("3")]));
+        expect!(results.iter().map(|result| result.consumer.clone()).collect::<Vec<String>>())
+            .to(be_equal_to(vec![s!("0"), s!("3")]));
+    }
+
+    #[test]
+    fn verify_provider_returns_an_error_if_the_filter_is_invalid() {
+        let provider = ProviderInfo { workers: 3, .. ProviderInfo::default() };
+        let filter = FilterInfo::Any(vec![FilterInfo::Index(1), FilterInfo::Description(s!("[a"))]);
+        let result = verify_provider(&provider, vec![], &filter, &vec![], &None);
+        expect!(result.pacts.len()).to(be_equal_to(1));
+        expect!(result.pacts[0].error.clone()).to(be_some());
+        let result = verify_message_provider(&provider, vec![], &filter, &vec![], &None);
+        expect!(result.pacts.len()).to(be_equal_to(1));
+        expect!(result.pacts[0].error.clone()).to(be_some());
+    }
+
+    #[test]
+    fn sort_interactions_sorts_by_the_interaction_order() {
+        let interaction = |description: &str, state: Option<&str>| Interaction {
+            description: s!(description),
+            provider_states: state.map(|state| vec![ProviderState::default(&s!(state))]).unwrap_or(vec![]),
+            .. Interaction::default()
+        };
+        let interactions = vec![
+            interaction("c", Some("state 1")),
+            interaction("a", Some("state 2")),
+            interaction("b", None),
+            interaction("d", Some("state 1"))
+        ];
+        let descriptions = |order: InteractionOrder| {
+            let mut sorted: Vec<&Interaction> = interactions.iter().collect();
+            sort_interactions(&mut sorted, &order,
+                |interaction| (interaction.description.clone(), interaction.provider_state_names()));
+            sorted.iter().map(|interaction| interaction.description.clone()).collect::<Vec<String>>()
+        };
+        expect!(descriptions(InteractionOrder::PactFile)).to(be_equal_to(vec![s!("c"), s!("a"), s!("b"), s!("d")]));
+        expect!(descriptions(InteractionOrder::Description)).to(be_equal_to(vec![s!("a"), s!("b"), s!("c"), s!("d")]));
+        expect!(descriptions(InteractionOrder::ProviderState)).to(be_equal_to(vec![s!("b"), s!("c"), s!("d"), s!("a")]));
+    }
+
+    #[test]
+    fn walkdir_returns_the_pacts_from_the_sub_directories() {
+        let dir = env::temp_dir().join("pact_verifier_walkdir_test");
+        fs::remove_dir_all(&dir).unwrap_or(());
+        fs::create_dir_all(dir.join("b").join("c")).unwrap();
+        for file in &["a.json", "b/b.json", "b/c/c.json", "d.json"] {
+            fs::File::create(dir.join(file)).unwrap();
+        }
+        let result = walkdir(&dir, &|path: &Path| Ok(path.strip_prefix(&dir).unwrap().to_string_lossy().to_string()));
+        fs::remove_dir_all(&dir).unwrap_or(());
+        expect!(result.unwrap().into_iter().map(|pact| pact.unwrap()).collect::<Vec<String>>())
+            .to(be_equal_to(vec![s!("a.json"), s!("b/b.json"), s!("b/c/c.json"), s!("d.json")]));
+    }
 }
@@ -1,284 +1,328 @@
-use pact_matching::models::{Pact, OptionalBody};
+use pact_matching::models::{Pact, MessagePact};
+use pact_broker_client::{HALClient, HttpAuth, Link, PactBrokerError};
 use serde_json;
-use itertools::Itertools;
 use std::collections::HashMap;
-use hyper::client::*;
-use std::error::Error;
-use super::provider_client::join_paths;
-use hyper::header::{Accept, qitem, ContentType};
-use hyper::mime::{Mime, TopLevel, SubLevel};
-use provider_client::extract_body;
-use regex::{Regex, Captures};
-use hyper::Url;
-use hyper::status::StatusCode;
-
-fn is_true(object: &serde_json::Map<String, serde_json::Value>, field: &String) -> bool {
-    match object.get(field) {
-        Some(json) => match json {
-            &serde_json::Value::Bool(b) => b,
-            _ => false
-        },
-        None => false
-    }
-}
+use super::{PactVerificationResult, VerificationStatus};
 
-fn as_string(json: &serde_json::Value) -> String {
-    match json {
-        &serde_json::Value::String(ref s) => s.clone(),
-        _ => format!("{}", json)
-    }
+/// Link to publish the verification results of a pact fetched from the pact broker
+#[derive(Debug, Clone)]
+pub struct PublishResultsLink {
+    /// URL of the pact broker the pact was fetched from
+    pub broker_url: String,
+    /// Credentials for the pact broker
+    pub auth: Option<HttpAuth>,
+    /// The `pb:publish-verification-results` link from the pact
+    pub link: Link
 }
 
-fn content_type(response: &Response) -> String {
-    match response.headers.get::<ContentType>() {
-        Some(header) => format!("{}", header),
-        None => s!("text/plain")
-    }
+/// Information about a pact fetched from the pact broker
+#[derive(Debug, Clone)]
+pub struct BrokerPactInfo {
+    /// Link to publish the verification results of the pact
+    pub publish_link: Option<PublishResultsLink>,
+    /// If the pact is pending. Failures verifying a pending pact do not fail the verification
+    pub pending: bool,
+    /// If the pact is a work in progress pact. Work in progress pacts are always pending
+    pub wip: bool
 }
 
-fn json_content_type(response: &Response) -> bool {
-    match response.headers.get::<ContentType>() {
-        Some(header) => {
-            let &ContentType(ref mime) = header;
-            match mime.clone() {
-                Mime(TopLevel::Application, SubLevel::Json, _) => true,
-                Mime(TopLevel::Application, SubLevel::Ext(ext), _) => ext == "hal+json",
-                _ => false
-            }
-        },
-        None => false
-    }
+/// Selects the consumer versions of the pacts to verify, when fetching the pacts for verification
+/// from the pact broker
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsumerVersionSelector {
+    /// Name of the consumer
+    pub consumer: Option<String>,
+    /// Tag of the consumer versions
+    pub tag: Option<String>,
+    /// Tag to use if no versions have the tag
+    pub fallback_tag: Option<String>,
+    /// Only the latest version (for the tag, if set)
+    pub latest: Option<bool>,
+    /// Versions currently deployed to an environment
+    pub deployed: Option<bool>,
+    /// Versions currently released and supported in an environment
+    pub released: Option<bool>,
+    /// Environment the versions are deployed or released to
+    pub environment: Option<String>
 }
 
-fn find_entry(map: &serde_json::Map<String, serde_json::Value>, key: &String) -> Option<(String, serde_json::Value)> {
-    match map.keys().find(|k| k.to_lowercase() == key.to_lowercase() ) {
-        Some(k) => map.get(k).map(|v| (key.clone(), v.clone()) ),
-        None => None
+impl ConsumerVersionSelector {
+    /// Returns a selector that matches nothing
+    pub fn default() -> ConsumerVersionSelector {
+        ConsumerVersionSelector {
+            consumer: None,
+            tag: None,
+            fallback_tag: None,
+            latest: None,
+            deployed: None,
+            released: None,
+            environment: None
+        }
     }
-}
-
-#[derive(Debug, Clone)]
-pub enum PactBrokerError {
-    LinkError(String),
-    ContentError(String),
-    IoError(String),
-    NotFound(String),
-    UrlError(String)
-}
 
-impl PartialEq<String> for PactBrokerError {
-    fn eq(&self, other: &String) -> bool {
-        let message = match self {
-            &PactBrokerError::LinkError(ref s) => s.clone(),
-            &PactBrokerError::ContentError(ref s) => s.clone(),
-            &PactBrokerError::IoError(ref s) => s.clone(),
-            &PactBrokerError::NotFound(ref s) => s.clone(),
-            &PactBrokerError::UrlError(ref s) => s.clone()
-        };
-        message == *other
+    /// Parses a selector from its JSON form, as sent to the pact broker
+    pub fn from_json(json: &serde_json::Value) -> Result<ConsumerVersionSelector, String> {
+        match *json {
+            serde_json::Value::Object(ref map) => {
+                let string_value = |key: &str| -> Result<Option<String>, String> {
+                    match map.get(key) {
+                        Some(&serde_json::Value::String(ref s)) => Ok(Some(s.clone())),
+                        Some(&serde_json::Value::Null) | None => Ok(None),
+                        Some(value) => Err(format!("Expected '{}' to be a string, but was {}", key, value))
+                    }
+                };
+                let bool_value = |key: &str| -> Result<Option<bool>, String> {
+                    match map.get(key) {
+                        Some(&serde_json::Value::Bool(b)) => Ok(Some(b)),
+                        Some(&serde_json::Value::Null) | None => Ok(None),
+                        Some(value) => Err(format!("Expected '{}' to be a boolean, but was {}", key, value))
+                    }
+                };
+                Ok(ConsumerVersionSelector {
+                    consumer: try!(string_value("consumer")),
+                    tag: try!(string_value("tag")),
+                    fallback_tag: try!(string_value("fallbackTag")),
+                    latest: try!(bool_value("latest")),
+                    deployed: try!(bool_value("deployed")),
+                    released: try!(bool_value("released")),
+                    environment: try!(string_value("environment"))
+                })
+            },
+            _ => Err(format!("Expected a consumer version selector to be a JSON object, but was {}", json))
+        }
     }
-}
 
-impl <'a> PartialEq<&'a str> for PactBrokerError {
-    fn eq(&self, other: &&str) -> bool {
-        let message = match self {
-            &PactBrokerError::LinkError(ref s) => s.clone(),
-            &PactBrokerError::ContentError(ref s) => s.clone(),
-            &PactBrokerError::IoError(ref s) => s.clone(),
-            &PactBrokerError::NotFound(ref s) => s.clone(),
-            &PactBrokerError::UrlError(ref s) => s.clone()
-        };
-        message.as_str() == *other
+    /// Converts this selector to the JSON form sent to the pact broker
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut map = serde_json::Map::new();
+        for &(key, ref value) in &[("consumer", &self.consumer), ("tag", &self.tag),
+            ("fallbackTag", &self.fallback_tag), ("environment", &self.environment)] {
+            match **value {
+                Some(ref v) => { map.insert(s!(key), json!(v)); },
+                None => ()
+            }
+        }
+        for &(key, ref value) in &[("latest", &self.latest), ("deployed", &self.deployed), ("released", &self.released)] {
+            match **value {
+                Some(v) => { map.insert(s!(key), json!(v)); },
+                None => ()
+            }
+        }
+        serde_json::Value::Object(map)
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct Link {
-    name: String,
-    href: Option<String>,
-    templated: bool
+/// Options for the pending and work in progress pacts, when fetching the pacts for verification
+/// from the pact broker
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingPactOptions {
+    /// Include the pending status of the pacts
+    pub enable_pending: bool,
+    /// Include any work in progress pacts created since this date (ISO 8601 format)
+    pub include_wip_pacts_since: Option<String>,
+    /// Tags of the provider version, used by the pact broker to work out which pacts are pending
+    pub provider_tags: Vec<String>
 }
 
-impl Link {
-
-    pub fn from_json(link: &String, link_data: &serde_json::Map<String, serde_json::Value>) -> Link {
-        Link {
-            name: link.clone(),
-            href: find_entry(link_data, &s!("href")).map(|(_, href)| as_string(&href)),
-            templated: is_true(link_data, &s!("templated"))
+impl PendingPactOptions {
+    /// Returns the options with pending and work in progress pacts disabled
+    pub fn default() -> PendingPactOptions {
+        PendingPactOptions {
+            enable_pending: false,
+            include_wip_pacts_since: None,
+            provider_tags: vec![]
         }
     }
+}
 
+/// Which pacts to fetch from the pact broker
+#[derive(Debug, Clone)]
+pub enum PactSelection {
+    /// The latest pact for each consumer (`pb:latest-provider-pacts`)
+    Latest,
+    /// The latest pact for each consumer with each of the tags (`pb:latest-provider-pacts-with-tag`)
+    LatestWithTags(Vec<String>),
+    /// The pacts matching the consumer version selectors (`pb:provider-pacts-for-verification`),
+    /// including any pending and work in progress pacts
+    Selectors(Vec<ConsumerVersionSelector>, PendingPactOptions)
 }
 
-pub struct HALClient {
-    url: String,
-    path_info: Option<serde_json::Value>
+pub fn fetch_pacts_from_broker(broker_url: &String, provider_name: &String, selection: &PactSelection, auth: &Option<HttpAuth>) -> Result<Vec<Result<(Pact, BrokerPactInfo), PactBrokerError>>, PactBrokerError> {
+    fetch_pact_documents(broker_url, provider_name, selection, auth).map(|pacts| pacts.into_iter()
+        .map(|result| result.map(|(href, pact_json, info)| (Pact::from_json(&href, &pact_json), info)))
+        .collect())
 }
 
-impl HALClient {
+pub fn fetch_message_pacts_from_broker(broker_url: &String, provider_name: &String, selection: &PactSelection, auth: &Option<HttpAuth>) -> Result<Vec<Result<(MessagePact, BrokerPactInfo), PactBrokerError>>, PactBrokerError> {
+    fetch_pact_documents(broker_url, provider_name, selection, auth).map(|pacts| pacts.into_iter()
+        .map(|result| result.map(|(href, pact_json, info)| (MessagePact::from_json(&href, &pact_json), info)))
+        .collect())
+}
 
-    fn default() -> HALClient {
-        HALClient{ url: s!(""), path_info: None }
+fn publish_results_link(broker_url: &String, auth: &Option<HttpAuth>, pact_json: &serde_json::Value) -> Option<PublishResultsLink> {
+    match pact_json.get("_links") {
+        Some(links) => match links.get("pb:publish-verification-results") {
+            Some(&serde_json::Value::Object(ref link_data)) => Some(PublishResultsLink {
+                broker_url: broker_url.clone(),
+                auth: auth.clone(),
+                link: Link::from_json(&s!("pb:publish-verification-results"), link_data)
+            }),
+            _ => None
+        },
+        None => None
     }
+}
 
-    fn navigate(&mut self, link: &str, template_values: &HashMap<String, String>) -> Result<serde_json::Value, PactBrokerError> {
-        if self.path_info.is_none() {
-            self.path_info = Some(try!(self.fetch("/")));
-        }
-        self.path_info = Some(try!(self.fetch_link(link, template_values)));
-        Ok(self.path_info.clone().unwrap())
-    }
+/// Publishes the result of verifying a pact to the pact broker, using the
+/// `pb:publish-verification-results` link from the pact
+pub fn publish_verification_results(link: &PublishResultsLink, result: &PactVerificationResult,
+    provider_version: &String, build_url: &Option<String>) -> Result<serde_json::Value, PactBrokerError> {
+    let client = HALClient::with_url(&link.broker_url, link.auth.clone());
+    let body = verification_results_json(result, provider_version, build_url);
+    debug!("Publishing verification results {} to pact broker. URL: '{}'", body, link.broker_url);
+    client.post_json(&link.link, &hashmap!{}, &body.to_string())
+}
 
-    fn find_link(&self, link: &str) -> Result<Link, PactBrokerError> {
-        match self.path_info {
-            None => Err(PactBrokerError::LinkError(format!("No previous resource has been fetched from the pact broker. URL: '{}', LINK: '{}'",
-                self.url, link))),
-            Some(ref json) => match json.get("_links") {
-                Some(json) => match json.get(link) {
-                    Some(link_data) => link_data.as_object()
-                        .map(|link_data| Link::from_json(&s!(link), &link_data))
-                        .ok_or(PactBrokerError::LinkError(format!("Link is malformed, expcted an object but got {}. URL: '{}', LINK: '{}'",
-                            link_data, self.url, link))),
-                    None => Err(PactBrokerError::LinkError(format!("Link '{}' was not found in the response, only the following links where found: {:?}. URL: '{}', LINK: '{}'",
-                        link, json.as_object().unwrap_or(&json!({}).as_object().unwrap()).keys().join(", "), self.url, link)))
-                },
-                None => Err(PactBrokerError::LinkError(format!("Expected a HAL+JSON response from the pact broker, but got a response with no '_links'. URL: '{}', LINK: '{}'",
-                    self.url, link)))
-            }
-        }
-    }
+fn verification_results_json(result: &PactVerificationResult, provider_version: &String,
+    build_url: &Option<String>) -> serde_json::Value {
+    let mut json = json!({
+        s!("success"): json!(result.status == VerificationStatus::Passed),
+        s!("providerApplicationVersion"): json!(provider_version),
+        s!("testResults"): serde_json::Value::Array(result.interactions.iter().map(|i| i.to_json()).collect())
+    });
+    match *build_url {
+        Some(ref url) => { json.as_object_mut().unwrap().insert(s!("buildUrl"), json!(url)); },
+        None => ()
+    }
+    json
+}
 
-    fn fetch_link(&self, link: &str, template_values: &HashMap<String, String>) -> Result<serde_json::Value, PactBrokerError> {
-        let link_data = try!(self.find_link(link));
-        self.fetch_url(&link_data, template_values)
-    }
+#[derive(Debug, Clone)]
+struct PactLink {
+    link: Link,
+    pending: bool,
+    wip: bool
+}
 
-    fn fetch_url(&self, link: &Link, template_values: &HashMap<String, String>) -> Result<serde_json::Value, PactBrokerError> {
-        let link_url = try!(if link.templated {
-            debug!("Link URL is templated");
-            self.parse_link_url(&link, template_values)
-        } else {
-            link.href.clone().ok_or(
-                PactBrokerError::LinkError(format!("Link is malformed, there is no href. URL: '{}', LINK: '{}'",
-                    self.url, link.name)))
-        });
-        let base = try!(Url::parse(&self.url).map_err(|err| PactBrokerError::UrlError(format!("{}", err.description()))));
-        let url = try!(base.join(&link_url).map_err(|err| PactBrokerError::UrlError(format!("{}", err.description()))));
-        self.fetch(&url.path())
+impl PactLink {
+    fn new(link: Link) -> PactLink {
+        PactLink { link: link, pending: false, wip: false }
     }
+}
 
-    fn fetch(&self, path: &str) -> Result<serde_json::Value, PactBrokerError> {
-        debug!("Fetching path '{}' from pact broker", path);
-        let client = Client::new();
-        let res = client.get(&join_paths(&self.url.clone(), s!(path)))
-            .header(Accept(vec![
-                qitem(Mime(TopLevel::Application, SubLevel::Ext(s!("hal+json")), vec![])),
-                qitem(Mime(TopLevel::Application, SubLevel::Json, vec![]))
-            ]))
-            .send();
-        match res {
-            Ok(mut response) => {
-                if response.status.is_success() {
-                    if json_content_type(&response) {
-                        match extract_body(&mut response) {
-                            OptionalBody::Present(body) => serde_json::from_str(&body)
-                                    .map_err(|err| PactBrokerError::ContentError(format!("Did not get a valid HAL response body from pact broker path '{}' - {}: {}. URL: '{}'",
-                                                                                         path, err.description(), err, self.url))),
-                            _ => Err(PactBrokerError::ContentError(format!("Did not get a valid HAL response body from pact broker path '{}'. URL: '{}'",
-                                                                          path, self.url)))
+fn fetch_pact_documents(broker_url: &String, provider_name: &String, selection: &PactSelection, auth: &Option<HttpAuth>) -> Result<Vec<Result<(String, serde_json::Value, BrokerPactInfo), PactBrokerError>>, PactBrokerError> {
+    let mut client = HALClient::with_url(broker_url, auth.clone());
+    let template_values = hashmap!{ s!("provider") => provider_name.clone() };
+    let pact_links = try!(match *selection {
+        PactSelection::Latest => latest_pact_links(&mut client, "pb:latest-provider-pacts", &template_values)
+            .map_err(|err| match err {
+                PactBrokerError::NotFound(_) => PactBrokerError::NotFound(
+                    format!("No pacts for provider '{}' where found in the pact broker. URL: '{}'",
+                        provider_name, broker_url)),
+                _ => err
+            })
+            .map(|links| links.into_iter().map(PactLink::new).collect()),
+        PactSelection::LatestWithTags(ref tags) => latest_pact_links_with_tags(&mut client, provider_name, tags)
+            .map(|links| links.into_iter().map(PactLink::new).collect()),
+        PactSelection::Selectors(ref selectors, ref options) => pacts_for_verification_links(&mut client,
+            provider_name, selectors, options)
+    });
+    debug!("Pact links = {:?}", pact_links);
+    let pacts = pact_links.iter().map(|pact_link| match pact_link.link.clone().href {
+        Some(href) => client.fetch_url(&pact_link.link, &template_values).map(|pact_json| {
+            let info = BrokerPactInfo {
+                publish_link: publish_results_link(broker_url, auth, &pact_json),
+                pending: pact_link.pending,
+                wip: pact_link.wip
+            };
+            (href, pact_json, info)
+        }),
+        None => Err(PactBrokerError::LinkError(format!("Expected a HAL+JSON response from the pact broker, but got a link with no HREF. URL: '{}', LINK: '{:?}'",
+            client.url, pact_link.link)))
+    }).collect();
+    debug!("pacts = {:?}", pacts);
+    Ok(pacts)
+}
 
-                        }
-                    } else {
-                        Err(PactBrokerError::ContentError(format!("Did not get a HAL response from pact broker path '{}', content type is '{}'. URL: '{}'",
-                            path, content_type(&response), self.url)))
-                    }
-                } else {
-                    if response.status == StatusCode::NotFound {
-                        Err(PactBrokerError::NotFound(format!("Request to pact broker path '{}' failed: {}. URL: '{}'", path,
-                            response.status, self.url)))
-                    } else {
-                        Err(PactBrokerError::IoError(format!("Request to pact broker path '{}' failed: {}. URL: '{}'", path,
-                            response.status, self.url)))
-                    }
+fn latest_pact_links(client: &mut HALClient, link: &str, template_values: &HashMap<String, String>) -> Result<Vec<Link>, PactBrokerError> {
+    client.path_info = None;
+    try!(client.navigate(link, template_values));
+    client.iter_links_across_pages(s!("pacts"))
+}
+
+fn latest_pact_links_with_tags(client: &mut HALClient, provider_name: &String, tags: &Vec<String>) -> Result<Vec<Link>, PactBrokerError> {
+    let mut pact_links: Vec<Link> = vec![];
+    for tag in tags {
+        let template_values = hashmap!{ s!("provider") => provider_name.clone(), s!("tag") => tag.clone() };
+        match latest_pact_links(client, "pb:latest-provider-pacts-with-tag", &template_values) {
+            Ok(links) => for link in links {
+                if !pact_links.iter().any(|l| l.href == link.href) {
+                    pact_links.push(link);
                 }
             },
-            Err(err) => Err(PactBrokerError::IoError(format!("Failed to access pact broker path '{}' - {:?}. URL: '{}'",
-                path, err.description(), self.url)))
+            Err(PactBrokerError::NotFound(_)) => warn!("No pacts for provider '{}' with tag '{}' where found in the pact broker",
+                provider_name, tag),
+            Err(err) => return Err(err)
         }
     }
-
-    fn parse_link_url(&self, link: &Link, values: &HashMap<String, String>) -> Result<String, PactBrokerError> {
-        match link.href {
-            Some(ref href) => {
-                debug!("templated URL = {}", href);
-                let re = Regex::new(r"\{(\w+)\}").unwrap();
-                let final_url = re.replace_all(href, |caps: &Captures| {
-                    let lookup = caps.at(1).unwrap();
-                    debug!("Looking up value for key '{}'", lookup);
-                    match values.get(lookup) {
-                        Some(val) => val.clone(),
-                        None => {
-                            warn!("No value was found for key '{}', mapped values are {:?}",
-                                lookup, values);
-                            format!("{{{}}}", lookup)
-                        }
-                    }
-                });
-                debug!("final URL = {}", final_url);
-                Ok(final_url)
-            },
-            None => Err(PactBrokerError::LinkError(format!("Expected a HAL+JSON response from the pact broker, but got a link with no HREF. URL: '{}', LINK: '{}'",
-                self.url, link.name)))
-        }
+    if pact_links.is_empty() {
+        Err(PactBrokerError::NotFound(format!("No pacts for provider '{}' with tags {:?} where found in the pact broker. URL: '{}'",
+            provider_name, tags, client.url)))
+    } else {
+        Ok(pact_links)
     }
+}
 
-    fn iter_links(&self, link: String) -> Result<Vec<Link>, PactBrokerError> {
-        match self.path_info {
-            None => Err(PactBrokerError::LinkError(format!("No previous resource has been fetched from the pact broker. URL: '{}', LINK: '{}'",
-                self.url, link))),
-            Some(ref json) => match json.get("_links") {
-                Some(json) => match json.get(&link) {
-                    Some(link_data) => link_data.as_array()
-                        .map(|link_data| link_data.iter().map(|link_json| match link_json {
-                            &serde_json::Value::Object(ref data) => Link::from_json(&link, data),
-                            &serde_json::Value::String(ref s) => Link { name: link.clone(), href: Some(s.clone()), templated: false },
-                            _ => Link { name: link.clone(), href: Some(link_json.to_string()), templated: false }
-                        }).collect())
-                        .ok_or(PactBrokerError::LinkError(format!("Link is malformed, expcted an object but got {}. URL: '{}', LINK: '{}'",
-                            link_data, self.url, link))),
-                    None => Err(PactBrokerError::LinkError(format!("Link '{}' was not found in the response, only the following links where found: {:?}. URL: '{}', LINK: '{}'",
-                        link, json.as_object().unwrap_or(&json!({}).as_object().unwrap()).keys().join(", "), self.url, link)))
-                },
-                None => Err(PactBrokerError::LinkError(format!("Expected a HAL+JSON response from the pact broker, but got a response with no '_links'. URL: '{}', LINK: '{}'",
-                    self.url, link)))
-            }
+fn pacts_for_verification_body(selectors: &Vec<ConsumerVersionSelector>, options: &PendingPactOptions) -> serde_json::Value {
+    let mut body = json!({
+        s!("consumerVersionSelectors"): serde_json::Value::Array(selectors.iter().map(|s| s.to_json()).collect())
+    });
+    {
+        let map = body.as_object_mut().unwrap();
+        if !options.provider_tags.is_empty() {
+            map.insert(s!("providerVersionTags"), json!(options.provider_tags));
+        }
+        if options.enable_pending {
+            map.insert(s!("includePendingStatus"), json!(true));
+        }
+        match options.include_wip_pacts_since {
+            Some(ref since) => { map.insert(s!("includeWipPactsSince"), json!(since)); },
+            None => ()
         }
     }
+    body
 }
 
-pub fn fetch_pacts_from_broker(broker_url: &String, provider_name: &String) -> Result<Vec<Result<Pact, PactBrokerError>>, PactBrokerError> {
-    let mut client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
-    let template_values = hashmap!{ s!("provider") => provider_name.clone() };
-    match client.navigate("pb:latest-provider-pacts", &template_values) {
-        Ok(_) => {
-            let pact_links = try!(client.iter_links(s!("pacts")));
-            debug!("Pact links = {:?}", pact_links);
-            let pacts = pact_links.iter().map(|link| match link.clone().href {
-                Some(_) => client.fetch_url(&link, &template_values).map(|pact_json| Pact::from_json(&link.href.clone().unwrap(), &pact_json)),
-                None => Err(PactBrokerError::LinkError(format!("Expected a HAL+JSON response from the pact broker, but got a link with no HREF. URL: '{}', LINK: '{:?}'",
-                    client.url, link)))
-            }).collect();
-            debug!("pacts = {:?}", pacts);
-            Ok(pacts)
+fn pacts_for_verification_links(client: &mut HALClient, provider_name: &String, selectors: &Vec<ConsumerVersionSelector>,
+    options: &PendingPactOptions) -> Result<Vec<PactLink>, PactBrokerError> {
+    client.path_info = Some(try!(client.fetch("/")));
+    let link = try!(client.find_link("pb:provider-pacts-for-verification"));
+    let body = pacts_for_verification_body(selectors, options);
+    let response = try!(client.post_json(&link, &hashmap!{ s!("provider") => provider_name.clone() }, &body.to_string()));
+    match response.get("_embedded").and_then(|embedded| embedded.get("pacts")) {
+        Some(&serde_json::Value::Array(ref pacts)) => {
+            if pacts.is_empty() {
+                return Err(PactBrokerError::NotFound(format!("No pacts for provider '{}' matching the consumer version selectors where found in the pact broker. URL: '{}'",
+                    provider_name, client.url)));
+            }
+            pacts.iter().map(|pact| match pact.get("_links").and_then(|links| links.get("self")) {
+                Some(&serde_json::Value::Object(ref link_data)) => {
+                    let properties = pact.get("verificationProperties");
+                    let flag = |name: &str| properties.and_then(|p| p.get(name))
+                        .and_then(|value| value.as_bool()).unwrap_or(false);
+                    let wip = flag("wip");
+                    Ok(PactLink {
+                        link: Link::from_json(&s!("self"), link_data),
+                        pending: wip || flag("pending"),
+                        wip: wip
+                    })
+                },
+                _ => Err(PactBrokerError::LinkError(format!("Expected the pact for verification to have a 'self' link, but got {}. URL: '{}'",
+                    pact, client.url)))
+            }).collect()
         },
-        Err(err) => match err {
-            PactBrokerError::NotFound(_) => Err(
-                PactBrokerError::NotFound(
-                    format!("No pacts for provider '{}' where found in the pact broker. URL: '{}'",
-                        provider_name, broker_url))),
-            _ => Err(err)
-        }
+        _ => Err(PactBrokerError::ContentError(format!("Expected the pacts for verification to be embedded in the response, but got {}. URL: '{}'",
+            response, client.url)))
     }
 }
 
@@ -286,411 +330,406 @@ pub fn fetch_pacts_from_broker(broker_url: &String, provider_name: &String) -> R
 mod tests {
     use expectest::prelude::*;
     use super::*;
-    use super::{content_type, json_content_type};
     use pact_consumer::*;
     use env_logger::*;
     use pact_matching::models::{Pact, OptionalBody, Consumer, Provider, Interaction};
-    use hyper::Url;
-    use hyper::client::response::Response;
-    use std::io::{self, Write, Read};
-    use hyper::http::{
-        RawStatus,
-        HttpMessage,
-        RequestHead,
-        ResponseHead,
-    };
-    use hyper::error::Error;
-    use hyper::version::HttpVersion;
     use std::time::Duration;
-    use hyper::header::{Headers, ContentType};
-    use std::borrow::Cow;
-    use hyper::mime::{Mime, TopLevel, SubLevel, Attr, Value};
+    use {PactVerificationResult, InteractionVerificationResult, VerificationDetails, VerificationStatus};
 
     #[test]
-    fn fetch_returns_an_error_if_there_is_no_pact_broker() {
-        let client = HALClient{ url: s!("http://idont.exist:6666"), .. HALClient::default() };
-        expect!(client.fetch(&s!("/"))).to(be_err());
-    }
+    fn fetch_pacts_from_broker_returns_empty_list_if_there_are_no_pacts() {
+        init().unwrap_or(());
 
-    #[test]
-    fn fetch_returns_an_error_if_it_does_not_get_a_success_response() {
         let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
             .has_pact_with(s!("PactBroker"))
-            .given(s!("the pact broker has a valid pact"))
-            .upon_receiving(s!("a request to a non-existant path"))
-                .path(s!("/hello"))
-            .will_respond_with()
-                .status(404)
-            .build();
-
-        let result = pact_runner.run(&|broker_url| {
-            let client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
-            let result = client.fetch(&s!("/hello"));
-            expect!(result).to(be_err().value(format!("Request to pact broker path \'/hello\' failed: 404 Not Found. URL: '{}'",
-                broker_url)));
-            Ok(())
-        });
-        expect!(result).to(be_equal_to(VerificationResult::PactVerified));
-    }
-
-    #[test]
-    fn fetch_returns_an_error_if_it_does_not_get_a_hal_response() {
-        let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
-            .has_pact_with(s!("PactBrokerStub"))
-            .upon_receiving(s!("a request to a non-json resource"))
-                .path(s!("/nonjson"))
+            .upon_receiving(s!("a request to the pact broker root"))
+                .path(s!("/"))
+                .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
             .will_respond_with()
                 .status(200)
-                .headers(hashmap!{ s!("Content-Type") => s!("text/html") })
-                .body(OptionalBody::Present(s!("<html></html>")))
+                .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
+                .body(OptionalBody::Present(s!(r#"
+                    {
+                        "_links":{
+                            "pb:latest-provider-pacts":{"href":"http://localhost/pacts/provider/{provider}/latest","templated":true}
+                        }
+                    }
+                "#).into()))
+            .given(s!("There are no pacts in the pact broker"))
+            .upon_receiving(s!("a request for a providers pacts"))
+                .path(s!("/pacts/provider/sad_provider/latest"))
+                .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
+            .will_respond_with()
+                .status(404)
             .build();
 
         let result = pact_runner.run(&|broker_url| {
-            let client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
-            let result = client.fetch(&s!("/nonjson"));
-            expect!(result).to(be_err().value(format!("Did not get a HAL response from pact broker path \'/nonjson\', content type is 'text/html'. URL: '{}'",
+            let result = fetch_pacts_from_broker(&broker_url, &s!("sad_provider"), &PactSelection::Latest, &None);
+            expect!(result).to(be_err().value(format!("No pacts for provider 'sad_provider' where found in the pact broker. URL: '{}'",
                 broker_url)));
             Ok(())
         });
         expect!(result).to(be_equal_to(VerificationResult::PactVerified));
     }
 
-    #[derive(Debug, Clone)]
-    struct MockHttpMessage {
-        pub body: Option<String>,
-        pub headers: Headers,
-        pub status: RawStatus
-    }
-
-    impl HttpMessage for MockHttpMessage {
-
-        fn set_outgoing(&mut self, _head: RequestHead) -> Result<RequestHead, Error> {
-            Err(Error::Io(io::Error::new(io::ErrorKind::Other, "Not supported with MockHttpMessage")))
-        }
-
-        fn get_incoming(&mut self) -> Result<ResponseHead, Error> {
-            Ok(ResponseHead {
-                headers: self.headers.clone(),
-                raw_status: self.status.clone(),
-                version: HttpVersion::Http11,
-            })
-        }
-
-        fn has_body(&self) -> bool {
-            self.body.is_some()
-        }
-
-        fn set_read_timeout(&self, _dur: Option<Duration>) -> io::Result<()> {
-            Ok(())
-        }
-
-        fn set_write_timeout(&self, _dur: Option<Duration>) -> io::Result<()> {
-            Ok(())
-        }
-
-        fn close_connection(&mut self) -> Result<(), Error> {
-            Ok(())
-        }
-    }
-
-    impl Write for MockHttpMessage {
-
-        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
-            Err(io::Error::new(io::ErrorKind::Other, "Not supported with MockHttpMessage"))
-        }
-
-        fn flush(&mut self) -> io::Result<()> {
-            Err(io::Error::new(io::ErrorKind::Other, "Not supported with MockHttpMessage"))
-        }
-
-    }
-
-    impl Read for MockHttpMessage {
-
-        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
-            Err(io::Error::new(io::ErrorKind::Other, "Not supported with MockHttpMessage"))
-        }
-
-    }
-
     #[test]
-    fn content_type_test() {
-        let mut message = MockHttpMessage {
-            body: None,
-            status: RawStatus(200, Cow::Owned(s!("OK"))),
-            headers: Headers::new()
-        };
-        let url = Url::parse("http://localhost").unwrap();
-
-        let response = Response::with_message(url.clone(), Box::new(message.clone())).unwrap();
-        expect!(content_type(&response)).to(be_equal_to(s!("text/plain")));
-
-        message.headers.set::<ContentType>(
-            ContentType(Mime(TopLevel::Application, SubLevel::Ext(s!("hal+json")),
-                vec![(Attr::Charset, Value::Utf8)])));
-        let response = Response::with_message(url.clone(), Box::new(message.clone())).unwrap();
-        expect!(content_type(&response)).to(be_equal_to(s!("application/hal+json; charset=utf-8")));
-    }
-
-    #[test]
-    fn json_content_type_test() {
-        let mut message = MockHttpMessage {
-            body: None,
-            status: RawStatus(200, Cow::Owned(s!("OK"))),
-            headers: Headers::new()
-        };
-        let url = Url::parse("http://localhost").unwrap();
-
-        let response = Response::with_message(url.clone(), Box::new(message.clone())).unwrap();
-        expect!(json_content_type(&response)).to(be_false());
-
-        message.headers.set::<ContentType>(
-            ContentType(Mime(TopLevel::Application, SubLevel::Json, vec![])));
-        let response = Response::with_message(url.clone(), Box::new(message.clone())).unwrap();
-        expect!(json_content_type(&response)).to(be_true());
-
-        message.headers.set::<ContentType>(
-            ContentType(Mime(TopLevel::Application, SubLevel::Ext(s!("hal+json")),
-                vec![(Attr::Charset, Value::Utf8)])));
-        let response = Response::with_message(url.clone(), Box::new(message.clone())).unwrap();
-        expect!(json_content_type(&response)).to(be_true());
-    }
+    fn fetch_pacts_from_broker_returns_a_list_of_pacts() {
+        init().unwrap_or(());
 
-    #[test]
-    fn fetch_returns_an_error_if_it_does_not_get_a_valid_hal_response() {
+        let pact = Pact { consumer: Consumer { name: s!("Consumer") },
+            provider: Provider { name: s!("happy_provider") },
+            .. Pact::default() }
+            .to_json().to_string();
+        let pact2 = Pact { consumer: Consumer { name: s!("Consumer2") },
+            provider: Provider { name: s!("happy_provider") },
+            interactions: vec![ Interaction { description: s!("a request friends"), .. Interaction::default() } ],
+            .. Pact::default() }
+            .to_json().to_string();
         let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
-            .has_pact_with(s!("PactBrokerStub"))
-            .upon_receiving(s!("a request to a non-hal resource"))
-                .path(s!("/nonhal"))
+            .has_pact_with(s!("PactBroker"))
+            .upon_receiving(s!("a request to the pact broker root"))
+                .path(s!("/"))
+                .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
             .will_respond_with()
                 .status(200)
                 .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
-            .upon_receiving(s!("a request to a non-hal resource 2"))
-                .path(s!("/nonhal2"))
+                .body(OptionalBody::Present(s!(r#"
+                    {
+                        "_links":{
+                            "pb:latest-provider-pacts":{"href":"http://localhost/pacts/provider/{provider}/latest","templated":true}
+                        }
+                    }
+                "#).into()))
+            .given(s!("There are two pacts in the pact broker"))
+            .upon_receiving(s!("a request for a providers pacts"))
+                .path(s!("/pacts/provider/happy_provider/latest"))
+                .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
             .will_respond_with()
                 .status(200)
                 .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
-                .body(OptionalBody::Present(s!("<html>This is not JSON</html>")))
+                .body(OptionalBody::Present(s!(r#"
+                    {
+                        "_links":{
+                            "pacts":[
+                                {"href":"http://localhost/pacts/provider/happy_provider/consumer/Consumer/version/1.0.0"},
+                                {"href":"http://localhost/pacts/provider/happy_provider/consumer/Consumer2/version/1.0.0"}
+                            ]
+                        }
+                    }
+                "#).into()))
+            .given(s!("There are two pacts in the pact broker"))
+            .upon_receiving(s!("a request for the first provider pact"))
+                .path(s!("/pacts/provider/happy_provider/consumer/Consumer/version/1.0.0"))
+                .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
+            .will_respond_with()
+                .status(200)
+                .headers(hashmap!{ s!("Content-Type") => s!("application/json") })
+                .body(OptionalBody::Present(pact.clone().into()))
+            .given(s!("There are two pacts in the pact broker"))
+            .upon_receiving(s!("a request for the second provider pact"))
+                .path(s!("/pacts/provider/happy_provider/consumer/Consumer2/version/1.0.0"))
+                .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
+            .will_respond_with()
+                .status(200)
+                .headers(hashmap!{ s!("Content-Type") => s!("application/json") })
+                .body(OptionalBody::Present(pact2.clone().into()))
             .build();
 
         let result = pact_runner.run(&|broker_url| {
-            let client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
-            let result = client.fetch(&s!("/nonhal"));
-            expect!(result).to(be_err().value(format!("Did not get a valid HAL response body from pact broker path \'/nonhal\'. URL: '{}'",
-                broker_url)));
-            let result = client.fetch(&s!("/nonhal2"));
-            expect!(result).to(be_err().value(format!("Did not get a valid HAL response body from pact broker path \'/nonhal2\' - JSON error: expected value at line 1 column 1. URL: '{}'",
-                broker_url)));
+            let result = fetch_pacts_from_broker(&broker_url, &s!("happy_provider"), &PactSelection::Latest, &None);
+            expect!(result.clone()).to(be_ok());
+            let pacts = result.unwrap();
+            expect!(pacts.len()).to(be_equal_to(2));
+            for pact in pacts {
+                expect!(pact).to(be_ok());
+            }
             Ok(())
         });
         expect!(result).to(be_equal_to(VerificationResult::PactVerified));
     }
 
-    #[test]
-    fn parse_link_url_returns_error_if_there_is_no_href() {
-        let client = HALClient::default();
-        let link = Link { name: s!("link"), href: None, templated: false };
-        expect!(client.parse_link_url(&link, &hashmap!{})).to(be_err().value(
-            "Expected a HAL+JSON response from the pact broker, but got a link with no HREF. URL: '', LINK: 'link'"));
+    fn pact_result() -> PactVerificationResult {
+        PactVerificationResult::new(s!("Consumer"), s!("happy_provider"), Duration::from_millis(100), vec![
+            InteractionVerificationResult {
+                index: 0,
+                description: s!("a request friends"),
+                provider_states: vec![],
+                status: VerificationStatus::Passed,
+                duration: Duration::from_millis(50),
+                mismatches: vec![],
+                error: None,
+                details: VerificationDetails::Response(::pact_matching::models::Response::default_response(), None)
+            }
+        ])
     }
 
     #[test]
-    fn parse_link_url_replaces_all_tokens_in_href() {
-        let client = HALClient::default();
-        let values = hashmap!{ s!("valA") => s!("A"), s!("valB") => s!("B") };
-
-        let link = Link { name: s!("link"), href: Some(s!("http://localhost")), templated: false };
-        expect!(client.parse_link_url(&link, &values)).to(be_ok().value("http://localhost"));
-
-        let link = Link { name: s!("link"), href: Some(s!("http://{valA}/{valB}")), templated: false };
-        expect!(client.parse_link_url(&link, &values)).to(be_ok().value("http://A/B"));
-
-        let link = Link { name: s!("link"), href: Some(s!("http://{valA}/{valC}")), templated: false };
-        expect!(client.parse_link_url(&link, &values)).to(be_ok().value("http://A/{valC}"));
+    fn publish_results_link_returns_the_link_from_the_pact_json() {
+        let pact_json = json!({
+            "_links": {
+                "pb:publish-verification-results": { "href": "http://localhost/pacts/provider/happy_provider/consumer/Consumer/pact-version/1234/verification-results" }
+            }
+        });
+        let link = publish_results_link(&s!("http://localhost"), &None, &pact_json);
+        expect!(link.clone()).to(be_some());
+        expect!(link.unwrap().link.href).to(be_some().value(s!("http://localhost/pacts/provider/happy_provider/consumer/Consumer/pact-version/1234/verification-results")));
+        expect!(publish_results_link(&s!("http://localhost"), &None, &json!({ "_links": {} }))).to(be_none());
+        expect!(publish_results_link(&s!("http://localhost"), &None, &json!({}))).to(be_none());
     }
 
     #[test]
-    fn fetch_link_returns_an_error_if_a_previous_resource_has_not_been_fetched() {
-        let client = HALClient{ url: s!("http://localhost"), .. HALClient::default() };
-        let result = client.fetch_link(&s!("anything_will_do"), &hashmap!{});
-        expect!(result).to(be_err().value(s!("No previous resource has been fetched from the pact broker. URL: 'http://localhost', LINK: 'anything_will_do'")));
+    fn verification_results_json_includes_the_build_url_if_provided() {
+        expect!(verification_results_json(&pact_result(), &s!("1.0.0"), &None).to_string()).to(be_equal_to(
+            s!(r#"{"providerApplicationVersion":"1.0.0","success":true,"testResults":[{"description":"a request friends","durationMs":50,"index":0,"mismatches":[],"providerStates":[],"status":"passed"}]}"#)));
+        expect!(verification_results_json(&pact_result(), &s!("1.0.0"), &Some(s!("http://ci/build/1"))).get("buildUrl"))
+            .to(be_some().value(&json!("http://ci/build/1")));
     }
 
     #[test]
-    fn fetch_link_returns_an_error_if_the_previous_resource_was_not_hal() {
+    fn publish_verification_results_posts_the_results_to_the_link() {
         init().unwrap_or(());
+
         let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
-            .has_pact_with(s!("PactBrokerStub"))
-                .upon_receiving(s!("a request to a non-hal json resource"))
-                .path(s!("/"))
+            .has_pact_with(s!("PactBroker"))
+            .given(s!("There is a pact between Consumer and happy_provider"))
+            .upon_receiving(s!("a request to publish verification results"))
+                .method(s!("POST"))
+                .path(s!("/pacts/provider/happy_provider/consumer/Consumer/pact-version/1234/verification-results"))
+                .headers(hashmap!{ s!("Content-Type") => s!("application/json") })
+                .body(OptionalBody::Present(s!(r#"{"buildUrl":"http://ci/build/1","providerApplicationVersion":"1.0.0","success":true,"testResults":[{"description":"a request friends","durationMs":50,"index":0,"mismatches":[],"providerStates":[],"status":"passed"}]}"#).into()))
             .will_respond_with()
-                .status(200)
+                .status(201)
                 .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
-                .body(OptionalBody::Present(s!("{}")))
+                .body(OptionalBody::Present(s!(r#"{"success": true, "providerApplicationVersion": "1.0.0", "_links": {}}"#).into()))
             .build();
 
         let result = pact_runner.run(&|broker_url| {
-            let mut client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
-            let result = client.fetch(&s!("/"));
-            expect!(result.clone()).to(be_ok());
-            client.path_info = result.ok();
-            let result = client.fetch_link(&s!("hal2"), &hashmap!{});
-            expect!(result).to(be_err().value(format!("Expected a HAL+JSON response from the pact broker, but got a response with no '_links'. URL: '{}', LINK: 'hal2'",
-                broker_url)));
+            let link = PublishResultsLink {
+                broker_url: broker_url.clone(),
+                auth: None,
+                link: Link { name: s!("pb:publish-verification-results"),
+                    href: Some(format!("{}/pacts/provider/happy_provider/consumer/Consumer/pact-version/1234/verification-results", broker_url)),
+                    templated: false }
+            };
+            let result = publish_verification_results(&link, &pact_result(), &s!("1.0.0"),
+                &Some(s!("http://ci/build/1")));
+            expect!(result).to(be_ok());
             Ok(())
         });
         expect!(result).to(be_equal_to(VerificationResult::PactVerified));
     }
 
     #[test]
-    fn fetch_link_returns_an_error_if_the_previous_resource_links_are_not_correctly_formed() {
+    fn fetch_pacts_from_broker_returns_an_auth_error_if_the_credentials_are_rejected() {
         init().unwrap_or(());
+
         let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
-            .has_pact_with(s!("PactBrokerStub"))
-                .upon_receiving(s!("a request to a hal resource with invalid links"))
+            .has_pact_with(s!("PactBroker"))
+            .given(s!("The pact broker requires authentication"))
+            .upon_receiving(s!("a request to the pact broker root with invalid credentials"))
                 .path(s!("/"))
+                .headers(hashmap!{
+                    s!("Accept") => s!("application/hal+json, application/json"),
+                    s!("Authorization") => s!("Bearer not-a-valid-token")
+                })
             .will_respond_with()
-                .status(200)
-                .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
-                .body(OptionalBody::Present(s!("{\"_links\":[{\"next\":{\"href\":\"abc\"}},{\"prev\":{\"href\":\"def\"}}]}")))
+                .status(401)
             .build();
 
         let result = pact_runner.run(&|broker_url| {
-            let mut client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
-            let result = client.fetch(&s!("/"));
-            expect!(result.clone()).to(be_ok());
-            client.path_info = result.ok();
-            let result = client.fetch_link(&s!("any"), &hashmap!{});
-            expect!(result).to(be_err().value(format!("Link 'any' was not found in the response, only the following links where found: \"\". URL: '{}', LINK: 'any'",
-                broker_url)));
+            let result = fetch_pacts_from_broker(&broker_url, &s!("happy_provider"), &PactSelection::Latest,
+                &Some(HttpAuth::Token(s!("not-a-valid-token"))));
+            match result {
+                Err(PactBrokerError::AuthError(_)) => (),
+                _ => panic!("Expected an auth error, but got {:?}", result)
+            }
             Ok(())
         });
         expect!(result).to(be_equal_to(VerificationResult::PactVerified));
     }
 
     #[test]
-    fn fetch_link_returns_an_error_if_the_previous_resource_does_not_have_the_link() {
-        let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
-            .has_pact_with(s!("PactBrokerStub"))
-                .upon_receiving(s!("a request to a hal resource"))
-                .path(s!("/"))
-            .will_respond_with()
-                .status(200)
-                .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
-                .body(OptionalBody::Present(s!("{\"_links\":{\"next\":{\"href\":\"/abc\"},\"prev\":{\"href\":\"/def\"}}}")))
-            .build();
+    fn consumer_version_selector_json_round_trip() {
+        let selector = ConsumerVersionSelector { tag: Some(s!("prod")), latest: Some(true),
+            .. ConsumerVersionSelector::default() };
+        expect!(selector.to_json().to_string()).to(be_equal_to(s!(r#"{"latest":true,"tag":"prod"}"#)));
+        expect!(ConsumerVersionSelector::from_json(&selector.to_json())).to(be_ok().value(selector));
 
-        let result = pact_runner.run(&|broker_url| {
-            let mut client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
-            let result = client.fetch(&s!("/"));
-            expect!(result.clone()).to(be_ok());
-            client.path_info = result.ok();
-            let result = client.fetch_link(&s!("any"), &hashmap!{});
-            expect!(result).to(be_err().value(format!("Link 'any' was not found in the response, only the following links where found: \"next, prev\". URL: '{}', LINK: 'any'",
-                broker_url)));
-            Ok(())
-        });
-        expect!(result).to(be_equal_to(VerificationResult::PactVerified));
+        let selector = ConsumerVersionSelector::from_json(&json!({ "deployed": true, "environment": "production" }));
+        expect!(selector).to(be_ok().value(ConsumerVersionSelector { deployed: Some(true),
+            environment: Some(s!("production")), .. ConsumerVersionSelector::default() }));
+        expect!(ConsumerVersionSelector::from_json(&json!({ "latest": "yes" }))).to(be_err());
+        expect!(ConsumerVersionSelector::from_json(&json!([]))).to(be_err());
     }
 
     #[test]
-    fn fetch_link_returns_the_resource_for_the_link() {
+    fn fetch_pacts_from_broker_with_tags_returns_the_latest_pacts_for_each_tag() {
+        init().unwrap_or(());
+
+        let pact = Pact { consumer: Consumer { name: s!("Consumer") },
+            provider: Provider { name: s!("happy_provider") },
+            .. Pact::default() }
+            .to_json().to_string();
         let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
-            .has_pact_with(s!("PactBrokerStub"))
-            .upon_receiving(s!("a request to a hal resource"))
+            .has_pact_with(s!("PactBroker"))
+            .given(s!("There is a pact tagged prod and main in the pact broker"))
+            .upon_receiving(s!("a request to the pact broker root"))
                 .path(s!("/"))
+                .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
             .will_respond_with()
                 .status(200)
                 .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
-                .body(OptionalBody::Present(s!("{\"_links\":{\"next\":{\"href\":\"/abc\"},\"prev\":{\"href\":\"/def\"}}}")))
-            .upon_receiving(s!("a request to next"))
-                .path(s!("/abc"))
+                .body(OptionalBody::Present(s!(r#"
+                    {
+                        "_links":{
+                            "pb:latest-provider-pacts-with-tag":{"href":"http://localhost/pacts/provider/{provider}/latest/{tag}","templated":true}
+                        }
+                    }
+                "#).into()))
+            .given(s!("There is a pact tagged prod and main in the pact broker"))
+            .upon_receiving(s!("a request for a providers pacts tagged prod"))
+                .path(s!("/pacts/provider/happy_provider/latest/prod"))
+                .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
             .will_respond_with()
                 .status(200)
-                .headers(hashmap!{ s!("Content-Type") => s!("application/json") })
-                .body(OptionalBody::Present(s!("\"Yay! You found your way here\"")))
-            .build();
-
-        let result = pact_runner.run(&|broker_url| {
-            let mut client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
-            let result = client.fetch(&s!("/"));
-            expect!(result.clone()).to(be_ok());
-            client.path_info = result.ok();
-            let result = client.fetch_link(&s!("next"), &hashmap!{});
-            expect!(result).to(be_ok().value(serde_json::Value::String(s!("Yay! You found your way here"))));
-            Ok(())
-        });
-        expect!(result).to(be_equal_to(VerificationResult::PactVerified));
-    }
-
-    #[test]
-    fn fetch_link_returns_handles_absolute_resource_links() {
-        init().unwrap_or(());
-        let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
-            .has_pact_with(s!("PactBrokerStub"))
-            .upon_receiving(s!("a request to a hal resource with absolute paths"))
-                .path(s!("/"))
+                .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
+                .body(OptionalBody::Present(s!(r#"
+                    {
+                        "_links":{
+                            "pacts":[
+                                {"href":"http://localhost/pacts/provider/happy_provider/consumer/Consumer/version/1.0.0"}
+                            ]
+                        }
+                    }
+                "#).into()))
+            .given(s!("There is a pact tagged prod and main in the pact broker"))
+            .upon_receiving(s!("a request for a providers pacts tagged main"))
+                .path(s!("/pacts/provider/happy_provider/latest/main"))
+                .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
             .will_respond_with()
                 .status(200)
                 .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
-                .body(OptionalBody::Present(s!("{\"_links\":{\"next\":{\"href\":\"http://localhost/abc\"},\"prev\":{\"href\":\"http://localhost/def\"}}}")))
-            .upon_receiving(s!("a request to next"))
-                .path(s!("/abc"))
+                .body(OptionalBody::Present(s!(r#"
+                    {
+                        "_links":{
+                            "pacts":[
+                                {"href":"http://localhost/pacts/provider/happy_provider/consumer/Consumer/version/1.0.0"}
+                            ]
+                        }
+                    }
+                "#).into()))
+            .given(s!("There is a pact tagged prod and main in the pact broker"))
+            .upon_receiving(s!("a request for the tagged provider pact"))
+                .path(s!("/pacts/provider/happy_provider/consumer/Consumer/version/1.0.0"))
+                .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
             .will_respond_with()
                 .status(200)
                 .headers(hashmap!{ s!("Content-Type") => s!("application/json") })
-                .body(OptionalBody::Present(s!("\"Yay! You found your way here\"")))
+                .body(OptionalBody::Present(pact.clone().into()))
             .build();
 
         let result = pact_runner.run(&|broker_url| {
-            let mut client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
-            let result = client.fetch(&s!("/"));
+            let result = fetch_pacts_from_broker(&broker_url, &s!("happy_provider"),
+                &PactSelection::LatestWithTags(vec![s!("prod"), s!("main")]), &None);
             expect!(result.clone()).to(be_ok());
-            client.path_info = result.ok();
-            let result = client.fetch_link(&s!("next"), &hashmap!{});
-            expect!(result).to(be_ok().value(serde_json::Value::String(s!("Yay! You found your way here"))));
+            let pacts = result.unwrap();
+            expect!(pacts.len()).to(be_equal_to(1));
             Ok(())
         });
         expect!(result).to(be_equal_to(VerificationResult::PactVerified));
     }
 
     #[test]
-    fn fetch_link_returns_the_resource_for_the_templated_link() {
+    fn fetch_pacts_from_broker_with_selectors_uses_the_pacts_for_verification() {
         init().unwrap_or(());
+
+        let pact = Pact { consumer: Consumer { name: s!("Consumer") },
+            provider: Provider { name: s!("happy_provider") },
+            .. Pact::default() }
+            .to_json().to_string();
         let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
-            .has_pact_with(s!("PactBrokerStub"))
-            .upon_receiving(s!("a request to a templated hal resource"))
+            .has_pact_with(s!("PactBroker"))
+            .given(s!("There is a pact deployed to production in the pact broker"))
+            .upon_receiving(s!("a request to the pact broker root"))
                 .path(s!("/"))
+                .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
             .will_respond_with()
                 .status(200)
                 .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
-                .body(OptionalBody::Present(s!("{\"_links\":{\"document\":{\"href\":\"/doc/{id}\",\"templated\":true}}}")))
-            .upon_receiving(s!("a request for a document"))
-                .path(s!("/doc/abc"))
+                .body(OptionalBody::Present(s!(r#"
+                    {
+                        "_links":{
+                            "pb:provider-pacts-for-verification":{"href":"http://localhost/pacts/provider/{provider}/for-verification","templated":true}
+                        }
+                    }
+                "#).into()))
+            .given(s!("There is a pact deployed to production in the pact broker"))
+            .upon_receiving(s!("a request for the pacts for verification"))
+                .method(s!("POST"))
+                .path(s!("/pacts/provider/happy_provider/for-verification"))
+                .headers(hashmap!{ s!("Content-Type") => s!("application/json") })
+                .body(OptionalBody::Present(s!(r#"{"consumerVersionSelectors":[{"deployed":true,"environment":"production"}]}"#).into()))
+            .will_respond_with()
+                .status(200)
+                .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
+                .body(OptionalBody::Present(s!(r#"
+                    {
+                        "_embedded":{
+                            "pacts":[
+                                {"_links":{"self":{"href":"http://localhost/pacts/provider/happy_provider/consumer/Consumer/pact-version/1234","name":"Pact between Consumer and happy_provider"}}}
+                            ]
+                        }
+                    }
+                "#).into()))
+            .given(s!("There is a pact deployed to production in the pact broker"))
+            .upon_receiving(s!("a request for the pact for verification"))
+                .path(s!("/pacts/provider/happy_provider/consumer/Consumer/pact-version/1234"))
+                .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
             .will_respond_with()
                 .status(200)
                 .headers(hashmap!{ s!("Content-Type") => s!("application/json") })
-                .body(OptionalBody::Present(s!("\"Yay! You found your way here\"")))
+                .body(OptionalBody::Present(pact.clone().into()))
             .build();
 
         let result = pact_runner.run(&|broker_url| {
-            let mut client = HALClient{ url: broker_url.clone(), .. HALClient::default() };
-            let result = client.fetch(&s!("/"));
+            let selectors = vec![ConsumerVersionSelector { deployed: Some(true), environment: Some(s!("production")),
+                .. ConsumerVersionSelector::default() }];
+            let result = fetch_pacts_from_broker(&broker_url, &s!("happy_provider"),
+                &PactSelection::Selectors(selectors, PendingPactOptions::default()), &None);
             expect!(result.clone()).to(be_ok());
-            client.path_info = result.ok();
-            let result = client.fetch_link(&s!("document"), &hashmap!{ s!("id") => s!("abc") });
-            expect!(result).to(be_ok().value(serde_json::Value::String(s!("Yay! You found your way here"))));
+            let pacts = result.unwrap();
+            expect!(pacts.len()).to(be_equal_to(1));
+            expect!(pacts[0].clone()).to(be_ok());
             Ok(())
         });
         expect!(result).to(be_equal_to(VerificationResult::PactVerified));
     }
 
     #[test]
-    fn fetch_pacts_from_broker_returns_empty_list_if_there_are_no_pacts() {
+    fn pacts_for_verification_body_includes_the_pending_options() {
+        let selectors = vec![ConsumerVersionSelector { tag: Some(s!("prod")), .. ConsumerVersionSelector::default() }];
+        expect!(pacts_for_verification_body(&selectors, &PendingPactOptions::default()).to_string())
+            .to(be_equal_to(s!(r#"{"consumerVersionSelectors":[{"tag":"prod"}]}"#)));
+        let options = PendingPactOptions {
+            enable_pending: true,
+            include_wip_pacts_since: Some(s!("2020-01-01")),
+            provider_tags: vec![s!("main")]
+        };
+        expect!(pacts_for_verification_body(&selectors, &options).to_string())
+            .to(be_equal_to(s!(r#"{"consumerVersionSelectors":[{"tag":"prod"}],"includePendingStatus":true,"includeWipPactsSince":"2020-01-01","providerVersionTags":["main"]}"#)));
+    }
+
+    #[test]
+    fn fetch_pacts_from_broker_returns_the_pending_status_of_the_pacts() {
         init().unwrap_or(());
 
+        let pact = Pact { consumer: Consumer { name: s!("Consumer") },
+            provider: Provider { name: s!("happy_provider") },
+            .. Pact::default() }
+            .to_json().to_string();
         let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
             .has_pact_with(s!("PactBroker"))
+            .given(s!("There is a pending pact and a work in progress pact in the pact broker"))
             .upon_receiving(s!("a request to the pact broker root"))
                 .path(s!("/"))
                 .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
@@ -700,29 +739,77 @@ mod tests {
                 .body(OptionalBody::Present(s!(r#"
                     {
                         "_links":{
-                            "pb:latest-provider-pacts":{"href":"http://localhost/pacts/provider/{provider}/latest","templated":true}
+                            "pb:provider-pacts-for-verification":{"href":"http://localhost/pacts/provider/{provider}/for-verification","templated":true}
                         }
                     }
-                "#)))
-            .given(s!("There are no pacts in the pact broker"))
-            .upon_receiving(s!("a request for a providers pacts"))
-                .path(s!("/pacts/provider/sad_provider/latest"))
+                "#).into()))
+            .given(s!("There is a pending pact and a work in progress pact in the pact broker"))
+            .upon_receiving(s!("a request for the pacts for verification including pending and wip pacts"))
+                .method(s!("POST"))
+                .path(s!("/pacts/provider/happy_provider/for-verification"))
+                .headers(hashmap!{ s!("Content-Type") => s!("application/json") })
+                .body(OptionalBody::Present(s!(r#"{"consumerVersionSelectors":[],"includePendingStatus":true,"includeWipPactsSince":"2020-01-01","providerVersionTags":["main"]}"#).into()))
+            .will_respond_with()
+                .status(200)
+                .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
+                .body(OptionalBody::Present(s!(r#"
+                    {
+                        "_embedded":{
+                            "pacts":[
+                                {
+                                    "verificationProperties":{"pending":true},
+                                    "_links":{"self":{"href":"http://localhost/pacts/provider/happy_provider/consumer/Consumer/pact-version/1234"}}
+                                },
+                                {
+                                    "verificationProperties":{"pending":true,"wip":true},
+                                    "_links":{"self":{"href":"http://localhost/pacts/provider/happy_provider/consumer/Consumer/pact-version/5678"}}
+                                }
+                            ]
+                        }
+                    }
+                "#).into()))
+            .given(s!("There is a pending pact and a work in progress pact in the pact broker"))
+            .upon_receiving(s!("a request for the pending pact"))
+                .path(s!("/pacts/provider/happy_provider/consumer/Consumer/pact-version/1234"))
                 .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
             .will_respond_with()
-                .status(404)
+                .status(200)
+                .headers(hashmap!{ s!("Content-Type") => s!("application/json") })
+                .body(OptionalBody::Present(pact.clone().into()))
+            .given(s!("There is a pending pact and a work in progress pact in the pact broker"))
+            .upon_receiving(s!("a request for the work in progress pact"))
+                .path(s!("/pacts/provider/happy_provider/consumer/Consumer/pact-version/5678"))
+                .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
+            .will_respond_with()
+                .status(200)
+                .headers(hashmap!{ s!("Content-Type") => s!("application/json") })
+                .body(OptionalBody::Present(pact.clone().into()))
             .build();
 
         let result = pact_runner.run(&|broker_url| {
-            let result = fetch_pacts_from_broker(&broker_url, &s!("sad_provider"));
-            expect!(result).to(be_err().value(format!("No pacts for provider 'sad_provider' where found in the pact broker. URL: '{}'",
-                broker_url)));
+            let options = PendingPactOptions {
+                enable_pending: true,
+                include_wip_pacts_since: Some(s!("2020-01-01")),
+                provider_tags: vec![s!("main")]
+            };
+            let result = fetch_pacts_from_broker(&broker_url, &s!("happy_provider"),
+                &PactSelection::Selectors(vec![], options), &None);
+            expect!(result.clone()).to(be_ok());
+            let pacts = result.unwrap();
+            expect!(pacts.len()).to(be_equal_to(2));
+            let (_, ref info) = pacts[0].clone().unwrap();
+            expect!(info.pending).to(be_true());
+            expect!(info.wip).to(be_false());
+            let (_, ref info) = pacts[1].clone().unwrap();
+            expect!(info.pending).to(be_true());
+            expect!(info.wip).to(be_true());
             Ok(())
         });
         expect!(result).to(be_equal_to(VerificationResult::PactVerified));
     }
 
     #[test]
-    fn fetch_pacts_from_broker_returns_a_list_of_pacts() {
+    fn fetch_pacts_from_broker_follows_the_next_links_of_paged_pacts() {
         init().unwrap_or(());
 
         let pact = Pact { consumer: Consumer { name: s!("Consumer") },
@@ -731,7 +818,6 @@ mod tests {
             .to_json().to_string();
         let pact2 = Pact { consumer: Consumer { name: s!("Consumer2") },
             provider: Provider { name: s!("happy_provider") },
-            interactions: vec![ Interaction { description: s!("a request friends"), .. Interaction::default() } ],
             .. Pact::default() }
             .to_json().to_string();
         let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
@@ -748,9 +834,9 @@ mod tests {
                             "pb:latest-provider-pacts":{"href":"http://localhost/pacts/provider/{provider}/latest","templated":true}
                         }
                     }
-                "#)))
-            .given(s!("There are two pacts in the pact broker"))
-            .upon_receiving(s!("a request for a providers pacts"))
+                "#).into()))
+            .given(s!("There are two pages of pacts in the pact broker"))
+            .upon_receiving(s!("a request for the first page of a providers pacts"))
                 .path(s!("/pacts/provider/happy_provider/latest"))
                 .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
             .will_respond_with()
@@ -760,40 +846,56 @@ mod tests {
                     {
                         "_links":{
                             "pacts":[
-                                {"href":"http://localhost/pacts/provider/happy_provider/consumer/Consumer/version/1.0.0"},
+                                {"href":"http://localhost/pacts/provider/happy_provider/consumer/Consumer/version/1.0.0"}
+                            ],
+                            "next":{"href":"http://localhost/pacts/provider/happy_provider/latest?page=2"}
+                        }
+                    }
+                "#).into()))
+            .given(s!("There are two pages of pacts in the pact broker"))
+            .upon_receiving(s!("a request for the second page of a providers pacts"))
+                .path(s!("/pacts/provider/happy_provider/latest"))
+                .query(hashmap!{ s!("page") => vec![s!("2")] })
+                .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
+            .will_respond_with()
+                .status(200)
+                .headers(hashmap!{ s!("Content-Type") => s!("application/hal+json") })
+                .body(OptionalBody::Present(s!(r#"
+                    {
+                        "_links":{
+                            "pacts":[
                                 {"href":"http://localhost/pacts/provider/happy_provider/consumer/Consumer2/version/1.0.0"}
-                            ]
+                            ],
+                            "prev":{"href":"http://localhost/pacts/provider/happy_provider/latest"}
                         }
                     }
-                "#)))
-            .given(s!("There are two pacts in the pact broker"))
-            .upon_receiving(s!("a request for the first provider pact"))
+                "#).into()))
+            .given(s!("There are two pages of pacts in the pact broker"))
+            .upon_receiving(s!("a request for the pact on the first page"))
                 .path(s!("/pacts/provider/happy_provider/consumer/Consumer/version/1.0.0"))
                 .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
             .will_respond_with()
                 .status(200)
                 .headers(hashmap!{ s!("Content-Type") => s!("application/json") })
-                .body(OptionalBody::Present(pact.clone()))
-            .given(s!("There are two pacts in the pact broker"))
-            .upon_receiving(s!("a request for the second provider pact"))
+                .body(OptionalBody::Present(pact.clone().into()))
+            .given(s!("There are two pages of pacts in the pact broker"))
+            .upon_receiving(s!("a request for the pact on the second page"))
                 .path(s!("/pacts/provider/happy_provider/consumer/Consumer2/version/1.0.0"))
                 .headers(hashmap!{ s!("Accept") => s!("application/hal+json, application/json") })
             .will_respond_with()
                 .status(200)
                 .headers(hashmap!{ s!("Content-Type") => s!("application/json") })
-                .body(OptionalBody::Present(pact2.clone()))
+                .body(OptionalBody::Present(pact2.clone().into()))
             .build();
 
         let result = pact_runner.run(&|broker_url| {
-            let result = fetch_pacts_from_broker(&broker_url, &s!("happy_provider"));
+            let result = fetch_pacts_from_broker(&broker_url, &s!("happy_provider"), &PactSelection::Latest, &None);
             expect!(result.clone()).to(be_ok());
             let pacts = result.unwrap();
             expect!(pacts.len()).to(be_equal_to(2));
-            for pact in pacts {
-                expect!(pact).to(be_ok());
-            }
             Ok(())
         });
         expect!(result).to(be_equal_to(VerificationResult::PactVerified));
     }
+
 }
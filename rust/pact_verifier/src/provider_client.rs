@@ -1,14 +1,93 @@
 use super::*;
 use pact_matching::models::*;
+use pact_broker_client::extract_body;
 use std::str::FromStr;
 use std::collections::hash_map::HashMap;
-use std::io::Read;
 use hyper::client::Client;
 use hyper::client::response::Response as HyperResponse;
 use hyper::error::Error as HyperError;
 use hyper::method::Method;
+use hyper::status::StatusCode;
 use hyper::header::{Headers, ContentType};
 use hyper::mime::{Mime, TopLevel, SubLevel};
+use std::fmt;
+use std::error::Error;
+use std::sync::Arc;
+
+/// Filter that is applied to each request before it is sent to the provider. It can add or
+/// replace headers, or rewrite any other part of the request.
+#[derive(Clone)]
+pub struct RequestFilter(Arc<Fn(Request) -> Request + Send + Sync>);
+
+impl RequestFilter {
+    /// Creates a request filter from the callback
+    pub fn new<F>(filter: F) -> RequestFilter where F: Fn(Request) -> Request + Send + Sync + 'static {
+        RequestFilter(Arc::new(filter))
+    }
+
+    /// Creates a request filter that adds the headers to each request, replacing any existing
+    /// headers with the same name (ignoring case)
+    pub fn with_headers(headers: HashMap<String, String>) -> RequestFilter {
+        RequestFilter::new(move |request| {
+            let mut request_headers = request.headers.clone().unwrap_or(hashmap!{});
+            for (name, value) in &headers {
+                let existing: Vec<String> = request_headers.keys()
+                    .filter(|key| key.to_lowercase() == name.to_lowercase())
+                    .cloned().collect();
+                for key in existing {
+                    request_headers.remove(&key);
+                }
+                request_headers.insert(name.clone(), value.clone());
+            }
+            Request { headers: Some(request_headers), .. request }
+        })
+    }
+
+    /// Applies the filter to the request
+    pub fn apply(&self, request: Request) -> Request {
+        (self.0)(request)
+    }
+}
+
+impl fmt::Debug for RequestFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RequestFilter")
+    }
+}
+
+/// Transport used to send requests to the provider. Requests can be sent over HTTP to the
+/// running provider, or passed to a function that handles them in-process, so no socket needs to
+/// be bound for the provider.
+#[derive(Clone)]
+pub enum ProviderTransport {
+    /// Send the requests over HTTP (or HTTPS) to the provider host and port
+    Http,
+    /// Pass the requests to the function, which returns the provider response
+    InProcess(Arc<Fn(Request) -> Response + Send + Sync>)
+}
+
+impl ProviderTransport {
+    /// Creates an in-process transport from the provider function
+    pub fn in_process<F>(provider: F) -> ProviderTransport where F: Fn(Request) -> Response + Send + Sync + 'static {
+        ProviderTransport::InProcess(Arc::new(provider))
+    }
+}
+
+impl fmt::Debug for ProviderTransport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProviderTransport::Http => write!(f, "Http"),
+            ProviderTransport::InProcess(_) => write!(f, "InProcess")
+        }
+    }
+}
+
+fn filter_request(provider: &ProviderInfo, request: &Request) -> Request {
+    match provider.request_filter {
+        Some(ref filter) => filter.apply(request.clone()),
+        None => request.clone()
+    }
+}
 
 pub fn join_paths(base: &String, path: String) -> String {
     let mut full_path = s!(base.trim_right_matches("/"));
@@ -45,7 +124,7 @@ fn make_request(base_url: &String, request: &Request, client: &Client) -> Result
             let hyper_request = client.request(method, &url)
                 .headers(setup_headers(&request.headers.clone()));
             match request.body {
-                OptionalBody::Present(ref s) => hyper_request.body(s.as_str()),
+                OptionalBody::Present(ref s) => hyper_request.body(s.as_slice()),
                 OptionalBody::Null => {
                     if request.content_type() == "application/json" {
                         hyper_request.body("null")
@@ -68,54 +147,123 @@ fn extract_headers(headers: &Headers) -> Option<HashMap<String, String>> {
     }
 }
 
-pub fn extract_body(response: &mut HyperResponse) -> OptionalBody {
-    let mut buffer = String::new();
-    match response.read_to_string(&mut buffer) {
-        Ok(size) => if size > 0 {
-                OptionalBody::Present(buffer)
-            } else {
-                OptionalBody::Empty
-            },
-        Err(err) => {
-            warn!("Failed to read request body: {}", err);
-            OptionalBody::Missing
-        }
-    }
-}
-
 fn hyper_response_to_pact_response(response: &mut HyperResponse) -> Response {
     Response {
         status: response.status.to_u16(),
         headers: extract_headers(&response.headers),
         body: extract_body(response),
-        matching_rules: None
+        matching_rules: None,
+        generators: None
     }
 }
 
-pub fn make_provider_request(provider: &ProviderInfo, request: &Request) -> Result<Response, HyperError> {
-    debug!("Sending {:?} to provider", request);
-    let client = Client::new();
-    match make_request(&format!("{}://{}:{}{}", provider.protocol, provider.host, provider.port,
-        provider.path), request, &client) {
-        Ok(ref mut response) => {
+fn send_request(provider: &ProviderInfo, request: Request) -> Result<Response, String> {
+    match provider.transport {
+        ProviderTransport::InProcess(ref provider_fn) => {
+            let response = provider_fn(request);
             debug!("Received response: {:?}", response);
-            Ok(hyper_response_to_pact_response(response))
+            Ok(response)
         },
-        Err(err) => {
-            debug!("Request failed: {}", err);
-            Err(err)
+        ProviderTransport::Http => {
+            let client = try!(provider.http_client.client(&provider.tls_options));
+            match make_request(&format!("{}://{}:{}{}", provider.protocol, provider.host, provider.port,
+                provider.path), &request, &client) {
+                Ok(ref mut response) => {
+                    debug!("Received response: {:?}", response);
+                    Ok(hyper_response_to_pact_response(response))
+                },
+                Err(err) => {
+                    debug!("Request failed: {}", err);
+                    Err(s!(err.description()))
+                }
+            }
         }
     }
 }
 
-pub fn make_state_change_request(provider: &ProviderInfo, request: &Request) -> Result<(), String> {
+pub fn make_provider_request(provider: &ProviderInfo, request: &Request) -> Result<Response, String> {
+    let request = filter_request(provider, request);
+    debug!("Sending {:?} to provider", request);
+    send_request(provider, request)
+}
+
+/// Extracts the message metadata from the provider response. The provider can return the metadata
+/// as a JSON object in the `Pact-Message-Metadata` header, and the `Content-Type` header is used as
+/// the `contentType` metadata value if it is not in the metadata header.
+fn message_metadata(response: &Response) -> Result<HashMap<String, String>, String> {
+    let mut metadata = hashmap!{};
+    let headers = response.headers.clone().unwrap_or(hashmap!{});
+    match headers.iter().find(|&(k, _)| k.to_lowercase() == "pact-message-metadata") {
+        Some((_, value)) => match serde_json::from_str::<serde_json::Value>(value) {
+            Ok(serde_json::Value::Object(ref map)) => for (k, v) in map {
+                metadata.insert(k.clone(), match *v {
+                    serde_json::Value::String(ref s) => s.clone(),
+                    _ => v.to_string()
+                });
+            },
+            _ => return Err(format!("Pact-Message-Metadata header is not a JSON object: '{}'", value))
+        },
+        None => ()
+    }
+    if !metadata.keys().any(|k| k.to_lowercase() == "contenttype" || k.to_lowercase() == "content-type") {
+        match headers.iter().find(|&(k, _)| k.to_lowercase() == "content-type") {
+            Some((_, value)) => { metadata.insert(s!("contentType"), value.clone()); },
+            None => ()
+        }
+    }
+    Ok(metadata)
+}
+
+pub fn make_message_request(provider: &ProviderInfo, message: &Message) -> Result<Message, String> {
+    let mut json_body = json!({
+        s!("description") : json!(message.description.clone())
+    });
+    if !message.provider_states.is_empty() {
+        let map = json_body.as_object_mut().unwrap();
+        map.insert(s!("providerStates"), serde_json::Value::Array(message.provider_states.iter()
+            .map(|state| state.to_json()).collect()));
+    }
+    let request = filter_request(provider, &Request {
+        method: s!("POST"),
+        headers: Some(hashmap!{ s!("Content-Type") => s!("application/json") }),
+        body: OptionalBody::Present(json_body.to_string().into_bytes()),
+        .. Request::default_request()
+    });
+    debug!("Sending {:?} to provider to generate message", request);
+    let response = try!(send_request(provider, request)
+        .map_err(|err| format!("Message request failed: {}", err)));
+    let status = StatusCode::from_u16(response.status);
+    if status.is_success() {
+        let metadata = try!(message_metadata(&response)
+            .map_err(|err| format!("Message request failed: {}", err)));
+        Ok(Message {
+            description: message.description.clone(),
+            provider_states: message.provider_states.clone(),
+            contents: response.body,
+            metadata: metadata,
+            matching_rules: None
+        })
+    } else {
+        debug!("Request failed: {}", status);
+        Err(format!("Message request failed: {}", status))
+    }
+}
+
+pub fn make_state_change_request(provider: &ProviderInfo, request: &Request) -> Result<HashMap<String, serde_json::Value>, String> {
     debug!("Sending {:?} to state change handler", request);
-    let client = Client::new();
+    let client = try!(provider.http_client.client(&provider.tls_options));
     match make_request(&provider.state_change_url.clone().unwrap(), request, &client) {
         Ok(ref mut response) => {
             debug!("Received response: {:?}", response);
             if response.status.is_success() {
-                Ok(())
+                match extract_body(response) {
+                    OptionalBody::Present(ref body) => match serde_json::from_slice::<serde_json::Value>(body) {
+                        Ok(serde_json::Value::Object(ref map)) => Ok(map.iter()
+                            .map(|(k, v)| (k.clone(), v.clone())).collect()),
+                        _ => Ok(hashmap!{})
+                    },
+                    _ => Ok(hashmap!{})
+                }
             } else {
                 debug!("Request failed: {}", response.status);
                 Err(format!("State change request failed: {}", response.status))
@@ -131,7 +279,10 @@ pub fn make_state_change_request(provider: &ProviderInfo, request: &Request) ->
 #[cfg(test)]
 mod tests {
     use expectest::prelude::*;
-    use super::join_paths;
+    use super::{join_paths, make_provider_request, make_message_request, RequestFilter, ProviderTransport};
+    use pact_consumer::*;
+    use pact_matching::models::{Request, Response, Message, OptionalBody};
+    use ProviderInfo;
 
     #[test]
     fn join_paths_test() {
@@ -142,4 +293,123 @@ mod tests {
         expect!(join_paths(&s!("/a/b"), s!("/c/d"))).to(be_equal_to(s!("/a/b/c/d")));
     }
 
+    #[test]
+    fn request_filter_with_headers_adds_or_replaces_the_headers() {
+        let filter = RequestFilter::with_headers(hashmap!{ s!("Authorization") => s!("Bearer 1234") });
+        let request = filter.apply(Request::default_request());
+        expect!(request.headers).to(be_some().value(hashmap!{ s!("Authorization") => s!("Bearer 1234") }));
+
+        let request = filter.apply(Request {
+            headers: Some(hashmap!{ s!("authorization") => s!("Basic abc"), s!("Accept") => s!("text/plain") }),
+            .. Request::default_request()
+        });
+        expect!(request.headers).to(be_some().value(hashmap!{
+            s!("Authorization") => s!("Bearer 1234"),
+            s!("Accept") => s!("text/plain")
+        }));
+    }
+
+    #[test]
+    fn make_provider_request_applies_the_request_filter() {
+        let pact_runner = ConsumerPactBuilder::consumer(s!("RustPactVerifier"))
+            .has_pact_with(s!("Provider"))
+            .upon_receiving(s!("a request with an auth token"))
+                .path(s!("/secure"))
+                .headers(hashmap!{ s!("Authorization") => s!("Bearer 1234") })
+            .will_respond_with()
+                .status(200)
+            .build();
+
+        let result = pact_runner.run(&|url| {
+            let port = url.rsplit(':').next().unwrap().parse::<u16>().unwrap();
+            let provider = ProviderInfo {
+                port: port,
+                request_filter: Some(RequestFilter::new(|request| Request {
+                    path: s!("/secure"),
+                    .. RequestFilter::with_headers(hashmap!{ s!("Authorization") => s!("Bearer 1234") }).apply(request)
+                })),
+                .. ProviderInfo::default()
+            };
+            let response = make_provider_request(&provider, &Request { path: s!("/insecure"), .. Request::default_request() });
+            expect!(response.map(|r| r.status)).to(be_ok().value(200));
+            Ok(())
+        });
+        expect!(result).to(be_equal_to(VerificationResult::PactVerified));
+    }
+
+    #[test]
+    fn make_provider_request_passes_the_request_to_an_in_process_provider() {
+        let provider = ProviderInfo {
+            transport: ProviderTransport::in_process(|request| Response {
+                status: if request.path == "/ping" { 200 } else { 404 },
+                body: OptionalBody::Present(request.method.into_bytes()),
+                .. Response::default_response()
+            }),
+            request_filter: Some(RequestFilter::with_headers(hashmap!{ s!("Authorization") => s!("Bearer 1234") })),
+            .. ProviderInfo::default()
+        };
+        let response = make_provider_request(&provider, &Request { path: s!("/ping"), .. Request::default_request() });
+        expect!(response.clone().map(|r| r.status)).to(be_ok().value(200));
+        expect!(response.map(|r| r.body)).to(be_ok().value(OptionalBody::Present(s!("GET").into_bytes())));
+    }
+
+    #[test]
+    fn make_message_request_passes_the_request_to_an_in_process_provider() {
+        let provider = ProviderInfo {
+            transport: ProviderTransport::in_process(|_| Response {
+                headers: Some(hashmap!{ s!("content-type") => s!("application/json") }),
+                body: OptionalBody::Present(s!("{\"id\":1}").into_bytes()),
+                .. Response::default_response()
+            }),
+            .. ProviderInfo::default()
+        };
+        let message = Message { description: s!("a user created message"), .. Message::default() };
+        let result = make_message_request(&provider, &message);
+        expect!(result.clone().map(|m| m.contents)).to(be_ok().value(OptionalBody::Present(s!("{\"id\":1}").into_bytes())));
+        expect!(result.map(|m| m.metadata)).to(be_ok().value(hashmap!{ s!("contentType") => s!("application/json") }));
+    }
+
+    #[test]
+    fn make_message_request_uses_the_metadata_returned_by_the_provider() {
+        let provider = ProviderInfo {
+            transport: ProviderTransport::in_process(|_| Response {
+                headers: Some(hashmap!{
+                    s!("Content-Type") => s!("application/json"),
+                    s!("Pact-Message-Metadata") => s!(r#"{"topic":"users","partition":1}"#)
+                }),
+                body: OptionalBody::Present(s!("{}").into_bytes()),
+                .. Response::default_response()
+            }),
+            .. ProviderInfo::default()
+        };
+        let result = make_message_request(&provider, &Message::default());
+        expect!(result.map(|m| m.metadata)).to(be_ok().value(hashmap!{
+            s!("contentType") => s!("application/json"),
+            s!("topic") => s!("users"),
+            s!("partition") => s!("1")
+        }));
+    }
+
+    #[test]
+    fn make_message_request_fails_if_the_metadata_header_is_not_a_json_object() {
+        let provider = ProviderInfo {
+            transport: ProviderTransport::in_process(|_| Response {
+                headers: Some(hashmap!{ s!("Pact-Message-Metadata") => s!("topic=users") }),
+                .. Response::default_response()
+            }),
+            .. ProviderInfo::default()
+        };
+        let result = make_message_request(&provider, &Message::default());
+        expect!(result).to(be_err().value(s!("Message request failed: Pact-Message-Metadata header is not a JSON object: 'topic=users'")));
+    }
+
+    #[test]
+    fn make_message_request_fails_if_the_in_process_provider_returns_an_error_status() {
+        let provider = ProviderInfo {
+            transport: ProviderTransport::in_process(|_| Response { status: 500, .. Response::default_response() }),
+            .. ProviderInfo::default()
+        };
+        let result = make_message_request(&provider, &Message::default());
+        expect!(result).to(be_err().value(s!("Message request failed: 500 Internal Server Error")));
+    }
 }
@@ -0,0 +1,119 @@
+//! The `state_handlers` module provides provider state callbacks that are executed in-process,
+//! as an alternative to posting state change requests to a state change URL.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use serde_json::Value;
+
+/// Callback that sets up (or tears down) a provider state. It is called with the provider state
+/// name, the provider state parameters and a flag that is true for setup and false for teardown,
+/// and can return values to be used by the provider state generators of the request.
+#[derive(Clone)]
+pub struct StateHandler(Arc<Fn(&String, &HashMap<String, Value>, bool) -> Result<HashMap<String, Value>, String> + Send + Sync>);
+
+impl StateHandler {
+    /// Creates a state handler from the callback
+    pub fn new<F>(handler: F) -> StateHandler
+        where F: Fn(&String, &HashMap<String, Value>, bool) -> Result<HashMap<String, Value>, String> + Send + Sync + 'static {
+        StateHandler(Arc::new(handler))
+    }
+
+    /// Executes the handler for the provider state
+    pub fn execute(&self, name: &String, params: &HashMap<String, Value>, setup: bool) -> Result<HashMap<String, Value>, String> {
+        (self.0)(name, params, setup)
+    }
+}
+
+impl fmt::Debug for StateHandler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "StateHandler")
+    }
+}
+
+/// Registry of the state handlers, keyed by provider state name, with an optional fallback
+/// handler for any provider states that do not have a handler registered
+#[derive(Debug, Clone)]
+pub struct StateHandlers {
+    handlers: HashMap<String, StateHandler>,
+    fallback: Option<StateHandler>
+}
+
+impl StateHandlers {
+    /// Creates an empty registry
+    pub fn new() -> StateHandlers {
+        StateHandlers {
+            handlers: hashmap!{},
+            fallback: None
+        }
+    }
+
+    /// Registers the handler for the provider state name, replacing any existing handler
+    pub fn with_state<F>(mut self, name: &str, handler: F) -> StateHandlers
+        where F: Fn(&String, &HashMap<String, Value>, bool) -> Result<HashMap<String, Value>, String> + Send + Sync + 'static {
+        self.handlers.insert(s!(name), StateHandler::new(handler));
+        self
+    }
+
+    /// Registers the fallback handler, used for provider states with no registered handler
+    pub fn with_fallback<F>(mut self, handler: F) -> StateHandlers
+        where F: Fn(&String, &HashMap<String, Value>, bool) -> Result<HashMap<String, Value>, String> + Send + Sync + 'static {
+        self.fallback = Some(StateHandler::new(handler));
+        self
+    }
+
+    /// Returns the handler for the provider state name, or the fallback handler if there is no
+    /// handler registered for it
+    pub fn handler_for(&self, name: &String) -> Option<&StateHandler> {
+        match self.handlers.get(name) {
+            Some(handler) => Some(handler),
+            None => self.fallback.as_ref()
+        }
+    }
+
+    /// If there are no handlers registered
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty() && self.fallback.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expectest::prelude::*;
+    use super::*;
+
+    #[test]
+    fn handler_for_returns_the_handler_registered_for_the_state() {
+        let handlers = StateHandlers::new()
+            .with_state("user exists", |_, _, _| Ok(hashmap!{ s!("id") => json!(1) }))
+            .with_state("no users", |_, _, _| Ok(hashmap!{ s!("id") => json!(2) }));
+        let result = handlers.handler_for(&s!("user exists")).unwrap().execute(&s!("user exists"), &hashmap!{}, true);
+        expect!(result).to(be_ok().value(hashmap!{ s!("id") => json!(1) }));
+        expect!(handlers.handler_for(&s!("other state"))).to(be_none());
+    }
+
+    #[test]
+    fn handler_for_returns_the_fallback_handler_if_there_is_no_handler_for_the_state() {
+        let handlers = StateHandlers::new()
+            .with_state("user exists", |_, _, _| Ok(hashmap!{}))
+            .with_fallback(|name, params, setup| Ok(hashmap!{
+                s!("name") => json!(name),
+                s!("params") => json!(params),
+                s!("setup") => json!(setup)
+            }));
+        let params = hashmap!{ s!("id") => json!(100) };
+        let result = handlers.handler_for(&s!("other state")).unwrap().execute(&s!("other state"), &params, false);
+        expect!(result).to(be_ok().value(hashmap!{
+            s!("name") => json!("other state"),
+            s!("params") => json!({ "id": 100 }),
+            s!("setup") => json!(false)
+        }));
+    }
+
+    #[test]
+    fn is_empty_test() {
+        expect!(StateHandlers::new().is_empty()).to(be_true());
+        expect!(StateHandlers::new().with_state("state", |_, _, _| Ok(hashmap!{})).is_empty()).to(be_false());
+        expect!(StateHandlers::new().with_fallback(|_, _, _| Ok(hashmap!{})).is_empty()).to(be_false());
+    }
+}
@@ -0,0 +1,329 @@
+//! The `tls` module provides the HTTP client used for requests to HTTPS providers, with support for
+//! custom CA certificates, client certificates and disabling certificate verification.
+//!
+//! HTTPS support uses OpenSSL, and is only available if the crate is built with the `tls` feature.
+//! Without it, only plain HTTP providers can be verified.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use hyper::client::Client;
+#[cfg(feature = "tls")]
+use std::io::{self, Read, Write};
+#[cfg(feature = "tls")]
+use std::net::{SocketAddr, Shutdown};
+#[cfg(feature = "tls")]
+use std::time::Duration;
+#[cfg(feature = "tls")]
+use hyper;
+#[cfg(feature = "tls")]
+use hyper::net::{HttpStream, HttpsConnector, NetworkStream, OpensslClient, SslClient};
+#[cfg(feature = "tls")]
+use openssl::ssl::{Ssl, SslContext, SslMethod, SslStream, SSL_VERIFY_NONE};
+#[cfg(feature = "tls")]
+use openssl::x509::X509FileType;
+
+/// Options for the TLS connections made to HTTPS providers
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsOptions {
+    /// PEM file of CA certificates to trust, in addition to the system CA certificates
+    pub ca_file: Option<String>,
+    /// Disables verification of the provider certificate. This should only be used with
+    /// self-signed test certificates.
+    pub insecure: bool,
+    /// PEM file of the client certificate to present to the provider (mutual TLS)
+    pub client_cert: Option<String>,
+    /// PEM file of the private key for the client certificate. If not set, the key is loaded from
+    /// the client certificate file.
+    pub client_key: Option<String>
+}
+
+impl TlsOptions {
+    /// Returns the default options, which verify the provider certificate against the system CA
+    /// certificates
+    pub fn default() -> TlsOptions {
+        TlsOptions {
+            ca_file: None,
+            insecure: false,
+            client_cert: None,
+            client_key: None
+        }
+    }
+}
+
+#[cfg(feature = "tls")]
+fn ssl_context(options: &TlsOptions) -> Result<SslContext, String> {
+    let mut context = try!(SslContext::new(SslMethod::Sslv23)
+        .map_err(|err| format!("Could not create the TLS context - {}", err)));
+    try!(context.set_default_verify_paths()
+        .map_err(|err| format!("Could not load the system CA certificates - {}", err)));
+    match options.ca_file {
+        Some(ref ca_file) => try!(context.set_CA_file(ca_file)
+            .map_err(|err| format!("Could not load the CA certificates from '{}' - {}", ca_file, err))),
+        None => ()
+    }
+    match options.client_cert {
+        Some(ref cert) => {
+            try!(context.set_certificate_file(cert, X509FileType::PEM)
+                .map_err(|err| format!("Could not load the client certificate from '{}' - {}", cert, err)));
+            let key = options.client_key.clone().unwrap_or(cert.clone());
+            try!(context.set_private_key_file(&key, X509FileType::PEM)
+                .map_err(|err| format!("Could not load the client private key from '{}' - {}", key, err)));
+            try!(context.check_private_key()
+                .map_err(|err| format!("The client private key does not match the client certificate - {}", err)));
+        },
+        None => ()
+    }
+    if options.insecure {
+        context.set_verify(SSL_VERIFY_NONE, None);
+    }
+    Ok(context)
+}
+
+/// Creates a HTTP client that uses the TLS options for HTTPS connections. Unless verification is
+/// disabled, the provider certificate and hostname are verified by the hyper `OpensslClient`.
+#[cfg(feature = "tls")]
+pub fn http_client(options: &TlsOptions) -> Result<Client, String> {
+    let context = try!(ssl_context(options));
+    if options.insecure {
+        Ok(Client::with_connector(HttpsConnector::new(InsecureOpenssl { context: Arc::new(context) })))
+    } else {
+        Ok(Client::with_connector(HttpsConnector::new(OpensslClient::new(context))))
+    }
+}
+
+/// Creates a plain HTTP client. Without the `tls` feature the TLS options can not be applied, so
+/// an error is returned if any of them are set.
+#[cfg(not(feature = "tls"))]
+pub fn http_client(options: &TlsOptions) -> Result<Client, String> {
+    if *options == TlsOptions::default() {
+        Ok(Client::new())
+    } else {
+        Err(s!("The TLS options require pact_verifier to be built with the `tls` feature"))
+    }
+}
+
+/// HTTP client used for the requests to the provider and the state change URL. The client is
+/// created from the TLS options on first use and shared by all the clones of the provider info,
+/// so the TLS context is only built once for a verification run. It is recreated if the TLS
+/// options change.
+#[derive(Clone)]
+pub struct HttpClient(Arc<Mutex<Option<(TlsOptions, Arc<Client>)>>>);
+
+impl HttpClient {
+    /// Creates a HTTP client that has not been initialised yet
+    pub fn new() -> HttpClient {
+        HttpClient(Arc::new(Mutex::new(None)))
+    }
+
+    /// Returns the client for the TLS options, creating it if it has not been created yet
+    pub fn client(&self, options: &TlsOptions) -> Result<Arc<Client>, String> {
+        let mut cached = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        match *cached {
+            Some((ref cached_options, ref client)) if cached_options == options => return Ok(client.clone()),
+            _ => ()
+        }
+        let client = Arc::new(try!(http_client(options)));
+        *cached = Some((options.clone(), client.clone()));
+        Ok(client)
+    }
+}
+
+impl fmt::Debug for HttpClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HttpClient")
+    }
+}
+
+/// SSL client that does not verify the certificate or hostname of the server. The hyper
+/// `OpensslClient` always verifies the server certificate.
+#[cfg(feature = "tls")]
+#[derive(Debug, Clone)]
+struct InsecureOpenssl {
+    context: Arc<SslContext>
+}
+
+#[cfg(feature = "tls")]
+impl SslClient for InsecureOpenssl {
+    type Stream = InsecureSslStream;
+
+    fn wrap_client(&self, stream: HttpStream, host: &str) -> hyper::Result<InsecureSslStream> {
+        let ssl = try!(Ssl::new(&self.context));
+        try!(ssl.set_hostname(host));
+        SslStream::connect(ssl, stream)
+            .map(|stream| InsecureSslStream(Arc::new(Mutex::new(stream))))
+            .map_err(From::from)
+    }
+}
+
+#[cfg(feature = "tls")]
+#[derive(Clone)]
+struct InsecureSslStream(Arc<Mutex<SslStream<HttpStream>>>);
+
+#[cfg(feature = "tls")]
+impl Read for InsecureSslStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().read(buf)
+    }
+}
+
+#[cfg(feature = "tls")]
+impl Write for InsecureSslStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.lock().unwrap().flush()
+    }
+}
+
+#[cfg(feature = "tls")]
+impl NetworkStream for InsecureSslStream {
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        self.0.lock().unwrap().get_mut().peer_addr()
+    }
+
+    fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.lock().unwrap().get_ref().set_read_timeout(dur)
+    }
+
+    fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.lock().unwrap().get_ref().set_write_timeout(dur)
+    }
+
+    fn close(&mut self, how: Shutdown) -> io::Result<()> {
+        self.0.lock().unwrap().get_mut().close(how)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expectest::prelude::*;
+    use super::{TlsOptions, HttpClient, http_client};
+    use std::sync::Arc;
+    #[cfg(feature = "tls")]
+    use std::io::{Read, Write};
+    #[cfg(feature = "tls")]
+    use std::net::TcpListener;
+    #[cfg(feature = "tls")]
+    use std::path::{Path, PathBuf};
+    #[cfg(feature = "tls")]
+    use std::thread;
+    #[cfg(feature = "tls")]
+    use openssl::ssl::{SslContext, SslMethod, SslStream};
+    #[cfg(feature = "tls")]
+    use openssl::x509::X509FileType;
+
+    #[cfg(feature = "tls")]
+    fn test_certificate(name: &str) -> PathBuf {
+        Path::new(file!()).parent().unwrap().join("../tests/tls").join(name)
+    }
+
+    /// Starts a HTTPS server on localhost with the self-signed test certificate, and returns its
+    /// port. Each request is responded to with a 200 response.
+    #[cfg(feature = "tls")]
+    fn https_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut context = SslContext::new(SslMethod::Sslv23).unwrap();
+        context.set_certificate_file(&test_certificate("server.pem"), X509FileType::PEM).unwrap();
+        context.set_private_key_file(&test_certificate("server.key"), X509FileType::PEM).unwrap();
+        thread::spawn(move || for stream in listener.incoming() {
+            match stream.ok().and_then(|stream| SslStream::accept(&context, stream).ok()) {
+                Some(mut stream) => {
+                    let mut request = vec![];
+                    let mut buffer = [0; 1024];
+                    while !String::from_utf8_lossy(&request).contains("\r\n\r\n") {
+                        match stream.read(&mut buffer) {
+                            Ok(0) | Err(_) => break,
+                            Ok(size) => request.extend_from_slice(&buffer[..size])
+                        }
+                    }
+                    let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nOK");
+                },
+                None => ()
+            }
+        });
+        port
+    }
+
+    #[test]
+    #[cfg(feature = "tls")]
+    fn http_client_returns_an_error_if_the_ca_file_can_not_be_loaded() {
+        let options = TlsOptions { ca_file: Some(s!("/does/not/exist.pem")), .. TlsOptions::default() };
+        let result = http_client(&options);
+        expect!(result.is_err()).to(be_true());
+        expect!(result.err().unwrap().starts_with("Could not load the CA certificates from '/does/not/exist.pem'")).to(be_true());
+    }
+
+    #[test]
+    #[cfg(feature = "tls")]
+    fn http_client_returns_an_error_if_the_client_certificate_can_not_be_loaded() {
+        let options = TlsOptions { client_cert: Some(s!("/does/not/exist.pem")), .. TlsOptions::default() };
+        let result = http_client(&options);
+        expect!(result.is_err()).to(be_true());
+        expect!(result.err().unwrap().starts_with("Could not load the client certificate from '/does/not/exist.pem'")).to(be_true());
+    }
+
+    #[test]
+    #[cfg(feature = "tls")]
+    fn http_client_can_be_created_with_the_default_options() {
+        expect!(http_client(&TlsOptions::default()).is_ok()).to(be_true());
+        expect!(http_client(&TlsOptions { insecure: true, .. TlsOptions::default() }).is_ok()).to(be_true());
+    }
+
+    #[test]
+    fn http_client_client_reuses_the_client_for_the_same_options() {
+        let client = HttpClient::new();
+        let options = TlsOptions::default();
+        let first = client.client(&options).unwrap();
+        expect!(Arc::ptr_eq(&first, &client.clone().client(&options).unwrap())).to(be_true());
+    }
+
+    #[test]
+    #[cfg(feature = "tls")]
+    fn http_client_client_recreates_the_client_if_the_options_change() {
+        let client = HttpClient::new();
+        let first = client.client(&TlsOptions::default()).unwrap();
+        let insecure = TlsOptions { insecure: true, .. TlsOptions::default() };
+        expect!(Arc::ptr_eq(&first, &client.client(&insecure).unwrap())).to(be_false());
+    }
+
+    #[test]
+    #[cfg(feature = "tls")]
+    fn http_client_rejects_a_self_signed_certificate_with_the_default_options() {
+        let port = https_server();
+        let client = http_client(&TlsOptions::default()).unwrap();
+        expect!(client.get(&format!("https://localhost:{}/", port)).send().is_err()).to(be_true());
+    }
+
+    #[test]
+    #[cfg(feature = "tls")]
+    fn http_client_accepts_a_self_signed_certificate_if_verification_is_disabled() {
+        let port = https_server();
+        let client = http_client(&TlsOptions { insecure: true, .. TlsOptions::default() }).unwrap();
+        let response = client.get(&format!("https://localhost:{}/", port)).send();
+        expect!(response.map(|response| response.status.to_u16())).to(be_ok().value(200));
+    }
+
+    #[test]
+    #[cfg(feature = "tls")]
+    fn http_client_accepts_a_certificate_signed_by_the_ca_file() {
+        let port = https_server();
+        let options = TlsOptions {
+            ca_file: Some(test_certificate("server.pem").to_string_lossy().into_owned()),
+            .. TlsOptions::default()
+        };
+        let client = http_client(&options).unwrap();
+        let response = client.get(&format!("https://localhost:{}/", port)).send();
+        expect!(response.map(|response| response.status.to_u16())).to(be_ok().value(200));
+    }
+
+    #[test]
+    #[cfg(not(feature = "tls"))]
+    fn http_client_returns_an_error_if_tls_options_are_set_without_the_tls_feature() {
+        expect!(http_client(&TlsOptions::default()).is_ok()).to(be_true());
+        let result = http_client(&TlsOptions { insecure: true, .. TlsOptions::default() });
+        expect!(result.err()).to(be_some().value(s!("The TLS options require pact_verifier to be built with the `tls` feature")));
+    }
+}
@@ -0,0 +1,250 @@
+//! The `verification_result` module provides the structured results of verifying a provider. There is
+//! a result for each pact, which has a result for each interaction or message that was verified.
+
+use std::fmt;
+use std::time::Duration;
+use serde_json::Value;
+use pact_matching::Mismatch;
+use pact_matching::models::{Response, Message};
+
+/// Status of the verification of an interaction, message or pact
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerificationStatus {
+    /// The verification passed
+    Passed,
+    /// The verification failed due to mismatches
+    Failed,
+    /// The verification could not be completed due to an error
+    Error
+}
+
+impl fmt::Display for VerificationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VerificationStatus::Passed => write!(f, "passed"),
+            VerificationStatus::Failed => write!(f, "failed"),
+            VerificationStatus::Error => write!(f, "error")
+        }
+    }
+}
+
+/// The expected and actual values of a verified interaction or message
+#[derive(Debug, Clone)]
+pub enum VerificationDetails {
+    /// An HTTP interaction, with the expected response and the response received from the provider
+    Response(Response, Option<Response>),
+    /// A message, with the expected message and the message generated by the provider
+    Message(Message, Option<Message>)
+}
+
+/// Result of verifying a single interaction or message
+#[derive(Debug, Clone)]
+pub struct InteractionVerificationResult {
+    /// Index of the interaction or message in the pact file (starting at 0)
+    pub index: usize,
+    /// Description of the interaction or message
+    pub description: String,
+    /// Names of the provider states of the interaction or message
+    pub provider_states: Vec<String>,
+    /// Status of the verification
+    pub status: VerificationStatus,
+    /// Time taken to verify the interaction, including any state change requests
+    pub duration: Duration,
+    /// Any mismatches between the expected and actual response or message
+    pub mismatches: Vec<Mismatch>,
+    /// The error if the verification could not be completed
+    pub error: Option<String>,
+    /// The expected and actual response or message
+    pub details: VerificationDetails
+}
+
+impl InteractionVerificationResult {
+    /// Converts this result to a JSON structure
+    pub fn to_json(&self) -> Value {
+        let mut json = json!({
+            s!("index"): json!(self.index),
+            s!("description"): json!(self.description),
+            s!("providerStates"): json!(self.provider_states),
+            s!("status"): json!(self.status.to_string()),
+            s!("durationMs"): json!(duration_in_millis(&self.duration)),
+            s!("mismatches"): Value::Array(self.mismatches.iter().map(|m| m.to_json()).collect())
+        });
+        match self.error {
+            Some(ref error) => { json.as_object_mut().unwrap().insert(s!("error"), json!(error)); },
+            None => ()
+        }
+        json
+    }
+}
+
+/// Result of verifying a pact
+#[derive(Debug, Clone)]
+pub struct PactVerificationResult {
+    /// Name of the consumer (empty if the pact could not be loaded)
+    pub consumer: String,
+    /// Name of the provider (empty if the pact could not be loaded)
+    pub provider: String,
+    /// Status of the verification. This is failed if any interaction failed, and error if the pact
+    /// could not be loaded or any interaction had an error.
+    pub status: VerificationStatus,
+    /// Time taken to verify the pact
+    pub duration: Duration,
+    /// Results of the interactions or messages that were verified
+    pub interactions: Vec<InteractionVerificationResult>,
+    /// The error if the pact could not be loaded
+    pub error: Option<String>,
+    /// If the pact is pending in the pact broker. A pending pact that fails verification does not
+    /// fail the overall verification
+    pub pending: bool
+}
+
+impl PactVerificationResult {
+    /// Creates the result for a pact from the results of its interactions
+    pub fn new(consumer: String, provider: String, duration: Duration,
+        interactions: Vec<InteractionVerificationResult>) -> PactVerificationResult {
+        let status = if interactions.iter().any(|i| i.status == VerificationStatus::Error) {
+            VerificationStatus::Error
+        } else if interactions.iter().any(|i| i.status == VerificationStatus::Failed) {
+            VerificationStatus::Failed
+        } else {
+            VerificationStatus::Passed
+        };
+        PactVerificationResult {
+            consumer: consumer,
+            provider: provider,
+            status: status,
+            duration: duration,
+            interactions: interactions,
+            error: None,
+            pending: false
+        }
+    }
+
+    /// Creates the result for a pact that could not be loaded
+    pub fn load_error(error: String) -> PactVerificationResult {
+        PactVerificationResult {
+            consumer: s!(""),
+            provider: s!(""),
+            status: VerificationStatus::Error,
+            duration: Duration::new(0, 0),
+            interactions: vec![],
+            error: Some(error),
+            pending: false
+        }
+    }
+
+    /// Converts this result to a JSON structure
+    pub fn to_json(&self) -> Value {
+        let mut json = json!({
+            s!("consumer"): json!(self.consumer),
+            s!("provider"): json!(self.provider),
+            s!("status"): json!(self.status.to_string()),
+            s!("pending"): json!(self.pending),
+            s!("durationMs"): json!(duration_in_millis(&self.duration)),
+            s!("interactions"): Value::Array(self.interactions.iter().map(|i| i.to_json()).collect())
+        });
+        match self.error {
+            Some(ref error) => { json.as_object_mut().unwrap().insert(s!("error"), json!(error)); },
+            None => ()
+        }
+        json
+    }
+}
+
+/// Result of verifying a provider against all its pacts
+#[derive(Debug, Clone)]
+pub struct VerificationResult {
+    /// Results for each pact
+    pub pacts: Vec<PactVerificationResult>,
+    /// Total time taken for the verification
+    pub duration: Duration
+}
+
+impl VerificationResult {
+    /// If all the pacts were loaded and all the interactions passed. Pending pacts are ignored.
+    pub fn success(&self) -> bool {
+        self.pacts.iter().all(|pact| pact.pending || pact.status == VerificationStatus::Passed)
+    }
+
+    /// Converts this result to a JSON structure
+    pub fn to_json(&self) -> Value {
+        json!({
+            s!("success"): json!(self.success()),
+            s!("durationMs"): json!(duration_in_millis(&self.duration)),
+            s!("pacts"): Value::Array(self.pacts.iter().map(|p| p.to_json()).collect())
+        })
+    }
+}
+
+/// Returns the duration in milliseconds
+pub fn duration_in_millis(duration: &Duration) -> u64 {
+    duration.as_secs() * 1000 + (duration.subsec_nanos() / 1_000_000) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expectest::prelude::*;
+
+    fn interaction_result(status: VerificationStatus) -> InteractionVerificationResult {
+        InteractionVerificationResult {
+            index: 1,
+            description: s!("a request"),
+            provider_states: vec![s!("state one")],
+            status: status,
+            duration: Duration::from_millis(1500),
+            mismatches: vec![],
+            error: None,
+            details: VerificationDetails::Response(Response::default_response(), None)
+        }
+    }
+
+    #[test]
+    fn pact_status_is_derived_from_the_interaction_results() {
+        let duration = Duration::from_millis(10);
+        expect!(PactVerificationResult::new(s!("c"), s!("p"), duration, vec![]).status)
+            .to(be_equal_to(VerificationStatus::Passed));
+        expect!(PactVerificationResult::new(s!("c"), s!("p"), duration, vec![
+            interaction_result(VerificationStatus::Passed), interaction_result(VerificationStatus::Failed)
+        ]).status).to(be_equal_to(VerificationStatus::Failed));
+        expect!(PactVerificationResult::new(s!("c"), s!("p"), duration, vec![
+            interaction_result(VerificationStatus::Error), interaction_result(VerificationStatus::Failed)
+        ]).status).to(be_equal_to(VerificationStatus::Error));
+    }
+
+    #[test]
+    fn verification_result_is_only_successful_if_all_pacts_passed() {
+        let passed = PactVerificationResult::new(s!("c"), s!("p"), Duration::from_millis(10),
+            vec![interaction_result(VerificationStatus::Passed)]);
+        let result = VerificationResult { pacts: vec![passed.clone()], duration: Duration::from_millis(10) };
+        expect!(result.success()).to(be_true());
+        let result = VerificationResult { pacts: vec![passed, PactVerificationResult::load_error(s!("boom"))],
+            duration: Duration::from_millis(10) };
+        expect!(result.success()).to(be_false());
+    }
+
+    #[test]
+    fn failures_of_pending_pacts_do_not_fail_the_verification() {
+        let mut pending = PactVerificationResult::new(s!("c"), s!("p"), Duration::from_millis(10),
+            vec![interaction_result(VerificationStatus::Failed)]);
+        pending.pending = true;
+        let result = VerificationResult { pacts: vec![pending.clone()], duration: Duration::from_millis(10) };
+        expect!(result.success()).to(be_true());
+        expect!(pending.to_json()["pending"].clone()).to(be_equal_to(json!(true)));
+        pending.pending = false;
+        let result = VerificationResult { pacts: vec![pending], duration: Duration::from_millis(10) };
+        expect!(result.success()).to(be_false());
+    }
+
+    #[test]
+    fn interaction_result_to_json_includes_the_mismatches_and_error() {
+        let result = InteractionVerificationResult {
+            status: VerificationStatus::Failed,
+            mismatches: vec![Mismatch::StatusMismatch { expected: 200, actual: 500 }],
+            error: Some(s!("it went bang")),
+            .. interaction_result(VerificationStatus::Passed)
+        };
+        expect!(result.to_json().to_string()).to(be_equal_to(
+            r#"{"description":"a request","durationMs":1500,"error":"it went bang","index":1,"mismatches":[{"actual":500,"expected":200,"type":"StatusMismatch"}],"providerStates":["state one"],"status":"failed"}"#));
+    }
+}
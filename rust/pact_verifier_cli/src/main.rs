@@ -18,25 +18,50 @@
 //!     pact_verifier_cli [FLAGS] [OPTIONS] --file <file> --dir <dir> --url <url> --broker-url <broker-url> --provider-name <provider-name>
 //!
 //! FLAGS:
+//!         --disable-ssl-verification    Disables verification of the provider certificate (only use this with self-signed test certificates)
+//!         --enable-pending           Enables pending pacts. Failures verifying pending pacts will not fail the verification
 //!         --filter-no-state          Only validate interactions that have no defined provider state
 //!         --help                     Prints help information
+//!         --publish                  Publish the verification results to the pact broker (requires the broker URL and provider version parameters)
 //!         --state-change-as-query    State change request data will be sent as query parameters instead of in the request body
 //!         --state-change-teardown    State change teardown requests are to be made after each interaction
 //!     -v, --version                  Prints version information
 //!
 //! OPTIONS:
 //!     -b, --broker-url <broker-url>                    URL of the pact broker to fetch pacts from to verify (requires the provider name parameter)
+//!         --build-url <build-url>                      URL of the build that performed the verification, used when publishing the verification results
+//!         --ca-file <ca-file>                          PEM file of CA certificates to trust when verifying the provider certificate
+//!         --client-cert <client-cert>                  PEM file of the client certificate to present to the provider
+//!         --client-key <client-key>                    PEM file of the private key for the client certificate (defaults to the client certificate file)
+//!         --consumer-version-selectors <consumer-version-selectors>    Consumer version selector as a JSON document to use when fetching pacts from the pact broker (can be repeated)
+//!         --consumer-version-tags <consumer-version-tags>...           Consumer tags to use when fetching pacts from the pact broker. Accepts comma-separated values or can be repeated
 //!     -d, --dir <dir>                                  Directory of pact files to verify (can be repeated)
+//!         --exclude-description <exclude-description>...    Do not validate interactions whose descriptions match this filter (can be repeated)
+//!         --exclude-state <exclude-state>...                Do not validate interactions whose provider states match this filter (can be repeated)
 //!     -f, --file <file>                                Pact file to verify (can be repeated)
 //!     -c, --filter-consumer <filter-consumer>       Consumer name to filter the pacts to be verified (can be repeated)
-//!         --filter-description <filter-description>    Only validate interactions whose descriptions match this filter
-//!         --filter-state <filter-state>                Only validate interactions whose provider states match this filter
+//!         --filter-consumer-regex <filter-consumer-regex>...    Only validate interactions of consumers whose names match this filter (can be repeated)
+//!         --filter-description <filter-description>...    Only validate interactions whose descriptions match this filter (can be repeated)
+//!         --filter-index <filter-index>...                Only validate the interactions at this index in the pact file, starting at 0 (can be repeated)
+//!         --filter-state <filter-state>...                Only validate interactions whose provider states match this filter (can be repeated)
+//!         --header <header>                            Header to add to (or replace in) each request sent to the provider, in the form 'Name: value' (can be repeated)
 //!     -h, --hostname <hostname>                        Provider hostname (defaults to localhost)
+//!         --include-wip-pacts-since <include-wip-pacts-since>    Also verify any work in progress pacts created since the given date (ISO 8601 format). Work in progress pacts are always pending
+//!         --json <json>                                Write a JSON report of the verification results to the given file
+//!         --junit <junit>                              Write a JUnit XML report of the verification results to the given file
 //!     -l, --loglevel <loglevel>                        Log level (defaults to warn) [values: error, warn, info, debug, trace, none]
 //!     -p, --port <port>                                Provider port (defaults to 8080)
+//!         --password <password>                        Password to use when fetching pacts from the pact broker (can also be set with PACT_BROKER_PASSWORD)
+//!         --order <order>                              Order to verify and display the interactions of each pact in (defaults to pact) [values: pact, description, state]
 //!     -n, --provider-name <provider-name>              Provider name (defaults to provider)
+//!         --provider-tags <provider-tags>...           Tags of the provider version, used by the pact broker to work out the pending and work in progress pacts. Accepts comma-separated values or can be repeated
+//!         --provider-version <provider-version>        Provider application version, used when publishing the verification results
+//!         --scheme <scheme>                            Provider URI scheme (defaults to http) [values: http, https]
 //!     -s, --state-change-url <state-change-url>        URL to post state change requests to
+//!     -t, --token <token>                              Bearer token to use when fetching pacts from the pact broker (can also be set with PACT_BROKER_TOKEN)
 //!     -u, --url <url>                                  URL of pact file to verify (can be repeated)
+//!         --user <user>                                Username to use when fetching pacts from the pact broker (can also be set with PACT_BROKER_USERNAME)
+//!         --workers <workers>                          Number of pacts to verify concurrently (defaults to 1)
 //! ```
 //!
 //! ## Options
@@ -56,6 +81,46 @@
 //! | `-d, --dir <dir>` | Directory | Loads all the pacts from the given directory |
 //! | `-b, --broker-url <broker-url>` | Pact Broker | Loads all the pacts for the provider from the pact broker. Requires the `-n, --provider-name <provider-name>` option |
 //!
+//! ### Selecting the pacts from the Pact Broker
+//!
+//! By default, the latest pact for each consumer of the provider is fetched from the pact broker. The pacts can be selected with one of the following options instead.
+//!
+//! #### `--consumer-version-tags <consumer-version-tags>`
+//!
+//! Fetches the latest pact for each consumer with any of the given tags, using the `pb:latest-provider-pacts-with-tag` link. You can specify multiple tags by either separating them with a comma, or repeating the option. Can't be used with the `--consumer-version-selectors` option.
+//!
+//! #### `--consumer-version-selectors <consumer-version-selectors>`
+//!
+//! Fetches the pacts matching the consumer version selectors from the `pb:provider-pacts-for-verification` endpoint. Each selector is a JSON document, for example `{"tag": "prod", "latest": true}`, and the option can be repeated. The supported attributes are `consumer`, `tag`, `fallbackTag`, `latest`, `deployed`, `released` and `environment`. Can't be used with the `--consumer-version-tags` option.
+//!
+//! ### Pending and work in progress pacts
+//!
+//! When a consumer publishes a pact with new expectations, the pact broker can mark it as pending until the provider has successfully verified it. Pending pacts are fetched with the `pb:provider-pacts-for-verification` endpoint, so these options can't be used with the `--consumer-version-tags` option.
+//!
+//! #### `--enable-pending`
+//!
+//! Pending pacts are verified and reported as normal, but any failures will not fail the verification.
+//!
+//! #### `--include-wip-pacts-since <include-wip-pacts-since>`
+//!
+//! Also verifies any work in progress pacts created since the given date (for example, `2020-01-01`). Work in progress pacts are pacts that have not been successfully verified by the provider, and they are always pending.
+//!
+//! #### `--provider-tags <provider-tags>`
+//!
+//! The tags of the provider version being verified. The pact broker uses these to work out which pacts are pending and work in progress.
+//!
+//! ### Pact Broker authentication
+//!
+//! If the pact broker requires authentication, the credentials can be provided with the following options. Each option can also be set with an environment variable.
+//!
+//! | Option | Environment variable | Description |
+//! |--------|----------------------|-------------|
+//! | `--user <user>` | `PACT_BROKER_USERNAME` | Username for basic authentication |
+//! | `--password <password>` | `PACT_BROKER_PASSWORD` | Password for basic authentication |
+//! | `-t, --token <token>` | `PACT_BROKER_TOKEN` | Bearer token authentication. Can't be used with the `--user` option |
+//!
+//! If the pact broker rejects the credentials, the verification will fail with an authentication error.
+//!
 //! ### Provider Options
 //!
 //! The running provider can be specified with the following options:
@@ -64,7 +129,22 @@
 //! |--------|-------------|
 //! | `-h, --hostname <hostname>` | The provider hostname, defaults to `localhost` |
 //! | `-p, --port <port>` | The provider port (defaults to 8080) |
+//! | `--scheme <scheme>` | The provider URI scheme, `http` or `https` (defaults to `http`) |
 //! | `-n, --provider-name <provider-name>` | The name of the provider. Required if you are loading pacts from a pact broker |
+//! | `--header <header>` | A header to add to each request made to the provider, in the form `Name: value`. It will replace any header with the same name defined in the pact. Can be repeated |
+//!
+//! Headers can be used to provide credentials that can't be stored in the pact, for example `--header "Authorization: Bearer 1234"`.
+//!
+//! ### HTTPS providers
+//!
+//! HTTPS providers are verified with the `--scheme https` option. The provider certificate is verified against the system CA certificates, and the following options can be used to change how the TLS connections are made. These options also apply to HTTPS state change URLs. HTTPS support requires OpenSSL, and is only included when the verifier is built with the `tls` feature (`cargo build --features tls`).
+//!
+//! | Option | Description |
+//! |--------|-------------|
+//! | `--ca-file <ca-file>` | A PEM file of CA certificates to trust, for providers with certificates signed by a private CA |
+//! | `--disable-ssl-verification` | Disables verification of the provider certificate. Only use this with self-signed test certificates |
+//! | `--client-cert <client-cert>` | A PEM file of the client certificate to present to the provider (mutual TLS) |
+//! | `--client-key <client-key>` | A PEM file of the private key for the client certificate. If not given, the key is loaded from the client certificate file |
 //!
 //! ### Filtering the interactions
 //!
@@ -76,16 +156,34 @@
 //!
 //! #### `--filter-description <filter-description>`
 //!
-//! This option will filter the interactions that are verified that match by desciption. You can use a regular expression to match.
+//! This option will filter the interactions that are verified that match by desciption. You can use a regular expression to match. If the option is repeated, interactions that match any of the values are verified.
 //!
 //! #### `--filter-state <filter-state>`
 //!
-//! This option will filter the interactions that are verified that match by provider state. You can use a regular expression to match. Can't be used with the `--filter-no-state` option.
+//! This option will filter the interactions that are verified that match by provider state. You can use a regular expression to match. If the option is repeated, interactions that match any of the values are verified. Can't be used with the `--filter-no-state` option.
 //!
 //! #### `--filter-no-state`
 //!
 //! This option will filter the interactions that are verified that don't have a defined provider state. Can't be used with the `--filter-state` option.
 //!
+//! #### `--filter-consumer-regex <filter-consumer-regex>`
+//!
+//! This option will filter the interactions that are verified to the consumers whose names match the regular expression. It can be repeated to match any of the values.
+//!
+//! #### `--filter-index <filter-index>`
+//!
+//! This option will only verify the interaction at the given index in each pact file, starting at 0. It can be repeated to verify several interactions. The index of each interaction is included in the JSON report (`--json`), so it can be used with `--filter-consumer-regex` to rerun the failures from a previous run.
+//!
+//! #### `--exclude-description <exclude-description>`
+//!
+//! This option will exclude the interactions whose descriptions match the regular expression. It can be repeated to exclude interactions that match any of the values.
+//!
+//! #### `--exclude-state <exclude-state>`
+//!
+//! This option will exclude the interactions whose provider states match the regular expression. It can be repeated to exclude interactions that match any of the values.
+//!
+//! When different filter options are given, only the interactions that match all of them are verified. For example, `--filter-state "user exists" --exclude-description "deleted"` verifies the interactions with the `user exists` provider state, apart from those whose description contains `deleted`.
+//!
 //! ### State change requests
 //!
 //! Provider states are a mechanism to define the state that the provider needs to be in to be able to verify a particular request. This is achieved by setting a state change URL that will receive a POST request with the provider state before the actual request is made.
@@ -102,6 +200,50 @@
 //!
 //! This option will cause the verifier to also make a tear down request after the main request is made. It will receive a second field in the body or a query parameter named `action` with the value `teardown`.
 //!
+//! ### Ordering the results
+//!
+//! The pacts are verified and displayed in the order they are loaded: the pact files, then the directories (with the pact files in each directory loaded in order of their file names), then the URLs and then the pacts from the pact broker. The interactions of each pact are verified and displayed in the order they are defined in the pact file, so the output of repeated runs can be compared.
+//!
+//! #### `--order <order>`
+//!
+//! Changes the order the interactions of each pact are verified and displayed in. `description` sorts them by the interaction description, and `state` sorts them by the provider states and then the description. The default, `pact`, keeps the order of the pact file. The failures listed at the end of the run follow the same order.
+//!
+//! ### Parallel verification
+//!
+//! #### `--workers <workers>`
+//!
+//! Verifies up to this many pacts concurrently (defaults to 1, which verifies the pacts one at a time). The interactions of each pact are still verified one at a time, with the state change requests made before and after each interaction, and the results are displayed in the order the pacts were loaded. As state change requests for different pacts can be made at the same time, the provider must be able to handle them concurrently.
+//!
+//! ### Publishing verification results
+//!
+//! The results of verifying the pacts fetched from a pact broker can be published back to the pact broker.
+//!
+//! #### `--publish`
+//!
+//! Enables publishing of the verification results. Requires the `-b, --broker-url <broker-url>` and `--provider-version <provider-version>` options. The results are posted to the `pb:publish-verification-results` link of each pact, and are not published if the interactions have been filtered.
+//!
+//! #### `--provider-version <provider-version>`
+//!
+//! The version of the provider application that was verified.
+//!
+//! #### `--build-url <build-url>`
+//!
+//! The URL of the build that performed the verification. This is optional.
+//!
+//! ### Reports
+//!
+//! The results of the verification can also be written out as reports, so they can be displayed by CI servers.
+//!
+//! #### `--junit <junit>`
+//!
+//! Writes a JUnit XML report to the given file. There is a test suite for each pact, and a test case for each interaction with the duration and any mismatches or errors.
+//!
+//! #### `--json <json>`
+//!
+//! Writes a JSON report to the given file, with the status, duration, mismatches and errors of each pact and interaction.
+//!
+//! If a report can not be written, the verifier will exit with a status of 3.
+//!
 //! ## Example run
 //!
 //! This will verify all the pacts for the `happy_provider` found in the pact broker (running on localhost) against the provider running on localhost port 5050. Only the pacts for the consumers `Consumer` and `Consumer2` will be verified.
@@ -203,6 +345,7 @@ extern crate pact_verifier;
 extern crate simplelog;
 extern crate rand;
 extern crate regex;
+extern crate serde_json;
 
 #[cfg(test)]
 #[macro_use(expect)]
@@ -214,6 +357,7 @@ extern crate quickcheck;
 use std::env;
 use clap::{Arg, App, AppSettings, ErrorKind, ArgMatches};
 use pact_matching::models::PactSpecification;
+use pact_verifier::HttpAuth;
 use pact_verifier::*;
 use log::LogLevelFilter;
 use simplelog::TermLogger;
@@ -221,6 +365,8 @@ use std::str::FromStr;
 use std::error::Error;
 use regex::Regex;
 
+mod reports;
+
 fn main() {
     match handle_command_args() {
         Ok(_) => (),
@@ -237,6 +383,22 @@ fn integer_value(v: String) -> Result<(), String> {
     v.parse::<u16>().map(|_| ()).map_err(|e| format!("'{}' is not a valid port value: {}", v, e) )
 }
 
+fn regex_value(v: String) -> Result<(), String> {
+    Regex::new(&v).map(|_| ()).map_err(|err| format!("'{}' is an invalid filter value: {}", v, err.description()))
+}
+
+fn index_value(v: String) -> Result<(), String> {
+    v.parse::<usize>().map(|_| ()).map_err(|e| format!("'{}' is not a valid interaction index: {}", v, e))
+}
+
+fn worker_count(v: String) -> Result<(), String> {
+    match v.parse::<usize>() {
+        Ok(workers) if workers > 0 => Ok(()),
+        Ok(_) => Err(format!("'{}' is not a valid number of workers: must be at least 1", v)),
+        Err(e) => Err(format!("'{}' is not a valid number of workers: {}", v, e))
+    }
+}
+
 fn pact_source(matches: &ArgMatches) -> Vec<PactSource> {
     let mut sources = vec![];
     match matches.values_of("file") {
@@ -252,34 +414,163 @@ fn pact_source(matches: &ArgMatches) -> Vec<PactSource> {
         None => ()
     };
     match matches.values_of("broker-url") {
-        Some(values) => sources.extend(values.map(|v| PactSource::BrokerUrl(s!(matches.value_of("provider-name").unwrap()),
-            s!(v))).collect::<Vec<PactSource>>()),
+        Some(values) => {
+            let auth = broker_auth(matches);
+            let provider_name = s!(matches.value_of("provider-name").unwrap());
+            let selectors = consumer_version_selectors(matches);
+            let pending_options = pending_pact_options(matches);
+            sources.extend(values.map(|v| match matches.values_of("consumer-version-tags") {
+                Some(tags) => PactSource::BrokerWithTags(provider_name.clone(), s!(v),
+                    tags.map(|tag| s!(tag)).collect(), auth.clone()),
+                None => if selectors.is_empty() && pending_options == PendingPactOptions::default() {
+                    PactSource::BrokerUrl(provider_name.clone(), s!(v), auth.clone())
+                } else {
+                    PactSource::BrokerWithSelectors(provider_name.clone(), s!(v), selectors.clone(),
+                        pending_options.clone(), auth.clone())
+                }
+            }).collect::<Vec<PactSource>>())
+        },
         None => ()
     };
     sources
 }
 
-fn interaction_filter(matches: &ArgMatches) -> FilterInfo {
-    if matches.is_present("filter-description") &&
-        (matches.is_present("filter-state") || matches.is_present("filter-no-state")) {
-        if matches.is_present("filter-state") {
-            FilterInfo::DescriptionAndState(s!(matches.value_of("filter-description").unwrap()),
-                s!(matches.value_of("filter-state").unwrap()))
-        } else {
-            FilterInfo::DescriptionAndState(s!(matches.value_of("filter-description").unwrap()),
-                s!(""))
+fn consumer_version_selector(value: &str) -> Result<ConsumerVersionSelector, String> {
+    serde_json::from_str(value)
+        .map_err(|err| err.to_string())
+        .and_then(|json| ConsumerVersionSelector::from_json(&json))
+}
+
+fn consumer_version_selectors(matches: &ArgMatches) -> Vec<ConsumerVersionSelector> {
+    match matches.values_of("consumer-version-selectors") {
+        Some(values) => values.map(|v| consumer_version_selector(v).unwrap()).collect(),
+        None => vec![]
+    }
+}
+
+fn pending_pact_options(matches: &ArgMatches) -> PendingPactOptions {
+    PendingPactOptions {
+        enable_pending: matches.is_present("enable-pending"),
+        include_wip_pacts_since: matches.value_of("include-wip-pacts-since").map(|since| s!(since)),
+        provider_tags: match matches.values_of("provider-tags") {
+            Some(values) => values.map(|tag| s!(tag)).collect(),
+            None => vec![]
         }
-    } else if matches.is_present("filter-description") {
-        FilterInfo::Description(s!(matches.value_of("filter-description").unwrap()))
-    } else if matches.is_present("filter-state") {
-        FilterInfo::State(s!(matches.value_of("filter-state").unwrap()))
-    } else if matches.is_present("filter-no-state") {
-        FilterInfo::State(s!(""))
+    }
+}
+
+fn option_or_env(matches: &ArgMatches, option: &str, env_var: &str) -> Option<String> {
+    match matches.value_of(option) {
+        Some(value) => Some(s!(value)),
+        None => match env::var(env_var) {
+            Ok(ref value) if !value.is_empty() => Some(value.clone()),
+            _ => None
+        }
+    }
+}
+
+fn broker_auth(matches: &ArgMatches) -> Option<HttpAuth> {
+    match option_or_env(matches, "token", "PACT_BROKER_TOKEN") {
+        Some(token) => Some(HttpAuth::Token(token)),
+        None => option_or_env(matches, "user", "PACT_BROKER_USERNAME")
+            .map(|user| HttpAuth::User(user, option_or_env(matches, "password", "PACT_BROKER_PASSWORD")))
+    }
+}
+
+fn any_filter(filters: Vec<FilterInfo>) -> Option<FilterInfo> {
+    match filters.len() {
+        0 => None,
+        1 => filters.into_iter().next(),
+        _ => Some(FilterInfo::Any(filters))
+    }
+}
+
+fn values_filter<F>(matches: &ArgMatches, name: &str, filter: F) -> Option<FilterInfo> where F: Fn(String) -> FilterInfo {
+    any_filter(matches.values_of_lossy(name).unwrap_or(vec![]).into_iter().map(filter).collect())
+}
+
+fn interaction_filter(matches: &ArgMatches) -> FilterInfo {
+    let state_filter = if matches.is_present("filter-no-state") {
+        Some(FilterInfo::State(s!("")))
     } else {
-        FilterInfo::None
+        values_filter(matches, "filter-state", FilterInfo::State)
+    };
+    let filters: Vec<FilterInfo> = vec![
+        values_filter(matches, "filter-description", FilterInfo::Description),
+        state_filter,
+        values_filter(matches, "filter-consumer-regex", FilterInfo::Consumer),
+        values_filter(matches, "filter-index", |index| FilterInfo::Index(index.parse::<usize>().unwrap())),
+        values_filter(matches, "exclude-description", FilterInfo::Description)
+            .map(|filter| FilterInfo::Not(Box::new(filter))),
+        values_filter(matches, "exclude-state", FilterInfo::State)
+            .map(|filter| FilterInfo::Not(Box::new(filter)))
+    ].into_iter().flat_map(|filter| filter).collect();
+    match filters.len() {
+        0 => FilterInfo::None,
+        1 => filters[0].clone(),
+        _ => FilterInfo::All(filters)
     }
 }
 
+fn parse_header(value: &str) -> Result<(String, String), String> {
+    match value.find(':') {
+        Some(index) if index > 0 => Ok((s!(value[..index].trim()), s!(value[index + 1..].trim()))),
+        _ => Err(format!("'{}' is not a valid header, it must be in the form 'Name: value'", value))
+    }
+}
+
+fn request_filter(matches: &ArgMatches) -> Option<RequestFilter> {
+    matches.values_of("header").map(|values| RequestFilter::with_headers(values
+        .map(|value| parse_header(value).unwrap())
+        .collect()))
+}
+
+fn interaction_order(matches: &ArgMatches) -> InteractionOrder {
+    match matches.value_of("order") {
+        Some("description") => InteractionOrder::Description,
+        Some("state") => InteractionOrder::ProviderState,
+        _ => InteractionOrder::PactFile
+    }
+}
+
+fn tls_options(matches: &ArgMatches) -> TlsOptions {
+    TlsOptions {
+        ca_file: matches.value_of("ca-file").map(|file| s!(file)),
+        insecure: matches.is_present("disable-ssl-verification"),
+        client_cert: matches.value_of("client-cert").map(|file| s!(file)),
+        client_key: matches.value_of("client-key").map(|file| s!(file))
+    }
+}
+
+fn publish_options(matches: &ArgMatches) -> Option<PublishOptions> {
+    if matches.is_present("publish") {
+        Some(PublishOptions {
+            provider_version: s!(matches.value_of("provider-version").unwrap()),
+            build_url: matches.value_of("build-url").map(|url| s!(url))
+        })
+    } else {
+        None
+    }
+}
+
+fn write_reports(matches: &ArgMatches, result: &VerificationResult) -> Result<(), i32> {
+    match matches.value_of("junit") {
+        Some(path) => try!(reports::write_junit_report(result, path).map_err(|err| {
+            error!("Failed to write the JUnit report to '{}' - {}", path, err);
+            3
+        })),
+        None => ()
+    };
+    match matches.value_of("json") {
+        Some(path) => try!(reports::write_json_report(result, path).map_err(|err| {
+            error!("Failed to write the JSON report to '{}' - {}", path, err);
+            3
+        })),
+        None => ()
+    };
+    Ok(())
+}
+
 fn handle_command_args() -> Result<(), i32> {
     let args: Vec<String> = env::args().collect();
     let program = args[0].clone();
@@ -339,6 +630,73 @@ fn handle_command_args() -> Result<(), i32> {
             .number_of_values(1)
             .empty_values(false)
             .help("URL of the pact broker to fetch pacts from to verify (requires the provider name parameter)"))
+        .arg(Arg::with_name("consumer-version-tags")
+            .long("consumer-version-tags")
+            .takes_value(true)
+            .use_delimiter(true)
+            .multiple(true)
+            .empty_values(false)
+            .requires("broker-url")
+            .conflicts_with("consumer-version-selectors")
+            .help("Consumer tags to use when fetching pacts from the pact broker. Accepts comma-separated values or can be repeated"))
+        .arg(Arg::with_name("consumer-version-selectors")
+            .long("consumer-version-selectors")
+            .takes_value(true)
+            .use_delimiter(false)
+            .multiple(true)
+            .number_of_values(1)
+            .empty_values(false)
+            .requires("broker-url")
+            .conflicts_with("consumer-version-tags")
+            .validator(|val| consumer_version_selector(&val).map(|_| ())
+                .map_err(|err| format!("'{}' is an invalid consumer version selector: {}", val, err)))
+            .help("Consumer version selector as a JSON document to use when fetching pacts from the pact broker (can be repeated)"))
+        .arg(Arg::with_name("enable-pending")
+            .long("enable-pending")
+            .requires("broker-url")
+            .conflicts_with("consumer-version-tags")
+            .help("Enables pending pacts. Failures verifying pending pacts will not fail the verification"))
+        .arg(Arg::with_name("include-wip-pacts-since")
+            .long("include-wip-pacts-since")
+            .takes_value(true)
+            .use_delimiter(false)
+            .requires("broker-url")
+            .conflicts_with("consumer-version-tags")
+            .validator(|val| if Regex::new(r"^\d{4}-\d{2}-\d{2}").unwrap().is_match(&val) {
+                    Ok(())
+                } else {
+                    Err(format!("'{}' is not a valid date, it must be in ISO 8601 format (YYYY-MM-DD)", val))
+                })
+            .help("Also verify any work in progress pacts created since the given date (ISO 8601 format). Work in progress pacts are always pending"))
+        .arg(Arg::with_name("provider-tags")
+            .long("provider-tags")
+            .takes_value(true)
+            .use_delimiter(true)
+            .multiple(true)
+            .empty_values(false)
+            .requires("broker-url")
+            .help("Tags of the provider version, used by the pact broker to work out the pending and work in progress pacts. Accepts comma-separated values or can be repeated"))
+        .arg(Arg::with_name("user")
+            .long("user")
+            .takes_value(true)
+            .use_delimiter(false)
+            .requires("broker-url")
+            .conflicts_with("token")
+            .help("Username to use when fetching pacts from the pact broker (can also be set with PACT_BROKER_USERNAME)"))
+        .arg(Arg::with_name("password")
+            .long("password")
+            .takes_value(true)
+            .use_delimiter(false)
+            .requires("user")
+            .help("Password to use when fetching pacts from the pact broker (can also be set with PACT_BROKER_PASSWORD)"))
+        .arg(Arg::with_name("token")
+            .short("t")
+            .long("token")
+            .takes_value(true)
+            .use_delimiter(false)
+            .requires("broker-url")
+            .conflicts_with("user")
+            .help("Bearer token to use when fetching pacts from the pact broker (can also be set with PACT_BROKER_TOKEN)"))
         .arg(Arg::with_name("hostname")
             .short("h")
             .long("hostname")
@@ -352,6 +710,40 @@ fn handle_command_args() -> Result<(), i32> {
             .use_delimiter(false)
             .help("Provider port (defaults to 8080)")
             .validator(integer_value))
+        .arg(Arg::with_name("scheme")
+            .long("scheme")
+            .takes_value(true)
+            .use_delimiter(false)
+            .possible_values(&["http", "https"])
+            .help("Provider URI scheme (defaults to http)"))
+        .arg(Arg::with_name("ca-file")
+            .long("ca-file")
+            .takes_value(true)
+            .use_delimiter(false)
+            .help("PEM file of CA certificates to trust when verifying the provider certificate"))
+        .arg(Arg::with_name("disable-ssl-verification")
+            .long("disable-ssl-verification")
+            .help("Disables verification of the provider certificate (only use this with self-signed test certificates)"))
+        .arg(Arg::with_name("client-cert")
+            .long("client-cert")
+            .takes_value(true)
+            .use_delimiter(false)
+            .help("PEM file of the client certificate to present to the provider"))
+        .arg(Arg::with_name("client-key")
+            .long("client-key")
+            .takes_value(true)
+            .use_delimiter(false)
+            .requires("client-cert")
+            .help("PEM file of the private key for the client certificate (defaults to the client certificate file)"))
+        .arg(Arg::with_name("header")
+            .long("header")
+            .takes_value(true)
+            .use_delimiter(false)
+            .multiple(true)
+            .number_of_values(1)
+            .empty_values(false)
+            .validator(|val| parse_header(&val).map(|_| ()))
+            .help("Header to add to (or replace in) each request sent to the provider, in the form 'Name: value' (can be repeated)"))
         .arg(Arg::with_name("provider-name")
             .short("n")
             .long("provider-name")
@@ -364,6 +756,18 @@ fn handle_command_args() -> Result<(), i32> {
             .takes_value(true)
             .use_delimiter(false)
             .help("URL to post state change requests to"))
+        .arg(Arg::with_name("workers")
+            .long("workers")
+            .takes_value(true)
+            .use_delimiter(false)
+            .validator(worker_count)
+            .help("Number of pacts to verify concurrently (defaults to 1)"))
+        .arg(Arg::with_name("order")
+            .long("order")
+            .takes_value(true)
+            .use_delimiter(false)
+            .possible_values(&["pact", "description", "state"])
+            .help("Order to verify and display the interactions of each pact in (defaults to pact)"))
         .arg(Arg::with_name("state-change-as-query")
             .long("state-change-as-query")
             .help("State change request data will be sent as query parameters instead of in the request body"))
@@ -374,19 +778,19 @@ fn handle_command_args() -> Result<(), i32> {
             .long("filter-description")
             .takes_value(true)
             .use_delimiter(false)
-            .validator(|val| Regex::new(&val)
-                .map(|_| ())
-                .map_err(|err| format!("'{}' is an invalid filter value: {}", val, err.description())))
-            .help("Only validate interactions whose descriptions match this filter"))
+            .multiple(true)
+            .number_of_values(1)
+            .validator(regex_value)
+            .help("Only validate interactions whose descriptions match this filter (can be repeated)"))
         .arg(Arg::with_name("filter-state")
             .long("filter-state")
             .takes_value(true)
             .use_delimiter(false)
+            .multiple(true)
+            .number_of_values(1)
             .conflicts_with("filter-no-state")
-            .validator(|val| Regex::new(&val)
-                .map(|_| ())
-                .map_err(|err| format!("'{}' is an invalid filter value: {}", val, err.description())))
-            .help("Only validate interactions whose provider states match this filter"))
+            .validator(regex_value)
+            .help("Only validate interactions whose provider states match this filter (can be repeated)"))
         .arg(Arg::with_name("filter-no-state")
             .long("filter-no-state")
             .conflicts_with("filter-state")
@@ -398,6 +802,68 @@ fn handle_command_args() -> Result<(), i32> {
             .multiple(true)
             .empty_values(false)
             .help("Consumer name to filter the pacts to be verified (can be repeated)"))
+        .arg(Arg::with_name("filter-consumer-regex")
+            .long("filter-consumer-regex")
+            .takes_value(true)
+            .use_delimiter(false)
+            .multiple(true)
+            .number_of_values(1)
+            .validator(regex_value)
+            .help("Only validate interactions of consumers whose names match this filter (can be repeated)"))
+        .arg(Arg::with_name("filter-index")
+            .long("filter-index")
+            .takes_value(true)
+            .use_delimiter(false)
+            .multiple(true)
+            .number_of_values(1)
+            .validator(index_value)
+            .help("Only validate the interactions at this index in the pact file, starting at 0 (can be repeated)"))
+        .arg(Arg::with_name("exclude-description")
+            .long("exclude-description")
+            .takes_value(true)
+            .use_delimiter(false)
+            .multiple(true)
+            .number_of_values(1)
+            .validator(regex_value)
+            .help("Do not validate interactions whose descriptions match this filter (can be repeated)"))
+        .arg(Arg::with_name("exclude-state")
+            .long("exclude-state")
+            .takes_value(true)
+            .use_delimiter(false)
+            .multiple(true)
+            .number_of_values(1)
+            .validator(regex_value)
+            .help("Do not validate interactions whose provider states match this filter (can be repeated)"))
+        .arg(Arg::with_name("publish")
+            .long("publish")
+            .requires("broker-url")
+            .requires("provider-version")
+            .help("Publish the verification results to the pact broker (requires the broker URL and provider version parameters)"))
+        .arg(Arg::with_name("provider-version")
+            .long("provider-version")
+            .takes_value(true)
+            .use_delimiter(false)
+            .empty_values(false)
+            .help("Provider application version, used when publishing the verification results"))
+        .arg(Arg::with_name("build-url")
+            .long("build-url")
+            .takes_value(true)
+            .use_delimiter(false)
+            .empty_values(false)
+            .requires("publish")
+            .help("URL of the build that performed the verification, used when publishing the verification results"))
+        .arg(Arg::with_name("junit")
+            .long("junit")
+            .takes_value(true)
+            .use_delimiter(false)
+            .empty_values(false)
+            .help("Write a JUnit XML report of the verification results to the given file"))
+        .arg(Arg::with_name("json")
+            .long("json")
+            .takes_value(true)
+            .use_delimiter(false)
+            .empty_values(false)
+            .help("Write a JSON report of the verification results to the given file"))
         ;
 
     let matches = app.get_matches_safe();
@@ -415,11 +881,20 @@ fn handle_command_args() -> Result<(), i32> {
                 state_change_url: matches.value_of("state-change-url").map(|s| s.to_string()),
                 state_change_body: !matches.is_present("state-change-as-query"),
                 state_change_teardown: matches.is_present("state-change-teardown"),
+                protocol: s!(matches.value_of("scheme").unwrap_or("http")),
+                request_filter: request_filter(matches),
+                tls_options: tls_options(matches),
+                interaction_order: interaction_order(matches),
+                workers: matches.value_of("workers").unwrap_or("1").parse::<usize>().unwrap(),
                 .. ProviderInfo::default()
             };
             let source = pact_source(matches);
             let filter = interaction_filter(matches);
-            if verify_provider(&provider, source, &filter, &matches.values_of_lossy("filter-consumer").unwrap_or(vec![])) {
+            let publish = publish_options(matches);
+            let result = verify_provider(&provider, source, &filter,
+                &matches.values_of_lossy("filter-consumer").unwrap_or(vec![]), &publish);
+            try!(write_reports(matches, &result));
+            if result.success() {
                 Ok(())
             } else {
                 Err(2)
@@ -450,7 +925,7 @@ mod test {
 
     use quickcheck::{TestResult, quickcheck};
     use rand::Rng;
-    use super::integer_value;
+    use super::{integer_value, index_value, regex_value, worker_count, parse_header};
     use expectest::prelude::*;
 
     #[test]
@@ -472,4 +947,31 @@ mod test {
         expect!(integer_value(s!("1234"))).to(be_ok());
         expect!(integer_value(s!("1234x"))).to(be_err());
     }
+
+    #[test]
+    fn validates_worker_count() {
+        expect!(worker_count(s!("1"))).to(be_ok());
+        expect!(worker_count(s!("8"))).to(be_ok());
+        expect!(worker_count(s!("0"))).to(be_err());
+        expect!(worker_count(s!("-1"))).to(be_err());
+        expect!(worker_count(s!("many"))).to(be_err());
+    }
+
+    #[test]
+    fn validates_filter_values() {
+        expect!(regex_value(s!("a.*"))).to(be_ok());
+        expect!(regex_value(s!("[a"))).to(be_err());
+        expect!(index_value(s!("0"))).to(be_ok());
+        expect!(index_value(s!("12"))).to(be_ok());
+        expect!(index_value(s!("-1"))).to(be_err());
+        expect!(index_value(s!("first"))).to(be_err());
+    }
+
+    #[test]
+    fn parses_header_values() {
+        expect!(parse_header("Authorization: Bearer 1234")).to(be_ok().value((s!("Authorization"), s!("Bearer 1234"))));
+        expect!(parse_header("X-Empty:")).to(be_ok().value((s!("X-Empty"), s!(""))));
+        expect!(parse_header("no-colon")).to(be_err());
+        expect!(parse_header(": value")).to(be_err());
+    }
 }
@@ -0,0 +1,163 @@
+//! Writes the verification results out as JSON or JUnit XML reports
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::time::Duration;
+use pact_verifier::*;
+use serde_json;
+
+/// Writes the verification result out to the given file as a JSON document
+pub fn write_json_report(result: &VerificationResult, path: &str) -> io::Result<()> {
+    let json = try!(serde_json::to_string_pretty(&result.to_json())
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err)));
+    let mut file = try!(File::create(path));
+    file.write_all(json.as_bytes())
+}
+
+/// Writes the verification result out to the given file as a JUnit XML report
+pub fn write_junit_report(result: &VerificationResult, path: &str) -> io::Result<()> {
+    let mut file = try!(File::create(path));
+    file.write_all(junit_report(result).as_bytes())
+}
+
+fn seconds(duration: &Duration) -> String {
+    format!("{}.{:03}", duration.as_secs(), duration.subsec_nanos() / 1_000_000)
+}
+
+fn escape_xml(value: &str) -> String {
+    value.chars().map(|ch| match ch {
+        '&' => s!("&amp;"),
+        '<' => s!("&lt;"),
+        '>' => s!("&gt;"),
+        '"' => s!("&quot;"),
+        '\'' => s!("&apos;"),
+        _ => ch.to_string()
+    }).collect()
+}
+
+fn count_status(pact: &PactVerificationResult, status: VerificationStatus) -> usize {
+    pact.interactions.iter().filter(|i| i.status == status).count()
+}
+
+fn testcase_name(interaction: &InteractionVerificationResult) -> String {
+    if interaction.provider_states.is_empty() {
+        interaction.description.clone()
+    } else {
+        format!("Given {} - {}", interaction.provider_states.join(" And "), interaction.description)
+    }
+}
+
+/// Generates a JUnit XML report from the verification result. There is a test suite for each pact,
+/// and a test case for each verified interaction or message.
+pub fn junit_report(result: &VerificationResult) -> String {
+    let mut tests = 0;
+    let mut failures = 0;
+    let mut errors = 0;
+    let mut suites = String::new();
+
+    for pact in &result.pacts {
+        match pact.error {
+            Some(ref err) => {
+                tests += 1;
+                errors += 1;
+                suites.push_str("  <testsuite name=\"Failed to load pact\" tests=\"1\" failures=\"0\" errors=\"1\" time=\"0.000\">\n");
+                suites.push_str("    <testcase classname=\"pact\" name=\"Failed to load pact\" time=\"0.000\">\n");
+                suites.push_str(&format!("      <error message=\"{}\"/>\n", escape_xml(err)));
+                suites.push_str("    </testcase>\n  </testsuite>\n");
+            },
+            None => {
+                let pact_failures = count_status(pact, VerificationStatus::Failed);
+                let pact_errors = count_status(pact, VerificationStatus::Error);
+                tests += pact.interactions.len();
+                failures += pact_failures;
+                errors += pact_errors;
+                suites.push_str(&format!("  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{}\">\n",
+                    escape_xml(&format!("Verifying a pact between {} and {}", pact.consumer, pact.provider)),
+                    pact.interactions.len(), pact_failures, pact_errors, seconds(&pact.duration)));
+                let classname = escape_xml(&format!("{}.{}", pact.consumer, pact.provider));
+                for interaction in &pact.interactions {
+                    suites.push_str(&format!("    <testcase classname=\"{}\" name=\"{}\" time=\"{}\"",
+                        classname, escape_xml(&testcase_name(interaction)), seconds(&interaction.duration)));
+                    match interaction.status {
+                        VerificationStatus::Passed => suites.push_str("/>\n"),
+                        VerificationStatus::Failed => {
+                            suites.push_str(">\n");
+                            for mismatch in &interaction.mismatches {
+                                suites.push_str(&format!("      <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+                                    mismatch.mismatch_type(), escape_xml(&mismatch.summary()),
+                                    escape_xml(&mismatch.description())));
+                            }
+                            suites.push_str("    </testcase>\n");
+                        },
+                        VerificationStatus::Error => {
+                            suites.push_str(">\n");
+                            suites.push_str(&format!("      <error message=\"{}\"/>\n",
+                                escape_xml(&interaction.error.clone().unwrap_or(s!("")))));
+                            suites.push_str("    </testcase>\n");
+                        }
+                    }
+                }
+                suites.push_str("  </testsuite>\n");
+            }
+        }
+    }
+
+    format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites name=\"pact verification\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{}\">\n{}</testsuites>\n",
+        tests, failures, errors, seconds(&result.duration), suites)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expectest::prelude::*;
+    use std::time::Duration;
+    use pact_matching::Mismatch;
+    use pact_matching::models::Response;
+
+    fn interaction(description: &str, status: VerificationStatus, mismatches: Vec<Mismatch>,
+        error: Option<String>) -> InteractionVerificationResult {
+        InteractionVerificationResult {
+            index: 0,
+            description: s!(description),
+            provider_states: vec![],
+            status: status,
+            duration: Duration::from_millis(25),
+            mismatches: mismatches,
+            error: error,
+            details: VerificationDetails::Response(Response::default_response(), None)
+        }
+    }
+
+    #[test]
+    fn junit_report_has_a_test_case_for_each_interaction() {
+        let pact = PactVerificationResult::new(s!("Consumer"), s!("Provider"), Duration::from_millis(1250), vec![
+            interaction("a request for <stuff>", VerificationStatus::Passed, vec![], None),
+            interaction("a failing request", VerificationStatus::Failed,
+                vec![Mismatch::StatusMismatch { expected: 200, actual: 404 }], None),
+            interaction("a broken request", VerificationStatus::Error, vec![], Some(s!("Connection refused")))
+        ]);
+        let result = VerificationResult { pacts: vec![pact], duration: Duration::from_millis(1500) };
+        expect!(junit_report(&result)).to(be_equal_to(s!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="pact verification" tests="3" failures="1" errors="1" time="1.500">
+  <testsuite name="Verifying a pact between Consumer and Provider" tests="3" failures="1" errors="1" time="1.250">
+    <testcase classname="Consumer.Provider" name="a request for &lt;stuff&gt;" time="0.025"/>
+    <testcase classname="Consumer.Provider" name="a failing request" time="0.025">
+      <failure type="StatusMismatch" message="has status code 200">expected 200 but was 404</failure>
+    </testcase>
+    <testcase classname="Consumer.Provider" name="a broken request" time="0.025">
+      <error message="Connection refused"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#)));
+    }
+
+    #[test]
+    fn junit_report_includes_pacts_that_failed_to_load_as_errors() {
+        let result = VerificationResult { pacts: vec![PactVerificationResult::load_error(s!("No such file"))],
+            duration: Duration::from_millis(5) };
+        let report = junit_report(&result);
+        expect!(report.contains(r#"tests="1" failures="0" errors="1" time="0.005""#)).to(be_true());
+        expect!(report.contains(r#"<error message="No such file"/>"#)).to(be_true());
+    }
+}